@@ -0,0 +1,87 @@
+//! Cost of the basic `TransformTree` operations as the tree grows from 1e2 to
+//! 1e6 frames. Each should stay flat per call except the full refresh, which
+//! is linear.
+
+use axisviz::{FileNode, FileTransformTree, NodeId, TransformTree};
+use bevy::prelude::*;
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
+
+const SIZES: [usize; 5] = [100, 1_000, 10_000, 100_000, 1_000_000];
+
+/// Four-way branching tree of `n` frames.
+fn tree(n: usize) -> TransformTree {
+    let nodes = (0..n)
+        .map(|i| FileNode {
+            name: format!("n{}", i),
+            parent: (i > 0).then(|| format!("n{}", (i - 1) / 4)),
+            t: [0.1, 0.0, 0.0],
+            r: [0.0, 0.0, 0.1],
+            ..Default::default()
+        })
+        .collect();
+    TransformTree::try_from(FileTransformTree { version: 1, nodes, ..Default::default() }).expect("valid tree")
+}
+
+fn bench(c: &mut Criterion) {
+    let pose = Isometry3d::from_translation(Vec3::X);
+    let mut group = c.benchmark_group("tree_ops");
+    group.sample_size(10);
+    for n in SIZES {
+        let names: Vec<String> = (0..n).map(|i| format!("n{}", i)).collect();
+        group.bench_with_input(BenchmarkId::new("add_node", n), &n, |b, _| {
+            b.iter(|| {
+                let mut dag = TransformTree::default();
+                for (i, name) in names.iter().enumerate() {
+                    dag.add_node(name, pose, (i > 0).then(|| (i - 1) / 4));
+                }
+                dag
+            })
+        });
+
+        let mut dag = tree(n);
+        // Moves the last leaf back and forth between two branches.
+        let leaf: NodeId = n - 1;
+        let parents = [0, n / 8];
+        group.bench_with_input(BenchmarkId::new("set_parent", n), &n, |b, _| {
+            let mut flip = false;
+            b.iter(|| {
+                flip = !flip;
+                dag.set_parent(leaf, Some(parents[flip as usize]));
+            })
+        });
+        dag.update_world();
+
+        // Spread the marked frames over the tree, mostly near the leaves.
+        let marked: Vec<NodeId> = (0..10).map(|i| n - 1 - i * n / 20).collect();
+        group.bench_with_input(BenchmarkId::new("mark_dirty", n), &n, |b, _| {
+            b.iter_batched_ref(
+                || dag.clone(),
+                |dag| {
+                    for &id in &marked {
+                        dag.mark_dirty(id);
+                    }
+                },
+                BatchSize::LargeInput,
+            )
+        });
+
+        group.bench_with_input(BenchmarkId::new("update_world_all", n), &n, |b, _| {
+            b.iter(|| {
+                dag.set_local(0, pose);
+                dag.update_world();
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("update_world_leaves", n), &n, |b, _| {
+            b.iter(|| {
+                for &id in &marked {
+                    dag.set_local(id, pose);
+                }
+                dag.update_world();
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
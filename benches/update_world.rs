@@ -0,0 +1,51 @@
+//! Incremental `update_world` against a full traversal, for a large tree where
+//! a handful of frames change between updates (the live streaming case).
+
+use axisviz::{FileNode, FileTransformTree, NodeId, TransformTree};
+use bevy::prelude::*;
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+
+/// Four-way branching tree of `n` frames.
+fn tree(n: usize) -> TransformTree {
+    let nodes = (0..n)
+        .map(|i| FileNode {
+            name: format!("n{}", i),
+            parent: (i > 0).then(|| format!("n{}", (i - 1) / 4)),
+            t: [0.1, 0.0, 0.0],
+            r: [0.0, 0.0, 0.1],
+            ..Default::default()
+        })
+        .collect();
+    TransformTree::try_from(FileTransformTree { version: 1, nodes, ..Default::default() }).expect("valid tree")
+}
+
+fn bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("update_world");
+    for n in [1_000, 10_000, 100_000] {
+        let mut dag = tree(n);
+        // Spread the changed frames over the tree, mostly near the leaves.
+        let changed: Vec<NodeId> = (0..10).map(|i| dag.find(&format!("n{}", n - 1 - i * n / 20)).unwrap()).collect();
+        let pose = Isometry3d::from_translation(Vec3::X);
+
+        group.bench_with_input(BenchmarkId::new("incremental", n), &n, |b, _| {
+            b.iter(|| {
+                for &id in &changed {
+                    dag.set_local(id, pose);
+                }
+                dag.update_world();
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("full", n), &n, |b, _| {
+            b.iter(|| {
+                for &id in &changed {
+                    dag.set_local(id, pose);
+                }
+                dag.update_world_full();
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
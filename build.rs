@@ -0,0 +1,6 @@
+fn main() {
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/axisviz.proto").expect("failed to compile proto/axisviz.proto");
+    #[cfg(feature = "proto")]
+    prost_build::compile_protos(&["proto/transform.proto"], &["proto"]).expect("failed to compile proto/transform.proto");
+}
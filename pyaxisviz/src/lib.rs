@@ -0,0 +1,125 @@
+//! Python bindings for axisviz tree files.
+//!
+//! ```python
+//! import pyaxisviz
+//!
+//! tree = pyaxisviz.FileTransformTree()
+//! tree.add_frame("base")
+//! tree.add_frame("lidar", parent="base", t=(0.5, 0.0, 0.2), r=(0.0, 0.0, 1.57))
+//! t, r = tree.relative("base", "lidar")
+//! tree.save("extrinsics.json")
+//! ```
+//!
+//! Rotations are `(roll, pitch, yaw)` in radians, as in the JSON format.
+
+use std::fs;
+use std::path::PathBuf;
+
+use axisviz::{FileNode, FileTransformTree, TransformTree, formats};
+use pyo3::exceptions::{PyIOError, PyKeyError, PyValueError};
+use pyo3::prelude::*;
+
+type Pose = ([f64; 3], [f64; 3]);
+
+#[pyclass(name = "FileTransformTree")]
+struct Tree {
+    inner: FileTransformTree,
+}
+
+impl Tree {
+    fn node(&self, name: &str) -> PyResult<&FileNode> {
+        self.inner
+            .nodes
+            .iter()
+            .find(|n| n.name == name)
+            .ok_or_else(|| PyKeyError::new_err(format!("unknown frame {}", name)))
+    }
+
+    fn node_mut(&mut self, name: &str) -> PyResult<&mut FileNode> {
+        self.inner
+            .nodes
+            .iter_mut()
+            .find(|n| n.name == name)
+            .ok_or_else(|| PyKeyError::new_err(format!("unknown frame {}", name)))
+    }
+}
+
+#[pymethods]
+impl Tree {
+    #[new]
+    fn new() -> Self {
+        Tree { inner: FileTransformTree { version: 1, nodes: vec![] } }
+    }
+
+    /// Loads any format the viewer can open, picked by file extension.
+    #[staticmethod]
+    fn load(path: PathBuf) -> PyResult<Self> {
+        formats::load(&path)
+            .map(|inner| Tree { inner })
+            .map_err(|e| PyIOError::new_err(format!("{}: {}", path.display(), e)))
+    }
+
+    #[staticmethod]
+    fn from_json(text: &str) -> PyResult<Self> {
+        serde_json::from_str(text)
+            .map(|inner| Tree { inner })
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    fn to_json(&self) -> PyResult<String> {
+        serde_json::to_string_pretty(&self.inner).map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Writes the tree as JSON.
+    fn save(&self, path: PathBuf) -> PyResult<()> {
+        fs::write(&path, self.to_json()?).map_err(|e| PyIOError::new_err(format!("{}: {}", path.display(), e)))
+    }
+
+    #[pyo3(signature = (name, parent=None, t=[0.0; 3], r=[0.0; 3]))]
+    fn add_frame(&mut self, name: String, parent: Option<String>, t: [f64; 3], r: [f64; 3]) -> PyResult<()> {
+        if self.inner.nodes.iter().any(|n| n.name == name) {
+            return Err(PyValueError::new_err(format!("frame {} already exists", name)));
+        }
+        self.inner.nodes.push(FileNode { name, parent, t, r, ..Default::default() });
+        Ok(())
+    }
+
+    fn frames(&self) -> Vec<String> {
+        self.inner.nodes.iter().map(|n| n.name.clone()).collect()
+    }
+
+    fn parent(&self, name: &str) -> PyResult<Option<String>> {
+        Ok(self.node(name)?.parent.clone())
+    }
+
+    /// `(t, r)` relative to the parent.
+    fn pose(&self, name: &str) -> PyResult<Pose> {
+        let node = self.node(name)?;
+        Ok((node.t, node.r))
+    }
+
+    fn set_pose(&mut self, name: &str, t: [f64; 3], r: [f64; 3]) -> PyResult<()> {
+        let node = self.node_mut(name)?;
+        node.t = t;
+        node.r = r;
+        Ok(())
+    }
+
+    /// `(t, r)` of `name` expressed in the frame of `reference`.
+    fn relative(&self, reference: &str, name: &str) -> PyResult<Pose> {
+        let dag = TransformTree::try_from(self.inner.clone()).map_err(|e| PyValueError::new_err(format!("{:?}", e)))?;
+        let find = |n: &str| dag.find(n).ok_or_else(|| PyKeyError::new_err(format!("unknown frame {}", n)));
+        let mut node = FileNode::default();
+        node.set_pose(dag.relative(find(reference)?, find(name)?));
+        Ok((node.t, node.r))
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.nodes.len()
+    }
+}
+
+#[pymodule]
+fn pyaxisviz(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Tree>()
+}
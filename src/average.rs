@@ -0,0 +1,142 @@
+//! Averages of repeated pose measurements, from several files or from live
+//! samples: per frame, the mean translation and the Karcher mean rotation on
+//! SO(3), the rotation minimizing the summed squared angles to the samples.
+//! The spread is the translation covariance, drawn as an ellipsoid, and the
+//! RMS angle of the samples from the mean.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use bevy::math::{DMat3, DQuat, DVec3};
+use bevy::prelude::*;
+
+use crate::{FileNode, FileTransformTree};
+
+/// Mean pose of one frame over its samples.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoseMean {
+    pub translation: DVec3,
+    pub rotation: DQuat,
+    /// Sample covariance of the translation, in the parent frame.
+    pub covariance: DMat3,
+    /// RMS angle of the samples from the mean rotation, in radians.
+    pub rotation_spread: f64,
+    pub samples: usize,
+}
+
+/// Karcher mean of unit quaternions, by averaging the samples in the tangent
+/// space of the current estimate until it stops moving.
+fn mean_rotation(rotations: &[DQuat]) -> DQuat {
+    let mut mean = rotations[0];
+    for _ in 0..32 {
+        let step = rotations.iter().map(|q| log(mean.inverse() * *q)).sum::<DVec3>() / rotations.len() as f64;
+        mean = (mean * DQuat::from_scaled_axis(step)).normalize();
+        if step.length() < 1e-12 {
+            break;
+        }
+    }
+    mean
+}
+
+/// Rotation vector of `q`, taking the shorter way round.
+fn log(q: DQuat) -> DVec3 {
+    if q.w < 0.0 { (-q).to_scaled_axis() } else { q.to_scaled_axis() }
+}
+
+pub fn mean_pose(samples: &[(DVec3, DQuat)]) -> Option<PoseMean> {
+    if samples.is_empty() {
+        return None;
+    }
+    let n = samples.len() as f64;
+    let translation = samples.iter().map(|(t, _)| *t).sum::<DVec3>() / n;
+    let rotations: Vec<DQuat> = samples.iter().map(|(_, q)| *q).collect();
+    let rotation = mean_rotation(&rotations);
+    let covariance = match samples.len() {
+        1 => DMat3::ZERO,
+        _ => {
+            let outer = |d: DVec3| DMat3::from_cols(d * d.x, d * d.y, d * d.z);
+            samples.iter().map(|(t, _)| outer(*t - translation)).fold(DMat3::ZERO, |a, b| a + b) * (1.0 / (n - 1.0))
+        }
+    };
+    let rotation_spread = (rotations.iter().map(|q| log(rotation.inverse() * *q).length_squared()).sum::<f64>() / n).sqrt();
+    Some(PoseMean { translation, rotation, covariance, rotation_spread, samples: samples.len() })
+}
+
+impl PoseMean {
+    /// Tree file entry at the mean pose, with the covariance turned into the
+    /// frame itself and the spread in its metadata.
+    pub fn file_node(&self, name: String, parent: Option<String>) -> FileNode {
+        let (rx, ry, rz) = self.rotation.to_euler(EulerRot::XYZ);
+        let r = DMat3::from_quat(self.rotation);
+        let covariance = r.transpose() * self.covariance * r;
+        let mut node = FileNode { name, parent, t: self.translation.to_array(), r: [rx, ry, rz], ..Default::default() };
+        if self.samples > 1 {
+            node.covariance = Some(covariance.transpose().to_cols_array().to_vec());
+        }
+        node.metadata.insert("samples".to_string(), self.samples.into());
+        node.metadata.insert("rotation_spread_deg".to_string(), self.rotation_spread.to_degrees().into());
+        node
+    }
+}
+
+/// Averages the local poses of same-named frames across `trees`. Frames keep
+/// the parent of the first tree that has them; frames only some trees have
+/// are averaged over those.
+pub fn average_trees(trees: &[FileTransformTree]) -> FileTransformTree {
+    let mut frames: Vec<(&FileNode, Vec<(DVec3, DQuat)>)> = vec![];
+    let mut index = BTreeMap::new();
+    for node in trees.iter().flat_map(|tree| &tree.nodes) {
+        let i = *index.entry(node.name.as_str()).or_insert_with(|| {
+            frames.push((node, vec![]));
+            frames.len() - 1
+        });
+        frames[i].1.push((DVec3::from_array(node.t), node.rotation()));
+    }
+    let nodes = frames
+        .iter()
+        .filter_map(|(node, samples)| Some(mean_pose(samples)?.file_node(node.name.clone(), node.parent.clone())))
+        .collect();
+    FileTransformTree { version: 1, nodes, ..Default::default() }
+}
+
+/// Live samples of streamed frames, averaged on request.
+#[derive(Resource, Debug, Default)]
+pub struct PoseSampler {
+    pub active: bool,
+    /// Seconds of samples kept; every sample since starting when zero.
+    pub window: f64,
+    samples: BTreeMap<String, VecDeque<(f64, DVec3, DQuat)>>,
+}
+
+impl PoseSampler {
+    /// Adds a streamed update, at app time `now`, while sampling.
+    pub fn record(&mut self, node: &FileNode, now: f64) {
+        if !self.active {
+            return;
+        }
+        let samples = self.samples.entry(node.name.clone()).or_default();
+        samples.push_back((now, DVec3::from_array(node.t), node.rotation()));
+        while self.window > 0.0 && samples.front().is_some_and(|(t, _, _)| now - t > self.window) {
+            samples.pop_front();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Frames sampled and samples kept.
+    pub fn count(&self) -> (usize, usize) {
+        (self.samples.len(), self.samples.values().map(VecDeque::len).sum())
+    }
+
+    /// Mean pose of every sampled frame, by name.
+    pub fn means(&self) -> Vec<(String, PoseMean)> {
+        self.samples
+            .iter()
+            .filter_map(|(name, samples)| {
+                let samples: Vec<(DVec3, DQuat)> = samples.iter().map(|(_, t, q)| (*t, *q)).collect();
+                Some((name.clone(), mean_pose(&samples)?))
+            })
+            .collect()
+    }
+}
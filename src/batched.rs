@@ -0,0 +1,142 @@
+//! Axis triads and parent links for large trees. Above a node count, every
+//! X axis, Y axis, Z axis and link is packed into one line-list mesh per
+//! color, so the whole tree takes four draw calls instead of four gizmo lines
+//! per frame.
+
+use bevy::asset::RenderAssetUsages;
+use bevy::mesh::PrimitiveTopology;
+use bevy::prelude::*;
+
+use crate::TransformTree;
+use crate::explode::FrameOffsets;
+use crate::style::Style;
+
+#[derive(Resource, Debug)]
+pub struct AxisBatching {
+    /// Trees with more nodes than this are drawn batched.
+    pub threshold: usize,
+}
+
+impl Default for AxisBatching {
+    fn default() -> Self {
+        AxisBatching { threshold: 2000 }
+    }
+}
+
+impl AxisBatching {
+    pub fn active(&self, dag: &TransformTree) -> bool {
+        dag.nodes.len() > self.threshold
+    }
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AxisBatch {
+    X,
+    Y,
+    Z,
+    Link,
+}
+
+impl AxisBatch {
+    const ALL: [AxisBatch; 4] = [AxisBatch::X, AxisBatch::Y, AxisBatch::Z, AxisBatch::Link];
+
+    fn color(self, style: &Style) -> Color {
+        let [x, y, z] = style.axis_colors();
+        match self {
+            AxisBatch::X => x,
+            AxisBatch::Y => y,
+            AxisBatch::Z => z,
+            // Vertex colors carry the link color.
+            AxisBatch::Link => Color::WHITE,
+        }
+    }
+
+    /// Line list of every visible node. Links carry per-vertex colors, since
+    /// the link style may color them by depth or subtree.
+    fn mesh(self, dag: &TransformTree, style: &Style, offsets: &FrameOffsets) -> Mesh {
+        let size = style.axis_scale;
+        let mut positions = Vec::with_capacity(dag.nodes.len() * 2);
+        let mut colors = vec![];
+        for (id, node) in dag.nodes.iter().enumerate().filter(|(_, n)| n.visible()) {
+            let o = offsets.position(dag, id);
+            let end = match self {
+                AxisBatch::X => o + node.world.rotation * Vec3::X * size,
+                AxisBatch::Y => o + node.world.rotation * Vec3::Y * size,
+                AxisBatch::Z => o + node.world.rotation * Vec3::Z * size,
+                AxisBatch::Link => {
+                    let Some(p) = node.parent else {
+                        continue;
+                    };
+                    let color = style.links.color(dag, id, style.link_color()).to_linear().to_f32_array();
+                    for (a, b) in style.links.segments(offsets.position(dag, p), o, size) {
+                        positions.extend([a.to_array(), b.to_array()]);
+                        colors.extend([color, color]);
+                    }
+                    continue;
+                }
+            };
+            positions.push(o.to_array());
+            positions.push(end.to_array());
+        }
+        let mesh = Mesh::new(PrimitiveTopology::LineList, RenderAssetUsages::default())
+            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        match self {
+            AxisBatch::Link => mesh.with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors),
+            _ => mesh,
+        }
+    }
+}
+
+fn material(color: Color) -> StandardMaterial {
+    StandardMaterial {
+        base_color: color,
+        unlit: true,
+        alpha_mode: if color.alpha() < 1.0 { AlphaMode::Blend } else { AlphaMode::Opaque },
+        ..default()
+    }
+}
+
+/// Spawns, rebuilds or removes the batched meshes as the tree and style change.
+pub fn sync_batches(
+    mut commands: Commands,
+    dag: Res<TransformTree>,
+    style: Res<Style>,
+    batching: Res<AxisBatching>,
+    offsets: Res<FrameOffsets>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    // The section view swaps the material while clipping.
+    batch_q: Query<(Entity, &AxisBatch, &Mesh3d, Option<&MeshMaterial3d<StandardMaterial>>)>,
+) {
+    if !batching.active(&dag) {
+        for (entity, ..) in &batch_q {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+    if batch_q.is_empty() {
+        for batch in AxisBatch::ALL {
+            commands.spawn((
+                batch,
+                Mesh3d(meshes.add(batch.mesh(&dag, &style, &offsets))),
+                MeshMaterial3d(materials.add(material(batch.color(&style)))),
+                Transform::default(),
+                Pickable::IGNORE,
+            ));
+        }
+        return;
+    }
+    if !dag.is_changed() && !style.is_changed() && !offsets.is_changed() {
+        return;
+    }
+    for (_, &batch, mesh, mat) in &batch_q {
+        if let Some(mesh) = meshes.get_mut(&mesh.0) {
+            *mesh = batch.mesh(&dag, &style, &offsets);
+        }
+        if style.is_changed()
+            && let Some(mat) = mat.and_then(|mat| materials.get_mut(&mat.0))
+        {
+            *mat = material(batch.color(&style));
+        }
+    }
+}
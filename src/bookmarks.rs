@@ -0,0 +1,91 @@
+use bevy::prelude::*;
+use bevy_egui::input::EguiWantsInput;
+use bevy_panorbit_camera::PanOrbitCamera;
+use serde::{Deserialize, Serialize};
+
+use crate::camera::MainCamera;
+
+/// A saved orbit camera pose.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub name: String,
+    pub focus: [f32; 3],
+    pub yaw: f32,
+    pub pitch: f32,
+    pub radius: f32,
+}
+
+impl Bookmark {
+    pub fn capture(name: String, camera: &PanOrbitCamera) -> Self {
+        Bookmark {
+            name,
+            focus: camera.focus.to_array(),
+            yaw: camera.yaw.unwrap_or(camera.target_yaw),
+            pitch: camera.pitch.unwrap_or(camera.target_pitch),
+            radius: camera.radius.unwrap_or(camera.target_radius),
+        }
+    }
+
+    pub fn apply(&self, camera: &mut PanOrbitCamera) {
+        camera.target_focus = Vec3::from_array(self.focus);
+        camera.target_yaw = self.yaw;
+        camera.target_pitch = self.pitch;
+        camera.target_radius = self.radius;
+    }
+}
+
+/// Saved views, persisted through the project config.
+#[derive(Resource, Debug, Default)]
+pub struct Bookmarks {
+    pub views: Vec<Bookmark>,
+}
+
+impl Bookmarks {
+    /// Adds a bookmark, replacing any existing one with the same name.
+    pub fn insert(&mut self, bookmark: Bookmark) {
+        match self.views.iter_mut().find(|b| b.name == bookmark.name) {
+            Some(b) => *b = bookmark,
+            None => self.views.push(bookmark),
+        }
+    }
+}
+
+const DIGITS: [KeyCode; 9] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+/// 1-9 jump to the corresponding bookmark; Ctrl+1-9 stores the current view there.
+pub fn shortcuts(
+    keys: Res<ButtonInput<KeyCode>>,
+    egui_input: Res<EguiWantsInput>,
+    mut bookmarks: ResMut<Bookmarks>,
+    mut camera_q: Query<&mut PanOrbitCamera, With<MainCamera>>,
+) {
+    if egui_input.wants_any_keyboard_input() {
+        return;
+    }
+    let Some(slot) = DIGITS.iter().position(|&k| keys.just_pressed(k)) else {
+        return;
+    };
+    let Ok(mut camera) = camera_q.single_mut() else {
+        return;
+    };
+    if keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        let bookmark = Bookmark::capture(format!("View {}", slot + 1), &camera);
+        if slot < bookmarks.views.len() {
+            bookmarks.views[slot] = bookmark;
+        } else {
+            bookmarks.insert(bookmark);
+        }
+    } else if let Some(bookmark) = bookmarks.views.get(slot) {
+        bookmark.apply(&mut camera);
+    }
+}
@@ -0,0 +1,200 @@
+use std::f32::consts::FRAC_PI_4;
+
+use bevy::prelude::*;
+use bevy_panorbit_camera::PanOrbitCamera;
+use clap::ValueEnum;
+
+use crate::TransformTree;
+
+/// The perspective camera labels, picking, bookmarks and focusing work with.
+/// Split view panes add more cameras next to it.
+#[derive(Component)]
+pub struct MainCamera;
+
+/// Direction the camera looks at the tree from when framing it.
+pub const VIEW_DIRECTION: Vec3 = Vec3::new(3.0, 2.0, 3.0);
+
+/// Center and radius of a sphere containing every frame origin, padded by the
+/// axis length so the triads at the edge stay on screen.
+pub fn bounding_sphere(dag: &TransformTree) -> Option<(Vec3, f32)> {
+    let mut points = dag.nodes.iter().map(|n| n.world.translation.to_vec3());
+    let first = points.next()?;
+    let (min, max) = points.fold((first, first), |(min, max), p| (min.min(p), max.max(p)));
+    let center = (min + max) * 0.5;
+    let radius = dag
+        .nodes
+        .iter()
+        .map(|n| n.world.translation.to_vec3().distance(center))
+        .fold(0.0, f32::max);
+    Some((center, radius + 0.2))
+}
+
+/// Focus point and orbit radius that fit the whole tree in view.
+pub fn framing(dag: &TransformTree) -> (Vec3, f32) {
+    match bounding_sphere(dag) {
+        // Default perspective fov is 45 degrees; leave a little margin around the sphere.
+        Some((center, radius)) => (center, 1.2 * radius / (FRAC_PI_4 / 2.0).sin()),
+        None => (Vec3::ZERO, VIEW_DIRECTION.length()),
+    }
+}
+
+/// Home frames the whole tree.
+pub fn frame_all(keys: Res<ButtonInput<KeyCode>>, dag: Res<TransformTree>, mut focus: ResMut<CameraFocus>) {
+    if !keys.just_pressed(KeyCode::Home) {
+        return;
+    }
+    let (center, radius) = framing(&dag);
+    focus.focus_on(center, Some(radius));
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Easing {
+    Linear,
+    /// Smoothstep: gentle start and stop
+    Smooth,
+    CubicInOut,
+    /// Fast start, slow approach
+    QuadOut,
+}
+
+impl Easing {
+    pub fn ease(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::Smooth => t * t * (3.0 - 2.0 * t),
+            Easing::CubicInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Easing::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Tween {
+    from: (Vec3, f32),
+    to: (Vec3, f32),
+    elapsed: f32,
+}
+
+/// Animated focus and distance changes of the orbit camera. Callers request a
+/// new focus; `animate_focus` eases the camera there over `duration` seconds.
+#[derive(Resource, Debug)]
+pub struct CameraFocus {
+    pub duration: f32,
+    pub easing: Easing,
+    pending: Option<(Vec3, Option<f32>)>,
+    tween: Option<Tween>,
+}
+
+impl CameraFocus {
+    pub fn new(duration: f32, easing: Easing) -> Self {
+        CameraFocus { duration, easing, pending: None, tween: None }
+    }
+
+    /// Moves the focus to `focus`, and the orbit distance to `radius` if given.
+    pub fn focus_on(&mut self, focus: Vec3, radius: Option<f32>) {
+        self.pending = Some((focus, radius));
+    }
+}
+
+impl Default for CameraFocus {
+    fn default() -> Self {
+        CameraFocus::new(0.5, Easing::Smooth)
+    }
+}
+
+pub fn animate_focus(time: Res<Time>, mut focus: ResMut<CameraFocus>, mut camera_q: Query<&mut PanOrbitCamera, With<MainCamera>>) {
+    let Ok(mut camera) = camera_q.single_mut() else {
+        return;
+    };
+    let current = (camera.focus, camera.radius.unwrap_or(camera.target_radius));
+    if let Some((to, radius)) = focus.pending.take() {
+        focus.tween = Some(Tween { from: current, to: (to, radius.unwrap_or(current.1)), elapsed: 0.0 });
+    }
+    let (duration, easing) = (focus.duration, focus.easing);
+    let Some(tween) = focus.tween.as_mut() else {
+        return;
+    };
+    tween.elapsed += time.delta_secs();
+    let t = if duration > 0.0 { tween.elapsed / duration } else { 1.0 };
+    let s = easing.ease(t);
+    let f = tween.from.0.lerp(tween.to.0, s);
+    let r = tween.from.1 + (tween.to.1 - tween.from.1) * s;
+    // Drive both current and target values so the camera's own smoothing doesn't add lag.
+    camera.focus = f;
+    camera.target_focus = f;
+    camera.radius = Some(r);
+    camera.target_radius = r;
+    camera.force_update = true;
+    if t >= 1.0 {
+        focus.tween = None;
+    }
+}
+
+/// One full orbit of the camera around the focus, for presenting a layout.
+#[derive(Resource, Debug, Clone)]
+pub struct Turntable {
+    /// Seconds per revolution.
+    pub seconds: f32,
+    /// Time into the revolution, while spinning.
+    pub elapsed: Option<f32>,
+    /// Where the View panel exports the spin to.
+    pub export: String,
+    start_yaw: Option<f32>,
+}
+
+impl Default for Turntable {
+    fn default() -> Self {
+        Turntable { seconds: 10.0, elapsed: None, export: "turntable.mp4".to_string(), start_yaw: None }
+    }
+}
+
+impl Turntable {
+    /// Spins from the current yaw.
+    pub fn start(&mut self) {
+        self.elapsed = Some(0.0);
+        self.start_yaw = None;
+    }
+
+    pub fn stop(&mut self) {
+        self.elapsed = None;
+    }
+
+    pub fn spinning(&self) -> bool {
+        self.elapsed.is_some()
+    }
+
+    /// Yaw turned `elapsed` seconds in.
+    pub fn angle(&self, elapsed: f32) -> f32 {
+        std::f32::consts::TAU * (elapsed / self.seconds).clamp(0.0, 1.0)
+    }
+}
+
+pub fn advance_turntable(time: Res<Time>, mut turntable: ResMut<Turntable>) {
+    if let Some(elapsed) = turntable.elapsed.as_mut() {
+        *elapsed += time.delta_secs();
+    }
+}
+
+pub fn spin_turntable(mut turntable: ResMut<Turntable>, mut camera_q: Query<&mut PanOrbitCamera, With<MainCamera>>) {
+    let Some(elapsed) = turntable.elapsed else {
+        return;
+    };
+    let Ok(mut camera) = camera_q.single_mut() else {
+        return;
+    };
+    let start = *turntable.start_yaw.get_or_insert(camera.target_yaw);
+    let yaw = start + turntable.angle(elapsed);
+    camera.yaw = Some(yaw);
+    camera.target_yaw = yaw;
+    camera.force_update = true;
+    if elapsed >= turntable.seconds {
+        turntable.stop();
+    }
+}
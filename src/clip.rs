@@ -0,0 +1,136 @@
+//! Section view: a clipping plane that cuts away everything on the side its
+//! normal points to, so frames inside dense meshes and clouds can be seen.
+//! Frames beyond the plane lose their axes and labels; meshes are cut per
+//! pixel by swapping their material for a copy that discards the far side.
+
+use std::collections::HashMap;
+
+use bevy::asset::embedded_asset;
+use bevy::pbr::{ExtendedMaterial, MaterialExtension};
+use bevy::prelude::*;
+use bevy::render::render_resource::AsBindGroup;
+use bevy::shader::ShaderRef;
+
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct ClipPlane {
+    pub enabled: bool,
+    /// A point on the plane.
+    pub point: Vec3,
+    /// Unit normal, pointing to the side that is cut away.
+    pub normal: Vec3,
+    /// Draw the plane's outline and normal.
+    pub show: bool,
+}
+
+impl Default for ClipPlane {
+    fn default() -> Self {
+        ClipPlane { enabled: false, point: Vec3::ZERO, normal: Vec3::X, show: true }
+    }
+}
+
+impl ClipPlane {
+    /// Signed distance of `p` from the plane, positive on the cut side.
+    pub fn distance(&self, p: Vec3) -> f32 {
+        (p - self.point).dot(self.normal)
+    }
+
+    /// Whether `p` is cut away.
+    pub fn clips(&self, p: Vec3) -> bool {
+        self.enabled && self.distance(p) > 0.0
+    }
+
+    /// Normal and offset as the shader takes them.
+    fn plane(&self) -> Vec4 {
+        self.normal.extend(self.point.dot(self.normal))
+    }
+}
+
+#[derive(Asset, AsBindGroup, Reflect, Debug, Clone, Default)]
+pub struct ClipExtension {
+    #[uniform(100)]
+    plane: Vec4,
+}
+
+impl MaterialExtension for ClipExtension {
+    fn fragment_shader() -> ShaderRef {
+        "embedded://axisviz/clip.wgsl".into()
+    }
+}
+
+pub type ClipMaterial = ExtendedMaterial<StandardMaterial, ClipExtension>;
+
+pub struct ClipPlugin;
+
+impl Plugin for ClipPlugin {
+    fn build(&self, app: &mut App) {
+        embedded_asset!(app, "clip.wgsl");
+        app.add_plugins(MaterialPlugin::<ClipMaterial>::default()).init_resource::<ClipPlane>();
+    }
+}
+
+/// Standard material an entity had before it was given a clipping copy.
+#[derive(Component)]
+pub struct Clipped(Handle<StandardMaterial>);
+
+/// Clipping copies of standard materials, one per original.
+#[derive(Default)]
+pub struct ClipCopies(HashMap<AssetId<StandardMaterial>, Handle<ClipMaterial>>);
+
+/// Swaps every mesh to a clipping copy of its material while the plane is
+/// enabled and back when it is not, and keeps the copies' plane current.
+/// Materials are copied when swapped, so edits to an original, such as
+/// collision highlights, show once clipping is turned off.
+pub fn sync_clip_materials(
+    mut commands: Commands,
+    clip: Res<ClipPlane>,
+    mut copies: Local<ClipCopies>,
+    standard: Res<Assets<StandardMaterial>>,
+    mut clip_materials: ResMut<Assets<ClipMaterial>>,
+    unclipped_q: Query<(Entity, &MeshMaterial3d<StandardMaterial>)>,
+    clipped_q: Query<(Entity, &Clipped)>,
+) {
+    if !clip.enabled {
+        for (entity, clipped) in &clipped_q {
+            commands
+                .entity(entity)
+                .remove::<(MeshMaterial3d<ClipMaterial>, Clipped)>()
+                .insert(MeshMaterial3d(clipped.0.clone()));
+        }
+        copies.0.clear();
+        return;
+    }
+    let plane = clip.plane();
+    for (entity, material) in &unclipped_q {
+        let Some(base) = standard.get(&material.0) else {
+            continue;
+        };
+        let copy = copies
+            .0
+            .entry(material.id())
+            .or_insert_with(|| clip_materials.add(ClipMaterial { base: base.clone(), extension: ClipExtension { plane } }))
+            .clone();
+        commands
+            .entity(entity)
+            .remove::<MeshMaterial3d<StandardMaterial>>()
+            .insert((MeshMaterial3d(copy), Clipped(material.0.clone())));
+    }
+    if clip.is_changed() {
+        for handle in copies.0.values() {
+            if let Some(mut material) = clip_materials.get_mut(handle) {
+                material.extension.plane = plane;
+            }
+        }
+    }
+}
+
+/// Outline of the plane, `size` across, and its normal.
+pub fn draw_clip_plane(clip: Res<ClipPlane>, dag: Res<crate::TransformTree>, mut gizmos: Gizmos) {
+    if !clip.enabled || !clip.show {
+        return;
+    }
+    let (_, radius) = crate::camera::bounding_sphere(&dag).unwrap_or((Vec3::ZERO, 1.0));
+    let rotation = Quat::from_rotation_arc(Vec3::Z, clip.normal);
+    let color = Color::srgb(0.9, 0.3, 0.9);
+    gizmos.rect(Isometry3d::new(clip.point, rotation), Vec2::splat(2.0 * radius), color);
+    gizmos.arrow(clip.point, clip.point + clip.normal * radius * 0.3, color);
+}
@@ -0,0 +1,285 @@
+//! Point clouds (PCD or PLY files) attached to frames and drawn in them, for
+//! checking sensor extrinsics against the rest of the tree.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow, bail};
+use bevy::asset::RenderAssetUsages;
+use bevy::mesh::PrimitiveTopology;
+use bevy::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{FrameMarkers, NodeId, TransformTree};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct FileCloud {
+    /// .pcd or .ply file, relative to the tree file.
+    pub path: String,
+    /// "r, g, b" in 0..1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<[f32; 3]>,
+}
+
+#[derive(Component)]
+pub struct PointCloud {
+    pub node: NodeId,
+}
+
+/// Reads the x, y, z of every point in a PCD or PLY file.
+pub fn load(path: &Path) -> Result<Vec<[f32; 3]>> {
+    let bytes = fs::read(path)?;
+    let points = match crate::formats::extension(path).as_str() {
+        "pcd" => parse_pcd(&bytes),
+        "ply" => parse_ply(&bytes),
+        other => bail!("unsupported point cloud format .{}", other),
+    };
+    points.with_context(|| path.display().to_string())
+}
+
+/// Splits off the header: every line up to and including the one starting with `last`.
+fn header<'a>(bytes: &'a [u8], last: &str) -> Result<(Vec<&'a str>, &'a [u8])> {
+    let mut lines = vec![];
+    let mut rest = bytes;
+    loop {
+        let end = rest.iter().position(|&b| b == b'\n').ok_or_else(|| anyhow!("header has no `{}` line", last))?;
+        let line = std::str::from_utf8(&rest[..end])?.trim();
+        rest = &rest[end + 1..];
+        lines.push(line);
+        if line.starts_with(last) {
+            return Ok((lines, rest));
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+
+    fn read(self, b: &[u8], little_endian: bool) -> f32 {
+        macro_rules! num {
+            ($t:ty) => {{
+                let a = b[..size_of::<$t>()].try_into().unwrap();
+                (if little_endian { <$t>::from_le_bytes(a) } else { <$t>::from_be_bytes(a) }) as f32
+            }};
+        }
+        match self {
+            Scalar::I8 => b[0] as i8 as f32,
+            Scalar::U8 => b[0] as f32,
+            Scalar::I16 => num!(i16),
+            Scalar::U16 => num!(u16),
+            Scalar::I32 => num!(i32),
+            Scalar::U32 => num!(u32),
+            Scalar::F32 => num!(f32),
+            Scalar::F64 => num!(f64),
+        }
+    }
+}
+
+/// Points from row-packed binary data with `stride` bytes per point and x, y, z
+/// at the given offsets.
+fn read_binary(data: &[u8], count: usize, stride: usize, xyz: [(usize, Scalar); 3], little_endian: bool) -> Result<Vec<[f32; 3]>> {
+    let size = count.checked_mul(stride).ok_or_else(|| anyhow!("{} points of {} bytes is too large", count, stride))?;
+    if stride == 0 || data.len() < size {
+        bail!("expected {} points of {} bytes, found {} bytes", count, stride, data.len());
+    }
+    Ok(data
+        .chunks_exact(stride)
+        .take(count)
+        .map(|row| xyz.map(|(offset, scalar)| scalar.read(&row[offset..], little_endian)))
+        .collect())
+}
+
+/// Points from whitespace separated text rows with x, y, z in the given columns.
+fn read_ascii(data: &[u8], count: usize, xyz: [usize; 3]) -> Result<Vec<[f32; 3]>> {
+    std::str::from_utf8(data)?
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .take(count)
+        .map(|line| {
+            let values: Vec<&str> = line.split_whitespace().collect();
+            let mut point = [0.0; 3];
+            for (p, &column) in point.iter_mut().zip(&xyz) {
+                *p = values.get(column).ok_or_else(|| anyhow!("short row {:?}", line))?.parse()?;
+            }
+            Ok(point)
+        })
+        .collect()
+}
+
+pub(crate) fn parse_pcd(bytes: &[u8]) -> Result<Vec<[f32; 3]>> {
+    let (lines, data) = header(bytes, "DATA")?;
+    let field = |key: &str| -> Vec<&str> {
+        lines
+            .iter()
+            .find_map(|l| l.strip_prefix(key).filter(|rest| rest.starts_with(' ')))
+            .map(|rest| rest.split_whitespace().collect())
+            .unwrap_or_default()
+    };
+    let fields = field("FIELDS");
+    let sizes: Vec<usize> = field("SIZE").iter().map(|s| s.parse()).collect::<Result<_, _>>()?;
+    let types = field("TYPE");
+    let counts: Vec<usize> = match field("COUNT") {
+        c if c.is_empty() => vec![1; fields.len()],
+        c => c.iter().map(|s| s.parse()).collect::<Result<_, _>>()?,
+    };
+    let points: usize = field("POINTS").first().ok_or_else(|| anyhow!("missing POINTS"))?.parse()?;
+    if sizes.len() != fields.len() || types.len() != fields.len() || counts.len() != fields.len() {
+        bail!("FIELDS, SIZE, TYPE and COUNT disagree");
+    }
+    let column = |name: &str| fields.iter().position(|f| *f == name).ok_or_else(|| anyhow!("no {} field", name));
+    let xyz = [column("x")?, column("y")?, column("z")?];
+
+    match field("DATA").first().copied() {
+        Some("ascii") => {
+            // Fields with COUNT > 1 take several columns.
+            let start = |i: usize| counts[..i].iter().sum::<usize>();
+            read_ascii(data, points, xyz.map(start))
+        }
+        Some("binary") => {
+            let offset = |i: usize| (0..i).map(|j| sizes[j] * counts[j]).sum::<usize>();
+            let scalar = |i: usize| match (types[i], sizes[i]) {
+                ("F", 4) => Ok(Scalar::F32),
+                ("F", 8) => Ok(Scalar::F64),
+                ("I", 1) => Ok(Scalar::I8),
+                ("I", 2) => Ok(Scalar::I16),
+                ("I", 4) => Ok(Scalar::I32),
+                ("U", 1) => Ok(Scalar::U8),
+                ("U", 2) => Ok(Scalar::U16),
+                ("U", 4) => Ok(Scalar::U32),
+                (t, s) => Err(anyhow!("unsupported field type {}{}", t, s)),
+            };
+            let stride = offset(fields.len());
+            let layout = [(offset(xyz[0]), scalar(xyz[0])?), (offset(xyz[1]), scalar(xyz[1])?), (offset(xyz[2]), scalar(xyz[2])?)];
+            read_binary(data, points, stride, layout, true)
+        }
+        Some(other) => bail!("unsupported PCD data encoding {}", other),
+        None => bail!("missing DATA encoding"),
+    }
+}
+
+pub(crate) fn parse_ply(bytes: &[u8]) -> Result<Vec<[f32; 3]>> {
+    let (lines, data) = header(bytes, "end_header")?;
+    if lines.first() != Some(&"ply") {
+        bail!("not a PLY file");
+    }
+    let mut format = None;
+    let mut count = None;
+    let mut properties: Vec<(String, Scalar)> = vec![];
+    let mut in_vertex = false;
+    for line in &lines {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["format", f, ..] => format = Some(*f),
+            ["element", "vertex", n] => {
+                if count.is_some() || !properties.is_empty() {
+                    bail!("the vertex element must come first");
+                }
+                count = Some(n.parse::<usize>()?);
+                in_vertex = true;
+            }
+            ["element", ..] => in_vertex = false,
+            ["property", "list", ..] if in_vertex => bail!("list properties on vertices are not supported"),
+            ["property", ty, name] if in_vertex => {
+                let scalar = match *ty {
+                    "char" | "int8" => Scalar::I8,
+                    "uchar" | "uint8" => Scalar::U8,
+                    "short" | "int16" => Scalar::I16,
+                    "ushort" | "uint16" => Scalar::U16,
+                    "int" | "int32" => Scalar::I32,
+                    "uint" | "uint32" => Scalar::U32,
+                    "float" | "float32" => Scalar::F32,
+                    "double" | "float64" => Scalar::F64,
+                    other => bail!("unsupported property type {}", other),
+                };
+                properties.push((name.to_string(), scalar));
+            }
+            _ => {}
+        }
+    }
+    let count = count.ok_or_else(|| anyhow!("no vertex element"))?;
+    let column = |name: &str| properties.iter().position(|(p, _)| p == name).ok_or_else(|| anyhow!("no {} property", name));
+    let xyz = [column("x")?, column("y")?, column("z")?];
+    let offset = |i: usize| properties[..i].iter().map(|(_, s)| s.size()).sum::<usize>();
+    let layout = xyz.map(|i| (offset(i), properties[i].1));
+    let stride = offset(properties.len());
+    match format {
+        Some("ascii") => read_ascii(data, count, xyz),
+        Some("binary_little_endian") => read_binary(data, count, stride, layout, true),
+        Some("binary_big_endian") => read_binary(data, count, stride, layout, false),
+        _ => bail!("unsupported PLY format"),
+    }
+}
+
+/// Loads each frame's cloud once and draws it as points parented to the frame.
+pub fn sync_clouds(
+    mut commands: Commands,
+    dag: Res<TransformTree>,
+    markers: Res<FrameMarkers>,
+    mut spawned: Local<HashMap<NodeId, Entity>>,
+    mut cloud_q: Query<(&PointCloud, &mut Visibility)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !dag.is_changed() {
+        return;
+    }
+    for (id, node) in dag.nodes.iter().enumerate() {
+        let (Some(cloud), false) = (&node.cloud, spawned.contains_key(&id)) else {
+            continue;
+        };
+        let Some(frame) = markers.entity(id) else {
+            continue;
+        };
+        let points = match load(Path::new(&cloud.path)) {
+            Ok(points) => points,
+            Err(e) => {
+                eprintln!("{}: {:#}", node.name, e);
+                // Don't retry on every change.
+                spawned.insert(id, Entity::PLACEHOLDER);
+                continue;
+            }
+        };
+        let mesh = Mesh::new(PrimitiveTopology::PointList, RenderAssetUsages::default())
+            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, points);
+        let entity = commands
+            .spawn((
+                PointCloud { node: id },
+                Mesh3d(meshes.add(mesh)),
+                MeshMaterial3d(materials.add(StandardMaterial {
+                    base_color: Color::srgb_from_array(cloud.color.unwrap_or([0.8, 0.8, 0.8])),
+                    unlit: true,
+                    ..default()
+                })),
+                Transform::default(),
+                Pickable::IGNORE,
+                ChildOf(frame),
+            ))
+            .id();
+        spawned.insert(id, entity);
+    }
+    for (cloud, mut visibility) in &mut cloud_q {
+        let shown = dag.nodes[cloud.node].visible();
+        visibility.set_if_neq(if shown { Visibility::Inherited } else { Visibility::Hidden });
+    }
+}
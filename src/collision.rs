@@ -0,0 +1,224 @@
+//! Collision primitives attached to frames, drawn as translucent solids so
+//! frame placement can be checked against physical envelopes.
+
+use std::collections::{BTreeSet, HashMap};
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{FrameMarkers, NodeId, TransformTree};
+
+/// Primitive shape, sized in meters. Cylinders and capsules run along their Z axis.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "shape", rename_all = "lowercase")]
+pub enum Shape {
+    Box { size: [f64; 3] },
+    Sphere { radius: f64 },
+    Cylinder { radius: f64, length: f64 },
+    /// `length` is between the centers of the end caps.
+    Capsule { radius: f64, length: f64 },
+}
+
+/// Collision primitive of a frame, posed in it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct FileCollision {
+    #[serde(flatten)]
+    pub shape: Shape,
+    #[serde(default)]
+    pub t: [f64; 3],
+    #[serde(default)]
+    pub r: [f64; 3],
+}
+
+impl FileCollision {
+    /// Pose of the primitive in its frame.
+    pub fn pose(&self) -> Isometry3d {
+        let [tx, ty, tz] = self.t;
+        let [r, p, y] = self.r;
+        Isometry3d::new(
+            Vec3::new(tx as f32, ty as f32, tz as f32),
+            Quat::from_euler(EulerRot::XYZ, r as f32, p as f32, y as f32),
+        )
+    }
+
+    /// Half extents of the primitive along its own axes.
+    fn half_extents(&self) -> Vec3 {
+        match self.shape {
+            Shape::Box { size: [x, y, z] } => Vec3::new(x as f32, y as f32, z as f32) / 2.0,
+            Shape::Sphere { radius } => Vec3::splat(radius as f32),
+            Shape::Cylinder { radius, length } => Vec3::new(radius as f32, radius as f32, length as f32 / 2.0),
+            Shape::Capsule { radius, length } => {
+                Vec3::new(radius as f32, radius as f32, (length / 2.0 + radius) as f32)
+            }
+        }
+    }
+
+    /// World axis-aligned bounding box, as (min, max), of the primitive in a
+    /// frame at `frame`.
+    pub fn aabb(&self, frame: Isometry3d) -> (Vec3, Vec3) {
+        let pose = frame * self.pose();
+        let axes = Mat3::from_quat(pose.rotation);
+        let half = axes.abs() * self.half_extents();
+        let center = pose.translation.to_vec3();
+        (center - half, center + half)
+    }
+
+    fn mesh(&self) -> Mesh {
+        // Bevy's round primitives run along Y.
+        let y_to_z = Quat::from_rotation_x(FRAC_PI_2);
+        match self.shape {
+            Shape::Box { size: [x, y, z] } => Cuboid::new(x as f32, y as f32, z as f32).into(),
+            Shape::Sphere { radius } => Sphere::new(radius as f32).into(),
+            Shape::Cylinder { radius, length } => Mesh::from(Cylinder::new(radius as f32, length as f32)).rotated_by(y_to_z),
+            Shape::Capsule { radius, length } => Mesh::from(Capsule3d::new(radius as f32, length as f32)).rotated_by(y_to_z),
+        }
+    }
+}
+
+pub const COLLISION_COLOR: Color = Color::srgba(0.2, 0.6, 1.0, 0.25);
+pub const OVERLAP_COLOR: Color = Color::srgba(1.0, 0.1, 0.1, 0.45);
+
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct CollisionSettings {
+    pub shown: bool,
+}
+
+impl Default for CollisionSettings {
+    fn default() -> Self {
+        CollisionSettings { shown: true }
+    }
+}
+
+#[derive(Component)]
+pub struct CollisionShape {
+    pub node: NodeId,
+    pub index: usize,
+}
+
+/// Spawns each frame's primitives once, parented to the frame, and shows or
+/// hides them with the frame and the toggle.
+pub fn sync_collision(
+    mut commands: Commands,
+    dag: Res<TransformTree>,
+    settings: Res<CollisionSettings>,
+    markers: Res<FrameMarkers>,
+    mut spawned: Local<HashMap<NodeId, Vec<FileCollision>>>,
+    mut shape_q: Query<(Entity, &CollisionShape, &mut Visibility)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !dag.is_changed() && !settings.is_changed() {
+        return;
+    }
+    for (id, node) in dag.nodes.iter().enumerate() {
+        if spawned.get(&id).is_some_and(|shapes| *shapes == node.collision) {
+            continue;
+        }
+        let Some(frame) = markers.entity(id) else {
+            continue;
+        };
+        for (entity, shape, _) in &shape_q {
+            if shape.node == id {
+                commands.entity(entity).despawn();
+            }
+        }
+        for (index, collision) in node.collision.iter().enumerate() {
+            commands.spawn((
+                CollisionShape { node: id, index },
+                Mesh3d(meshes.add(collision.mesh())),
+                MeshMaterial3d(materials.add(StandardMaterial {
+                    base_color: COLLISION_COLOR,
+                    alpha_mode: AlphaMode::Blend,
+                    double_sided: true,
+                    cull_mode: None,
+                    ..default()
+                })),
+                Transform::from_isometry(collision.pose()),
+                Pickable::IGNORE,
+                ChildOf(frame),
+            ));
+        }
+        spawned.insert(id, node.collision.clone());
+    }
+    for (_, shape, mut visibility) in &mut shape_q {
+        let shown = settings.shown && dag.nodes[shape.node].visible();
+        visibility.set_if_neq(if shown { Visibility::Inherited } else { Visibility::Hidden });
+    }
+}
+
+/// Frames whose primitives overlap, found by comparing world bounding boxes.
+/// Parent and child frames are not compared, as links meeting at a joint
+/// usually touch.
+#[derive(Resource, Debug, Default)]
+pub struct Overlaps {
+    /// Overlapping frame pairs, lower id first.
+    pub pairs: BTreeSet<(NodeId, NodeId)>,
+    /// Primitives in any overlap, as (frame, index).
+    shapes: BTreeSet<(NodeId, usize)>,
+}
+
+impl Overlaps {
+    pub fn contains(&self, node: NodeId, index: usize) -> bool {
+        self.shapes.contains(&(node, index))
+    }
+}
+
+/// Sweep and prune along X over the bounding boxes of every visible frame's primitives.
+pub fn find_overlaps(dag: Res<TransformTree>, mut overlaps: ResMut<Overlaps>) {
+    if !dag.is_changed() {
+        return;
+    }
+    let mut boxes: Vec<(Vec3, Vec3, NodeId, usize)> = dag
+        .nodes
+        .iter()
+        .enumerate()
+        .filter(|(_, node)| node.visible())
+        .flat_map(|(id, node)| {
+            node.collision.iter().enumerate().map(move |(index, c)| {
+                let (min, max) = c.aabb(node.world);
+                (min, max, id, index)
+            })
+        })
+        .collect();
+    boxes.sort_by(|a, b| a.0.x.total_cmp(&b.0.x));
+
+    let mut pairs = BTreeSet::new();
+    let mut shapes = BTreeSet::new();
+    for (i, &(min_a, max_a, a, index_a)) in boxes.iter().enumerate() {
+        for &(min_b, max_b, b, index_b) in boxes[i + 1..].iter().take_while(|other| other.0.x <= max_a.x) {
+            let related = a == b || dag.nodes[a].parent == Some(b) || dag.nodes[b].parent == Some(a);
+            if related || min_a.cmpgt(max_b).any() || min_b.cmpgt(max_a).any() {
+                continue;
+            }
+            pairs.insert((a.min(b), a.max(b)));
+            shapes.insert((a, index_a));
+            shapes.insert((b, index_b));
+        }
+    }
+    if pairs != overlaps.pairs || shapes != overlaps.shapes {
+        *overlaps = Overlaps { pairs, shapes };
+    }
+}
+
+/// Tints overlapping primitives red.
+pub fn highlight_overlaps(
+    overlaps: Res<Overlaps>,
+    added: Query<(), Added<CollisionShape>>,
+    shape_q: Query<(&CollisionShape, &MeshMaterial3d<StandardMaterial>)>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !overlaps.is_changed() && added.is_empty() {
+        return;
+    }
+    for (shape, material) in &shape_q {
+        let Some(material) = materials.get_mut(&material.0) else {
+            continue;
+        };
+        let color = if overlaps.contains(shape.node, shape.index) { OVERLAP_COLOR } else { COLLISION_COLOR };
+        if material.base_color != color {
+            material.base_color = color;
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use bevy::prelude::*;
+use bevy_panorbit_camera::PanOrbitCamera;
+use serde::{Deserialize, Serialize};
+
+use crate::TransformTree;
+use crate::bookmarks::{Bookmark, Bookmarks};
+use crate::camera::MainCamera;
+use crate::gamepad::GamepadBindings;
+use crate::style::Style;
+
+pub const FILE_NAME: &str = "axisviz.toml";
+
+/// Per-project UI state kept in `axisviz.toml` next to the input file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub style: Style,
+    /// Names of frames hidden in the viewport.
+    pub hidden: Vec<String>,
+    /// Camera pose when the session was last saved.
+    pub camera: Option<Bookmark>,
+    pub bookmarks: Vec<Bookmark>,
+    pub gamepad: GamepadBindings,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(toml::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Where the config lives and the contents last written there.
+#[derive(Resource, Debug)]
+pub struct ConfigFile {
+    pub path: PathBuf,
+    pub saved: Config,
+    timer: Timer,
+}
+
+impl ConfigFile {
+    /// Config for the project containing `input`, or the working directory. A
+    /// missing or unreadable file yields the defaults; it is created on first save.
+    pub fn for_input(input: Option<&Path>) -> Self {
+        let dir = input
+            .and_then(Path::parent)
+            .filter(|d| !d.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let path = dir.join(FILE_NAME);
+        let saved = match Config::load(&path) {
+            Ok(config) => config,
+            Err(e) => {
+                if path.exists() {
+                    eprintln!("Ignoring {}: {}", path.display(), e);
+                }
+                Config::default()
+            }
+        };
+        ConfigFile { path, saved, timer: Timer::from_seconds(2.0, TimerMode::Repeating) }
+    }
+}
+
+/// Distributes the loaded config to the resources and nodes it describes.
+pub fn apply_config(
+    config: Res<ConfigFile>,
+    mut style: ResMut<Style>,
+    mut bookmarks: ResMut<Bookmarks>,
+    mut gamepad: ResMut<GamepadBindings>,
+    mut dag: ResMut<TransformTree>,
+    mut camera_q: Query<&mut PanOrbitCamera, With<MainCamera>>,
+) {
+    let saved = &config.saved;
+    *style = saved.style.clone();
+    bookmarks.views = saved.bookmarks.clone();
+    *gamepad = saved.gamepad.clone();
+    for name in &saved.hidden {
+        if let Some(id) = dag.find(name) {
+            dag.nodes[id].hidden = true;
+        }
+    }
+    if let Some(pose) = &saved.camera {
+        for mut camera in &mut camera_q {
+            pose.apply(&mut camera);
+        }
+    }
+}
+
+/// Periodically gathers the current state and rewrites the file when it changed.
+pub fn persist(
+    time: Res<Time>,
+    mut config: ResMut<ConfigFile>,
+    style: Res<Style>,
+    bookmarks: Res<Bookmarks>,
+    gamepad: Res<GamepadBindings>,
+    dag: Res<TransformTree>,
+    camera_q: Query<&PanOrbitCamera, With<MainCamera>>,
+) {
+    if !config.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let current = Config {
+        style: style.clone(),
+        hidden: dag.nodes.iter().filter(|n| n.hidden).map(|n| n.name.clone()).collect(),
+        camera: camera_q.single().ok().map(|c| Bookmark::capture("last".to_string(), c)),
+        bookmarks: bookmarks.views.clone(),
+        gamepad: gamepad.clone(),
+    };
+    if current == config.saved {
+        return;
+    }
+    match current.save(&config.path) {
+        Ok(()) => config.saved = current,
+        Err(e) => {
+            eprintln!("Failed to save {}: {}", config.path.display(), e);
+            // Don't retry every tick.
+            config.saved = current;
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! Coordinate conventions of tree sources. Frames are held in the ROS
+//! convention: right-handed, X forward, Y left, Z up. Files written by other
+//! tools are converted on import by a change of basis `S`: translations
+//! become `S t` and rotations `S R Sᵀ`, which stays a proper rotation even
+//! when `S` flips handedness, so imported trees aren't mirrored.
+
+use bevy::math::{DMat3, DQuat, DVec3};
+use bevy::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::FileTransformTree;
+use crate::euler::EulerOrder;
+use crate::joint::JointType;
+use crate::timeline::Animation;
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Convention {
+    /// Right-handed, X forward, Y left, Z up.
+    #[default]
+    Ros,
+    /// Left-handed, X right, Y up, Z forward.
+    Unity,
+    /// Left-handed, X forward, Y right, Z up. Unreal lengths are centimeters;
+    /// combine with `--units cm`.
+    Unreal,
+    /// Right-handed camera frames, X right, Y down, Z forward.
+    Opencv,
+}
+
+impl Convention {
+    /// Rows give the ROS axes in source coordinates.
+    fn basis(self) -> DMat3 {
+        let rows = match self {
+            Convention::Ros => return DMat3::IDENTITY,
+            Convention::Unity => [[0.0, 0.0, 1.0], [-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            Convention::Unreal => [[1.0, 0.0, 0.0], [0.0, -1.0, 0.0], [0.0, 0.0, 1.0]],
+            Convention::Opencv => [[0.0, 0.0, 1.0], [-1.0, 0.0, 0.0], [0.0, -1.0, 0.0]],
+        };
+        DMat3::from_cols_array_2d(&rows).transpose()
+    }
+}
+
+/// `S R Sᵀ` as intrinsic XYZ euler angles.
+fn rotate_euler(s: DMat3, rotation: DQuat) -> [f64; 3] {
+    let (x, y, z) = DQuat::from_mat3(&(s * DMat3::from_quat(rotation) * s.transpose())).to_euler(EulerRot::XYZ);
+    [x, y, z]
+}
+
+/// The ROS axis and sign each source axis maps to; every basis is a signed
+/// permutation.
+fn axis_map(s: DMat3) -> [(usize, f64); 3] {
+    std::array::from_fn(|j| {
+        let column = s.col(j);
+        let i = (0..3).find(|&i| column[i] != 0.0).unwrap();
+        (i, column[i])
+    })
+}
+
+/// `text`, negated when `sign` is.
+fn signed(text: &str, sign: f64) -> String {
+    if sign < 0.0 { format!("-({})", text) } else { text.to_string() }
+}
+
+/// Re-expresses every pose, joint axis and collision offset of `tree`, and
+/// its motion, in the basis `s`.
+fn convert(tree: &mut FileTransformTree, animation: Option<&mut Animation>, s: DMat3) {
+    // A rotation axis flips along with the handedness.
+    let det = s.determinant();
+    let map = axis_map(s);
+    for node in &mut tree.nodes {
+        node.t = (s * DVec3::from_array(node.t)).to_array();
+        match node.expressions.as_mut() {
+            // `set_param` re-evaluates expressions in the node's own order, so
+            // they are carried over rather than baked into XYZ angles:
+            // conjugating by `S` turns a rotation about a source axis into one
+            // about the ROS axis it maps to, so the order's axes are remapped
+            // and each angle takes that axis' sign.
+            Some(expressions) => {
+                let mut t: [Option<String>; 3] = Default::default();
+                for (j, text) in expressions.t.iter().enumerate() {
+                    let (i, sign) = map[j];
+                    t[i] = text.as_deref().map(|text| signed(text, sign));
+                }
+                expressions.t = t;
+                let (axes, extrinsic) = node.euler.unwrap_or_default().axes();
+                for (k, &axis) in axes.iter().enumerate() {
+                    let sign = map[axis].1 * det;
+                    node.r[k] *= sign;
+                    expressions.r[k] = expressions.r[k].as_deref().map(|text| signed(text, sign));
+                }
+                node.euler = EulerOrder::from_axes(axes.map(|axis| map[axis].0), extrinsic);
+            }
+            None => {
+                node.r = rotate_euler(s, node.rotation());
+                node.euler = None;
+            }
+        }
+        if let Some(joint) = node.joint.as_mut() {
+            let axis = s * DVec3::from_array(joint.axis);
+            joint.axis = match joint.kind {
+                JointType::Prismatic => axis,
+                _ => axis * det,
+            }
+            .to_array();
+        }
+        for collision in &mut node.collision {
+            collision.t = (s * DVec3::from_array(collision.t)).to_array();
+            collision.r = rotate_euler(s, EulerOrder::Xyz.rotation(collision.r));
+        }
+    }
+    let s32 = s.as_mat3();
+    for track in animation.into_iter().flat_map(|a| &mut a.tracks) {
+        for pose in &mut track.poses {
+            pose.translation = (s32 * pose.translation.to_vec3()).into();
+            pose.rotation = Quat::from_mat3(&(s32 * Mat3::from_quat(pose.rotation) * s32.transpose())).normalize();
+        }
+    }
+}
+
+/// Converts a loaded tree to the ROS convention. `convention` overrides the
+/// file's own `convention` field; returns the convention it was in.
+pub fn to_ros(tree: &mut FileTransformTree, animation: Option<&mut Animation>, convention: Option<Convention>) -> Convention {
+    let convention = convention.or(tree.convention).unwrap_or_default();
+    if convention != Convention::Ros {
+        convert(tree, animation, convention.basis());
+    }
+    tree.convention = None;
+    convention
+}
@@ -0,0 +1,14 @@
+//! View frustum tests so per-frame drawing can skip frames the camera can't see.
+
+use bevy::camera::primitives::{Frustum, Sphere};
+use bevy::prelude::*;
+
+/// Whether a sphere around `center` is at least partly inside the frustum.
+pub fn in_view(frustum: &Frustum, center: Vec3, radius: f32) -> bool {
+    frustum.intersects_sphere(&Sphere { center: center.into(), radius }, true)
+}
+
+/// Whether any part of the segment `a`–`b` may be inside the frustum.
+pub fn segment_in_view(frustum: &Frustum, a: Vec3, b: Vec3) -> bool {
+    in_view(frustum, a.midpoint(b), a.distance(b) * 0.5)
+}
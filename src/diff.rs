@@ -0,0 +1,289 @@
+use std::path::Path;
+
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+use serde::Serialize;
+
+use crate::TransformTree;
+use crate::camera::MainCamera;
+use crate::style::Style;
+
+/// Second tree loaded by `axisviz diff`, or the `--reference` a live tree is
+/// checked against, drawn ghosted on top of the primary tree.
+#[derive(Debug, Resource)]
+pub struct DiffTree(pub TransformTree);
+
+#[derive(Debug)]
+pub struct FrameDelta {
+    pub name: String,
+    pub translation: Vec3,
+    pub rotation_deg: f32,
+}
+
+impl FrameDelta {
+    pub fn distance(&self) -> f32 {
+        self.translation.length()
+    }
+}
+
+/// World-space deltas (b relative to a) for every frame name present in both trees.
+pub fn frame_deltas(a: &TransformTree, b: &TransformTree) -> Vec<FrameDelta> {
+    a.nodes
+        .iter()
+        .enumerate()
+        .filter_map(|(ia, na)| {
+            let ib = b.find(&na.name)?;
+            let nb = &b.nodes[ib];
+            Some(FrameDelta {
+                name: na.name.clone(),
+                translation: (b.world_position(ib) - a.world_position(ia)).as_vec3(),
+                rotation_deg: na.world.rotation.angle_between(nb.world.rotation).to_degrees(),
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FrameReport {
+    pub frame: String,
+    pub dx: f32,
+    pub dy: f32,
+    pub dz: f32,
+    /// Translation error in meters.
+    pub translation: f32,
+    pub rotation_deg: f32,
+}
+
+/// Aggregate errors over the frames both trees have.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Summary {
+    pub frames: usize,
+    pub mean_translation: f32,
+    pub max_translation: f32,
+    pub rmse_translation: f32,
+    pub mean_rotation_deg: f32,
+    pub max_rotation_deg: f32,
+    pub rmse_rotation_deg: f32,
+}
+
+/// Comparison of two trees, as `axisviz diff --report` writes it.
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub summary: Summary,
+    pub frames: Vec<FrameReport>,
+    pub only_in_first: Vec<String>,
+    pub only_in_second: Vec<String>,
+}
+
+impl Report {
+    pub fn new(a: &TransformTree, b: &TransformTree) -> Self {
+        let frames: Vec<FrameReport> = frame_deltas(a, b)
+            .into_iter()
+            .map(|d| FrameReport {
+                translation: d.distance(),
+                dx: d.translation.x,
+                dy: d.translation.y,
+                dz: d.translation.z,
+                rotation_deg: d.rotation_deg,
+                frame: d.name,
+            })
+            .collect();
+        let n = frames.len().max(1) as f32;
+        let stats = |f: fn(&FrameReport) -> f32| {
+            let values = frames.iter().map(f);
+            let mean = values.clone().sum::<f32>() / n;
+            let max = values.clone().fold(0.0, f32::max);
+            let rmse = (values.map(|v| v * v).sum::<f32>() / n).sqrt();
+            (mean, max, rmse)
+        };
+        let (mean_translation, max_translation, rmse_translation) = stats(|f| f.translation);
+        let (mean_rotation_deg, max_rotation_deg, rmse_rotation_deg) = stats(|f| f.rotation_deg);
+        let only = |x: &TransformTree, y: &TransformTree| x.nodes.iter().filter(|n| y.find(&n.name).is_none()).map(|n| n.name.clone()).collect();
+        Report {
+            summary: Summary {
+                frames: frames.len(),
+                mean_translation,
+                max_translation,
+                rmse_translation,
+                mean_rotation_deg,
+                max_rotation_deg,
+                rmse_rotation_deg,
+            },
+            frames,
+            only_in_first: only(a, b),
+            only_in_second: only(b, a),
+        }
+    }
+
+    /// CSV with one row per frame followed by `*mean*`, `*max*` and `*rmse*`
+    /// rows.
+    pub fn csv(&self) -> String {
+        let mut lines = vec!["frame,dx,dy,dz,translation,rotation_deg".to_string()];
+        for f in &self.frames {
+            let frame = if f.frame.contains([',', '"']) { format!("\"{}\"", f.frame.replace('"', "\"\"")) } else { f.frame.clone() };
+            lines.push(format!("{},{},{},{},{},{}", frame, f.dx, f.dy, f.dz, f.translation, f.rotation_deg));
+        }
+        let s = &self.summary;
+        lines.push(format!("*mean*,,,,{},{}", s.mean_translation, s.mean_rotation_deg));
+        lines.push(format!("*max*,,,,{},{}", s.max_translation, s.max_rotation_deg));
+        lines.push(format!("*rmse*,,,,{},{}", s.rmse_translation, s.rmse_rotation_deg));
+        lines.join("\n") + "\n"
+    }
+
+    /// Writes the report as CSV for a `.csv` path, JSON otherwise.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let text = match crate::formats::extension(path).as_str() {
+            "csv" => self.csv(),
+            _ => serde_json::to_string_pretty(self)? + "\n",
+        };
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+}
+
+pub fn print_deltas(a: &TransformTree, b: &TransformTree) {
+    println!("{:<24} {:>10} {:>10} {:>10} {:>10} {:>10}", "frame", "dx", "dy", "dz", "|dt|", "drot(deg)");
+    for d in frame_deltas(a, b) {
+        println!(
+            "{:<24} {:>10.5} {:>10.5} {:>10.5} {:>10.5} {:>10.4}",
+            d.name, d.translation.x, d.translation.y, d.translation.z, d.distance(), d.rotation_deg
+        );
+    }
+    for n in a.nodes.iter().filter(|n| b.find(&n.name).is_none()) {
+        println!("{:<24} only in first tree", n.name);
+    }
+    for n in b.nodes.iter().filter(|n| a.find(&n.name).is_none()) {
+        println!("{:<24} only in second tree", n.name);
+    }
+}
+
+pub fn draw_diff(dag: Res<TransformTree>, other: Res<DiffTree>, style: Res<Style>, mut gizmos: Gizmos) {
+    let size = style.axis_scale;
+    let other = &other.0;
+    // The trees may have rebased on different origins.
+    let shift = (other.origin() - dag.origin()).as_vec3();
+
+    for node in other.nodes.iter() {
+        let o = node.world.translation.to_vec3() + shift;
+        gizmos.line(o, o + node.world.rotation * Vec3::X * size, Color::srgba(1.0, 0.5, 0.5, 0.5));
+        gizmos.line(o, o + node.world.rotation * Vec3::Y * size, Color::srgba(0.5, 1.0, 0.5, 0.5));
+        gizmos.line(o, o + node.world.rotation * Vec3::Z * size, Color::srgba(0.5, 0.5, 1.0, 0.5));
+        if let Some(p) = node.parent {
+            gizmos.line(other.nodes[p].world.translation.to_vec3() + shift, o, Color::srgba(1.0, 1.0, 0.0, 0.3));
+        }
+        if let Some(id) = dag.find(&node.name) {
+            gizmos.arrow(dag.nodes[id].world.translation.to_vec3(), o, Color::srgb(1.0, 0.0, 1.0));
+        }
+    }
+}
+
+/// Error heatmap over the compared frames: a sphere colored by translation
+/// error and a ring colored and sized by rotation error, both on a scale
+/// from zero to a full-scale error.
+#[derive(Debug, Clone, PartialEq, Resource)]
+pub struct Heatmap {
+    pub enabled: bool,
+    /// Full-scale translation error in meters; the largest one when unset.
+    pub translation: Option<f32>,
+    /// Full-scale rotation error in degrees; the largest one when unset.
+    pub rotation: Option<f32>,
+}
+
+impl Default for Heatmap {
+    fn default() -> Self {
+        Heatmap { enabled: true, translation: None, rotation: None }
+    }
+}
+
+impl Heatmap {
+    /// Full-scale translation and rotation errors for `deltas`.
+    pub fn scale(&self, deltas: &[FrameDelta]) -> (f32, f32) {
+        let largest = |f: fn(&FrameDelta) -> f32| deltas.iter().map(f).fold(0.0, f32::max).max(f32::EPSILON);
+        (
+            self.translation.unwrap_or_else(|| largest(FrameDelta::distance)),
+            self.rotation.unwrap_or_else(|| largest(|d| d.rotation_deg)),
+        )
+    }
+}
+
+/// Green through yellow to red as `t` goes from 0 to 1.
+pub fn translation_color(t: f32) -> Color {
+    Color::hsl(120.0 * (1.0 - t.clamp(0.0, 1.0)), 0.9, 0.5)
+}
+
+/// Blue through purple to magenta as `t` goes from 0 to 1.
+pub fn rotation_color(t: f32) -> Color {
+    Color::hsl(220.0 + 80.0 * t.clamp(0.0, 1.0), 0.9, 0.6)
+}
+
+pub fn draw_heatmap(
+    dag: Res<TransformTree>,
+    other: Res<DiffTree>,
+    heatmap: Res<Heatmap>,
+    style: Res<Style>,
+    camera_q: Query<&GlobalTransform, With<MainCamera>>,
+    mut gizmos: Gizmos,
+) {
+    if !heatmap.enabled {
+        return;
+    }
+    let Ok(camera) = camera_q.single() else {
+        return;
+    };
+    let deltas = frame_deltas(&other.0, &dag);
+    let (translation, rotation) = heatmap.scale(&deltas);
+    let size = style.axis_scale;
+    for d in &deltas {
+        let Some(id) = dag.find(&d.name) else {
+            continue;
+        };
+        let o = dag.nodes[id].world.translation.to_vec3();
+        let (t, r) = (d.distance() / translation, d.rotation_deg / rotation);
+        gizmos.sphere(Isometry3d::from_translation(o), size * 0.2, translation_color(t));
+        // Facing the camera so the ring reads the same from every side.
+        let ring = Isometry3d::new(o, camera.rotation());
+        gizmos.circle(ring, size * (0.3 + 0.4 * r.clamp(0.0, 1.0)), rotation_color(r));
+    }
+}
+
+fn gradient_bar(ui: &mut egui::Ui, color: fn(f32) -> Color) {
+    const STEPS: usize = 24;
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(120.0, 10.0), egui::Sense::hover());
+    for i in 0..STEPS {
+        let [r, g, b, _] = color(i as f32 / (STEPS - 1) as f32).to_srgba().to_u8_array();
+        let x = rect.min.x + rect.width() * i as f32 / STEPS as f32;
+        let step = egui::Rect::from_min_size(egui::pos2(x, rect.min.y), egui::vec2(rect.width() / STEPS as f32 + 0.5, rect.height()));
+        ui.painter().rect_filled(step, 0.0, egui::Color32::from_rgb(r, g, b));
+    }
+}
+
+/// Legend for the heatmap's two scales.
+pub fn heatmap_legend(
+    mut contexts: EguiContexts,
+    dag: Res<TransformTree>,
+    other: Option<Res<DiffTree>>,
+    heatmap: Res<Heatmap>,
+) -> Result {
+    let Some(other) = other.filter(|_| heatmap.enabled) else {
+        return Ok(());
+    };
+    let (translation, rotation) = heatmap.scale(&frame_deltas(&other.0, &dag));
+    egui::Area::new(egui::Id::new("heatmap_legend"))
+        .anchor(egui::Align2::LEFT_BOTTOM, egui::vec2(8.0, -64.0))
+        .interactable(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                egui::Grid::new("heatmap_legend_grid").show(ui, |ui| {
+                    ui.label("Sphere: translation");
+                    gradient_bar(ui, translation_color);
+                    ui.monospace(format!("0 – {:.2} mm", translation * 1000.0));
+                    ui.end_row();
+                    ui.label("Ring: rotation");
+                    gradient_bar(ui, rotation_color);
+                    ui.monospace(format!("0 – {:.3}°", rotation));
+                    ui.end_row();
+                });
+            });
+        });
+    Ok(())
+}
@@ -0,0 +1,316 @@
+//! Moving frames with the mouse: dragging a frame's sphere moves it in the
+//! plane facing the camera, dragging with the right button turns it about the
+//! view axis. Holding Ctrl snaps to the configured grid and angle steps.
+
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy_panorbit_camera::PanOrbitCamera;
+
+use crate::camera::MainCamera;
+use crate::ik::IkDrag;
+use crate::joint::JointType;
+use crate::{FileTransformTreeError, FrameSphere, NodeId, TNode, TransformTree};
+
+/// Grid steps offered for translation snapping, in meters.
+pub const TRANSLATION_STEPS: [f32; 5] = [0.01, 0.05, 0.1, 0.5, 1.0];
+/// Angle steps offered for rotation snapping, in degrees.
+pub const ANGLE_STEPS: [f32; 4] = [1.0, 5.0, 15.0, 90.0];
+
+#[derive(Resource, Debug)]
+pub struct EditSettings {
+    pub enabled: bool,
+    /// Translation snap step in meters.
+    pub translation_step: f32,
+    /// Rotation snap step in degrees.
+    pub angle_step: f32,
+    active: Option<FrameDrag>,
+}
+
+impl Default for EditSettings {
+    fn default() -> Self {
+        EditSettings { enabled: false, translation_step: 0.05, angle_step: 15.0, active: None }
+    }
+}
+
+impl EditSettings {
+    /// Whether the last drag update snapped.
+    pub fn snapping(&self) -> bool {
+        self.active.as_ref().is_some_and(|drag| drag.snapped)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct FrameDrag {
+    node: NodeId,
+    rotate: bool,
+    /// From the pointer's point on the drag plane to the frame origin.
+    grab: Vec3,
+    /// World rotation of the frame when the drag started.
+    rotation: Quat,
+    /// Pointer angle around the frame on screen when the drag started.
+    angle: f32,
+    snapped: bool,
+}
+
+impl TransformTree {
+    /// Moves a frame in its parent. A joint's origin moves along, so the
+    /// joint keeps its value.
+    pub fn edit_local(&mut self, id: NodeId, local: Isometry3d) {
+        if let Some(joint) = self.nodes[id].joint.as_mut() {
+            joint.origin = local * joint.motion(joint.value).inverse();
+        }
+        self.set_local(id, local);
+    }
+}
+
+/// Frame axis picked for a constraint.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+    #[default]
+    Z,
+}
+
+impl Axis {
+    pub const ALL: [Axis; 3] = [Axis::X, Axis::Y, Axis::Z];
+
+    pub fn unit(self) -> Vec3 {
+        match self {
+            Axis::X => Vec3::X,
+            Axis::Y => Vec3::Y,
+            Axis::Z => Vec3::Z,
+        }
+    }
+}
+
+/// One-shot constraints between two frames. Each edits the local pose of
+/// `id` only; `target` stays where it is.
+impl TransformTree {
+    /// Moves a frame to a world pose, keeping its parent.
+    pub fn set_world(&mut self, id: NodeId, world: Isometry3d) {
+        let parent = self.nodes[id].parent.map_or(Isometry3d::IDENTITY, |p| self.nodes[p].world);
+        self.edit_local(id, parent.inverse() * world);
+        self.update_world();
+    }
+
+    /// Turns `id` by the smallest rotation that points its `axis` along the
+    /// `target_axis` of `target`.
+    pub fn align_axis(&mut self, id: NodeId, axis: Axis, target: NodeId, target_axis: Axis) {
+        let world = self.nodes[id].world;
+        let from = world.rotation * axis.unit();
+        let to = self.nodes[target].world.rotation * target_axis.unit();
+        let rotation = Quat::from_rotation_arc(from, to) * world.rotation;
+        self.set_world(id, Isometry3d::new(world.translation, rotation));
+    }
+
+    /// Gives `id` the world pose of `target`.
+    pub fn make_coincident(&mut self, id: NodeId, target: NodeId) {
+        self.set_world(id, self.nodes[target].world);
+    }
+
+    /// Slides `id` along `target`'s axis until its origin is `distance` from
+    /// `target`'s origin along that axis. Its offset across the axis and its
+    /// rotation are kept.
+    pub fn set_distance(&mut self, id: NodeId, target: NodeId, target_axis: Axis, distance: f32) {
+        let world = self.nodes[id].world;
+        let target_world = self.nodes[target].world;
+        let axis = target_world.rotation * target_axis.unit();
+        let offset = (world.translation - target_world.translation).to_vec3();
+        let translation = world.translation.to_vec3() + axis * (distance - offset.dot(axis));
+        self.set_world(id, Isometry3d::new(translation, world.rotation));
+    }
+}
+
+/// Plane of a reference frame to mirror across.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MirrorPlane {
+    Xy,
+    #[default]
+    Yz,
+    Xz,
+}
+
+impl MirrorPlane {
+    pub const ALL: [MirrorPlane; 3] = [MirrorPlane::Xy, MirrorPlane::Yz, MirrorPlane::Xz];
+
+    /// Diagonal of the reflection matrix.
+    fn reflection(self) -> Vec3 {
+        match self {
+            MirrorPlane::Xy => Vec3::new(1.0, 1.0, -1.0),
+            MirrorPlane::Yz => Vec3::new(-1.0, 1.0, 1.0),
+            MirrorPlane::Xz => Vec3::new(1.0, -1.0, 1.0),
+        }
+    }
+}
+
+/// `s * pose * s` for a reflection `s`: the mirror image of a pose, kept
+/// right-handed by also flipping the frame's axis across the plane. The
+/// rotation axis is a pseudovector, so it flips the opposite way to points.
+fn reflect(pose: Isometry3d, s: Vec3) -> Isometry3d {
+    let q = pose.rotation;
+    let axis = -(s * q.xyz());
+    Isometry3d::new(s * pose.translation.to_vec3(), Quat::from_xyzw(axis.x, axis.y, axis.z, q.w))
+}
+
+impl TransformTree {
+    /// Mirrors `id` and everything below it across `plane` of `reference`, or
+    /// of the world when there is none. Joint axes are mirrored too, so joint
+    /// values move the mirrored chain symmetrically.
+    pub fn mirror_subtree(&mut self, id: NodeId, reference: Option<NodeId>, plane: MirrorPlane) {
+        let s = plane.reflection();
+        let frame = reference.map_or(Isometry3d::IDENTITY, |r| self.nodes[r].world);
+        let world = frame * reflect(frame.inverse() * self.nodes[id].world, s);
+        for n in self.subtree(id) {
+            if let Some(joint) = self.nodes[n].joint.as_mut() {
+                joint.axis = match joint.kind {
+                    JointType::Prismatic => s * joint.axis,
+                    _ => -(s * joint.axis),
+                };
+            }
+            if n != id {
+                let local = reflect(self.nodes[n].local, s);
+                self.edit_local(n, local);
+            }
+        }
+        self.set_world(id, world);
+    }
+}
+
+impl TransformTree {
+    /// Copies `id` and everything below it under `parent` (a new root if
+    /// `None`), naming each copy `prefix + name + suffix`. All per-frame data
+    /// comes along except aliases, which would clash. Returns the copy of `id`.
+    pub fn duplicate_subtree(
+        &mut self,
+        id: NodeId,
+        parent: Option<NodeId>,
+        prefix: &str,
+        suffix: &str,
+    ) -> Result<NodeId, FileTransformTreeError> {
+        let subtree = self.subtree(id);
+        let names: Vec<String> = subtree.iter().map(|&n| format!("{}{}{}", prefix, self.nodes[n].name, suffix)).collect();
+        if let Some(name) = names.iter().find(|name| self.find(name).is_some()) {
+            return Err(FileTransformTreeError::Duplicate(name.clone()));
+        }
+        let mut copies = HashMap::new();
+        for (&n, name) in subtree.iter().zip(names) {
+            let copy_parent = if n == id { parent } else { self.nodes[n].parent.and_then(|p| copies.get(&p).copied()) };
+            let copy = self.add_node(&name, self.nodes[n].local_f64, copy_parent);
+            self.nodes[copy] = TNode {
+                name,
+                parent: copy_parent,
+                children: vec![],
+                dirty: true,
+                aliases: vec![],
+                collapsed: false,
+                group_summary: None,
+                ..self.nodes[n].clone()
+            };
+            copies.insert(n, copy);
+        }
+        self.update_world();
+        Ok(copies[&id])
+    }
+}
+
+/// Rounds each component of `v` to a multiple of `step`.
+pub fn snap(v: Vec3, step: f32) -> Vec3 {
+    (v / step).round() * step
+}
+
+/// Where the pointer ray meets the plane through `anchor` facing the camera,
+/// and the pointer's angle around `anchor` on screen.
+fn pointer_on_plane(camera: &Camera, camera_transform: &GlobalTransform, pointer: Vec2, anchor: Vec3) -> Option<(Vec3, f32)> {
+    let offset = camera.logical_viewport_rect().map_or(Vec2::ZERO, |rect| rect.min);
+    let ray = camera.viewport_to_world(camera_transform, pointer - offset).ok()?;
+    let distance = ray.intersect_plane(anchor, InfinitePlane3d::new(camera_transform.forward()))?;
+    let center = camera.world_to_viewport(camera_transform, anchor).ok()? + offset;
+    let d = pointer - center;
+    Some((ray.get_point(distance), d.y.atan2(d.x)))
+}
+
+pub fn on_drag_start(
+    drag: On<Pointer<DragStart>>,
+    sphere_q: Query<&FrameSphere>,
+    dag: Res<TransformTree>,
+    ik: Res<IkDrag>,
+    mut edit: ResMut<EditSettings>,
+    camera_q: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut orbit_q: Query<&mut PanOrbitCamera, With<MainCamera>>,
+) {
+    let rotate = match drag.button {
+        PointerButton::Primary => false,
+        PointerButton::Secondary => true,
+        PointerButton::Middle => return,
+    };
+    if !edit.enabled || ik.enabled {
+        return;
+    }
+    let (Ok(sphere), Ok((camera, camera_transform))) = (sphere_q.get(drag.entity), camera_q.single()) else {
+        return;
+    };
+    let world = dag.nodes[sphere.node].world;
+    let origin = world.translation.to_vec3();
+    let Some((point, angle)) = pointer_on_plane(camera, camera_transform, drag.pointer_location.position, origin) else {
+        return;
+    };
+    edit.active = Some(FrameDrag { node: sphere.node, rotate, grab: origin - point, rotation: world.rotation, angle, snapped: false });
+    for mut orbit in &mut orbit_q {
+        orbit.enabled = false;
+    }
+}
+
+pub fn on_drag(
+    drag: On<Pointer<Drag>>,
+    mut dag: ResMut<TransformTree>,
+    mut edit: ResMut<EditSettings>,
+    keys: Res<ButtonInput<KeyCode>>,
+    camera_q: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) {
+    let Some(mut active) = edit.active else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = camera_q.single() else {
+        return;
+    };
+    let id = active.node;
+    let world = dag.nodes[id].world;
+    let parent = dag.nodes[id].parent.map_or(Isometry3d::IDENTITY, |p| dag.nodes[p].world);
+    let origin = world.translation.to_vec3();
+    let Some((point, angle)) = pointer_on_plane(camera, camera_transform, drag.pointer_location.position, origin) else {
+        return;
+    };
+    active.snapped = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    let mut local = dag.nodes[id].local;
+    if active.rotate {
+        let mut swept = (angle - active.angle).rem_euclid(TAU);
+        if active.snapped {
+            swept = (swept / edit.angle_step.to_radians()).round() * edit.angle_step.to_radians();
+        }
+        let rotation = Quat::from_axis_angle(camera_transform.forward().into(), swept) * active.rotation;
+        local.rotation = parent.rotation.inverse() * rotation;
+    } else {
+        local.translation = (parent.inverse() * (point + active.grab)).into();
+        if active.snapped {
+            local.translation = snap(local.translation.into(), edit.translation_step).into();
+        }
+    }
+    dag.edit_local(id, local);
+    dag.update_world();
+    edit.active = Some(active);
+}
+
+pub fn on_drag_end(
+    _drag: On<Pointer<DragEnd>>,
+    mut edit: ResMut<EditSettings>,
+    mut orbit_q: Query<&mut PanOrbitCamera, With<MainCamera>>,
+) {
+    if edit.active.take().is_some() {
+        for mut orbit in &mut orbit_q {
+            orbit.enabled = true;
+        }
+    }
+}
@@ -0,0 +1,94 @@
+//! Euler angle orders for `r`. Tree files default to intrinsic XYZ; a file
+//! or a single node may declare another order, e.g. `"euler": "rpy"` for the
+//! fixed-axis roll, pitch, yaw most robotics tools write. The angles are
+//! always given in the order the name spells: `r[0]` about the first axis.
+
+use bevy::math::DQuat;
+use bevy::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EulerOrder {
+    /// About X, then the new Y, then the new Z.
+    #[default]
+    Xyz,
+    Xzy,
+    Yxz,
+    Yzx,
+    Zxy,
+    /// Yaw, pitch, roll about the moving axes.
+    Zyx,
+    /// About the fixed X, then fixed Y, then fixed Z.
+    ExtrinsicXyz,
+    ExtrinsicXzy,
+    ExtrinsicYxz,
+    ExtrinsicYzx,
+    ExtrinsicZxy,
+    ExtrinsicZyx,
+    /// Roll, pitch, yaw as in URDF and ROS; the same as `extrinsic_xyz`.
+    Rpy,
+}
+
+impl EulerOrder {
+    fn rot(self) -> EulerRot {
+        match self {
+            EulerOrder::Xyz => EulerRot::XYZ,
+            EulerOrder::Xzy => EulerRot::XZY,
+            EulerOrder::Yxz => EulerRot::YXZ,
+            EulerOrder::Yzx => EulerRot::YZX,
+            EulerOrder::Zxy => EulerRot::ZXY,
+            EulerOrder::Zyx => EulerRot::ZYX,
+            EulerOrder::ExtrinsicXyz | EulerOrder::Rpy => EulerRot::XYZEx,
+            EulerOrder::ExtrinsicXzy => EulerRot::XZYEx,
+            EulerOrder::ExtrinsicYxz => EulerRot::YXZEx,
+            EulerOrder::ExtrinsicYzx => EulerRot::YZXEx,
+            EulerOrder::ExtrinsicZxy => EulerRot::ZXYEx,
+            EulerOrder::ExtrinsicZyx => EulerRot::ZYXEx,
+        }
+    }
+
+    /// Axes the angles turn about, in order, and whether those are fixed.
+    pub fn axes(self) -> ([usize; 3], bool) {
+        match self {
+            EulerOrder::Xyz => ([0, 1, 2], false),
+            EulerOrder::Xzy => ([0, 2, 1], false),
+            EulerOrder::Yxz => ([1, 0, 2], false),
+            EulerOrder::Yzx => ([1, 2, 0], false),
+            EulerOrder::Zxy => ([2, 0, 1], false),
+            EulerOrder::Zyx => ([2, 1, 0], false),
+            EulerOrder::ExtrinsicXyz | EulerOrder::Rpy => ([0, 1, 2], true),
+            EulerOrder::ExtrinsicXzy => ([0, 2, 1], true),
+            EulerOrder::ExtrinsicYxz => ([1, 0, 2], true),
+            EulerOrder::ExtrinsicYzx => ([1, 2, 0], true),
+            EulerOrder::ExtrinsicZxy => ([2, 0, 1], true),
+            EulerOrder::ExtrinsicZyx => ([2, 1, 0], true),
+        }
+    }
+
+    /// Inverse of `axes`; `None` unless the three axes differ.
+    pub fn from_axes(axes: [usize; 3], extrinsic: bool) -> Option<EulerOrder> {
+        [
+            EulerOrder::Xyz,
+            EulerOrder::Xzy,
+            EulerOrder::Yxz,
+            EulerOrder::Yzx,
+            EulerOrder::Zxy,
+            EulerOrder::Zyx,
+            EulerOrder::ExtrinsicXyz,
+            EulerOrder::ExtrinsicXzy,
+            EulerOrder::ExtrinsicYxz,
+            EulerOrder::ExtrinsicYzx,
+            EulerOrder::ExtrinsicZxy,
+            EulerOrder::ExtrinsicZyx,
+        ]
+        .into_iter()
+        .find(|order| order.axes() == (axes, extrinsic))
+    }
+
+    /// Rotation of the angles `r` taken in this order.
+    pub fn rotation(self, r: [f64; 3]) -> DQuat {
+        DQuat::from_euler(self.rot(), r[0], r[1], r[2])
+    }
+}
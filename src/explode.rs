@@ -0,0 +1,102 @@
+//! Exploded view: frames pushed away from their parent along the
+//! parent-to-child direction, so clusters of nearly coincident frames such as
+//! `base_link`, `base_footprint` and `imu_link` come apart. Only the drawing
+//! moves; the tree keeps its poses, and a dashed leader joins each moved
+//! frame to where it really is.
+
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+use crate::{NodeId, TransformTree};
+
+/// Children closer to their parent than this fan out instead of following
+/// their own, unreliable, direction.
+const COINCIDENT: f32 = 1e-3;
+
+#[derive(Resource, Debug, Clone, PartialEq, Default)]
+pub struct ExplodedView {
+    /// How far each frame moves from its parent, in meters; 0 turns it off.
+    pub distance: f32,
+}
+
+/// Displacement of each frame's drawing, in the world.
+#[derive(Resource, Debug, Default)]
+pub struct FrameOffsets {
+    pub offsets: Vec<Vec3>,
+}
+
+impl FrameOffsets {
+    pub fn get(&self, id: NodeId) -> Vec3 {
+        self.offsets.get(id).copied().unwrap_or(Vec3::ZERO)
+    }
+
+    /// Where frame `id` is drawn.
+    pub fn position(&self, dag: &TransformTree, id: NodeId) -> Vec3 {
+        dag.nodes[id].world.translation.to_vec3() + self.get(id)
+    }
+}
+
+/// Direction of the `k`th coincident child: a golden-angle spiral around the
+/// parent's Z axis, so any number of them spread out evenly.
+fn fan(k: usize) -> Vec3 {
+    let angle = k as f32 * PI * (3.0 - 5f32.sqrt());
+    Vec3::new(angle.cos(), angle.sin(), 1.0).normalize()
+}
+
+/// Offsets of every frame exploded by `distance`. Each frame moves with its
+/// parent's offset and `distance` further along the parent-to-child direction.
+pub fn explode(dag: &TransformTree, distance: f32) -> Vec<Vec3> {
+    let mut offsets = vec![Vec3::ZERO; dag.nodes.len()];
+    if distance <= 0.0 {
+        return offsets;
+    }
+    let mut coincident = vec![0; dag.nodes.len()];
+    for id in dag.topological_order() {
+        let Some(p) = dag.nodes[id].parent else {
+            continue;
+        };
+        let (parent, child) = (dag.nodes[p].world, dag.nodes[id].world);
+        let d = (child.translation - parent.translation).to_vec3();
+        let dir = if d.length() > COINCIDENT {
+            d.normalize()
+        } else {
+            coincident[p] += 1;
+            parent.rotation * fan(coincident[p] - 1)
+        };
+        offsets[id] = offsets[p] + dir * distance;
+    }
+    offsets
+}
+
+pub fn update_offsets(dag: Res<TransformTree>, view: Res<ExplodedView>, mut offsets: ResMut<FrameOffsets>) {
+    if !dag.is_changed() && !view.is_changed() {
+        return;
+    }
+    let exploded = explode(&dag, view.distance);
+    if offsets.offsets != exploded {
+        offsets.offsets = exploded;
+    }
+}
+
+/// Dashed leader from each moved frame back to its true position.
+pub fn draw_leaders(dag: Res<TransformTree>, view: Res<ExplodedView>, offsets: Res<FrameOffsets>, mut gizmos: Gizmos) {
+    if view.distance <= 0.0 {
+        return;
+    }
+    let color = Color::srgba(0.7, 0.7, 0.7, 0.6);
+    let dash = view.distance * 0.1;
+    for (id, node) in dag.nodes.iter().enumerate().filter(|(_, n)| n.visible()) {
+        let from = node.world.translation.to_vec3();
+        let to = from + offsets.get(id);
+        let length = from.distance(to);
+        let mut t = 0.0;
+        while t < length {
+            gizmos.line(from.lerp(to, t / length), from.lerp(to, (t + dash).min(length) / length), color);
+            t += 2.0 * dash;
+        }
+        if length > 0.0 {
+            gizmos.sphere(Isometry3d::from_translation(from), dash * 0.5, color);
+        }
+    }
+}
@@ -0,0 +1,305 @@
+//! Arithmetic expressions in tree files. A `t` or `r` component may be a
+//! string such as `"wheelbase / 2"` using the file's top-level `params`; it is
+//! evaluated on load and again whenever a parameter changes.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Result, anyhow, bail};
+use bevy::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::euler::EulerOrder;
+use crate::{FileNode, TransformTree, formats};
+
+/// Named parameter values.
+pub type Params = BTreeMap<String, f64>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Param(String),
+    Neg(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+    Call(String, Box<Expr>),
+}
+
+const FUNCTIONS: [&str; 9] = ["sin", "cos", "tan", "asin", "acos", "atan", "sqrt", "abs", "radians"];
+
+impl Expr {
+    /// Parses `+ - * / ^`, parentheses, numbers, parameter names, `pi` and
+    /// the functions sin, cos, tan, asin, acos, atan, sqrt, abs and radians.
+    pub fn parse(text: &str) -> Result<Expr> {
+        let mut parser = Parser { tokens: tokenize(text)?, pos: 0 };
+        let expr = parser.sum()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(expr),
+            Some(token) => bail!("unexpected {:?} in {:?}", token, text),
+        }
+    }
+
+    pub fn eval(&self, params: &Params) -> Result<f64> {
+        Ok(match self {
+            Expr::Number(v) => *v,
+            Expr::Param(name) if name == "pi" => std::f64::consts::PI,
+            Expr::Param(name) => *params.get(name).ok_or_else(|| anyhow!("unknown parameter {:?}", name))?,
+            Expr::Neg(e) => -e.eval(params)?,
+            Expr::Binary(op, a, b) => {
+                let (a, b) = (a.eval(params)?, b.eval(params)?);
+                match op {
+                    '+' => a + b,
+                    '-' => a - b,
+                    '*' => a * b,
+                    '/' => a / b,
+                    _ => a.powf(b),
+                }
+            }
+            Expr::Call(f, e) => {
+                let x = e.eval(params)?;
+                match f.as_str() {
+                    "sin" => x.sin(),
+                    "cos" => x.cos(),
+                    "tan" => x.tan(),
+                    "asin" => x.asin(),
+                    "acos" => x.acos(),
+                    "atan" => x.atan(),
+                    "sqrt" => x.sqrt(),
+                    "abs" => x.abs(),
+                    _ => x.to_radians(),
+                }
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Op(char),
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = text.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut end = start;
+            while let Some(&(i, c)) = chars.peek() {
+                // Exponents like 1e-3 carry their own sign.
+                let exponent_sign = (c == '-' || c == '+') && text[..i].ends_with(['e', 'E']);
+                if !(c.is_ascii_alphanumeric() || c == '.' || exponent_sign) {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            let number = &text[start..end];
+            tokens.push(Token::Number(number.parse().map_err(|_| anyhow!("bad number {:?}", number))?));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut end = start;
+            while let Some(&(i, c)) = chars.peek() {
+                if !(c.is_alphanumeric() || c == '_') {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            tokens.push(Token::Name(text[start..end].to_string()));
+        } else if "+-*/^()".contains(c) {
+            tokens.push(Token::Op(c));
+            chars.next();
+        } else {
+            bail!("unexpected {:?} in {:?}", c, text);
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn eat(&mut self, op: char) -> bool {
+        let found = self.tokens.get(self.pos) == Some(&Token::Op(op));
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn sum(&mut self) -> Result<Expr> {
+        let mut lhs = self.product()?;
+        while let Some(op) = ['+', '-'].into_iter().find(|&op| self.eat(op)) {
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.product()?));
+        }
+        Ok(lhs)
+    }
+
+    fn product(&mut self) -> Result<Expr> {
+        let mut lhs = self.unary()?;
+        while let Some(op) = ['*', '/'].into_iter().find(|&op| self.eat(op)) {
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.unary()?));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.eat('-') {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        let base = self.atom()?;
+        if self.eat('^') {
+            return Ok(Expr::Binary('^', Box::new(base), Box::new(self.unary()?)));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Expr> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        match token {
+            Some(Token::Number(v)) => Ok(Expr::Number(v)),
+            Some(Token::Name(name)) if FUNCTIONS.contains(&name.as_str()) => {
+                if !self.eat('(') {
+                    bail!("expected ( after {}", name);
+                }
+                let arg = self.sum()?;
+                if !self.eat(')') {
+                    bail!("missing )");
+                }
+                Ok(Expr::Call(name, Box::new(arg)))
+            }
+            Some(Token::Name(name)) => Ok(Expr::Param(name)),
+            Some(Token::Op('(')) => {
+                let e = self.sum()?;
+                if !self.eat(')') {
+                    bail!("missing )");
+                }
+                Ok(e)
+            }
+            Some(token) => bail!("unexpected {:?}", token),
+            None => bail!("unexpected end of expression"),
+        }
+    }
+}
+
+/// Expressions of a node's `t` and `r` components, as moved out of the file
+/// by `substitute`; `None` where the component is a plain number.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct FileExpressions {
+    #[serde(default)]
+    pub t: [Option<String>; 3],
+    #[serde(default)]
+    pub r: [Option<String>; 3],
+}
+
+/// Parsed expressions of a node, and the values of its plain components.
+#[derive(Debug, Clone)]
+pub struct NodeExpressions {
+    pub t: [f64; 3],
+    pub r: [f64; 3],
+    /// Order of the angles in `r`.
+    pub euler: Option<EulerOrder>,
+    /// The expressions as written, for saving the tree again.
+    pub source: FileExpressions,
+    exprs: [Option<Expr>; 6],
+}
+
+impl NodeExpressions {
+    pub fn new(t: [f64; 3], r: [f64; 3], euler: Option<EulerOrder>, file: &FileExpressions) -> Result<Self> {
+        let mut exprs: [Option<Expr>; 6] = Default::default();
+        for (slot, text) in exprs.iter_mut().zip(file.t.iter().chain(&file.r)) {
+            *slot = text.as_deref().map(Expr::parse).transpose()?;
+        }
+        Ok(NodeExpressions { t, r, euler, source: file.clone(), exprs })
+    }
+
+    /// `t` and `r` with every expression evaluated.
+    pub fn eval(&self, params: &Params) -> Result<([f64; 3], [f64; 3])> {
+        let mut values = [self.t[0], self.t[1], self.t[2], self.r[0], self.r[1], self.r[2]];
+        for (value, expr) in values.iter_mut().zip(&self.exprs) {
+            if let Some(expr) = expr {
+                *value = expr.eval(params)?;
+            }
+        }
+        let [tx, ty, tz, rx, ry, rz] = values;
+        Ok(([tx, ty, tz], [rx, ry, rz]))
+    }
+}
+
+impl TransformTree {
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
+
+    /// Changes a parameter and re-poses every frame whose expressions use it.
+    pub fn set_param(&mut self, name: &str, value: f64) -> Result<()> {
+        self.params.insert(name.to_string(), value);
+        let mut poses = vec![];
+        for (id, node) in self.nodes.iter().enumerate() {
+            if let Some(expressions) = &node.expressions {
+                let (t, r) = expressions.eval(&self.params).map_err(|e| anyhow!("{}: {}", node.name, e))?;
+                let euler = expressions.euler;
+                // In full precision: a root's expressions give its world pose.
+                poses.push((id, formats::isometry(&FileNode { t, r, euler, ..Default::default() })));
+            }
+        }
+        for (id, pose) in poses {
+            // Expressions describe a joint's origin; its value still applies on top.
+            let origin = self.narrow_local(id, &pose);
+            match self.nodes[id].joint.as_mut() {
+                Some(joint) => {
+                    joint.origin = origin;
+                    let value = joint.value;
+                    self.set_joint(id, value);
+                }
+                None => self.set_local_f64(id, pose),
+            }
+        }
+        self.update_world();
+        Ok(())
+    }
+}
+
+/// Evaluates the string `t` and `r` components of a JSON tree file in place,
+/// moving the strings to each node's `expressions`, so the file then reads
+/// like one with plain numbers.
+pub fn substitute(tree: &mut Value) -> Result<()> {
+    let params: Params = match tree.get("params") {
+        Some(params) => serde_json::from_value(params.clone()).map_err(|e| anyhow!("/params: {}", e))?,
+        None => Params::new(),
+    };
+    let Some(nodes) = tree.get_mut("nodes").and_then(Value::as_array_mut) else {
+        return Ok(());
+    };
+    for (i, node) in nodes.iter_mut().enumerate() {
+        let mut expressions = FileExpressions::default();
+        let mut found = false;
+        for (key, slots) in [("t", &mut expressions.t), ("r", &mut expressions.r)] {
+            let Some(components) = node.get_mut(key).and_then(Value::as_array_mut) else {
+                continue;
+            };
+            for (j, (component, slot)) in components.iter_mut().zip(slots.iter_mut()).enumerate() {
+                let Some(text) = component.as_str() else {
+                    continue;
+                };
+                let value = Expr::parse(text)
+                    .and_then(|e| e.eval(&params))
+                    .map_err(|e| anyhow!("/nodes/{}/{}/{}: {}", i, key, j, e))?;
+                *component = value.into();
+                *slot = Some(text.to_string());
+                found = true;
+            }
+        }
+        if found {
+            node["expressions"] = serde_json::to_value(expressions)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,125 @@
+//! Fiducial tags, as in AprilTag and ArUco maps. A tag frame follows the
+//! AprilTag convention: X right and Y down as seen from the front, Z into
+//! the tag. Each is drawn as a square of its edge length with a marker
+//! pattern derived from its id; the pattern only tells tags apart and is
+//! not the family's real code, so it won't detect if printed.
+
+use std::collections::HashMap;
+
+use bevy::asset::RenderAssetUsages;
+use bevy::image::ImageSampler;
+use bevy::mesh::{Indices, PrimitiveTopology};
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{FrameMarkers, NodeId, TransformTree};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct FileTag {
+    pub id: u32,
+    /// Edge length of the black square, in meters.
+    pub size: f64,
+    /// Tag family, e.g. "tag36h11" or "DICT_4X4_50".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub family: Option<String>,
+}
+
+#[derive(Component)]
+pub struct TagQuad {
+    pub node: NodeId,
+}
+
+/// Cells across the pattern: a white quiet zone, a black border and 6x6 bits.
+const CELLS: usize = 10;
+
+/// Bits of the pattern, scrambled from the id so neighbouring ids differ.
+fn pattern(id: u32) -> u64 {
+    let mut x = (id as u64).wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+fn tag_image(id: u32) -> Image {
+    let bits = pattern(id);
+    let mut data = Vec::with_capacity(CELLS * CELLS * 4);
+    for row in 0..CELLS {
+        for col in 0..CELLS {
+            let white = match (row.min(col).min(CELLS - 1 - row).min(CELLS - 1 - col), (row, col)) {
+                (0, _) => true,
+                (1, _) => false,
+                (_, (row, col)) => (bits >> ((row - 2) * 6 + col - 2)) & 1 == 1,
+            };
+            let v = if white { 255 } else { 0 };
+            data.extend([v, v, v, 255]);
+        }
+    }
+    let mut image = Image::new(
+        Extent3d { width: CELLS as u32, height: CELLS as u32, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    // Sharp cells rather than a blur.
+    image.sampler = ImageSampler::nearest();
+    image
+}
+
+/// Square in the tag's XY plane; `size` spans the black border, so the quiet
+/// zone reaches past it.
+fn tag_quad(size: f32) -> Mesh {
+    let h = size / 2.0 * CELLS as f32 / (CELLS - 2) as f32;
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, vec![[-h, -h, 0.0], [h, -h, 0.0], [h, h, 0.0], [-h, h, 0.0]])
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 0.0, -1.0]; 4])
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]])
+        .with_inserted_indices(Indices::U32(vec![0, 1, 2, 0, 2, 3]))
+}
+
+/// Shows each tag frame's square, parented to the frame.
+pub fn sync_tags(
+    mut commands: Commands,
+    dag: Res<TransformTree>,
+    markers: Res<FrameMarkers>,
+    mut spawned: Local<HashMap<NodeId, Entity>>,
+    mut quad_q: Query<(&TagQuad, &mut Visibility)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    if !dag.is_changed() {
+        return;
+    }
+    for (id, node) in dag.nodes.iter().enumerate() {
+        let (Some(tag), false) = (&node.tag, spawned.contains_key(&id)) else {
+            continue;
+        };
+        let Some(frame) = markers.entity(id) else {
+            continue;
+        };
+        let entity = commands
+            .spawn((
+                TagQuad { node: id },
+                Mesh3d(meshes.add(tag_quad(tag.size as f32))),
+                MeshMaterial3d(materials.add(StandardMaterial {
+                    base_color_texture: Some(images.add(tag_image(tag.id))),
+                    unlit: true,
+                    double_sided: true,
+                    cull_mode: None,
+                    ..default()
+                })),
+                Transform::default(),
+                Pickable::IGNORE,
+                ChildOf(frame),
+            ))
+            .id();
+        spawned.insert(id, entity);
+    }
+    for (quad, mut visibility) in &mut quad_q {
+        let shown = dag.nodes[quad.node].visible();
+        visibility.set_if_neq(if shown { Visibility::Inherited } else { Visibility::Hidden });
+    }
+}
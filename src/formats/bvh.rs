@@ -0,0 +1,135 @@
+//! Biovision BVH import. The hierarchy becomes the tree (in its rest pose) and
+//! the MOTION section becomes one animation track per joint.
+
+use anyhow::{Result, anyhow, bail};
+use bevy::math::{DQuat, DVec3, Isometry3d};
+use na::Isometry3;
+use nalgebra as na;
+
+use super::file_node;
+use crate::FileTransformTree;
+use crate::timeline::{Animation, Track};
+
+#[derive(Clone, Copy)]
+enum Channel {
+    Position(usize),
+    Rotation(DVec3),
+}
+
+struct Joint {
+    name: String,
+    parent: Option<usize>,
+    offset: [f64; 3],
+    channels: Vec<Channel>,
+}
+
+struct Tokens<'a> {
+    iter: std::str::SplitWhitespace<'a>,
+}
+
+impl<'a> Tokens<'a> {
+    fn next(&mut self) -> Result<&'a str> {
+        self.iter.next().ok_or_else(|| anyhow!("unexpected end of BVH file"))
+    }
+
+    fn expect(&mut self, token: &str) -> Result<()> {
+        let t = self.next()?;
+        if t != token {
+            bail!("expected {:?}, found {:?}", token, t);
+        }
+        Ok(())
+    }
+
+    fn float(&mut self) -> Result<f64> {
+        let t = self.next()?;
+        t.parse().map_err(|_| anyhow!("expected a number, found {:?}", t))
+    }
+}
+
+pub fn parse(text: &str) -> Result<(FileTransformTree, Animation)> {
+    let mut tokens = Tokens { iter: text.split_whitespace() };
+
+    tokens.expect("HIERARCHY")?;
+    let mut joints = vec![];
+    let mut tok = tokens.next()?;
+    while tok == "ROOT" {
+        read_joint(&mut tokens, None, &mut joints)?;
+        tok = tokens.next()?;
+    }
+    if tok != "MOTION" {
+        bail!("expected MOTION, found {:?}", tok);
+    }
+
+    tokens.expect("Frames:")?;
+    let frames = tokens.float()? as usize;
+    tokens.expect("Frame")?;
+    tokens.expect("Time:")?;
+    let frame_time = tokens.float()?;
+
+    let nodes = joints
+        .iter()
+        .map(|j| {
+            let [x, y, z] = j.offset;
+            let pose = Isometry3::translation(x, y, z);
+            file_node(j.name.clone(), j.parent.map(|p| joints[p].name.clone()), &pose)
+        })
+        .collect();
+
+    let mut tracks: Vec<Track> = joints.iter().map(|j| Track::new(j.name.clone())).collect();
+    for frame in 0..frames {
+        let time = frame as f64 * frame_time;
+        for (joint, track) in joints.iter().zip(&mut tracks) {
+            let mut t = DVec3::from_array(joint.offset);
+            let mut q = DQuat::IDENTITY;
+            for channel in &joint.channels {
+                let v = tokens.float()?;
+                match *channel {
+                    Channel::Position(axis) => t[axis] += v,
+                    Channel::Rotation(axis) => q *= DQuat::from_axis_angle(axis, v.to_radians()),
+                }
+            }
+            track.push(time, Isometry3d::new(t.as_vec3(), q.as_quat()));
+        }
+    }
+
+    Ok((FileTransformTree { version: 1, nodes, ..Default::default() }, Animation { tracks }))
+}
+
+fn read_joint(tokens: &mut Tokens, parent: Option<usize>, joints: &mut Vec<Joint>) -> Result<()> {
+    let name = tokens.next()?.to_string();
+    tokens.expect("{")?;
+    let id = joints.len();
+    joints.push(Joint { name, parent, offset: [0.0; 3], channels: vec![] });
+    loop {
+        match tokens.next()? {
+            "OFFSET" => joints[id].offset = [tokens.float()?, tokens.float()?, tokens.float()?],
+            "CHANNELS" => {
+                let count = tokens.float()? as usize;
+                for _ in 0..count {
+                    let channel = match tokens.next()? {
+                        "Xposition" => Channel::Position(0),
+                        "Yposition" => Channel::Position(1),
+                        "Zposition" => Channel::Position(2),
+                        "Xrotation" => Channel::Rotation(DVec3::X),
+                        "Yrotation" => Channel::Rotation(DVec3::Y),
+                        "Zrotation" => Channel::Rotation(DVec3::Z),
+                        other => bail!("unknown BVH channel {:?}", other),
+                    };
+                    joints[id].channels.push(channel);
+                }
+            }
+            "JOINT" => read_joint(tokens, Some(id), joints)?,
+            "End" => {
+                tokens.expect("Site")?;
+                tokens.expect("{")?;
+                tokens.expect("OFFSET")?;
+                let offset = [tokens.float()?, tokens.float()?, tokens.float()?];
+                tokens.expect("}")?;
+                let name = format!("{}_end", joints[id].name);
+                joints.push(Joint { name, parent: Some(id), offset, channels: vec![] });
+            }
+            "}" => return Ok(()),
+            other => bail!("unexpected token {:?} in joint {}", other, joints[id].name),
+        }
+    }
+}
@@ -0,0 +1,132 @@
+//! Camera calibration YAML. Kalibr camchains become an `imu` root with the
+//! cameras placed by their `T_cam_imu`, or a chain of cameras linked by
+//! `T_cn_cnm1` when there is no IMU. OpenCV stereo calibrations (`R` and `T`,
+//! as `stereoCalibrate` writes them) become `cam0` with `cam1` below it.
+//! Both describe where points land (`p_cam = T p_imu`), so the frame poses
+//! are the inverses. Cameras are optical frames with their pinhole matrix.
+
+use anyhow::{Context, Result, anyhow, bail};
+use na::Isometry3;
+use nalgebra as na;
+use serde_yaml::Value;
+
+use super::file_node;
+use crate::intrinsics::FileCamera;
+use crate::matrix::rigid;
+use crate::{FileNode, FileTransformTree};
+
+pub fn parse(doc: &Value) -> Result<FileTransformTree> {
+    let nodes = if doc.get("cam0").is_some() {
+        kalibr(doc)?
+    } else if doc.get("R").is_some() && doc.get("T").is_some() {
+        opencv_stereo(doc)?
+    } else {
+        bail!("expected a Kalibr camchain (cam0, cam1, ...) or an OpenCV stereo calibration (R, T)");
+    };
+    Ok(FileTransformTree { version: 1, nodes, ..Default::default() })
+}
+
+fn kalibr(doc: &Value) -> Result<Vec<FileNode>> {
+    let cameras: Vec<(String, &Value)> = (0..).map_while(|i| Some((format!("cam{}", i), doc.get(format!("cam{}", i))?))).collect();
+    let with_imu = cameras.iter().any(|(_, cam)| cam.get("T_cam_imu").is_some());
+    let mut nodes = vec![];
+    if with_imu {
+        nodes.push(file_node("imu".to_string(), None, &Isometry3::identity()));
+    }
+    for (i, (name, cam)) in cameras.iter().enumerate() {
+        let (parent, key) = match (with_imu, i) {
+            (true, _) => (Some("imu".to_string()), "T_cam_imu"),
+            (false, 0) => (None, ""),
+            (false, _) => (Some(cameras[i - 1].0.clone()), "T_cn_cnm1"),
+        };
+        let pose = match parent {
+            Some(_) => {
+                let values = matrix(cam.get(key).ok_or_else(|| anyhow!("{}: missing {}", name, key))?)?;
+                transform(&values).with_context(|| format!("{}: {}", name, key))?.inverse()
+            }
+            None => Isometry3::identity(),
+        };
+        let mut node = camera_node(name, parent, &pose);
+        if cam.get("camera_model").and_then(Value::as_str).is_none_or(|m| m == "pinhole")
+            && let Some([fu, fv, pu, pv]) = cam.get("intrinsics").map(matrix).transpose()?.as_deref().and_then(|v| <[f64; 4]>::try_from(v).ok())
+        {
+            node.camera = Some(FileCamera {
+                k: Some([fu, 0.0, pu, 0.0, fv, pv, 0.0, 0.0, 1.0]),
+                resolution: cam.get("resolution").map(resolution).transpose()?,
+                ..Default::default()
+            });
+        }
+        if let Some(topic) = cam.get("rostopic").and_then(Value::as_str) {
+            node.metadata.insert("rostopic".to_string(), topic.into());
+        }
+        nodes.push(node);
+    }
+    Ok(nodes)
+}
+
+fn opencv_stereo(doc: &Value) -> Result<Vec<FileNode>> {
+    let r = matrix(&doc["R"]).context("R")?;
+    let t = matrix(&doc["T"]).context("T")?;
+    let (r, t): (&[f64; 9], &[f64; 3]) = match (r.as_slice().try_into(), t.as_slice().try_into()) {
+        (Ok(r), Ok(t)) => (r, t),
+        _ => bail!("R should be 3x3 and T 3x1"),
+    };
+    let values = [r[0], r[1], r[2], t[0], r[3], r[4], r[5], t[1], r[6], r[7], r[8], t[2]];
+    let pose = transform(&values).context("R, T")?.inverse();
+    let size = ["image_size", "imageSize"]
+        .into_iter()
+        .find_map(|key| doc.get(key))
+        .map(resolution)
+        .transpose()?
+        .or(match (doc.get("image_width").and_then(Value::as_u64), doc.get("image_height").and_then(Value::as_u64)) {
+            (Some(w), Some(h)) => Some([w as u32, h as u32]),
+            _ => None,
+        });
+    let mut nodes = vec![];
+    for (i, (name, parent, pose)) in [("cam0", None, Isometry3::identity()), ("cam1", Some("cam0".to_string()), pose)].into_iter().enumerate() {
+        let mut node = camera_node(name, parent, &pose);
+        let keys = [format!("K{}", i + 1), format!("M{}", i + 1), format!("cameraMatrix{}", i + 1)];
+        if let Some(k) = keys.iter().find_map(|key| doc.get(key)) {
+            let k: [f64; 9] = matrix(k)?.try_into().map_err(|_| anyhow!("{}: camera matrix should be 3x3", name))?;
+            node.camera = Some(FileCamera { k: Some(k), resolution: size, ..Default::default() });
+        }
+        nodes.push(node);
+    }
+    Ok(nodes)
+}
+
+fn camera_node(name: &str, parent: Option<String>, pose: &Isometry3<f64>) -> FileNode {
+    let mut node = file_node(name.to_string(), parent, pose);
+    node.optical = true;
+    node.tags.push("camera".to_string());
+    node
+}
+
+/// Rigid transform of a row-major 4x4 or 3x4 matrix, snapped to the nearest
+/// rotation: calibrations print too few digits to be exactly orthonormal.
+fn transform(values: &[f64]) -> Result<Isometry3<f64>> {
+    let (t, q) = rigid(values, true).map_err(|e| anyhow!(e))?;
+    Ok(Isometry3::from_parts(
+        na::Translation3::new(t.x, t.y, t.z),
+        na::UnitQuaternion::from_quaternion(na::Quaternion::new(q.w, q.x, q.y, q.z)),
+    ))
+}
+
+/// Numbers of a matrix: nested lists as Kalibr writes them, or an OpenCV
+/// `!!opencv-matrix` with `rows`, `cols` and row-major `data`.
+fn matrix(value: &Value) -> Result<Vec<f64>> {
+    match value {
+        Value::Tagged(tagged) => matrix(&tagged.value),
+        Value::Mapping(_) => matrix(value.get("data").ok_or_else(|| anyhow!("matrix without data"))?),
+        Value::Sequence(items) => Ok(items.iter().map(matrix).collect::<Result<Vec<_>>>()?.concat()),
+        Value::Number(n) => Ok(vec![n.as_f64().ok_or_else(|| anyhow!("{} is not a number", n))?]),
+        _ => bail!("expected a matrix"),
+    }
+}
+
+fn resolution(value: &Value) -> Result<[u32; 2]> {
+    match matrix(value)?.as_slice() {
+        &[w, h] if w > 0.0 && h > 0.0 => Ok([w as u32, h as u32]),
+        _ => bail!("expected a resolution [width, height]"),
+    }
+}
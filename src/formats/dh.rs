@@ -0,0 +1,100 @@
+//! Denavit–Hartenberg parameter tables. The file is JSON:
+//!
+//! ```json
+//! { "convention": "standard", "base": "base", "degrees": false,
+//!   "joints": [{ "name": "link1", "a": 0.0, "alpha": 1.5708, "d": 0.3, "theta": 0.0 }] }
+//! ```
+//!
+//! `convention` may be `standard`, `modified` (Craig) or `both`, which emits the
+//! two chains side by side with `_std`/`_mdh` suffixes for comparison.
+
+use anyhow::Result;
+use na::{Isometry3, Vector3};
+use nalgebra as na;
+use serde::{Deserialize, Serialize};
+
+use super::file_node;
+use crate::{FileNode, FileTransformTree};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Convention {
+    #[default]
+    Standard,
+    Modified,
+    Both,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DhJoint {
+    pub name: String,
+    pub a: f64,
+    pub alpha: f64,
+    pub d: f64,
+    pub theta: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DhTable {
+    #[serde(default)]
+    pub convention: Convention,
+    #[serde(default = "default_base")]
+    pub base: String,
+    #[serde(default)]
+    pub degrees: bool,
+    pub joints: Vec<DhJoint>,
+}
+
+fn default_base() -> String {
+    "base".to_string()
+}
+
+impl DhJoint {
+    fn angles(&self, degrees: bool) -> (f64, f64) {
+        if degrees {
+            (self.alpha.to_radians(), self.theta.to_radians())
+        } else {
+            (self.alpha, self.theta)
+        }
+    }
+
+    /// Rz(theta) Tz(d) Tx(a) Rx(alpha)
+    pub fn standard(&self, degrees: bool) -> Isometry3<f64> {
+        let (alpha, theta) = self.angles(degrees);
+        Isometry3::rotation(Vector3::z() * theta)
+            * Isometry3::translation(self.a, 0.0, self.d)
+            * Isometry3::rotation(Vector3::x() * alpha)
+    }
+
+    /// Rx(alpha) Tx(a) Rz(theta) Tz(d)
+    pub fn modified(&self, degrees: bool) -> Isometry3<f64> {
+        let (alpha, theta) = self.angles(degrees);
+        Isometry3::rotation(Vector3::x() * alpha)
+            * Isometry3::translation(self.a, 0.0, 0.0)
+            * Isometry3::rotation(Vector3::z() * theta)
+            * Isometry3::translation(0.0, 0.0, self.d)
+    }
+}
+
+pub fn parse(text: &str) -> Result<FileTransformTree> {
+    let table: DhTable = serde_json::from_str(text)?;
+    let mut nodes = vec![file_node(table.base.clone(), None, &Isometry3::identity())];
+    match table.convention {
+        Convention::Standard => chain(&table, "", DhJoint::standard, &mut nodes),
+        Convention::Modified => chain(&table, "", DhJoint::modified, &mut nodes),
+        Convention::Both => {
+            chain(&table, "_std", DhJoint::standard, &mut nodes);
+            chain(&table, "_mdh", DhJoint::modified, &mut nodes);
+        }
+    }
+    Ok(FileTransformTree { version: 1, nodes, ..Default::default() })
+}
+
+fn chain(table: &DhTable, suffix: &str, transform: fn(&DhJoint, bool) -> Isometry3<f64>, out: &mut Vec<FileNode>) {
+    let mut parent = table.base.clone();
+    for joint in &table.joints {
+        let name = format!("{}{}", joint.name, suffix);
+        out.push(file_node(name.clone(), Some(parent), &transform(joint, table.degrees)));
+        parent = name;
+    }
+}
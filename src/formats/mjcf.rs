@@ -0,0 +1,232 @@
+//! MuJoCo MJCF import. Every `<body>` under `<worldbody>` becomes a frame, along
+//! with its sites and cameras; `<frame>` elements are folded into their children.
+//! A site or camera keeps its element in the `mjcf` metadata entry, so export
+//! writes it back as one. `<include>` and default classes that place sites or
+//! cameras are refused rather than read wrong.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{Result, anyhow, bail};
+use na::{Isometry3, Matrix3, Quaternion, Rotation3, Translation3, Unit, UnitQuaternion, Vector3};
+use nalgebra as na;
+use roxmltree::Node;
+
+use super::xml::escape;
+use super::{file_node, floats, isometry, parse_floats};
+use crate::joint::JointType;
+use crate::{FileNode, FileTransformTree};
+
+/// Attributes that place an element relative to its body.
+const POSE_ATTRIBUTES: [&str; 6] = ["pos", "quat", "axisangle", "euler", "xyaxes", "zaxis"];
+
+/// Metadata entry holding the element a site or camera was read from.
+const ELEMENT: &str = "mjcf";
+
+struct Compiler {
+    degrees: bool,
+    eulerseq: String,
+}
+
+impl Compiler {
+    fn angle(&self, a: f64) -> f64 {
+        if self.degrees { a.to_radians() } else { a }
+    }
+}
+
+pub fn parse(text: &str) -> Result<FileTransformTree> {
+    let doc = roxmltree::Document::parse(text)?;
+    let root = doc.root_element();
+    if root.tag_name().name() != "mujoco" {
+        bail!("expected <mujoco> root element, found <{}>", root.tag_name().name());
+    }
+
+    let compiler = root.children().find(|c| c.has_tag_name("compiler"));
+    let compiler = Compiler {
+        degrees: compiler.and_then(|c| c.attribute("angle")).unwrap_or("degree") != "radian",
+        eulerseq: compiler.and_then(|c| c.attribute("eulerseq")).unwrap_or("xyz").to_string(),
+    };
+
+    if root.descendants().any(|n| n.has_tag_name("include")) {
+        bail!("<include> is not supported; merge the included files first");
+    }
+    for default in root.descendants().filter(|n| n.has_tag_name("default")) {
+        let placed = default
+            .children()
+            .filter(|c| c.has_tag_name("site") || c.has_tag_name("camera"))
+            .any(|c| POSE_ATTRIBUTES.iter().any(|a| c.has_attribute(*a)));
+        if placed {
+            bail!("default classes that place sites or cameras are not supported");
+        }
+    }
+
+    let world = "world".to_string();
+    let mut nodes = vec![file_node(world.clone(), None, &Isometry3::identity())];
+    let mut unnamed = 0;
+    for worldbody in root.children().filter(|c| c.has_tag_name("worldbody")) {
+        read_children(worldbody, &world, Isometry3::identity(), &compiler, &mut unnamed, &mut nodes)?;
+    }
+    Ok(FileTransformTree { version: 1, nodes, ..Default::default() })
+}
+
+/// Emits frames for the bodies, sites and cameras below `node`. `offset` carries
+/// the accumulated pose of any `<frame>` elements between `parent` and the child.
+fn read_children(
+    node: Node,
+    parent: &str,
+    offset: Isometry3<f64>,
+    compiler: &Compiler,
+    unnamed: &mut usize,
+    out: &mut Vec<FileNode>,
+) -> Result<()> {
+    for child in node.children().filter(Node::is_element) {
+        let tag = child.tag_name().name();
+        match tag {
+            "frame" => {
+                read_children(child, parent, offset * pose(child, compiler)?, compiler, unnamed, out)?;
+            }
+            "body" | "site" | "camera" => {
+                let name = match child.attribute("name") {
+                    Some(name) => name.to_string(),
+                    None => {
+                        *unnamed += 1;
+                        format!("{}{}", tag, unnamed)
+                    }
+                };
+                let mut frame = file_node(name.clone(), Some(parent.to_string()), &(offset * pose(child, compiler)?));
+                if tag == "body" {
+                    out.push(frame);
+                    read_children(child, &name, Isometry3::identity(), compiler, unnamed, out)?;
+                } else {
+                    frame.metadata.insert(ELEMENT.to_string(), tag.into());
+                    out.push(frame);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn pose(node: Node, compiler: &Compiler) -> Result<Isometry3<f64>> {
+    let [x, y, z] = match node.attribute("pos") {
+        Some(pos) => parse_floats::<3>(pos)?,
+        None => [0.0; 3],
+    };
+    Ok(Isometry3::from_parts(Translation3::new(x, y, z), orientation(node, compiler)?))
+}
+
+/// Resolves whichever of MJCF's alternative orientation attributes is present.
+fn orientation(node: Node, compiler: &Compiler) -> Result<UnitQuaternion<f64>> {
+    if let Some(quat) = node.attribute("quat") {
+        let [w, x, y, z] = parse_floats::<4>(quat)?;
+        return Ok(UnitQuaternion::from_quaternion(Quaternion::new(w, x, y, z)));
+    }
+    if let Some(axisangle) = node.attribute("axisangle") {
+        let [x, y, z, a] = parse_floats::<4>(axisangle)?;
+        return Ok(UnitQuaternion::from_axis_angle(&Unit::new_normalize(Vector3::new(x, y, z)), compiler.angle(a)));
+    }
+    if let Some(euler) = node.attribute("euler") {
+        let angles = parse_floats::<3>(euler)?;
+        let mut rot = UnitQuaternion::identity();
+        for (axis, a) in compiler.eulerseq.chars().zip(angles) {
+            let r = match axis.to_ascii_lowercase() {
+                'x' => UnitQuaternion::from_axis_angle(&Vector3::x_axis(), compiler.angle(a)),
+                'y' => UnitQuaternion::from_axis_angle(&Vector3::y_axis(), compiler.angle(a)),
+                'z' => UnitQuaternion::from_axis_angle(&Vector3::z_axis(), compiler.angle(a)),
+                _ => return Err(anyhow!("invalid eulerseq {:?}", compiler.eulerseq)),
+            };
+            // Lowercase axes rotate with the frame, uppercase axes stay fixed.
+            rot = if axis.is_ascii_lowercase() { rot * r } else { r * rot };
+        }
+        return Ok(rot);
+    }
+    if let Some(xyaxes) = node.attribute("xyaxes") {
+        let [x0, x1, x2, y0, y1, y2] = parse_floats::<6>(xyaxes)?;
+        let x = Vector3::new(x0, x1, x2).normalize();
+        let y = Vector3::new(y0, y1, y2);
+        let y = (y - x * x.dot(&y)).normalize();
+        let z = x.cross(&y);
+        let m = Matrix3::from_columns(&[x, y, z]);
+        return Ok(UnitQuaternion::from_rotation_matrix(&Rotation3::from_matrix_unchecked(m)));
+    }
+    if let Some(zaxis) = node.attribute("zaxis") {
+        let [x, y, z] = parse_floats::<3>(zaxis)?;
+        let z = Vector3::new(x, y, z);
+        return Ok(UnitQuaternion::rotation_between(&Vector3::z(), &z)
+            .unwrap_or_else(|| UnitQuaternion::from_axis_angle(&Vector3::x_axis(), std::f64::consts::PI)));
+    }
+    Ok(UnitQuaternion::identity())
+}
+
+/// Writes the frames as nested bodies, with angles in radians. A root frame
+/// named `world`, as produced by the importer, becomes the worldbody itself.
+/// Sites and cameras without child frames are written as such.
+pub fn write(tree: &FileTransformTree) -> String {
+    let names: HashSet<&str> = tree.nodes.iter().map(|n| n.name.as_str()).collect();
+    let mut children: HashMap<Option<&str>, Vec<&FileNode>> = HashMap::new();
+    for node in &tree.nodes {
+        let parent = node.parent.as_deref().filter(|p| names.contains(p));
+        children.entry(parent).or_default().push(node);
+    }
+    let mut lines = vec![
+        "<mujoco model=\"axisviz\">".to_string(),
+        "  <compiler angle=\"radian\"/>".to_string(),
+        "  <worldbody>".to_string(),
+    ];
+    for root in children.get(&None).into_iter().flatten() {
+        if root.name == "world" && isometry(root) == Isometry3::identity() {
+            for node in children.get(&Some("world")).into_iter().flatten() {
+                write_body(node, &children, 2, &mut lines);
+            }
+        } else {
+            write_body(root, &children, 2, &mut lines);
+        }
+    }
+    lines.push("  </worldbody>".to_string());
+    lines.push("</mujoco>".to_string());
+    lines.join("\n") + "\n"
+}
+
+fn write_body(node: &FileNode, children: &HashMap<Option<&str>, Vec<&FileNode>>, depth: usize, lines: &mut Vec<String>) {
+    let indent = "  ".repeat(depth);
+    let pose = isometry(node);
+    let (t, q) = (pose.translation.vector, pose.rotation);
+    let attributes = format!(
+        "name=\"{}\" pos=\"{}\" quat=\"{}\"",
+        escape(&node.name),
+        floats(&[t.x, t.y, t.z]),
+        floats(&[q.w, q.i, q.j, q.k])
+    );
+    let below = children.get(&Some(node.name.as_str()));
+    let element = node.metadata.get(ELEMENT).and_then(|e| e.as_str()).filter(|e| matches!(*e, "site" | "camera"));
+    if let (Some(element), None) = (element, below) {
+        lines.push(format!("{}<{} {}/>", indent, element, attributes));
+        return;
+    }
+    lines.push(format!("{}<body {}>", indent, attributes));
+    if let Some(joint) = &node.joint {
+        let kind = match joint.kind {
+            JointType::Revolute | JointType::Continuous => Some("hinge"),
+            JointType::Prismatic => Some("slide"),
+            JointType::Fixed => None,
+        };
+        if let Some(kind) = kind {
+            let range = match (joint.kind, joint.limits) {
+                (JointType::Continuous, _) | (_, None) => String::new(),
+                (_, Some(limits)) => format!(" limited=\"true\" range=\"{}\"", floats(&limits)),
+            };
+            lines.push(format!(
+                "{}  <joint name=\"{}_joint\" type=\"{}\" axis=\"{}\"{}/>",
+                indent,
+                escape(&node.name),
+                kind,
+                floats(&joint.axis),
+                range
+            ));
+        }
+    }
+    for child in below.into_iter().flatten() {
+        write_body(child, children, depth + 1, lines);
+    }
+    lines.push(format!("{}</body>", indent));
+}
@@ -0,0 +1,167 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Result, bail};
+use bevy::log::info_span;
+use bevy::math::{DQuat, EulerRot};
+use na::{Isometry3, Quaternion, Translation3, UnitQuaternion};
+use nalgebra as na;
+
+use crate::timeline::Animation;
+use crate::{FileNode, FileTransformTree};
+
+mod bvh;
+mod calibration;
+mod dh;
+mod mjcf;
+mod ros;
+#[cfg(feature = "rosbag")]
+pub(crate) mod rosbag;
+mod sdf;
+mod tags;
+mod urdf;
+mod usd;
+mod xml;
+
+/// Loads any supported tree description, picking the importer from the file extension.
+pub fn load(path: impl AsRef<Path>) -> Result<FileTransformTree> {
+    Ok(load_animated(path)?.0)
+}
+
+/// Like `load`, but also returns the motion for formats that carry it.
+/// rosbag2 directories and their `.db3`/`.mcap` files are binary, so they
+/// are read here rather than through `parse_animated`.
+pub fn load_animated(path: impl AsRef<Path>) -> Result<(FileTransformTree, Option<Animation>)> {
+    let path = path.as_ref();
+    let _span = info_span!("load_tree", path = %path.display()).entered();
+    if path.is_dir() || matches!(extension(path).as_str(), "db3" | "mcap") {
+        #[cfg(feature = "rosbag")]
+        return rosbag::load(path);
+        #[cfg(not(feature = "rosbag"))]
+        bail!("{}: reading rosbag2 files needs the `rosbag` feature", path.display());
+    }
+    parse_animated(&extension(path), &fs::read_to_string(path)?)
+}
+
+/// Parses file contents in the format a file `extension` ("urdf", "bvh", ...)
+/// stands for. Unknown extensions are read as JSON: a tree file, or a WPILib
+/// AprilTag field layout.
+pub fn parse(extension: &str, text: &str) -> Result<FileTransformTree> {
+    Ok(parse_animated(extension, text)?.0)
+}
+
+pub fn parse_animated(extension: &str, text: &str) -> Result<(FileTransformTree, Option<Animation>)> {
+    let tree = match extension {
+        "bvh" => {
+            let (tree, animation) = bvh::parse(text)?;
+            return Ok((tree, Some(animation)));
+        }
+        "azl" => return crate::recording::parse(text),
+        "dh" => dh::parse(text)?,
+        "sdf" | "world" => sdf::parse(text)?,
+        "urdf" => urdf::parse(text)?,
+        "yaml" | "yml" => yaml(text)?,
+        "xml" | "mjcf" => mjcf::parse(text)?,
+        _ => match serde_json::from_str::<tags::FieldLayout>(text) {
+            Ok(layout) => tags::field_layout(layout)?,
+            Err(_) => crate::schema::parse(text)?,
+        },
+    };
+    Ok((tree, None))
+}
+
+/// Calibrations and tag maps, told apart by their keys.
+fn yaml(text: &str) -> Result<FileTransformTree> {
+    // OpenCV writes a `%YAML:1.0` directive YAML parsers don't accept.
+    let text: String = text.lines().filter(|l| !l.starts_with("%YAML:")).collect::<Vec<_>>().join("\n");
+    let doc: serde_yaml::Value = serde_yaml::from_str(&text)?;
+    if tags::is_tag_map(&doc) {
+        tags::parse_yaml(&doc)
+    } else {
+        calibration::parse(&doc)
+    }
+}
+
+/// Writes `tree` to `path` in the format its extension stands for.
+pub fn save(path: impl AsRef<Path>, tree: &FileTransformTree) -> Result<()> {
+    let path = path.as_ref();
+    fs::write(path, write(&extension(path), tree)?)?;
+    Ok(())
+}
+
+/// Serializes `tree` in the format a file `extension` stands for. Unknown
+/// extensions are written as JSON.
+pub fn write(extension: &str, tree: &FileTransformTree) -> Result<String> {
+    Ok(match extension {
+        "urdf" => urdf::write(tree),
+        "sdf" | "world" => sdf::write(tree),
+        "xml" | "mjcf" => mjcf::write(tree),
+        "py" => ros::write_launch(tree),
+        "sh" => ros::write_script(tree),
+        "usda" | "usd" => usd::write(tree),
+        "azl" | "bvh" | "dh" => bail!("writing .{} files is not supported", extension),
+        _ => serde_json::to_string_pretty(tree)? + "\n",
+    })
+}
+
+/// Lowercased extension of `path`, empty if it has none.
+pub fn extension(path: &Path) -> String {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+/// Builds a `FileNode` from an isometry, converting the rotation to the file's
+/// intrinsic XYZ euler angles.
+pub(crate) fn file_node(name: String, parent: Option<String>, pose: &Isometry3<f64>) -> FileNode {
+    let t = pose.translation.vector;
+    let q = pose.rotation;
+    let (rx, ry, rz) = DQuat::from_xyzw(q.i, q.j, q.k, q.w).to_euler(EulerRot::XYZ);
+    FileNode {
+        name,
+        parent,
+        t: [t.x, t.y, t.z],
+        r: [rx, ry, rz],
+        ..Default::default()
+    }
+}
+
+/// Inverse of `file_node`.
+pub(crate) fn isometry(node: &FileNode) -> Isometry3<f64> {
+    let q = node.rotation();
+    Isometry3::from_parts(
+        Translation3::new(node.t[0], node.t[1], node.t[2]),
+        UnitQuaternion::from_quaternion(Quaternion::new(q.w, q.x, q.y, q.z)),
+    )
+}
+
+/// ROS/SDF style fixed-axis roll, pitch, yaw of a rotation; the inverse of `xyz_rpy`.
+pub(crate) fn rpy(pose: &Isometry3<f64>) -> [f64; 3] {
+    let (roll, pitch, yaw) = pose.rotation.euler_angles();
+    [roll, pitch, yaw]
+}
+
+/// Space separated values, as used in XML attributes.
+pub(crate) fn floats(values: &[f64]) -> String {
+    values.iter().map(f64::to_string).collect::<Vec<_>>().join(" ")
+}
+
+/// Isometry from a translation and ROS/SDF style fixed-axis roll, pitch, yaw.
+pub(crate) fn xyz_rpy(t: [f64; 3], rpy: [f64; 3]) -> Isometry3<f64> {
+    Isometry3::from_parts(
+        Translation3::new(t[0], t[1], t[2]),
+        UnitQuaternion::from_euler_angles(rpy[0], rpy[1], rpy[2]),
+    )
+}
+
+/// Parses a whitespace separated list of exactly `N` floats.
+pub(crate) fn parse_floats<const N: usize>(text: &str) -> Result<[f64; N]> {
+    let values = text
+        .split_whitespace()
+        .map(str::parse::<f64>)
+        .collect::<Result<Vec<_>, _>>()?;
+    match <[f64; N]>::try_from(values) {
+        Ok(values) => Ok(values),
+        Err(values) => bail!("expected {} numbers, found {} in {:?}", N, values.len(), text),
+    }
+}
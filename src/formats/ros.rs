@@ -0,0 +1,76 @@
+//! ROS 2 bringup for the static frames of a tree: every frame with a parent
+//! and no movable joint becomes a `static_transform_publisher`, either as
+//! nodes of a Python launch file (`.py`, e.g. `frames.launch.py`) or as
+//! commands of a shell script (`.sh`).
+
+use crate::joint::JointType;
+use crate::{FileNode, FileTransformTree};
+
+use super::isometry;
+
+/// Frames to publish with their static_transform_publisher arguments.
+fn publishers(tree: &FileTransformTree) -> Vec<(&FileNode, Vec<String>)> {
+    tree.nodes
+        .iter()
+        .filter(|n| n.joint.as_ref().is_none_or(|j| j.kind == JointType::Fixed))
+        .filter_map(|node| {
+            let parent = node.parent.as_ref()?;
+            let pose = isometry(node);
+            let (t, q) = (pose.translation.vector, pose.rotation);
+            let mut args = vec![];
+            for (flag, value) in [("x", t.x), ("y", t.y), ("z", t.z), ("qx", q.i), ("qy", q.j), ("qz", q.k), ("qw", q.w)] {
+                args.extend([format!("--{}", flag), value.to_string()]);
+            }
+            args.extend(["--frame-id".to_string(), parent.clone(), "--child-frame-id".to_string(), node.name.clone()]);
+            Some((node, args))
+        })
+        .collect()
+}
+
+/// Python string literal.
+fn py(text: &str) -> String {
+    format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// Shell word.
+fn sh(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+pub fn write_launch(tree: &FileTransformTree) -> String {
+    let mut lines = vec![
+        "# Static frames exported by axisviz.".to_string(),
+        "from launch import LaunchDescription".to_string(),
+        "from launch_ros.actions import Node".to_string(),
+        String::new(),
+        String::new(),
+        "def generate_launch_description():".to_string(),
+        "    return LaunchDescription([".to_string(),
+    ];
+    for (node, args) in publishers(tree) {
+        let name: String = node.name.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+        let args: Vec<String> = args.iter().map(|a| py(a)).collect();
+        lines.push("        Node(".to_string());
+        lines.push("            package='tf2_ros',".to_string());
+        lines.push("            executable='static_transform_publisher',".to_string());
+        lines.push(format!("            name={},", py(&format!("static_tf_{}", name))));
+        lines.push(format!("            arguments=[{}],", args.join(", ")));
+        lines.push("        ),".to_string());
+    }
+    lines.push("    ])".to_string());
+    lines.join("\n") + "\n"
+}
+
+pub fn write_script(tree: &FileTransformTree) -> String {
+    let mut lines = vec![
+        "#!/bin/sh".to_string(),
+        "# Static frames exported by axisviz. Ctrl+C stops them all.".to_string(),
+        "trap 'kill 0' INT TERM".to_string(),
+    ];
+    for (_, args) in publishers(tree) {
+        let args: Vec<String> = args.iter().map(|a| sh(a)).collect();
+        lines.push(format!("ros2 run tf2_ros static_transform_publisher {} &", args.join(" ")));
+    }
+    lines.push("wait".to_string());
+    lines.join("\n") + "\n"
+}
@@ -0,0 +1,177 @@
+//! rosbag2 import (feature `rosbag`): a bag directory, or a single `.db3` or
+//! `.mcap` file from one. `/tf` and `/tf_static` messages are decoded from CDR
+//! directly, so no ROS installation is needed. Each frame starts at its first
+//! recorded pose, and frames that move get an animation track.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+use bevy::math::DVec3;
+use nalgebra::Isometry3;
+
+use crate::origin;
+use crate::timeline::{Animation, Track};
+use crate::{FileNode, FileTransformTree};
+
+const TOPICS: [&str; 2] = ["/tf", "/tf_static"];
+
+/// One `geometry_msgs/TransformStamped`.
+pub(crate) struct Transform {
+    pub(crate) parent: String,
+    pub(crate) child: String,
+    pub(crate) pose: Isometry3<f64>,
+}
+
+/// Reads a bag directory or one of its storage files.
+pub fn load(path: &Path) -> Result<(FileTransformTree, Option<Animation>)> {
+    let files = if path.is_dir() {
+        let mut files: Vec<PathBuf> = fs::read_dir(path)?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| matches!(super::extension(p).as_str(), "db3" | "mcap"))
+            .collect();
+        files.sort();
+        if files.is_empty() {
+            bail!("{} has no .db3 or .mcap files", path.display());
+        }
+        files
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    // (timestamp in ns, topic, CDR payload)
+    let mut messages = vec![];
+    for file in &files {
+        let read = match super::extension(file).as_str() {
+            "mcap" => read_mcap(file, &mut messages),
+            _ => read_sqlite(file, &mut messages),
+        };
+        read.with_context(|| file.display().to_string())?;
+    }
+    messages.sort_by_key(|(stamp, ..)| *stamp);
+    let start = messages.first().map_or(0, |(stamp, ..)| *stamp);
+
+    let mut tree = FileTransformTree { version: 1, ..Default::default() };
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut tracks: HashMap<String, Track> = HashMap::new();
+    for (stamp, topic, data) in &messages {
+        let time = (stamp - start) as f64 * 1e-9;
+        for tf in decode_tf_message(data)? {
+            if !index.contains_key(&tf.child) {
+                let mut node = FileNode { name: tf.child.clone(), parent: Some(tf.parent.clone()), ..Default::default() };
+                node.set_pose_f64(&tf.pose);
+                index.insert(tf.child.clone(), tree.nodes.len());
+                tree.nodes.push(node);
+            }
+            if topic == "/tf" {
+                tracks.entry(tf.child.clone()).or_insert_with(|| Track::new(&tf.child)).push(time, origin::narrow(&tf.pose, DVec3::ZERO));
+            }
+        }
+    }
+    // Frames that only ever appear as parents are roots.
+    let parents: Vec<String> = tree.nodes.iter().filter_map(|n| n.parent.clone()).collect();
+    for parent in parents {
+        if !index.contains_key(&parent) {
+            index.insert(parent.clone(), tree.nodes.len());
+            tree.nodes.push(FileNode { name: parent, ..Default::default() });
+        }
+    }
+
+    let mut tracks: Vec<Track> = tracks.into_values().filter(|t| t.times.len() > 1).collect();
+    tracks.sort_by(|a, b| a.node.cmp(&b.node));
+    let animation = (!tracks.is_empty()).then_some(Animation { tracks });
+    Ok((tree, animation))
+}
+
+fn read_sqlite(path: &Path, messages: &mut Vec<(i64, String, Vec<u8>)>) -> Result<()> {
+    let db = rusqlite::Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut query = db.prepare(
+        "SELECT messages.timestamp, topics.name, messages.data FROM messages \
+         JOIN topics ON messages.topic_id = topics.id WHERE topics.name IN (?1, ?2)",
+    )?;
+    let rows = query.query_map(TOPICS, |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+    for row in rows {
+        messages.push(row?);
+    }
+    Ok(())
+}
+
+fn read_mcap(path: &Path, messages: &mut Vec<(i64, String, Vec<u8>)>) -> Result<()> {
+    let bytes = fs::read(path)?;
+    for message in mcap::MessageStream::new(&bytes)? {
+        let message = message?;
+        if TOPICS.contains(&message.channel.topic.as_str()) {
+            messages.push((message.log_time as i64, message.channel.topic.clone(), message.data.into_owned()));
+        }
+    }
+    Ok(())
+}
+
+/// Reader for the OMG CDR encoding ROS 2 serializes messages with.
+struct Cdr<'a> {
+    data: &'a [u8],
+    pos: usize,
+    little_endian: bool,
+}
+
+impl<'a> Cdr<'a> {
+    fn new(data: &'a [u8]) -> Result<Self> {
+        // 4-byte encapsulation header: 0x0000 big endian, 0x0001 little endian.
+        let header = data.get(..4).ok_or_else(|| anyhow!("CDR payload too short"))?;
+        Ok(Cdr { data: &data[4..], pos: 0, little_endian: header[1] & 1 == 1 })
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N]> {
+        // Primitives are aligned to their size, relative to the end of the header.
+        self.pos = self.pos.next_multiple_of(N);
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + N)
+            .ok_or_else(|| anyhow!("CDR payload ends at byte {}", self.pos))?;
+        self.pos += N;
+        Ok(bytes.try_into().expect("slice of length N"))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let b = self.take()?;
+        Ok(if self.little_endian { u32::from_le_bytes(b) } else { u32::from_be_bytes(b) })
+    }
+
+    fn f64(&mut self) -> Result<f64> {
+        let b = self.take()?;
+        Ok(if self.little_endian { f64::from_le_bytes(b) } else { f64::from_be_bytes(b) })
+    }
+
+    fn string(&mut self) -> Result<String> {
+        // Length includes the trailing NUL.
+        let len = self.u32()? as usize;
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or_else(|| anyhow!("CDR string runs past the payload"))?;
+        self.pos += len;
+        Ok(String::from_utf8_lossy(bytes.strip_suffix(&[0]).unwrap_or(bytes)).into_owned())
+    }
+}
+
+/// Decodes a `tf2_msgs/msg/TFMessage`.
+pub(crate) fn decode_tf_message(data: &[u8]) -> Result<Vec<Transform>> {
+    let mut cdr = Cdr::new(data)?;
+    let count = cdr.u32()?;
+    (0..count)
+        .map(|_| {
+            cdr.u32()?; // stamp.sec
+            cdr.u32()?; // stamp.nanosec
+            let parent = cdr.string()?;
+            let child = cdr.string()?;
+            let t = [cdr.f64()?, cdr.f64()?, cdr.f64()?];
+            let q = [cdr.f64()?, cdr.f64()?, cdr.f64()?, cdr.f64()?];
+            Ok(Transform {
+                parent: parent.trim_start_matches('/').to_string(),
+                child: child.trim_start_matches('/').to_string(),
+                pose: origin::pose_f64(t, q),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,241 @@
+//! SDFormat import and export. Links, frames and nested models become frames
+//! scoped with `::`, placed under the joint parent where one exists so the tree
+//! follows the kinematic chain rather than the flat model layout. Export writes
+//! one flat model, so scoped names are written with `__` in place of `::`.
+
+use std::collections::HashMap;
+
+use anyhow::{Result, anyhow, bail};
+use na::Isometry3;
+use nalgebra as na;
+use roxmltree::Node;
+
+use super::xml::{attr, child, children, escape, text};
+use super::{file_node, floats, isometry, parse_floats, rpy, xyz_rpy};
+use crate::FileTransformTree;
+use crate::joint::{FileJoint, JointType};
+
+struct Element {
+    name: String,
+    pose: Isometry3<f64>,
+    relative_to: Option<String>,
+}
+
+/// Joint parent of a link, model relative. `None` is the model frame.
+struct JointInfo {
+    parent: Option<String>,
+    joint: Option<FileJoint>,
+}
+
+#[derive(Default)]
+struct Model {
+    elements: Vec<Element>,
+    /// Keyed by the joint's child link.
+    joints: HashMap<String, JointInfo>,
+}
+
+pub fn parse(text: &str) -> Result<FileTransformTree> {
+    let doc = roxmltree::Document::parse(text)?;
+    let root = doc.root_element();
+    if root.tag_name().name() != "sdf" {
+        bail!("expected <sdf> root element, found <{}>", root.tag_name().name());
+    }
+
+    let mut nodes = vec![];
+    let models = children(root, "model")
+        .chain(children(root, "world").flat_map(|w| children(w, "model")));
+    for model in models {
+        let name = attr(model, "name")?;
+        nodes.push(file_node(name.to_string(), None, &pose(model)?));
+        let contents = read_model(model)?;
+        let in_model = resolve_poses(&contents)?;
+        for element in &contents.elements {
+            let joint = contents.joints.get(&element.name);
+            let parent = match joint {
+                Some(j) => j.parent.clone(),
+                None => element.relative_to.clone(),
+            };
+            let parent_pose = parent
+                .as_ref()
+                .map(|p| in_model[p])
+                .unwrap_or_else(Isometry3::identity);
+            let local = parent_pose.inverse() * in_model[&element.name];
+            let parent = match parent {
+                Some(p) => format!("{}::{}", name, p),
+                None => name.to_string(),
+            };
+            let mut node = file_node(format!("{}::{}", name, element.name), Some(parent), &local);
+            node.joint = joint.and_then(|j| j.joint.clone());
+            nodes.push(node);
+        }
+    }
+    Ok(FileTransformTree { version: 1, nodes, ..Default::default() })
+}
+
+/// Flattens a model's links, frames and nested models into model-relative elements.
+fn read_model(model: Node) -> Result<Model> {
+    let mut res = Model::default();
+    for elem in model.children().filter(Node::is_element) {
+        match elem.tag_name().name() {
+            "link" | "frame" => {
+                let relative_to = relative_to(elem)
+                    .or_else(|| elem.attribute("attached_to").map(str::to_string));
+                res.elements.push(Element {
+                    name: attr(elem, "name")?.to_string(),
+                    pose: pose(elem)?,
+                    relative_to,
+                });
+            }
+            "model" => {
+                let scope = attr(elem, "name")?;
+                res.elements.push(Element {
+                    name: scope.to_string(),
+                    pose: pose(elem)?,
+                    relative_to: relative_to(elem),
+                });
+                let nested = read_model(elem)?;
+                for e in nested.elements {
+                    res.elements.push(Element {
+                        name: format!("{}::{}", scope, e.name),
+                        pose: e.pose,
+                        relative_to: Some(match e.relative_to {
+                            Some(r) => format!("{}::{}", scope, r),
+                            None => scope.to_string(),
+                        }),
+                    });
+                }
+                for (c, j) in nested.joints {
+                    let p = j.parent.map(|p| format!("{}::{}", scope, p)).unwrap_or_else(|| scope.to_string());
+                    res.joints.insert(format!("{}::{}", scope, c), JointInfo { parent: Some(p), joint: j.joint });
+                }
+            }
+            "joint" => {
+                let parent = text(elem, "parent")?;
+                let child_link = text(elem, "child")?;
+                let parent = (parent != "world" && parent != "__model__").then(|| parent.to_string());
+                res.joints.insert(child_link.to_string(), JointInfo { parent, joint: joint(elem)? });
+            }
+            _ => {}
+        }
+    }
+    Ok(res)
+}
+
+/// Resolves every element's pose into the model frame by following `relative_to`.
+fn resolve_poses(model: &Model) -> Result<HashMap<String, Isometry3<f64>>> {
+    let by_name: HashMap<&str, &Element> = model.elements.iter().map(|e| (e.name.as_str(), e)).collect();
+    let mut res = HashMap::with_capacity(by_name.len());
+    for element in &model.elements {
+        let mut pose = element.pose;
+        let mut frame = element.relative_to.as_deref();
+        let mut depth = 0;
+        while let Some(name) = frame.filter(|&f| f != "__model__") {
+            let e = by_name
+                .get(name)
+                .ok_or_else(|| anyhow!("{} is relative to unknown frame {}", element.name, name))?;
+            pose = e.pose * pose;
+            frame = e.relative_to.as_deref();
+            depth += 1;
+            if depth > by_name.len() {
+                bail!("cycle in relative_to chain of {}", element.name);
+            }
+        }
+        res.insert(element.name.clone(), pose);
+    }
+    for (child, info) in &model.joints {
+        for name in std::iter::once(child).chain(&info.parent) {
+            if !res.contains_key(name) {
+                bail!("joint references unknown link {}", name);
+            }
+        }
+    }
+    Ok(res)
+}
+
+/// Joint type, axis and limits. The axis is taken to be expressed in the child frame.
+fn joint(node: Node) -> Result<Option<FileJoint>> {
+    let kind = match attr(node, "type")? {
+        "revolute" => JointType::Revolute,
+        "continuous" => JointType::Continuous,
+        "prismatic" => JointType::Prismatic,
+        "fixed" => JointType::Fixed,
+        _ => return Ok(None),
+    };
+    let axis = child(node, "axis");
+    let xyz = match axis.and_then(|a| child(a, "xyz")).and_then(|x| x.text()) {
+        Some(xyz) => parse_floats::<3>(xyz)?,
+        None => [0.0, 0.0, 1.0],
+    };
+    let limits = match axis.and_then(|a| child(a, "limit")) {
+        Some(limit) => match (text(limit, "lower"), text(limit, "upper")) {
+            (Ok(lower), Ok(upper)) => Some([lower.parse()?, upper.parse()?]),
+            _ => None,
+        },
+        None => None,
+    };
+    Ok(Some(FileJoint { kind, axis: xyz, limits, value: None }))
+}
+
+fn pose(node: Node) -> Result<Isometry3<f64>> {
+    match child(node, "pose").and_then(|p| p.text()) {
+        Some(text) => {
+            let [x, y, z, roll, pitch, yaw] = parse_floats::<6>(text)?;
+            Ok(xyz_rpy([x, y, z], [roll, pitch, yaw]))
+        }
+        None => Ok(Isometry3::identity()),
+    }
+}
+
+fn relative_to(node: Node) -> Option<String> {
+    child(node, "pose")
+        .and_then(|p| p.attribute("relative_to"))
+        .map(str::to_string)
+}
+
+/// Writes one model with a link per frame, posed relative to its parent and
+/// joined to it by its joint or a fixed joint.
+pub fn write(tree: &FileTransformTree) -> String {
+    let mut lines = vec![
+        "<?xml version=\"1.0\"?>".to_string(),
+        "<sdf version=\"1.9\">".to_string(),
+        "  <model name=\"axisviz\">".to_string(),
+    ];
+    for node in &tree.nodes {
+        let pose = isometry(node);
+        let (t, [roll, pitch, yaw]) = (pose.translation.vector, rpy(&pose));
+        let relative_to = match &node.parent {
+            Some(p) => format!(" relative_to=\"{}\"", escape(&unscoped(p))),
+            None => String::new(),
+        };
+        lines.push(format!("    <link name=\"{}\">", escape(&unscoped(&node.name))));
+        lines.push(format!("      <pose{}>{}</pose>", relative_to, floats(&[t.x, t.y, t.z, roll, pitch, yaw])));
+        lines.push("    </link>".to_string());
+    }
+    for node in &tree.nodes {
+        let Some(parent) = &node.parent else {
+            continue;
+        };
+        let kind = node.joint.as_ref().map_or(JointType::Fixed, |j| j.kind);
+        let name = escape(&unscoped(&node.name));
+        lines.push(format!("    <joint name=\"{}_joint\" type=\"{}\">", name, kind.as_str()));
+        lines.push(format!("      <parent>{}</parent>", escape(&unscoped(parent))));
+        lines.push(format!("      <child>{}</child>", name));
+        if let Some(joint) = node.joint.as_ref().filter(|j| j.kind != JointType::Fixed) {
+            lines.push("      <axis>".to_string());
+            lines.push(format!("        <xyz>{}</xyz>", floats(&joint.axis)));
+            if let Some([lower, upper]) = joint.limits {
+                lines.push(format!("        <limit><lower>{}</lower><upper>{}</upper></limit>", lower, upper));
+            }
+            lines.push("      </axis>".to_string());
+        }
+        lines.push("    </joint>".to_string());
+    }
+    lines.push("  </model>".to_string());
+    lines.push("</sdf>".to_string());
+    lines.join("\n") + "\n"
+}
+
+/// `name` without the `::` SDFormat reserves for referring into nested models.
+fn unscoped(name: &str) -> String {
+    name.replace("::", "__")
+}
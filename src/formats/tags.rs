@@ -0,0 +1,146 @@
+//! Fiducial maps: WPILib AprilTag field layouts (JSON), apriltag_ros tag
+//! bundles and ArUco marker maps (YAML). Each tag becomes a frame named
+//! `tag_<id>` in the AprilTag convention of `fiducial`, below one root for
+//! the map.
+
+use anyhow::{Result, anyhow, bail};
+use na::{Isometry3, Matrix3, Rotation3, Translation3, UnitQuaternion, Vector3};
+use nalgebra as na;
+use serde::Deserialize;
+use serde_yaml::Value;
+
+use super::file_node;
+use crate::fiducial::FileTag;
+use crate::{FileNode, FileTransformTree};
+
+/// Edge of the 36h11 tags WPILib layouts place, 6.5 in; the files don't say.
+const WPILIB_TAG_SIZE: f64 = 0.1651;
+
+#[derive(Debug, Deserialize)]
+pub struct FieldLayout {
+    tags: Vec<WpilibTag>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WpilibTag {
+    #[serde(rename = "ID")]
+    id: u32,
+    pose: WpilibPose,
+}
+
+#[derive(Debug, Deserialize)]
+struct WpilibPose {
+    translation: WpilibTranslation,
+    rotation: WpilibRotation,
+}
+
+#[derive(Debug, Deserialize)]
+struct WpilibTranslation {
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct WpilibRotation {
+    quaternion: WpilibQuaternion,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct WpilibQuaternion {
+    w: f64,
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+fn tag_node(parent: &str, id: u32, size: f64, family: Option<&str>, pose: &Isometry3<f64>) -> FileNode {
+    let mut node = file_node(format!("tag_{}", id), Some(parent.to_string()), pose);
+    node.tag = Some(FileTag { id, size, family: family.map(str::to_string) });
+    node.tags.push("fiducial".to_string());
+    node
+}
+
+fn root(name: &str) -> FileNode {
+    file_node(name.to_string(), None, &Isometry3::identity())
+}
+
+/// WPILib tag poses have X out of the tag face and Z up.
+pub fn field_layout(layout: FieldLayout) -> Result<FileTransformTree> {
+    // AprilTag axes in the WPILib tag frame: right, down and into the face.
+    let turn = UnitQuaternion::from_matrix(&Matrix3::new(0.0, 0.0, -1.0, 1.0, 0.0, 0.0, 0.0, -1.0, 0.0));
+    let mut nodes = vec![root("field")];
+    for tag in layout.tags {
+        let (t, q) = (tag.pose.translation, tag.pose.rotation.quaternion);
+        let rotation = UnitQuaternion::from_quaternion(na::Quaternion::new(q.w, q.x, q.y, q.z));
+        let pose = Isometry3::from_parts(Translation3::new(t.x, t.y, t.z), rotation * turn);
+        nodes.push(tag_node("field", tag.id, WPILIB_TAG_SIZE, Some("tag36h11"), &pose));
+    }
+    Ok(FileTransformTree { version: 1, nodes, ..Default::default() })
+}
+
+fn number(value: &Value, key: &str) -> Result<f64> {
+    value.get(key).and_then(Value::as_f64).ok_or_else(|| anyhow!("tag without {}", key))
+}
+
+/// apriltag_ros `tag_bundles`: one root per bundle with its layout below.
+fn bundles(doc: &Value) -> Result<Vec<FileNode>> {
+    let mut nodes = vec![];
+    for (i, bundle) in doc["tag_bundles"].as_sequence().into_iter().flatten().enumerate() {
+        let name = bundle.get("name").and_then(Value::as_str).map_or_else(|| format!("bundle_{}", i), str::to_string);
+        nodes.push(root(&name));
+        for tag in bundle["layout"].as_sequence().into_iter().flatten() {
+            let id = number(tag, "id")? as u32;
+            let v = |key| number(tag, key);
+            let pose = Isometry3::from_parts(
+                Translation3::new(v("x")?, v("y")?, v("z")?),
+                UnitQuaternion::from_quaternion(na::Quaternion::new(v("qw")?, v("qx")?, v("qy")?, v("qz")?)),
+            );
+            nodes.push(tag_node(&name, id, number(tag, "size")?, Some("tag36h11"), &pose));
+        }
+    }
+    Ok(nodes)
+}
+
+/// ArUco marker maps give the four corners of each marker, clockwise from
+/// the top left, in map coordinates.
+fn marker_map(doc: &Value) -> Result<Vec<FileNode>> {
+    if doc.get("aruco_bc_mInfoType").and_then(Value::as_i64) == Some(0) {
+        bail!("marker map is in pixels; calibrate it in meters");
+    }
+    let family = doc.get("aruco_bc_dict").and_then(Value::as_str);
+    let mut nodes = vec![root("map")];
+    for marker in doc["aruco_bc_markers"].as_sequence().into_iter().flatten() {
+        let id = number(marker, "id")? as u32;
+        let corners: Vec<Vector3<f64>> = marker["corners"]
+            .as_sequence()
+            .into_iter()
+            .flatten()
+            .map(|c| match c.as_sequence().map(|c| c.iter().filter_map(Value::as_f64).collect::<Vec<_>>()).as_deref() {
+                Some(&[x, y, z]) => Ok(Vector3::new(x, y, z)),
+                _ => Err(anyhow!("tag_{}: corners should be [x, y, z]", id)),
+            })
+            .collect::<Result<_>>()?;
+        let [c0, c1, _, c3] = corners[..] else {
+            bail!("tag_{}: expected 4 corners, got {}", id, corners.len());
+        };
+        let center = corners.iter().sum::<Vector3<f64>>() / 4.0;
+        let right = (c1 - c0).normalize();
+        let down = (c3 - c0).normalize();
+        let rotation = Rotation3::from_matrix(&Matrix3::from_columns(&[right, down, right.cross(&down)]));
+        let pose = Isometry3::from_parts(center.into(), UnitQuaternion::from_rotation_matrix(&rotation));
+        nodes.push(tag_node("map", id, (c1 - c0).norm(), family, &pose));
+    }
+    Ok(nodes)
+}
+
+/// Whether a YAML document is a tag map.
+pub fn is_tag_map(doc: &Value) -> bool {
+    doc.get("tag_bundles").is_some() || doc.get("aruco_bc_markers").is_some()
+}
+
+pub fn parse_yaml(doc: &Value) -> Result<FileTransformTree> {
+    let nodes = if doc.get("tag_bundles").is_some() { bundles(doc)? } else { marker_map(doc)? };
+    Ok(FileTransformTree { version: 1, nodes, ..Default::default() })
+}
@@ -0,0 +1,144 @@
+//! URDF import and export. Links become frames, parented through their joints
+//! with the joint origin as the local transform.
+
+use std::collections::HashMap;
+
+use anyhow::{Result, bail};
+use bevy::math::Isometry3d;
+use na::Isometry3;
+use nalgebra as na;
+use roxmltree::Node;
+
+use super::xml::{attr, child, children, escape};
+use super::{file_node, floats, isometry, parse_floats, rpy, xyz_rpy};
+use crate::joint::{FileJoint, Joint, JointType};
+use crate::{FileNode, FileTransformTree};
+
+struct UrdfJoint<'a> {
+    parent: &'a str,
+    origin: Isometry3<f64>,
+    joint: Option<FileJoint>,
+}
+
+pub fn parse(text: &str) -> Result<FileTransformTree> {
+    let doc = roxmltree::Document::parse(text)?;
+    let robot = doc.root_element();
+    if robot.tag_name().name() != "robot" {
+        bail!("expected <robot> root element, found <{}>", robot.tag_name().name());
+    }
+
+    let mut joints = HashMap::new();
+    for joint in children(robot, "joint") {
+        let child_link = attr(child(joint, "child").unwrap_or(joint), "link")?;
+        joints.insert(child_link, UrdfJoint {
+            parent: attr(child(joint, "parent").unwrap_or(joint), "link")?,
+            origin: origin(joint)?,
+            joint: file_joint(joint)?,
+        });
+    }
+
+    let mut nodes = vec![];
+    for link in children(robot, "link") {
+        let name = attr(link, "name")?;
+        let node = match joints.remove(name) {
+            Some(j) => {
+                let mut node = file_node(name.to_string(), Some(j.parent.to_string()), &j.origin);
+                node.joint = j.joint;
+                node
+            }
+            None => file_node(name.to_string(), None, &Isometry3::identity()),
+        };
+        nodes.push(node);
+    }
+    if let Some(child_link) = joints.keys().next() {
+        bail!("joint child link {} is not defined", child_link);
+    }
+    Ok(FileTransformTree { version: 1, nodes, ..Default::default() })
+}
+
+fn origin(joint: Node) -> Result<Isometry3<f64>> {
+    let Some(origin) = child(joint, "origin") else {
+        return Ok(Isometry3::identity());
+    };
+    let xyz = match origin.attribute("xyz") {
+        Some(xyz) => parse_floats::<3>(xyz)?,
+        None => [0.0; 3],
+    };
+    let rpy = match origin.attribute("rpy") {
+        Some(rpy) => parse_floats::<3>(rpy)?,
+        None => [0.0; 3],
+    };
+    Ok(xyz_rpy(xyz, rpy))
+}
+
+fn file_joint(joint: Node) -> Result<Option<FileJoint>> {
+    let kind = match attr(joint, "type")? {
+        "revolute" => JointType::Revolute,
+        "continuous" => JointType::Continuous,
+        "prismatic" => JointType::Prismatic,
+        "fixed" => JointType::Fixed,
+        _ => return Ok(None),
+    };
+    let axis = match child(joint, "axis").and_then(|a| a.attribute("xyz")) {
+        Some(xyz) => parse_floats::<3>(xyz)?,
+        None => [1.0, 0.0, 0.0],
+    };
+    let limits = match child(joint, "limit") {
+        Some(limit) => match (limit.attribute("lower"), limit.attribute("upper")) {
+            (Some(lower), Some(upper)) => Some([lower.parse()?, upper.parse()?]),
+            _ => None,
+        },
+        None => None,
+    };
+    Ok(Some(FileJoint { kind, axis, limits, value: None }))
+}
+
+/// Writes every frame as a link, joined to its parent by its joint or a fixed
+/// joint. URDF wants a single root link at the origin, so several roots, or a
+/// root away from the origin, hang from an added `world` link. Revolute and
+/// prismatic joints without limits get the viewer's slider range, since URDF
+/// requires one.
+pub fn write(tree: &FileTransformTree) -> String {
+    let mut lines = vec!["<?xml version=\"1.0\"?>".to_string(), "<robot name=\"axisviz\">".to_string()];
+    let roots: Vec<&FileNode> = tree.nodes.iter().filter(|n| n.parent.is_none()).collect();
+    let world = match roots.as_slice() {
+        [] => None,
+        [root] if isometry(root) == Isometry3::identity() => None,
+        _ if tree.nodes.iter().any(|n| n.name == "world") => Some("axisviz_world"),
+        _ => Some("world"),
+    };
+    if let Some(world) = world {
+        lines.push(format!("  <link name=\"{}\"/>", escape(world)));
+    }
+    for node in &tree.nodes {
+        lines.push(format!("  <link name=\"{}\"/>", escape(&node.name)));
+    }
+    for node in &tree.nodes {
+        let Some(parent) = node.parent.as_deref().or(world) else {
+            continue;
+        };
+        let origin = isometry(node);
+        let t = origin.translation.vector;
+        let kind = node.joint.as_ref().map_or(JointType::Fixed, |j| j.kind);
+        lines.push(format!("  <joint name=\"{}_joint\" type=\"{}\">", escape(&node.name), kind.as_str()));
+        lines.push(format!("    <parent link=\"{}\"/>", escape(parent)));
+        lines.push(format!("    <child link=\"{}\"/>", escape(&node.name)));
+        lines.push(format!("    <origin xyz=\"{}\" rpy=\"{}\"/>", floats(&[t.x, t.y, t.z]), floats(&rpy(&origin))));
+        if let Some(joint) = node.joint.as_ref().filter(|j| j.kind != JointType::Fixed) {
+            lines.push(format!("    <axis xyz=\"{}\"/>", floats(&joint.axis)));
+            if matches!(joint.kind, JointType::Revolute | JointType::Prismatic) {
+                let [lower, upper] = joint.limits.unwrap_or_else(|| {
+                    let (lower, upper) = Joint::new(joint, Isometry3d::IDENTITY).range();
+                    [lower as f64, upper as f64]
+                });
+                lines.push(format!(
+                    "    <limit lower=\"{}\" upper=\"{}\" effort=\"0\" velocity=\"0\"/>",
+                    lower, upper
+                ));
+            }
+        }
+        lines.push("  </joint>".to_string());
+    }
+    lines.push("</robot>".to_string());
+    lines.join("\n") + "\n"
+}
@@ -0,0 +1,94 @@
+//! USD export as a text layer (`.usda`): the frames become an Xform hierarchy
+//! under a `/World` prim, in meters with Z up, ready to reference from
+//! Omniverse or Isaac Sim stages.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{FileNode, FileTransformTree};
+
+use super::isometry;
+
+/// USD prim names are identifiers; other characters become `_`.
+fn prim_name(name: &str) -> String {
+    let mut prim: String = name.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+    if !prim.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        prim.insert(0, '_');
+    }
+    prim
+}
+
+fn string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn tuple(values: &[f64]) -> String {
+    format!("({})", values.iter().map(f64::to_string).collect::<Vec<_>>().join(", "))
+}
+
+fn write_prim<'a>(
+    lines: &mut Vec<String>,
+    node: &'a FileNode,
+    children: &HashMap<&str, Vec<&'a FileNode>>,
+    depth: usize,
+    taken: &mut HashSet<String>,
+) {
+    let indent = "    ".repeat(depth);
+    let mut prim = prim_name(&node.name);
+    // Siblings share a namespace; renamed frames may collide.
+    while !taken.insert(prim.clone()) {
+        prim.push('_');
+    }
+    if prim == node.name {
+        lines.push(format!("{}def Xform {}", indent, string(&prim)));
+    } else {
+        lines.push(format!("{}def Xform {} (", indent, string(&prim)));
+        lines.push(format!("{}    displayName = {}", indent, string(&node.name)));
+        lines.push(format!("{})", indent));
+    }
+    lines.push(format!("{}{{", indent));
+    let pose = isometry(node);
+    let (t, q) = (pose.translation.vector, pose.rotation);
+    let mut order = vec!["\"xformOp:translate\"", "\"xformOp:orient\""];
+    lines.push(format!("{}    double3 xformOp:translate = {}", indent, tuple(&[t.x, t.y, t.z])));
+    lines.push(format!("{}    quatd xformOp:orient = {}", indent, tuple(&[q.w, q.i, q.j, q.k])));
+    if let Some(s) = node.s {
+        lines.push(format!("{}    double3 xformOp:scale = {}", indent, tuple(&s)));
+        order.push("\"xformOp:scale\"");
+    }
+    lines.push(format!("{}    uniform token[] xformOpOrder = [{}]", indent, order.join(", ")));
+    let mut names = HashSet::new();
+    for child in children.get(node.name.as_str()).into_iter().flatten() {
+        lines.push(String::new());
+        write_prim(lines, child, children, depth + 1, &mut names);
+    }
+    lines.push(format!("{}}}", indent));
+}
+
+pub fn write(tree: &FileTransformTree) -> String {
+    let mut children: HashMap<&str, Vec<&FileNode>> = HashMap::new();
+    for node in &tree.nodes {
+        if let Some(parent) = &node.parent {
+            children.entry(parent.as_str()).or_default().push(node);
+        }
+    }
+    let mut lines = vec![
+        "#usda 1.0".to_string(),
+        "(".to_string(),
+        "    defaultPrim = \"World\"".to_string(),
+        "    metersPerUnit = 1".to_string(),
+        "    upAxis = \"Z\"".to_string(),
+        ")".to_string(),
+        String::new(),
+        "def Xform \"World\"".to_string(),
+        "{".to_string(),
+    ];
+    let mut names = HashSet::new();
+    for (i, root) in tree.nodes.iter().filter(|n| n.parent.is_none()).enumerate() {
+        if i > 0 {
+            lines.push(String::new());
+        }
+        write_prim(&mut lines, root, &children, 1, &mut names);
+    }
+    lines.push("}".to_string());
+    lines.join("\n") + "\n"
+}
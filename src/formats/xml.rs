@@ -0,0 +1,31 @@
+use anyhow::{Result, anyhow};
+use roxmltree::Node;
+
+/// Escapes text for use in attribute values and element content.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+pub fn children<'a, 'input>(node: Node<'a, 'input>, tag: &str) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children().filter(move |c| c.has_tag_name(tag))
+}
+
+pub fn child<'a, 'input>(node: Node<'a, 'input>, tag: &str) -> Option<Node<'a, 'input>> {
+    children(node, tag).next()
+}
+
+pub fn attr<'a>(node: Node<'a, '_>, name: &str) -> Result<&'a str> {
+    node.attribute(name)
+        .ok_or_else(|| anyhow!("<{}> is missing the {} attribute", node.tag_name().name(), name))
+}
+
+pub fn text<'a>(node: Node<'a, '_>, tag: &str) -> Result<&'a str> {
+    child(node, tag)
+        .and_then(|n| n.text())
+        .map(str::trim)
+        .ok_or_else(|| anyhow!("<{}> is missing <{}>", node.tag_name().name(), tag))
+}
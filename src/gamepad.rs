@@ -0,0 +1,119 @@
+//! Camera control from a gamepad, for demo rooms without a mouse. By default
+//! the left stick flies the orbit focus across the view and the bumpers raise
+//! and lower it, the right stick orbits, the triggers zoom, and North frames
+//! the whole tree. Bindings and speeds live in `axisviz.toml`.
+
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+use bevy_panorbit_camera::PanOrbitCamera;
+use serde::{Deserialize, Serialize};
+
+use crate::TransformTree;
+use crate::camera::{self, CameraFocus, MainCamera};
+
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GamepadBindings {
+    pub orbit_x: GamepadAxis,
+    pub orbit_y: GamepadAxis,
+    /// Moves the focus sideways.
+    pub fly_x: GamepadAxis,
+    /// Moves the focus forward along the view, level with the ground.
+    pub fly_y: GamepadAxis,
+    pub up: GamepadButton,
+    pub down: GamepadButton,
+    pub zoom_in: GamepadButton,
+    pub zoom_out: GamepadButton,
+    pub frame_all: GamepadButton,
+    /// Radians per second at full stick.
+    pub orbit_speed: f32,
+    /// Orbit radii per second at full stick, so flying keeps pace with the zoom.
+    pub fly_speed: f32,
+    /// Doublings of the orbit radius per second at full trigger.
+    pub zoom_speed: f32,
+    /// Stick deflection ignored around the center.
+    pub deadzone: f32,
+    pub invert_y: bool,
+}
+
+impl Default for GamepadBindings {
+    fn default() -> Self {
+        GamepadBindings {
+            orbit_x: GamepadAxis::RightStickX,
+            orbit_y: GamepadAxis::RightStickY,
+            fly_x: GamepadAxis::LeftStickX,
+            fly_y: GamepadAxis::LeftStickY,
+            up: GamepadButton::RightTrigger,
+            down: GamepadButton::LeftTrigger,
+            zoom_in: GamepadButton::RightTrigger2,
+            zoom_out: GamepadButton::LeftTrigger2,
+            frame_all: GamepadButton::North,
+            orbit_speed: 2.0,
+            fly_speed: 1.0,
+            zoom_speed: 1.5,
+            deadzone: 0.15,
+            invert_y: false,
+        }
+    }
+}
+
+/// Stick value with the dead zone cut out and the rest stretched back to
+/// the full range.
+pub fn deadzone(value: f32, zone: f32) -> f32 {
+    if value.abs() <= zone {
+        return 0.0;
+    }
+    value.signum() * (value.abs() - zone) / (1.0 - zone)
+}
+
+pub fn gamepad_camera(
+    time: Res<Time>,
+    bindings: Res<GamepadBindings>,
+    gamepads: Query<&Gamepad>,
+    dag: Res<TransformTree>,
+    mut focus: ResMut<CameraFocus>,
+    mut camera_q: Query<(&mut PanOrbitCamera, &Transform), With<MainCamera>>,
+) {
+    let Ok((mut camera, transform)) = camera_q.single_mut() else {
+        return;
+    };
+    let b = &*bindings;
+    let (mut orbit, mut fly, mut lift, mut zoom) = (Vec2::ZERO, Vec2::ZERO, 0.0, 0.0);
+    for gamepad in &gamepads {
+        let axis = |a: GamepadAxis| deadzone(gamepad.get(a).unwrap_or(0.0), b.deadzone);
+        // Triggers may be analog or plain buttons.
+        let button = |x: GamepadButton| gamepad.get(x).unwrap_or(if gamepad.pressed(x) { 1.0 } else { 0.0 });
+        orbit += Vec2::new(axis(b.orbit_x), axis(b.orbit_y));
+        fly += Vec2::new(axis(b.fly_x), axis(b.fly_y));
+        lift += button(b.up) - button(b.down);
+        zoom += button(b.zoom_out) - button(b.zoom_in);
+        if gamepad.just_pressed(b.frame_all) {
+            let (center, radius) = camera::framing(&dag);
+            focus.focus_on(center, Some(radius));
+        }
+    }
+    if orbit == Vec2::ZERO && fly == Vec2::ZERO && lift == 0.0 && zoom == 0.0 {
+        return;
+    }
+    let dt = time.delta_secs();
+    if b.invert_y {
+        orbit.y = -orbit.y;
+    }
+    let yaw = camera.target_yaw - orbit.x * b.orbit_speed * dt;
+    let pitch = (camera.target_pitch + orbit.y * b.orbit_speed * dt).clamp(-FRAC_PI_2 + 0.01, FRAC_PI_2 - 0.01);
+    let radius = camera.radius.unwrap_or(camera.target_radius);
+    let forward = Vec3::new(transform.forward().x, 0.0, transform.forward().z).normalize_or_zero();
+    let step = (transform.right() * fly.x + forward * fly.y + Vec3::Y * lift) * radius * b.fly_speed * dt;
+    let radius = radius * (zoom * b.zoom_speed * dt).exp2();
+    // Drive both current and target values so the camera's own smoothing doesn't add lag.
+    camera.yaw = Some(yaw);
+    camera.target_yaw = yaw;
+    camera.pitch = Some(pitch);
+    camera.target_pitch = pitch;
+    camera.focus += step;
+    camera.target_focus = camera.focus;
+    camera.radius = Some(radius);
+    camera.target_radius = radius;
+    camera.force_update = true;
+}
@@ -0,0 +1,139 @@
+//! Geodetic anchors. A frame may give its position as latitude, longitude and
+//! altitude instead of `t`; the first anchor in a tree becomes the reference
+//! whose local tangent plane is the world (X east, Y north, Z up), and every
+//! other anchor is placed in that plane on the WGS84 ellipsoid. An anchored
+//! frame's axes follow the east-north-up or north-east-down directions at its
+//! own position, turned further by its `r`.
+
+use bevy::math::{DMat3, DQuat, DVec3};
+use bevy::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::origin;
+use crate::{FileNode, NodeId, TransformTree};
+
+/// WGS84 semi-major axis in meters.
+const A: f64 = 6_378_137.0;
+/// WGS84 flattening.
+const F: f64 = 1.0 / 298.257_223_563;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum GeoConvention {
+    /// X east, Y north, Z up.
+    #[default]
+    Enu,
+    /// X north, Y east, Z down.
+    Ned,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct FileGeo {
+    /// Degrees north.
+    pub lat: f64,
+    /// Degrees east.
+    pub lon: f64,
+    /// Meters above the ellipsoid.
+    #[serde(default)]
+    pub alt: f64,
+    #[serde(default)]
+    pub convention: GeoConvention,
+}
+
+impl FileGeo {
+    fn ecef(&self) -> DVec3 {
+        let (lat, lon) = (self.lat.to_radians(), self.lon.to_radians());
+        let e2 = F * (2.0 - F);
+        let n = A / (1.0 - e2 * lat.sin().powi(2)).sqrt();
+        DVec3::new(
+            (n + self.alt) * lat.cos() * lon.cos(),
+            (n + self.alt) * lat.cos() * lon.sin(),
+            (n * (1.0 - e2) + self.alt) * lat.sin(),
+        )
+    }
+
+    /// Columns east, north and up at this position, in ECEF.
+    fn enu(&self) -> DMat3 {
+        let (lat, lon) = (self.lat.to_radians(), self.lon.to_radians());
+        DMat3::from_cols(
+            DVec3::new(-lon.sin(), lon.cos(), 0.0),
+            DVec3::new(-lat.sin() * lon.cos(), -lat.sin() * lon.sin(), lat.cos()),
+            DVec3::new(lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin()),
+        )
+    }
+
+    /// Latitude, longitude and altitude of an ECEF position, by Bowring's
+    /// iteration; a few rounds reach millimeters.
+    fn from_ecef(p: DVec3) -> FileGeo {
+        let e2 = F * (2.0 - F);
+        let lon = p.y.atan2(p.x);
+        let r = p.x.hypot(p.y);
+        let mut lat = p.z.atan2(r * (1.0 - e2));
+        let mut alt = 0.0;
+        for _ in 0..5 {
+            let n = A / (1.0 - e2 * lat.sin().powi(2)).sqrt();
+            alt = r / lat.cos() - n;
+            lat = p.z.atan2(r * (1.0 - e2 * n / (n + alt)));
+        }
+        FileGeo { lat: lat.to_degrees(), lon: lon.to_degrees(), alt, convention: GeoConvention::Enu }
+    }
+}
+
+/// World pose, in full precision, of a frame anchored at `geo` and turned by
+/// `turn`, in the tangent plane of `reference`.
+fn anchored_pose(reference: &FileGeo, geo: &FileGeo, turn: DQuat) -> (DVec3, DQuat) {
+    let to_world = reference.enu().transpose();
+    let translation = to_world * (geo.ecef() - reference.ecef());
+    let mut axes = to_world * geo.enu();
+    if geo.convention == GeoConvention::Ned {
+        axes = DMat3::from_cols(axes.y_axis, axes.x_axis, -axes.z_axis);
+    }
+    let rotation = DQuat::from_mat3(&axes) * turn;
+    (translation, rotation.normalize())
+}
+
+/// Replaces the `t` and `r` of anchored roots with their place in the
+/// reference tangent plane, so a far anchor picks the floating origin.
+/// Anchored frames with a parent are placed by `TransformTree::place_anchors`
+/// once their parent's pose is known. Returns the reference, if any frame is
+/// anchored.
+pub(crate) fn place_roots(nodes: &mut [FileNode]) -> Option<FileGeo> {
+    let reference = nodes.iter().find_map(|n| n.geo)?;
+    for node in nodes.iter_mut().filter(|n| n.parent.is_none()) {
+        if let Some(geo) = node.geo {
+            let (t, rotation) = anchored_pose(&reference, &geo, node.rotation());
+            let (r, p, y) = rotation.to_euler(EulerRot::XYZ);
+            node.t = t.to_array();
+            node.r = [r, p, y];
+            node.euler = None;
+        }
+    }
+    Some(reference)
+}
+
+impl TransformTree {
+    /// Anchor whose tangent plane is the world, if the tree has anchors.
+    pub fn geo_reference(&self) -> Option<FileGeo> {
+        self.geo_reference
+    }
+
+    /// Moves each anchored frame in `nodes` to its anchor, through its local
+    /// pose. A tree without a reference takes the first anchor as one.
+    pub(crate) fn place_anchors(&mut self, nodes: &[FileNode]) {
+        for node in nodes {
+            let (Some(geo), Some(id)) = (node.geo, self.find(&node.name)) else {
+                continue;
+            };
+            let reference = *self.geo_reference.get_or_insert(geo);
+            let (t, q) = anchored_pose(&reference, &geo, node.rotation());
+            self.set_world_f64(id, origin::pose_f64(t.to_array(), [q.x, q.y, q.z, q.w]));
+        }
+    }
+
+    /// Latitude, longitude and altitude of a frame in an anchored tree.
+    pub fn geodetic(&self, id: NodeId) -> Option<FileGeo> {
+        let reference = self.geo_reference?;
+        Some(FileGeo::from_ecef(reference.ecef() + reference.enu() * self.world_position(id)))
+    }
+}
@@ -0,0 +1,105 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+use bevy_debug_grid::{Grid, GridAxis};
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GridPlane {
+    /// Floor plane for Y-up scenes
+    Xz,
+    /// Floor plane for Z-up (robotics) scenes
+    Xy,
+}
+
+impl GridPlane {
+    /// World up of scenes with this floor.
+    pub fn up(self) -> Vec3 {
+        match self {
+            GridPlane::Xz => Vec3::Y,
+            GridPlane::Xy => Vec3::Z,
+        }
+    }
+
+    /// Orbit camera axes (x, up, z) that put `up` at the top of the screen.
+    pub fn orbit_axes(self) -> [Vec3; 3] {
+        match self {
+            GridPlane::Xz => [Vec3::X, Vec3::Y, Vec3::Z],
+            GridPlane::Xy => [Vec3::X, Vec3::Z, -Vec3::Y],
+        }
+    }
+
+    /// Where `p` lands on a map seen from above: right, then down the screen.
+    pub fn top_down(self, p: Vec3) -> Vec2 {
+        match self {
+            GridPlane::Xz => Vec2::new(p.x, p.z),
+            GridPlane::Xy => Vec2::new(p.x, -p.y),
+        }
+    }
+
+    /// World point at `height` above the floor under map point `m`.
+    pub fn from_top_down(self, m: Vec2, height: f32) -> Vec3 {
+        match self {
+            GridPlane::Xz => Vec3::new(m.x, height, m.y),
+            GridPlane::Xy => Vec3::new(m.x, -m.y, height),
+        }
+    }
+}
+
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct GridSettings {
+    pub enabled: bool,
+    pub spacing: f32,
+    /// Number of cells from the origin to the edge of the grid.
+    pub count: usize,
+    pub plane: GridPlane,
+    pub color: [f32; 3],
+}
+
+impl Default for GridSettings {
+    fn default() -> Self {
+        GridSettings { enabled: true, spacing: 1.0, count: 10, plane: GridPlane::Xz, color: [0.5; 3] }
+    }
+}
+
+#[derive(Component)]
+pub struct FloorGrid;
+
+pub fn setup(mut commands: Commands, settings: Res<GridSettings>) {
+    commands.spawn((
+        FloorGrid,
+        Grid {
+            spacing: settings.spacing,
+            count: settings.count,
+            color: Color::srgb_from_array(settings.color),
+            ..default()
+        },
+        GridAxis::new_rgb(),
+        grid_transform(settings.plane),
+        grid_visibility(settings.enabled),
+    ));
+}
+
+pub fn apply_settings(settings: Res<GridSettings>, mut grid_q: Query<(&mut Grid, &mut Transform, &mut Visibility), With<FloorGrid>>) {
+    if !settings.is_changed() {
+        return;
+    }
+    for (mut grid, mut transform, mut visibility) in &mut grid_q {
+        grid.spacing = settings.spacing;
+        grid.count = settings.count;
+        grid.color = Color::srgb_from_array(settings.color);
+        *transform = grid_transform(settings.plane);
+        *visibility = grid_visibility(settings.enabled);
+    }
+}
+
+fn grid_transform(plane: GridPlane) -> Transform {
+    match plane {
+        GridPlane::Xz => Transform::IDENTITY,
+        GridPlane::Xy => Transform::from_rotation(Quat::from_rotation_x(FRAC_PI_2)),
+    }
+}
+
+fn grid_visibility(enabled: bool) -> Visibility {
+    if enabled { Visibility::Inherited } else { Visibility::Hidden }
+}
@@ -0,0 +1,82 @@
+//! Namespaces of frames, from an explicit `group` or the part of the name
+//! before the last `/` (`arm/wrist/tool0` is in `arm/wrist`, itself in `arm`).
+//! A collapsed namespace is drawn as its topmost frame only.
+
+use std::collections::{BTreeSet, HashSet};
+
+use bevy::prelude::*;
+
+use crate::{NodeId, TNode, TransformTree};
+
+/// Namespaces currently collapsed.
+#[derive(Resource, Debug, Default)]
+pub struct CollapsedGroups(pub BTreeSet<String>);
+
+impl TNode {
+    pub fn namespace(&self) -> Option<&str> {
+        self.group.as_deref().or_else(|| self.name.rsplit_once('/').map(|(ns, _)| ns))
+    }
+
+    /// Neither hidden by the user nor folded into a collapsed namespace.
+    pub fn visible(&self) -> bool {
+        !self.hidden && !self.collapsed
+    }
+}
+
+/// Whether namespace `ns` is `group` or nested inside it.
+fn in_group(ns: &str, group: &str) -> bool {
+    ns.strip_prefix(group).is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Every namespace in the tree, including the enclosing ones.
+pub fn namespaces(dag: &TransformTree) -> BTreeSet<String> {
+    let mut all = BTreeSet::new();
+    for ns in dag.nodes.iter().filter_map(TNode::namespace) {
+        for (i, _) in ns.match_indices('/') {
+            all.insert(ns[..i].to_string());
+        }
+        all.insert(ns.to_string());
+    }
+    all
+}
+
+/// Folds the frames of collapsed namespaces into their topmost member, which
+/// is labeled with the namespace and its frame count.
+pub fn apply_collapse(groups: Res<CollapsedGroups>, mut dag: ResMut<TransformTree>) {
+    if !groups.is_changed() && !dag.is_changed() {
+        return;
+    }
+    let mut collapsed = vec![false; dag.nodes.len()];
+    let mut summary = vec![None; dag.nodes.len()];
+    for group in &groups.0 {
+        // An enclosing collapsed namespace already covers this one.
+        if groups.0.iter().any(|g| g != group && in_group(group, g)) {
+            continue;
+        }
+        let members: HashSet<NodeId> = (0..dag.nodes.len())
+            .filter(|&id| dag.nodes[id].namespace().is_some_and(|ns| in_group(ns, group)))
+            .collect();
+        let Some(representative) = (0..dag.nodes.len())
+            .find(|id| members.contains(id) && dag.nodes[*id].parent.is_none_or(|p| !members.contains(&p)))
+        else {
+            continue;
+        };
+        for &id in &members {
+            collapsed[id] = id != representative;
+        }
+        summary[representative] = Some(format!("{}/ ({} frames)", group, members.len()));
+    }
+
+    let unchanged = dag
+        .nodes
+        .iter()
+        .zip(collapsed.iter().zip(&summary))
+        .all(|(n, (&c, s))| n.collapsed == c && n.group_summary == *s);
+    if unchanged {
+        return;
+    }
+    for (node, (c, s)) in dag.nodes.iter_mut().zip(collapsed.into_iter().zip(summary)) {
+        node.collapsed = c;
+        node.group_summary = s;
+    }
+}
@@ -0,0 +1,176 @@
+//! Optional gRPC control server (`--grpc <addr>`, feature `grpc`). Writes go
+//! through the live update channel; reads are served from a snapshot of the
+//! tree that is republished whenever it changes.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::mpsc::Sender;
+use std::thread;
+
+use bevy::prelude::*;
+use nalgebra::Isometry3;
+use tokio::sync::{mpsc, watch};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+use crate::{FileNode, NodeId, TransformTree};
+
+pub mod proto {
+    tonic::include_proto!("axisviz");
+}
+
+use proto::axis_viz_server::{AxisViz, AxisVizServer};
+use proto::{Empty, Frame, FrameName, Pose, RelativeRequest, SubscribeRequest};
+
+/// Latest tree, shared with the server.
+#[derive(Resource)]
+pub struct Snapshot(watch::Sender<Arc<TransformTree>>);
+
+pub fn publish(dag: Res<TransformTree>, snapshot: Res<Snapshot>) {
+    if dag.is_changed() {
+        snapshot.0.send_replace(Arc::new(dag.clone()));
+    }
+}
+
+/// Starts the server on a background thread and registers the snapshot system.
+pub fn serve(app: &mut App, addr: SocketAddr, updates: Sender<FileNode>) {
+    let (tx, tree) = watch::channel(Arc::new(TransformTree::default()));
+    app.insert_resource(Snapshot(tx)).add_systems(Last, publish);
+
+    let service = Service { tree, updates };
+    thread::spawn(move || {
+        let runtime = match tokio::runtime::Builder::new_multi_thread().enable_all().build() {
+            Ok(runtime) => runtime,
+            Err(e) => {
+                eprintln!("grpc: {}", e);
+                return;
+            }
+        };
+        let server = tonic::transport::Server::builder()
+            .add_service(AxisVizServer::new(service))
+            .serve(addr);
+        if let Err(e) = runtime.block_on(server) {
+            eprintln!("grpc: {}", e);
+        }
+    });
+}
+
+struct Service {
+    tree: watch::Receiver<Arc<TransformTree>>,
+    updates: Sender<FileNode>,
+}
+
+impl Service {
+    fn snapshot(&self) -> Arc<TransformTree> {
+        self.tree.borrow().clone()
+    }
+
+    fn send(&self, node: FileNode) -> Result<Response<Empty>, Status> {
+        self.updates
+            .send(node)
+            .map_err(|_| Status::unavailable("viewer is shutting down"))?;
+        Ok(Response::new(Empty {}))
+    }
+}
+
+fn lookup(dag: &TransformTree, name: &str) -> Result<NodeId, Status> {
+    dag.find(name).ok_or_else(|| Status::not_found(format!("unknown frame {}", name)))
+}
+
+fn pose(iso: &Isometry3<f64>) -> Pose {
+    let mut node = FileNode::default();
+    node.set_pose_f64(iso);
+    let ([x, y, z], [roll, pitch, yaw]) = (node.t, node.r);
+    Pose { x, y, z, roll, pitch, yaw }
+}
+
+fn frame(dag: &TransformTree, id: NodeId) -> Frame {
+    let node = &dag.nodes[id];
+    Frame {
+        name: node.name.clone(),
+        parent: node.parent.map(|p| dag.nodes[p].name.clone()),
+        pose: Some(pose(&node.local_f64)),
+        world: Some(pose(&node.world_f64)),
+    }
+}
+
+fn file_node(frame: Frame, parent: Option<String>) -> FileNode {
+    let p = frame.pose.unwrap_or_default();
+    FileNode {
+        name: frame.name,
+        parent,
+        t: [p.x, p.y, p.z],
+        r: [p.roll, p.pitch, p.yaw],
+        ..Default::default()
+    }
+}
+
+#[tonic::async_trait]
+impl AxisViz for Service {
+    async fn set_transform(&self, request: Request<Frame>) -> Result<Response<Empty>, Status> {
+        let frame = request.into_inner();
+        let dag = self.snapshot();
+        let id = lookup(&dag, &frame.name)?;
+        let parent = match &frame.parent {
+            Some(p) => Some(p.clone()),
+            None => dag.nodes[id].parent.map(|p| dag.nodes[p].name.clone()),
+        };
+        self.send(file_node(frame, parent))
+    }
+
+    async fn get_transform(&self, request: Request<FrameName>) -> Result<Response<Frame>, Status> {
+        let dag = self.snapshot();
+        let id = lookup(&dag, &request.into_inner().name)?;
+        Ok(Response::new(frame(&dag, id)))
+    }
+
+    async fn add_frame(&self, request: Request<Frame>) -> Result<Response<Empty>, Status> {
+        let frame = request.into_inner();
+        let dag = self.snapshot();
+        if dag.find(&frame.name).is_some() {
+            return Err(Status::already_exists(format!("frame {} already exists", frame.name)));
+        }
+        if let Some(p) = &frame.parent {
+            lookup(&dag, p)?;
+        }
+        let parent = frame.parent.clone();
+        self.send(file_node(frame, parent))
+    }
+
+    async fn lookup_relative(&self, request: Request<RelativeRequest>) -> Result<Response<Pose>, Status> {
+        let request = request.into_inner();
+        let dag = self.snapshot();
+        let (reference, id) = (lookup(&dag, &request.reference)?, lookup(&dag, &request.name)?);
+        Ok(Response::new(pose(&(dag.world_f64(reference).inverse() * dag.world_f64(id)))))
+    }
+
+    type SubscribeTransformsStream = ReceiverStream<Result<Frame, Status>>;
+
+    async fn subscribe_transforms(
+        &self,
+        request: Request<SubscribeRequest>,
+    ) -> Result<Response<Self::SubscribeTransformsStream>, Status> {
+        let names = request.into_inner().names;
+        let mut tree = self.tree.clone();
+        let (tx, rx) = mpsc::channel(256);
+        tokio::spawn(async move {
+            loop {
+                let dag = tree.borrow_and_update().clone();
+                let ids: Vec<NodeId> = if names.is_empty() {
+                    (0..dag.nodes.len()).collect()
+                } else {
+                    names.iter().filter_map(|n| dag.find(n)).collect()
+                };
+                for id in ids {
+                    if tx.send(Ok(frame(&dag, id))).await.is_err() {
+                        return;
+                    }
+                }
+                if tree.changed().await.is_err() {
+                    return;
+                }
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
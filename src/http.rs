@@ -0,0 +1,184 @@
+//! Minimal JSON/HTTP API (`--http <addr>`, feature `http`):
+//!
+//! - `GET /tree` the current tree in the JSON file format
+//! - `PUT /frames/<name>` body `{"t": [x, y, z], "r": [roll, pitch, yaw], "parent": ".."}`,
+//!   `parent` optional; the frame keeps its parent if left out
+//! - `GET /screenshot` the window as PNG
+//!
+//! Requests are answered by a system on the main thread, one at a time.
+
+use std::io::{Cursor, Read};
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::render::view::screenshot::{Screenshot, ScreenshotCaptured};
+use serde::Deserialize;
+
+use crate::{FileNode, FileTransformTree, TransformTree};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+struct Reply {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Reply {
+    fn json(body: String) -> Self {
+        Reply { status: 200, content_type: "application/json", body: body.into_bytes() }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Reply { status, content_type: "text/plain", body: message.into().into_bytes() }
+    }
+}
+
+#[derive(Deserialize)]
+struct PoseUpdate {
+    t: [f64; 3],
+    r: [f64; 3],
+    #[serde(default)]
+    parent: Option<String>,
+}
+
+enum Job {
+    Tree,
+    SetPose(String, PoseUpdate),
+    Screenshot,
+}
+
+/// Requests waiting for the main thread, each with the channel to answer on.
+#[derive(Resource)]
+pub struct Jobs(Mutex<Receiver<(Job, Sender<Reply>)>>);
+
+/// Starts the server on a background thread and registers the system answering it.
+pub fn serve(app: &mut App, addr: SocketAddr) {
+    let server = match tiny_http::Server::http(addr) {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("http: cannot listen on {}: {}", addr, e);
+            return;
+        }
+    };
+    let (tx, rx) = mpsc::channel();
+    app.insert_resource(Jobs(Mutex::new(rx))).add_systems(Update, handle_jobs);
+
+    thread::spawn(move || {
+        for mut request in server.incoming_requests() {
+            let reply = match route(&mut request) {
+                Ok(job) => {
+                    let (reply_tx, reply_rx) = mpsc::channel();
+                    if tx.send((job, reply_tx)).is_err() {
+                        break;
+                    }
+                    reply_rx
+                        .recv_timeout(TIMEOUT)
+                        .unwrap_or_else(|_| Reply::error(503, "viewer did not respond"))
+                }
+                Err(reply) => reply,
+            };
+            let header = tiny_http::Header::from_bytes("Content-Type", reply.content_type)
+                .expect("static header is valid");
+            let response = tiny_http::Response::from_data(reply.body)
+                .with_status_code(reply.status)
+                .with_header(header);
+            if let Err(e) = request.respond(response) {
+                eprintln!("http: {}", e);
+            }
+        }
+    });
+}
+
+/// Decodes `%XX` escapes; `None` for a malformed escape or if the result
+/// is not UTF-8.
+pub fn percent_decode(text: &str) -> Option<String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+fn route(request: &mut tiny_http::Request) -> Result<Job, Reply> {
+    use tiny_http::Method;
+
+    let url = request.url().to_string();
+    let path = url.split_once('?').map_or(url.as_str(), |(path, _)| path);
+    match (request.method(), path) {
+        (Method::Get, "/tree") => Ok(Job::Tree),
+        (Method::Get, "/screenshot") => Ok(Job::Screenshot),
+        (Method::Put, path) if path.starts_with("/frames/") => {
+            let name = percent_decode(&path["/frames/".len()..]).ok_or_else(|| Reply::error(400, "malformed frame name"))?;
+            let mut body = String::new();
+            request
+                .as_reader()
+                .read_to_string(&mut body)
+                .map_err(|e| Reply::error(400, e.to_string()))?;
+            let update = serde_json::from_str(&body).map_err(|e| Reply::error(400, e.to_string()))?;
+            Ok(Job::SetPose(name, update))
+        }
+        _ => Err(Reply::error(404, "not found")),
+    }
+}
+
+pub fn handle_jobs(mut commands: Commands, jobs: Res<Jobs>, mut dag: ResMut<TransformTree>) {
+    let Ok(rx) = jobs.0.lock() else {
+        return;
+    };
+    for (job, reply) in rx.try_iter() {
+        match job {
+            Job::Tree => {
+                let body = serde_json::to_string(&FileTransformTree::from(&*dag));
+                let _ = reply.send(match body {
+                    Ok(body) => Reply::json(body),
+                    Err(e) => Reply::error(500, e.to_string()),
+                });
+            }
+            Job::SetPose(name, update) => {
+                let Some(id) = dag.find(&name) else {
+                    let _ = reply.send(Reply::error(404, format!("unknown frame {}", name)));
+                    continue;
+                };
+                let parent = update
+                    .parent
+                    .or_else(|| dag.nodes[id].parent.map(|p| dag.nodes[p].name.clone()));
+                dag.apply(&FileNode { name, parent, t: update.t, r: update.r, ..Default::default() });
+                dag.update_world();
+                let _ = reply.send(Reply::json("{}".to_string()));
+            }
+            Job::Screenshot => {
+                commands
+                    .spawn(Screenshot::primary_window())
+                    .observe(move |captured: On<ScreenshotCaptured>| {
+                        let _ = reply.send(png(&captured.image));
+                    });
+            }
+        }
+    }
+}
+
+fn png(image: &Image) -> Reply {
+    let dynamic = match image.clone().try_into_dynamic() {
+        Ok(dynamic) => dynamic,
+        Err(e) => return Reply::error(500, e.to_string()),
+    };
+    let mut bytes = Cursor::new(vec![]);
+    match dynamic.to_rgba8().write_to(&mut bytes, image::ImageFormat::Png) {
+        Ok(()) => Reply { status: 200, content_type: "image/png", body: bytes.into_inner() },
+        Err(e) => Reply::error(500, e.to_string()),
+    }
+}
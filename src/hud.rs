@@ -0,0 +1,262 @@
+//! Screen-corner aids for reading the main view: a compass showing where the
+//! world axes point, a map-style scale bar for distances at the focus, tree
+//! and frame statistics, and a top-down mini-map of the whole scene.
+
+use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+use bevy_panorbit_camera::PanOrbitCamera;
+
+use crate::camera::{CameraFocus, MainCamera};
+use crate::grid::GridSettings;
+use crate::style::Style;
+use crate::TransformTree;
+
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct HudSettings {
+    pub compass: bool,
+    pub scale_bar: bool,
+    /// Node counts, update cost and frame rate; toggled with F3.
+    pub stats: bool,
+    /// Top-down overview with the camera's footprint; toggled with F4.
+    pub minimap: bool,
+}
+
+impl Default for HudSettings {
+    fn default() -> Self {
+        HudSettings { compass: true, scale_bar: true, stats: false, minimap: false }
+    }
+}
+
+/// Longest scale bar on screen, in logical pixels.
+const MAX_BAR: f32 = 120.0;
+/// Compass axis length, in logical pixels.
+const COMPASS: f32 = 32.0;
+/// Mini-map side, in logical pixels.
+const MINIMAP: f32 = 180.0;
+
+/// Largest 1, 2 or 5 times a power of ten not above `max`.
+pub fn nice_length(max: f32) -> f32 {
+    let power = 10f32.powf(max.log10().floor());
+    [5.0, 2.0, 1.0].into_iter().map(|m| m * power).find(|&l| l <= max).unwrap_or(power)
+}
+
+fn length_text(meters: f32) -> String {
+    // Rounding hides float noise from `nice_length`; the values are round already.
+    let (value, unit) = match meters {
+        m if m >= 1000.0 => (m / 1000.0, "km"),
+        m if m >= 1.0 => (m, "m"),
+        m if m >= 0.01 => (m * 100.0, "cm"),
+        m => (m * 1000.0, "mm"),
+    };
+    format!("{} {}", (value * 1000.0).round() / 1000.0, unit)
+}
+
+pub fn draw_hud(
+    mut contexts: EguiContexts,
+    settings: Res<HudSettings>,
+    style: Res<Style>,
+    camera_q: Query<(&Camera, &GlobalTransform, &Projection, &PanOrbitCamera), With<MainCamera>>,
+) -> Result {
+    if !settings.compass && !settings.scale_bar {
+        return Ok(());
+    }
+    let Ok((camera, transform, projection, orbit)) = camera_q.single() else {
+        return Ok(());
+    };
+    let Some(rect) = camera.logical_viewport_rect() else {
+        return Ok(());
+    };
+    let ctx = contexts.ctx_mut()?;
+    let painter = ctx.layer_painter(egui::LayerId::background());
+    let text = egui::Color32::from_rgb(
+        (style.label[0] * 255.0) as u8,
+        (style.label[1] * 255.0) as u8,
+        (style.label[2] * 255.0) as u8,
+    );
+    if settings.compass {
+        let center = egui::pos2(rect.max.x - COMPASS - 24.0, rect.max.y - COMPASS - 24.0);
+        let rotation = transform.rotation().inverse();
+        let mut axes: Vec<(Vec3, [f32; 3], &str)> = [(Vec3::X, style.x, "X"), (Vec3::Y, style.y, "Y"), (Vec3::Z, style.z, "Z")]
+            .into_iter()
+            .map(|(axis, color, name)| (rotation * axis, color, name))
+            .collect();
+        // Camera space looks down -Z: draw the axes pointing away first.
+        axes.sort_by(|a, b| a.0.z.total_cmp(&b.0.z));
+        painter.circle_stroke(center, COMPASS + 6.0, egui::Stroke::new(1.0, text.gamma_multiply(0.3)));
+        for (v, [r, g, b], name) in axes {
+            let color = egui::Color32::from_rgb((r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8);
+            let color = if v.z < 0.0 { color.gamma_multiply(0.5) } else { color };
+            let tip = center + egui::vec2(v.x, -v.y) * COMPASS;
+            painter.line_segment([center, tip], egui::Stroke::new(2.0, color));
+            painter.text(tip, egui::Align2::CENTER_CENTER, name, egui::FontId::proportional(12.0), color);
+        }
+    }
+    if settings.scale_bar
+        && let Projection::Perspective(perspective) = projection
+    {
+        // World size of one logical pixel at the orbit focus.
+        let per_pixel = 2.0 * orbit.radius.unwrap_or(orbit.target_radius) * (perspective.fov / 2.0).tan() / rect.height();
+        let length = nice_length(MAX_BAR * per_pixel);
+        let width = length / per_pixel;
+        let left = egui::pos2(rect.min.x + 16.0, rect.max.y - 40.0);
+        let right = left + egui::vec2(width, 0.0);
+        let stroke = egui::Stroke::new(2.0, text);
+        painter.line_segment([left, right], stroke);
+        painter.line_segment([left, left - egui::vec2(0.0, 6.0)], stroke);
+        painter.line_segment([right, right - egui::vec2(0.0, 6.0)], stroke);
+        painter.text(left - egui::vec2(0.0, 8.0), egui::Align2::LEFT_BOTTOM, length_text(length), egui::FontId::proportional(12.0), text);
+    }
+    Ok(())
+}
+
+/// Number of levels below the deepest root.
+fn depth(dag: &TransformTree) -> usize {
+    let mut depths = vec![0; dag.nodes.len()];
+    let mut deepest = 0;
+    for id in dag.topological_order() {
+        if let Some(p) = dag.nodes[id].parent {
+            depths[id] = depths[p] + 1;
+            deepest = deepest.max(depths[id]);
+        }
+    }
+    deepest
+}
+
+pub fn toggle_stats(keys: Res<ButtonInput<KeyCode>>, mut settings: ResMut<HudSettings>) {
+    if keys.just_pressed(KeyCode::F3) {
+        settings.stats = !settings.stats;
+    }
+}
+
+pub fn draw_stats(
+    mut contexts: EguiContexts,
+    settings: Res<HudSettings>,
+    mut dag: ResMut<TransformTree>,
+    diagnostics: Res<DiagnosticsStore>,
+) -> Result {
+    // Taken every frame so the counts cover one frame when shown. Reading them
+    // is not a change to the tree.
+    let update = dag.bypass_change_detection().take_update_stats();
+    if !settings.stats {
+        return Ok(());
+    }
+    let fps = diagnostics.get(&FrameTimeDiagnosticsPlugin::FPS).and_then(|d| d.smoothed()).unwrap_or_default();
+    let roots = dag.nodes.iter().filter(|n| n.parent.is_none()).count();
+    egui::Area::new(egui::Id::new("stats"))
+        .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-8.0, 8.0))
+        .interactable(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                egui::Grid::new("stats_grid").show(ui, |ui| {
+                    for (name, value) in [
+                        ("Nodes", dag.nodes.len().to_string()),
+                        ("Depth", depth(&dag).to_string()),
+                        ("Roots", roots.to_string()),
+                        ("Refreshed this frame", update.refreshed.to_string()),
+                        ("update_world", format!("{:.3} ms", update.elapsed.as_secs_f64() * 1000.0)),
+                        ("FPS", format!("{:.0}", fps)),
+                    ] {
+                        ui.label(name);
+                        ui.monospace(value);
+                        ui.end_row();
+                    }
+                });
+            });
+        });
+    Ok(())
+}
+
+pub fn toggle_minimap(keys: Res<ButtonInput<KeyCode>>, mut settings: ResMut<HudSettings>) {
+    if keys.just_pressed(KeyCode::F4) {
+        settings.minimap = !settings.minimap;
+    }
+}
+
+/// Where `ray` meets the plane `height` along `up`. Rays that miss it, or
+/// meet it further than `reach` away, end `reach` out along their heading, so
+/// a view of the horizon still has a bounded footprint.
+pub fn ground_point(ray: Ray3d, up: Vec3, height: f32, reach: f32) -> Vec3 {
+    let hit = ray.intersect_plane(up * height, InfinitePlane3d::new(up));
+    match hit {
+        Some(t) if t <= reach => ray.get_point(t),
+        _ => {
+            let heading = ray.direction.reject_from_normalized(up).normalize_or_zero();
+            let p = ray.origin + heading * reach;
+            p + up * (height - p.dot(up))
+        }
+    }
+}
+
+/// Top-down view of every frame, looking down the grid's up axis like the
+/// split view's top pane, with the area the main camera sees on the plane of
+/// its focus. Clicking moves the focus there.
+pub fn draw_minimap(
+    mut contexts: EguiContexts,
+    settings: Res<HudSettings>,
+    style: Res<Style>,
+    dag: Res<TransformTree>,
+    grid: Res<GridSettings>,
+    mut focus: ResMut<CameraFocus>,
+    camera_q: Query<(&Camera, &GlobalTransform, &PanOrbitCamera), With<MainCamera>>,
+) -> Result {
+    if !settings.minimap {
+        return Ok(());
+    }
+    let Ok((camera, transform, orbit)) = camera_q.single() else {
+        return Ok(());
+    };
+    let Some(view) = camera.logical_viewport_rect() else {
+        return Ok(());
+    };
+    let radius = orbit.radius.unwrap_or(orbit.target_radius);
+    let plane = grid.plane;
+    let (up, height) = (plane.up(), orbit.focus.dot(plane.up()));
+    let footprint: Vec<Vec3> = [view.min, Vec2::new(view.max.x, view.min.y), view.max, Vec2::new(view.min.x, view.max.y)]
+        .into_iter()
+        .filter_map(|corner| camera.viewport_to_world(transform, corner).ok())
+        .map(|ray| ground_point(ray, up, height, 10.0 * radius))
+        .collect();
+    let eye = transform.translation();
+    // The map spans the frames, the footprint and the eye, squared up and padded.
+    let points = dag.nodes.iter().map(|n| n.world.translation.to_vec3()).chain(footprint.iter().copied()).chain([eye]);
+    let (min, max) = points.fold((Vec2::MAX, Vec2::MIN), |(min, max), p| (min.min(plane.top_down(p)), max.max(plane.top_down(p))));
+    let center = (min + max) * 0.5;
+    let half = ((max - min).max_element() * 0.55).max(1e-3);
+    let to_map = |rect: egui::Rect, p: Vec3| {
+        let uv = (plane.top_down(p) - center) / half * 0.5 + 0.5;
+        egui::pos2(rect.min.x + uv.x * rect.width(), rect.min.y + uv.y * rect.height())
+    };
+    let color = |[r, g, b]: [f32; 3]| egui::Color32::from_rgb((r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8);
+    let text = color(style.label);
+    egui::Area::new(egui::Id::new("minimap"))
+        .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-8.0, -(2.0 * COMPASS + 56.0)))
+        .show(contexts.ctx_mut()?, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                let (response, painter) = ui.allocate_painter(egui::vec2(MINIMAP, MINIMAP), egui::Sense::click());
+                let rect = response.rect;
+                let seen: Vec<egui::Pos2> = footprint.iter().map(|&p| to_map(rect, p)).collect();
+                if seen.len() == 4 {
+                    painter.add(egui::Shape::convex_polygon(seen, text.gamma_multiply(0.1), egui::Stroke::new(1.0, text.gamma_multiply(0.6))));
+                }
+                let link = color(style.link);
+                for node in dag.nodes.iter().filter(|n| n.visible()) {
+                    let p = to_map(rect, node.world.translation.to_vec3());
+                    if let Some(parent) = node.parent {
+                        painter.line_segment([to_map(rect, dag.nodes[parent].world.translation.to_vec3()), p], egui::Stroke::new(1.0, link.gamma_multiply(0.5)));
+                    }
+                    painter.circle_filled(p, 2.0, link);
+                }
+                painter.circle_filled(to_map(rect, eye), 4.0, color(style.x));
+                painter.circle_stroke(to_map(rect, orbit.focus), 4.0, egui::Stroke::new(1.0, text));
+                if response.clicked()
+                    && let Some(pos) = response.interact_pointer_pos()
+                {
+                    let uv = Vec2::new((pos.x - rect.min.x) / rect.width(), (pos.y - rect.min.y) / rect.height());
+                    let p = center + (uv - 0.5) * 2.0 * half;
+                    focus.focus_on(plane.from_top_down(p, height), None);
+                }
+            });
+        });
+    Ok(())
+}
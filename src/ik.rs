@@ -0,0 +1,146 @@
+//! Experimental inverse kinematics: dragging a frame below movable joints
+//! solves for joint values that bring it to the pointer.
+
+use bevy::prelude::*;
+use bevy_panorbit_camera::PanOrbitCamera;
+use nalgebra::{DMatrix, DVector};
+
+use crate::camera::MainCamera;
+use crate::joint::{Joint, JointType};
+use crate::{FrameSphere, NodeId, TransformTree};
+
+/// Damping of the least squares step; keeps the solve stable near singular poses.
+const DAMPING: f32 = 0.1;
+const ITERATIONS: usize = 20;
+
+#[derive(Resource, Debug, Default)]
+pub struct IkDrag {
+    pub enabled: bool,
+    /// Frame being dragged.
+    active: Option<NodeId>,
+}
+
+/// Movable joints from the root down to `end`, the ones that can move it.
+pub fn chain(dag: &TransformTree, end: NodeId) -> Vec<NodeId> {
+    let mut joints = vec![];
+    let mut cur = Some(end);
+    while let Some(id) = cur {
+        if dag.nodes[id].joint.as_ref().is_some_and(Joint::is_movable) {
+            joints.push(id);
+        }
+        cur = dag.nodes[id].parent;
+    }
+    joints.reverse();
+    joints
+}
+
+/// Moves the joints above `end` to bring its origin as close to `target` as
+/// they can, by damped least squares on the positional Jacobian. Declared
+/// limits are respected. Returns the remaining distance.
+pub fn solve(dag: &mut TransformTree, end: NodeId, target: Vec3) -> f32 {
+    let joints = chain(dag, end);
+    let mut error = target - dag.nodes[end].world.translation.to_vec3();
+    for _ in 0..ITERATIONS {
+        if joints.is_empty() || error.length() < 1e-4 {
+            break;
+        }
+        let p = dag.nodes[end].world.translation.to_vec3();
+        // A revolute joint moves the end frame around its axis, a prismatic
+        // one along it.
+        let columns: Vec<Vec3> = joints
+            .iter()
+            .map(|&id| {
+                let node = &dag.nodes[id];
+                let Some(joint) = &node.joint else {
+                    return Vec3::ZERO;
+                };
+                let axis = node.world.rotation * joint.axis;
+                match joint.kind {
+                    JointType::Prismatic => axis,
+                    _ => axis.cross(p - node.world.translation.to_vec3()),
+                }
+            })
+            .collect();
+        let j = DMatrix::from_fn(3, joints.len(), |r, c| columns[c][r]);
+        let e = DVector::from_column_slice(&error.to_array());
+        let jjt = &j * j.transpose() + DMatrix::identity(3, 3) * (DAMPING * DAMPING);
+        let Some(y) = jjt.lu().solve(&e) else {
+            break;
+        };
+        let step = j.transpose() * y;
+        for (i, &id) in joints.iter().enumerate() {
+            let Some(joint) = &dag.nodes[id].joint else {
+                continue;
+            };
+            let mut value = joint.value + step[i];
+            if let (Some((lo, hi)), JointType::Revolute | JointType::Prismatic) = (joint.limits, joint.kind) {
+                value = value.clamp(lo, hi);
+            }
+            dag.set_joint(id, value);
+        }
+        dag.update_world();
+        error = target - dag.nodes[end].world.translation.to_vec3();
+    }
+    error.length()
+}
+
+/// Starts an IK drag on a frame that has movable joints above it, holding the
+/// camera still meanwhile.
+pub fn on_drag_start(
+    drag: On<Pointer<DragStart>>,
+    sphere_q: Query<&FrameSphere>,
+    dag: Res<TransformTree>,
+    mut ik: ResMut<IkDrag>,
+    mut orbit_q: Query<&mut PanOrbitCamera, With<MainCamera>>,
+) {
+    if !ik.enabled || drag.button != PointerButton::Primary {
+        return;
+    }
+    let Ok(sphere) = sphere_q.get(drag.entity) else {
+        return;
+    };
+    if chain(&dag, sphere.node).is_empty() {
+        return;
+    }
+    ik.active = Some(sphere.node);
+    for mut orbit in &mut orbit_q {
+        orbit.enabled = false;
+    }
+}
+
+/// Solves for the pointer's position on the plane through the dragged frame
+/// facing the camera.
+pub fn on_drag(
+    drag: On<Pointer<Drag>>,
+    ik: Res<IkDrag>,
+    mut dag: ResMut<TransformTree>,
+    camera_q: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) {
+    let Some(end) = ik.active else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = camera_q.single() else {
+        return;
+    };
+    let offset = camera.logical_viewport_rect().map_or(Vec2::ZERO, |rect| rect.min);
+    let Ok(ray) = camera.viewport_to_world(camera_transform, drag.pointer_location.position - offset) else {
+        return;
+    };
+    let anchor = dag.nodes[end].world.translation.to_vec3();
+    let Some(distance) = ray.intersect_plane(anchor, InfinitePlane3d::new(camera_transform.forward())) else {
+        return;
+    };
+    solve(&mut dag, end, ray.get_point(distance));
+}
+
+pub fn on_drag_end(
+    _drag: On<Pointer<DragEnd>>,
+    mut ik: ResMut<IkDrag>,
+    mut orbit_q: Query<&mut PanOrbitCamera, With<MainCamera>>,
+) {
+    if ik.active.take().is_some() {
+        for mut orbit in &mut orbit_q {
+            orbit.enabled = true;
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use bevy::asset::RenderAssetUsages;
+use bevy::image::{CompressedImageFormats, ImageSampler, ImageType};
+use bevy::mesh::{Indices, PrimitiveTopology};
+use bevy::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::style::Style;
+use crate::{FileTransformTreeError, FrameMarkers, NodeId, TransformTree};
+
+/// Pinhole camera parameters for a frame, in the optical convention (+Z
+/// forward, +X right, +Y down).
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct FileCamera {
+    /// Horizontal field of view in degrees, used when `k` is not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fov: Option<f64>,
+    /// Image width and height in pixels; 640x480 if not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<[u32; 2]>,
+    /// Row-major 3x3 camera matrix [fx 0 cx; 0 fy cy; 0 0 1].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub k: Option<[f64; 9]>,
+    /// How far out to draw the frustum; a few axis lengths if not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depth: Option<f64>,
+    /// Image shown on a plane inside the frustum, relative to the tree file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    /// Distance of the image plane along the optical axis; the frustum depth if not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_distance: Option<f64>,
+}
+
+/// An image shown in a camera frame's frustum.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImagePlane {
+    pub path: String,
+    pub distance: Option<f32>,
+}
+
+impl ImagePlane {
+    pub fn from_file(camera: &FileCamera) -> Option<Self> {
+        let path = camera.image.clone()?;
+        Some(ImagePlane { path, distance: camera.image_distance.or(camera.depth).map(|d| d as f32) })
+    }
+}
+
+#[derive(Component)]
+pub struct ImagePlaneQuad {
+    pub node: NodeId,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Intrinsics {
+    pub fx: f32,
+    pub fy: f32,
+    pub cx: f32,
+    pub cy: f32,
+    pub width: f32,
+    pub height: f32,
+    pub depth: Option<f32>,
+}
+
+const FRUSTUM_COLOR: Color = Color::srgb(0.0, 0.8, 0.8);
+
+impl Intrinsics {
+    pub fn from_file(name: &str, camera: &FileCamera) -> Result<Self, FileTransformTreeError> {
+        let invalid = |msg: &str| FileTransformTreeError::Intrinsics(format!("{}: {}", name, msg));
+        let [width, height] = camera.resolution.unwrap_or([640, 480]).map(|v| v as f32);
+        if width <= 0.0 || height <= 0.0 {
+            return Err(invalid("resolution must be positive"));
+        }
+        let (fx, fy, cx, cy) = match (camera.k, camera.fov) {
+            (Some(k), _) => (k[0] as f32, k[4] as f32, k[2] as f32, k[5] as f32),
+            (None, fov) => {
+                let fov = fov.unwrap_or(60.0);
+                if fov <= 0.0 || fov >= 180.0 {
+                    return Err(invalid("fov must be between 0 and 180 degrees"));
+                }
+                let f = width * 0.5 / (fov.to_radians() as f32 * 0.5).tan();
+                (f, f, width * 0.5, height * 0.5)
+            }
+        };
+        if fx <= 0.0 || fy <= 0.0 {
+            return Err(invalid("focal lengths must be positive"));
+        }
+        Ok(Intrinsics { fx, fy, cx, cy, width, height, depth: camera.depth.map(|d| d as f32) })
+    }
+
+    /// The camera as written in tree files, with the image shown in its frustum.
+    pub fn to_file(&self, image: Option<&ImagePlane>) -> FileCamera {
+        let k = [self.fx, 0.0, self.cx, 0.0, self.fy, self.cy, 0.0, 0.0, 1.0].map(f64::from);
+        FileCamera {
+            fov: None,
+            resolution: Some([self.width as u32, self.height as u32]),
+            k: Some(k),
+            depth: self.depth.map(f64::from),
+            image: image.map(|plane| plane.path.clone()),
+            image_distance: image.and_then(|plane| plane.distance).map(f64::from),
+        }
+    }
+
+    /// Vertical field of view in radians.
+    pub fn vertical_fov(&self) -> f32 {
+        (self.cy / self.fy).atan() + ((self.height - self.cy) / self.fy).atan()
+    }
+
+    /// Image corners (top left, top right, bottom right, bottom left) at
+    /// `depth` along the optical axis, in the camera frame.
+    pub fn corners(&self, depth: f32) -> [Vec3; 4] {
+        [(0.0, 0.0), (self.width, 0.0), (self.width, self.height), (0.0, self.height)]
+            .map(|(u, v)| Vec3::new((u - self.cx) / self.fx, (v - self.cy) / self.fy, 1.0) * depth)
+    }
+}
+
+/// Wireframe view frustum of every visible frame with camera intrinsics, with
+/// a triangle on the top edge marking the image's up direction.
+pub fn draw_frustums(dag: Res<TransformTree>, style: Res<Style>, mut gizmos: Gizmos) {
+    for node in dag.nodes.iter().filter(|n| n.visible()) {
+        let Some(camera) = &node.camera else {
+            continue;
+        };
+        let depth = camera.depth.unwrap_or(style.axis_scale * 3.0);
+        let o = node.world.translation.to_vec3();
+        let corners = camera.corners(depth).map(|c| node.world * c);
+        for (i, &corner) in corners.iter().enumerate() {
+            gizmos.line(o, corner, FRUSTUM_COLOR);
+            gizmos.line(corner, corners[(i + 1) % 4], FRUSTUM_COLOR);
+        }
+        let (left, right) = (corners[0], corners[1]);
+        let up = (corners[0] - corners[3]).normalize_or_zero() * left.distance(right) * 0.15;
+        let mid = left.midpoint(right);
+        gizmos.line(left.lerp(mid, 0.7), mid + up, FRUSTUM_COLOR);
+        gizmos.line(right.lerp(mid, 0.7), mid + up, FRUSTUM_COLOR);
+    }
+}
+
+/// Rotation from a ROS body frame (X forward, Y left, Z up) to the optical
+/// frame of the same camera (Z forward, X right, Y down).
+pub const BODY_TO_OPTICAL: Quat = Quat::from_xyzw(-0.5, 0.5, -0.5, 0.5);
+
+const OPTICAL_COLOR: Color = Color::srgb(1.0, 0.85, 0.2);
+
+impl TransformTree {
+    /// Adds `<name>_optical` under `id`, turned by `BODY_TO_OPTICAL` and
+    /// marked optical.
+    pub fn add_optical_frame(&mut self, id: NodeId) -> Result<NodeId, FileTransformTreeError> {
+        let name = format!("{}_optical", self.nodes[id].name);
+        if self.find(&name).is_some() {
+            return Err(FileTransformTreeError::Duplicate(name));
+        }
+        let optical = self.add_node(&name, crate::origin::widen(Isometry3d::from_rotation(BODY_TO_OPTICAL)), Some(id));
+        self.nodes[optical].optical = true;
+        self.update_world();
+        Ok(optical)
+    }
+}
+
+/// Marks optical frames with their viewing direction along Z and a small
+/// image outline whose corner tick sits at the image origin (top left, X
+/// right, Y down), so they can't be mistaken for body frames.
+pub fn draw_optical_axes(dag: Res<TransformTree>, style: Res<Style>, mut gizmos: Gizmos) {
+    let s = style.axis_scale;
+    for node in dag.nodes.iter().filter(|n| n.optical && n.visible()) {
+        let world = node.world;
+        let o = world.translation.to_vec3();
+        gizmos.arrow(o, world * (Vec3::Z * s * 2.0), OPTICAL_COLOR);
+        let corners = [Vec3::new(-0.6, -0.4, 1.0), Vec3::new(0.6, -0.4, 1.0), Vec3::new(0.6, 0.4, 1.0), Vec3::new(-0.6, 0.4, 1.0)]
+            .map(|c| world * (c * s));
+        for (i, &corner) in corners.iter().enumerate() {
+            gizmos.line(corner, corners[(i + 1) % 4], OPTICAL_COLOR);
+        }
+        let (x, y) = (world.rotation * Vec3::X * s * 0.25, world.rotation * Vec3::Y * s * 0.25);
+        gizmos.line(corners[0], corners[0] + x, OPTICAL_COLOR);
+        gizmos.line(corners[0], corners[0] + y, OPTICAL_COLOR);
+    }
+}
+
+fn load_image(path: &Path) -> anyhow::Result<Image> {
+    let bytes = std::fs::read(path)?;
+    let extension = crate::formats::extension(path);
+    Ok(Image::from_buffer(
+        &bytes,
+        ImageType::Extension(&extension),
+        CompressedImageFormats::NONE,
+        true,
+        ImageSampler::default(),
+        RenderAssetUsages::default(),
+    )?)
+}
+
+/// Quad spanning the image at `distance` along the optical axis.
+fn image_quad(camera: &Intrinsics, distance: f32) -> Mesh {
+    let corners = camera.corners(distance);
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, corners.map(|c| c.to_array()).to_vec())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 0.0, -1.0]; 4])
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]])
+        .with_inserted_indices(Indices::U32(vec![0, 1, 2, 0, 2, 3]))
+}
+
+/// Loads each camera frame's image once and shows it on a translucent quad
+/// parented to the frame.
+pub fn sync_image_planes(
+    mut commands: Commands,
+    dag: Res<TransformTree>,
+    style: Res<Style>,
+    markers: Res<FrameMarkers>,
+    mut spawned: Local<HashMap<NodeId, Entity>>,
+    mut quad_q: Query<(&ImagePlaneQuad, &mut Visibility)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    if !dag.is_changed() {
+        return;
+    }
+    for (id, node) in dag.nodes.iter().enumerate() {
+        let (Some(camera), Some(plane), false) = (&node.camera, &node.image_plane, spawned.contains_key(&id)) else {
+            continue;
+        };
+        let Some(frame) = markers.entity(id) else {
+            continue;
+        };
+        let image = match load_image(Path::new(&plane.path)) {
+            Ok(image) => image,
+            Err(e) => {
+                eprintln!("{}: {}: {:#}", node.name, plane.path, e);
+                // Don't retry on every change.
+                spawned.insert(id, Entity::PLACEHOLDER);
+                continue;
+            }
+        };
+        let distance = plane.distance.unwrap_or(style.axis_scale * 3.0);
+        let entity = commands
+            .spawn((
+                ImagePlaneQuad { node: id },
+                Mesh3d(meshes.add(image_quad(camera, distance))),
+                MeshMaterial3d(materials.add(StandardMaterial {
+                    base_color: Color::srgba(1.0, 1.0, 1.0, 0.85),
+                    base_color_texture: Some(images.add(image)),
+                    unlit: true,
+                    double_sided: true,
+                    cull_mode: None,
+                    alpha_mode: AlphaMode::Blend,
+                    ..default()
+                })),
+                Transform::default(),
+                Pickable::IGNORE,
+                ChildOf(frame),
+            ))
+            .id();
+        spawned.insert(id, entity);
+    }
+    for (quad, mut visibility) in &mut quad_q {
+        let shown = dag.nodes[quad.node].visible();
+        visibility.set_if_neq(if shown { Visibility::Inherited } else { Visibility::Hidden });
+    }
+}
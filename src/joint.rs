@@ -0,0 +1,162 @@
+use std::f64::consts::PI;
+
+use bevy::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::style::Style;
+use crate::{NodeId, TransformTree};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum JointType {
+    Revolute,
+    Continuous,
+    Prismatic,
+    Fixed,
+}
+
+/// Joint connecting a node to its parent, as written in tree files.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FileJoint {
+    #[serde(rename = "type")]
+    pub kind: JointType,
+    #[serde(default = "default_axis")]
+    pub axis: [f64; 3],
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limits: Option<[f64; 2]>,
+    /// Initial value, in radians or meters; zero when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
+}
+
+impl JointType {
+    /// Name as written in tree, URDF and SDF files.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JointType::Revolute => "revolute",
+            JointType::Continuous => "continuous",
+            JointType::Prismatic => "prismatic",
+            JointType::Fixed => "fixed",
+        }
+    }
+}
+
+const AXIS_COLOR: Color = Color::srgb(1.0, 0.6, 0.0);
+const LIMIT_COLOR: Color = Color::srgb(1.0, 0.85, 0.3);
+
+fn default_axis() -> [f64; 3] {
+    [0.0, 0.0, 1.0]
+}
+
+/// Joint state of a node. The node's local transform is `origin * motion(value)`.
+#[derive(Debug, Clone)]
+pub struct Joint {
+    pub kind: JointType,
+    pub axis: Vec3,
+    pub limits: Option<(f32, f32)>,
+    pub origin: Isometry3d,
+    pub value: f32,
+}
+
+impl Joint {
+    pub fn new(joint: &FileJoint, origin: Isometry3d) -> Self {
+        let [x, y, z] = joint.axis;
+        Joint {
+            kind: joint.kind,
+            axis: Vec3::new(x as f32, y as f32, z as f32).normalize_or(Vec3::Z),
+            limits: joint.limits.map(|[lo, hi]| (lo as f32, hi as f32)),
+            origin,
+            value: joint.value.unwrap_or(0.0) as f32,
+        }
+    }
+
+    /// The joint as written in tree files.
+    pub fn to_file(&self) -> FileJoint {
+        FileJoint {
+            kind: self.kind,
+            axis: self.axis.as_dvec3().to_array(),
+            limits: self.limits.map(|(lo, hi)| [lo as f64, hi as f64]),
+            value: (self.value != 0.0).then_some(self.value as f64),
+        }
+    }
+
+    pub fn is_movable(&self) -> bool {
+        self.kind != JointType::Fixed
+    }
+
+    /// Slider range: the declared limits, or a sensible default per joint type.
+    pub fn range(&self) -> (f32, f32) {
+        match (self.limits, self.kind) {
+            (Some(limits), JointType::Revolute | JointType::Prismatic) => limits,
+            (_, JointType::Prismatic) => (-1.0, 1.0),
+            _ => (-PI as f32, PI as f32),
+        }
+    }
+
+    pub fn motion(&self, value: f32) -> Isometry3d {
+        match self.kind {
+            JointType::Revolute | JointType::Continuous => {
+                Isometry3d::from_rotation(Quat::from_axis_angle(self.axis, value))
+            }
+            JointType::Prismatic => Isometry3d::from_translation(self.axis * value),
+            JointType::Fixed => Isometry3d::IDENTITY,
+        }
+    }
+}
+
+impl TransformTree {
+    pub fn set_joint(&mut self, id: NodeId, value: f32) {
+        let Some(joint) = self.nodes[id].joint.as_mut() else {
+            return;
+        };
+        joint.value = value;
+        let local = joint.origin * joint.motion(value);
+        self.set_local(id, local);
+    }
+}
+
+/// Joint axis of every visible movable frame as an orange arrow, with its
+/// limits drawn in the joint frame (parent pose times joint origin), so they
+/// stay put while the joint moves: an arc between the angle limits of a
+/// revolute joint, a segment between the travel limits of a prismatic one.
+/// A tick from the joint origin marks the current value on the limits.
+pub fn draw_joints(dag: Res<TransformTree>, style: Res<Style>, mut gizmos: Gizmos) {
+    let size = style.axis_scale * 1.5;
+    let segments = 32;
+
+    for node in dag.nodes.iter().filter(|n| n.visible()) {
+        let Some(joint) = node.joint.as_ref().filter(|j| j.is_movable()) else {
+            continue;
+        };
+        let parent = node.parent.map_or(Isometry3d::IDENTITY, |p| dag.nodes[p].world);
+        let frame = parent * joint.origin;
+        let o = frame.translation.to_vec3();
+        let axis = frame.rotation * joint.axis;
+        gizmos.arrow(o, o + axis * size, AXIS_COLOR);
+
+        match (joint.kind, joint.limits) {
+            (JointType::Revolute, Some((lo, hi))) => {
+                let radius = size * 0.5;
+                let u = axis.any_orthonormal_vector();
+                let v = axis.cross(u);
+                let at = |a: f32| o + radius * (a.cos() * u + a.sin() * v);
+                let points = (0..=segments).map(|i| at(lo + (hi - lo) * i as f32 / segments as f32));
+                gizmos.linestrip(points, LIMIT_COLOR);
+                gizmos.line(o, at(lo), LIMIT_COLOR);
+                gizmos.line(o, at(hi), LIMIT_COLOR);
+                gizmos.line(o, at(joint.value), AXIS_COLOR);
+            }
+            (JointType::Prismatic, Some((lo, hi))) => {
+                let tick = axis.any_orthonormal_vector() * size * 0.1;
+                let (a, b) = (o + axis * lo, o + axis * hi);
+                gizmos.line(a, b, LIMIT_COLOR);
+                gizmos.line(a - tick, a + tick, LIMIT_COLOR);
+                gizmos.line(b - tick, b + tick, LIMIT_COLOR);
+                let at = o + axis * joint.value;
+                gizmos.line(at - tick * 2.0, at + tick * 2.0, AXIS_COLOR);
+            }
+            _ => {}
+        }
+    }
+}
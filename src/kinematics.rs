@@ -0,0 +1,102 @@
+//! Forward kinematics: posing a tree from joint values instead of baked
+//! transforms. Values come from a file, the command line or a stream of JSON
+//! objects on standard input, and each moves the named joint's frame to
+//! `origin * motion(value)`.
+
+use std::collections::BTreeMap;
+use std::io::BufRead;
+use std::sync::Mutex;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use anyhow::{Result, bail};
+use bevy::prelude::*;
+
+use crate::TransformTree;
+
+/// Joint values by frame name: radians for revolute and continuous joints,
+/// meters for prismatic ones.
+pub type JointValues = BTreeMap<String, f64>;
+
+/// Parses joint values written either as a JSON object (`{"elbow": 0.5}`) or
+/// as whitespace separated `name=value` pairs, one or more per line.
+pub fn parse_values(text: &str) -> Result<JointValues> {
+    if text.trim_start().starts_with('{') {
+        return Ok(serde_json::from_str(text)?);
+    }
+    text.split_whitespace().map(parse_assignment).collect()
+}
+
+/// Parses a single `name=value` pair, as given to `--joint`.
+pub fn parse_assignment(text: &str) -> Result<(String, f64)> {
+    let Some((name, value)) = text.split_once('=') else {
+        bail!("expected name=value, found {:?}", text);
+    };
+    Ok((name.to_string(), value.trim().parse()?))
+}
+
+impl TransformTree {
+    /// Sets every named joint and recomputes world poses. Names that aren't
+    /// movable joints are skipped and returned, so callers can report them.
+    pub fn set_joints(&mut self, values: &JointValues) -> Vec<String> {
+        let mut unknown = vec![];
+        for (name, &value) in values {
+            match self.find(name).filter(|&id| self.nodes[id].joint.as_ref().is_some_and(|j| j.is_movable())) {
+                Some(id) => self.set_joint(id, value as f32),
+                None => unknown.push(name.clone()),
+            }
+        }
+        self.update_world();
+        unknown
+    }
+}
+
+/// Channel end for streamed joint values.
+#[derive(Resource)]
+pub struct JointReceiver(Mutex<Receiver<JointValues>>);
+
+impl JointReceiver {
+    pub fn new() -> (Sender<JointValues>, Self) {
+        let (tx, rx) = mpsc::channel();
+        (tx, JointReceiver(Mutex::new(rx)))
+    }
+}
+
+/// Reads one JSON object of joint values per line from standard input.
+pub fn spawn_stdin_reader(tx: Sender<JointValues>) {
+    thread::spawn(move || {
+        let stdin = std::io::stdin();
+        for line in stdin.lock().lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    eprintln!("stdin: {}", e);
+                    break;
+                }
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<JointValues>(&line) {
+                Ok(values) => {
+                    if tx.send(values).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => eprintln!("stdin: skipping malformed joint values: {}", e),
+            }
+        }
+    });
+}
+
+/// Applies pending streamed joint values to the tree.
+pub fn apply_joint_values(mut dag: ResMut<TransformTree>, rx: Res<JointReceiver>) {
+    let Ok(rx) = rx.0.lock() else {
+        return;
+    };
+    for values in rx.try_iter() {
+        for name in dag.set_joints(&values) {
+            eprintln!("joints: no movable joint named {}", name);
+        }
+    }
+}
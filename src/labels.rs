@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+
+use bevy::camera::primitives::Frustum;
+use bevy::prelude::*;
+use bevy_panorbit_camera::PanOrbitCamera;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::camera::MainCamera;
+use crate::clip::ClipPlane;
+use crate::culling;
+use crate::explode::FrameOffsets;
+use crate::lod::FrameLod;
+use crate::style::Style;
+use crate::uncertainty::CovarianceEllipsoid;
+use crate::{AxisOverlayLabel, FrameSphere, TNode, TransformTree};
+
+/// Per-node label display overrides from the tree file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct FileLabel {
+    /// Shown instead of the node name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// "r, g, b" in 0..1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<[f32; 3]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub show: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font_size: Option<f32>,
+}
+
+impl FileLabel {
+    /// Overrides the fields that `other` sets.
+    pub fn merge(&mut self, other: &FileLabel) {
+        if other.text.is_some() {
+            self.text = other.text.clone();
+        }
+        if other.color.is_some() {
+            self.color = other.color;
+        }
+        if other.show.is_some() {
+            self.show = other.show;
+        }
+        if other.font_size.is_some() {
+            self.font_size = other.font_size;
+        }
+    }
+}
+
+impl TNode {
+    pub fn label_text(&self) -> String {
+        self.group_summary
+            .clone()
+            .or_else(|| self.label.text.clone())
+            .unwrap_or_else(|| self.name.clone())
+    }
+
+    pub fn label_color(&self, style: &Style) -> Color {
+        self.label.color.map(Color::srgb_from_array).unwrap_or_else(|| style.label_color())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Occlusion {
+    /// Always draw labels at full opacity.
+    Off,
+    /// Draw labels hidden behind geometry at reduced opacity.
+    Fade,
+    /// Don't draw labels hidden behind geometry.
+    Hide,
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct LabelSettings {
+    pub font_size: f32,
+    /// Labels that would shrink below this size are hidden.
+    pub min_font_size: f32,
+    pub occlusion: Occlusion,
+    pub occluded_alpha: f32,
+    /// Nudge overlapping labels apart and connect moved ones to their frame.
+    pub layout: bool,
+}
+
+impl Default for LabelSettings {
+    fn default() -> Self {
+        LabelSettings {
+            font_size: 20.0,
+            min_font_size: 8.0,
+            occlusion: Occlusion::Fade,
+            occluded_alpha: 0.25,
+            layout: true,
+        }
+    }
+}
+
+/// Pushes label text and color changes from the tree onto the label entities,
+/// keeping the opacity `update_labels` manages.
+pub fn sync_label_style(dag: Res<TransformTree>, style: Res<Style>, mut label_q: Query<(&AxisOverlayLabel, &mut Text, &mut TextColor)>) {
+    if !dag.is_changed() && !style.is_changed() {
+        return;
+    }
+    for (label, mut text, mut color) in &mut label_q {
+        let node = &dag.nodes[label.node];
+        let wanted = node.label_text();
+        if text.0 != wanted {
+            text.0 = wanted;
+        }
+        let wanted = node.label_color(&style).with_alpha(color.0.alpha());
+        if color.0 != wanted {
+            color.0 = wanted;
+        }
+    }
+}
+
+/// Positions labels over their frames. Labels farther away than the orbit focus
+/// shrink with distance, and labels whose frame is hidden behind a mesh (as seen
+/// from the camera) fade or hide depending on `LabelSettings::occlusion`.
+pub fn update_labels(
+    dag: Res<TransformTree>,
+    settings: Res<LabelSettings>,
+    lod: Res<FrameLod>,
+    clip: Res<ClipPlane>,
+    offsets: Res<FrameOffsets>,
+    camera_q: Query<(&Camera, &GlobalTransform, &PanOrbitCamera, &Frustum), With<MainCamera>>,
+    sphere_q: Query<(Entity, &FrameSphere)>,
+    ellipsoid_q: Query<(), With<CovarianceEllipsoid>>,
+    mut ray_cast: MeshRayCast,
+    mut label_q: Query<(Entity, &mut Node, &AxisOverlayLabel, &mut Visibility, &mut TextFont, &mut TextColor, &ComputedNode)>,
+    mut gizmos: Gizmos,
+) {
+    let Ok((camera, cam_transform, orbit, frustum)) = camera_q.single() else {
+        return;
+    };
+    let spheres: HashMap<_, _> = sphere_q.iter().map(|(e, s)| (s.node, e)).collect();
+    let eye = cam_transform.translation();
+
+    let mut placed = vec![];
+    for (entity, _, label, mut visibility, mut font, mut color, computed) in &mut label_q {
+        let tnode = &dag.nodes[label.node];
+        let world_pos = offsets.position(&dag, label.node);
+        if !tnode.visible()
+            || tnode.label.show == Some(false)
+            || lod.is_culled(label.node)
+            || clip.clips(world_pos)
+            || !culling::in_view(frustum, world_pos, 0.0)
+        {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+
+        let Ok(pos) = camera.world_to_viewport(cam_transform, world_pos) else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+
+        let distance = eye.distance(world_pos);
+        let focus_distance = orbit.radius.unwrap_or(distance);
+        let base_size = tnode.label.font_size.unwrap_or(settings.font_size);
+        let size = base_size * (focus_distance / distance.max(1e-3)).clamp(0.0, 1.0);
+        if size < settings.min_font_size {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+        if (font.font_size - size).abs() > 0.5 {
+            font.font_size = size;
+        }
+
+        let occluded = settings.occlusion != Occlusion::Off && {
+            let own = spheres.get(&label.node).copied();
+            let filter = |e: Entity| Some(e) != own && !ellipsoid_q.contains(e);
+            let mut ray_settings = MeshRayCastSettings::default().with_filter(&filter);
+            if clip.enabled {
+                // Geometry cut away by the clipping plane doesn't hide anything.
+                ray_settings = ray_settings.never_early_exit();
+            }
+            match Dir3::new(world_pos - eye) {
+                Ok(dir) => ray_cast
+                    .cast_ray(Ray3d::new(eye, dir), &ray_settings)
+                    .iter()
+                    .find(|(_, hit)| !clip.clips(hit.point))
+                    .is_some_and(|(_, hit)| hit.distance < distance - 1e-3),
+                Err(_) => false,
+            }
+        };
+        if occluded && settings.occlusion == Occlusion::Hide {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+        let alpha = if occluded { settings.occluded_alpha } else { 1.0 };
+        if color.0.alpha() != alpha {
+            color.0.set_alpha(alpha);
+        }
+
+        *visibility = Visibility::Visible;
+        let extent = computed.size() * computed.inverse_scale_factor();
+        placed.push(Placement { entity, anchor: pos, extent, distance, world_pos });
+    }
+
+    // Nearest labels claim their spot first; farther ones move out of the way.
+    placed.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+    let mut taken: Vec<Rect> = Vec::with_capacity(placed.len());
+    for p in &placed {
+        let pos = if settings.layout { free_spot(p, &taken) } else { p.anchor };
+        taken.push(Rect::from_corners(pos, pos + p.extent));
+        if pos != p.anchor
+            && let Ok(ray) = camera.viewport_to_world(cam_transform, pos)
+        {
+            // Leader line from the frame to the label, drawn at the frame's depth.
+            let end = ray.get_point(p.distance);
+            gizmos.line(p.world_pos, end, Color::srgba(1.0, 1.0, 1.0, 0.5));
+        }
+        if let Ok((_, mut node, ..)) = label_q.get_mut(p.entity) {
+            node.left = Val::Px(pos.x);
+            node.top = Val::Px(pos.y);
+        }
+    }
+}
+
+struct Placement {
+    entity: Entity,
+    anchor: Vec2,
+    extent: Vec2,
+    distance: f32,
+    world_pos: Vec3,
+}
+
+/// First position stepping away from the anchor, alternating up and down one
+/// label height at a time, that doesn't overlap an already placed label.
+fn free_spot(p: &Placement, taken: &[Rect]) -> Vec2 {
+    let step = p.extent.y.max(1.0);
+    (0..16)
+        .map(|i| {
+            let k = (i + 1) / 2;
+            let dir = if i % 2 == 1 { -1.0 } else { 1.0 };
+            p.anchor + Vec2::new(0.0, dir * k as f32 * step)
+        })
+        .find(|&pos| {
+            let rect = Rect::from_corners(pos, pos + p.extent);
+            taken.iter().all(|t| t.intersect(rect).is_empty())
+        })
+        .unwrap_or(p.anchor)
+}
@@ -0,0 +1,2245 @@
+use std::path::{Path, PathBuf};
+
+use bevy::camera::primitives::Frustum;
+use bevy::app::PluginGroupBuilder;
+use bevy::diagnostic::FrameTimeDiagnosticsPlugin;
+use bevy::math::{DQuat, DVec3};
+use bevy::platform::time::Instant;
+use bevy::prelude::*;
+use bevy_debug_grid::DebugGridPlugin;
+use bevy_egui::{EguiPlugin, EguiPrimaryContextPass};
+use bevy_panorbit_camera::{PanOrbitCamera, PanOrbitCameraPlugin};
+use nalgebra::{Isometry3, Translation3, Vector3};
+use schemars::JsonSchema;
+use serde::{ Deserialize, Serialize };
+use anyhow::Result;
+use std::fs::File;
+use std::io::BufReader;
+use thiserror::Error;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+pub mod average;
+pub mod batched;
+pub mod bookmarks;
+pub mod camera;
+pub mod clip;
+pub mod clouds;
+pub mod collision;
+pub mod config;
+pub mod convention;
+pub mod culling;
+pub mod diff;
+pub mod edit;
+pub mod euler;
+pub mod explode;
+pub mod expr;
+pub mod fiducial;
+pub mod formats;
+pub mod gamepad;
+pub mod geo;
+pub mod grid;
+pub mod groups;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod hud;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod ik;
+pub mod intrinsics;
+pub mod joint;
+pub mod kinematics;
+pub mod labels;
+pub mod links;
+pub mod matrix;
+pub mod lod;
+pub mod pip;
+pub mod plot;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+pub mod origin;
+pub mod print;
+#[cfg(feature = "profile")]
+pub mod profile;
+pub mod recording;
+#[cfg(feature = "rosbridge")]
+pub mod rosbridge;
+pub mod scene;
+pub mod schema;
+pub mod script;
+pub mod stream;
+pub mod selection;
+pub mod smoothing;
+pub mod snippets;
+pub mod split;
+pub mod stereo;
+pub mod stale;
+pub mod style;
+pub mod sweep;
+pub mod timeline;
+pub mod tips;
+pub mod tools;
+pub mod twist;
+pub mod ui;
+pub mod uncertainty;
+pub mod units;
+pub mod video;
+#[cfg(target_arch = "wasm32")]
+pub mod web;
+pub mod workspace;
+#[cfg(feature = "xr")]
+pub mod xr;
+
+
+pub type NodeId = usize;
+
+#[derive(Debug, Clone)]
+pub struct TNode {
+    name: String,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    /// Pose in the parent's frame, in full precision; a root's is its world pose.
+    local_f64: Isometry3<f64>,
+    world_f64: Isometry3<f64>,
+    /// `local_f64` narrowed for rendering and editing, a root's relative to
+    /// the origin.
+    local: Isometry3d,
+    /// `world_f64` relative to the origin, narrowed for rendering.
+    world: Isometry3d,
+    /// Scale from the file, for scene graphs that carry one. Frames with a
+    /// scale other than one are not rigid.
+    scale: Vec3,
+    /// Product of the scales from the root down.
+    world_scale: Vec3,
+    dirty: bool,
+    joint: Option<joint::Joint>,
+    covariance: Option<Mat3>,
+    twist: Option<twist::Twist>,
+    camera: Option<intrinsics::Intrinsics>,
+    image_plane: Option<intrinsics::ImagePlane>,
+    cloud: Option<clouds::FileCloud>,
+    collision: Vec<collision::FileCollision>,
+    /// Parameter expressions in `t` and `r`, re-evaluated when a parameter changes.
+    expressions: Option<expr::NodeExpressions>,
+    label: labels::FileLabel,
+    tags: Vec<String>,
+    metadata: BTreeMap<String, serde_json::Value>,
+    group: Option<String>,
+    /// Other names the node is found by.
+    aliases: Vec<String>,
+    hidden: bool,
+    /// Camera optical frame: Z forward, X right, Y down.
+    optical: bool,
+    tag: Option<fiducial::FileTag>,
+    /// Folded into a collapsed namespace.
+    collapsed: bool,
+    /// Set on the frame standing in for a collapsed namespace.
+    group_summary: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Resource)]
+pub struct TransformTree {
+    nodes: Vec<TNode>,
+    index: HashMap<String, NodeId>,
+    /// Nodes marked dirty since the last `update_world`.
+    changed: Vec<NodeId>,
+    /// Values of the parameters node expressions use.
+    params: expr::Params,
+    /// Units the tree was loaded from. Poses are always in meters.
+    units: units::Units,
+    /// Work done by `update_world` since `take_update_stats`.
+    stats: UpdateStats,
+    /// World position the render copies of the poses are relative to.
+    origin: DVec3,
+    /// Anchor whose local tangent plane is the world.
+    geo_reference: Option<geo::FileGeo>,
+}
+
+/// Nodes refreshed and time spent by `update_world` calls.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UpdateStats {
+    pub refreshed: usize,
+    pub elapsed: Duration,
+}
+
+impl TransformTree {
+    /// Appends a node. Its world pose is computed by the next `update_world`.
+    pub fn add_node(&mut self, name: &str, local: Isometry3<f64>, parent: Option<NodeId>) -> NodeId {
+        let id = self.nodes.len();
+        self.nodes.push(TNode {
+            name: name.to_string(),
+            parent: None,
+            children: vec![],
+            local_f64: local,
+            world_f64: Isometry3::identity(),
+            local: Isometry3d::IDENTITY,
+            world: Isometry3d::IDENTITY,
+            scale: Vec3::ONE,
+            world_scale: Vec3::ONE,
+            dirty: true,
+            joint: None,
+            covariance: None,
+            twist: None,
+            camera: None,
+            image_plane: None,
+            cloud: None,
+            collision: vec![],
+            expressions: None,
+            label: labels::FileLabel::default(),
+            tags: vec![],
+            metadata: BTreeMap::new(),
+            group: None,
+            aliases: vec![],
+            hidden: false,
+            optical: false,
+            tag: None,
+            collapsed: false,
+            group_summary: None,
+        });
+        self.index.insert(name.to_string(), id);
+        self.changed.push(id);
+        if let Some(p) = parent {
+            self.nodes[p].children.push(id);
+            self.nodes[id].parent = Some(p);
+        }
+        self.nodes[id].local = self.narrow_local(id, &local);
+        id
+    }
+    /// Names and aliases to ids; a name or alias used twice is an error.
+    fn name_hash(&self) -> Result<HashMap<String, NodeId>, FileTransformTreeError> {
+        let mut map = HashMap::with_capacity(self.nodes.len());
+        for (id, node) in self.nodes.iter().enumerate() {
+            for name in std::iter::once(&node.name).chain(&node.aliases) {
+                if map.insert(name.clone(), id).is_some() {
+                    return Err(FileTransformTreeError::Duplicate(name.clone()));
+                }
+            }
+        }
+        Ok(map)
+    }
+    /// Units the tree was loaded from, for display; poses are in meters.
+    pub fn units(&self) -> units::Units {
+        self.units
+    }
+    /// Looks a node up by its name or one of its aliases.
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        self.index.get(name).copied()
+    }
+    fn is_ancestor(&self, ancestor: NodeId, id: NodeId) -> bool {
+        let mut cur = Some(id);
+        while let Some(n) = cur {
+            if n == ancestor {
+                return true;
+            }
+            cur = self.nodes[n].parent;
+        }
+        false
+    }
+    /// `id` and every node below it, parents before their children.
+    pub fn subtree(&self, id: NodeId) -> Vec<NodeId> {
+        let mut nodes = vec![id];
+        let mut i = 0;
+        while i < nodes.len() {
+            nodes.extend(&self.nodes[nodes[i]].children);
+            i += 1;
+        }
+        nodes
+    }
+    /// Pose of `id` expressed in the frame of `reference`.
+    pub fn relative(&self, reference: NodeId, id: NodeId) -> Isometry3d {
+        origin::narrow(&(self.nodes[reference].world_f64.inverse() * self.nodes[id].world_f64), DVec3::ZERO)
+    }
+    /// Sets the local pose from its render copy, which for a root is relative
+    /// to the origin.
+    pub fn set_local(&mut self, id: NodeId, local: Isometry3d) {
+        let local = self.widen_local(id, local);
+        self.set_local_f64(id, local);
+    }
+    /// Sets the local pose in full precision; a root's is its world pose.
+    pub fn set_local_f64(&mut self, id: NodeId, local: Isometry3<f64>) {
+        self.nodes[id].local = self.narrow_local(id, &local);
+        self.nodes[id].local_f64 = local;
+        self.mark_dirty(id);
+    }
+    /// Applies a single node description to the tree, creating the node (and an
+    /// identity placeholder for an unknown parent) if it doesn't exist yet.
+    /// An existing node whose update leaves out the parent keeps its current
+    /// one. A `"parent": null` reads the same as a missing one, so updates
+    /// can move a frame under another but not make it a root again.
+    pub fn apply(&mut self, node: &FileNode) {
+        let local = match self.local_from_file(node) {
+            Ok(local) => local,
+            Err(e) => {
+                eprintln!("{:?}", e);
+                return;
+            }
+        };
+        let parent = node.parent.as_ref().map(|p| match self.find(p) {
+            Some(id) => id,
+            None => self.add_node(p, Isometry3::identity(), None),
+        });
+        let id = match self.find(&node.name) {
+            Some(id) => {
+                self.set_local_f64(id, local);
+                id
+            }
+            None => self.add_node(&node.name, local, None),
+        };
+        if let Err(e) = self.set_attributes(id, node) {
+            eprintln!("{:?}", e);
+        }
+        if parent.is_some() && self.nodes[id].parent != parent {
+            match parent {
+                Some(p) if self.is_ancestor(id, p) => {
+                    eprintln!("Ignoring parent {:?} for {}: would create a cycle", node.parent, node.name);
+                }
+                _ => self.set_parent(id, parent),
+            }
+        }
+        if node.geo.is_some() {
+            self.place_anchors(std::slice::from_ref(node));
+        }
+    }
+    /// Copies the optional per-node data of a file entry onto an existing node.
+    /// Fields the entry leaves out are kept as they are.
+    fn set_attributes(&mut self, id: NodeId, node: &FileNode) -> Result<(), FileTransformTreeError> {
+        for alias in &node.aliases {
+            match self.index.get(alias) {
+                Some(&other) if other != id => return Err(FileTransformTreeError::Duplicate(alias.clone())),
+                Some(_) => {}
+                None => {
+                    self.index.insert(alias.clone(), id);
+                    self.nodes[id].aliases.push(alias.clone());
+                }
+            }
+        }
+        if let Some(j) = &node.joint {
+            // The entry may not be attached to its parent yet. Its joint origin
+            // is a render pose, so a root's is relative to the origin.
+            let offset = if node.parent.is_some() { DVec3::ZERO } else { self.origin };
+            let joint = joint::Joint::new(j, origin::narrow(&self.nodes[id].local_f64, offset));
+            if joint.value != 0.0 {
+                let local = Translation3::new(offset.x, offset.y, offset.z) * origin::widen(joint.origin * joint.motion(joint.value));
+                self.set_local_f64(id, local);
+            }
+            self.nodes[id].joint = Some(joint);
+        }
+        let n = &mut self.nodes[id];
+        if let Some(cov) = &node.covariance {
+            n.covariance = Some(uncertainty::positional_covariance(&node.name, cov)?);
+        }
+        if let Some(twist) = &node.twist {
+            n.twist = Some(twist.into());
+        }
+        if let Some(camera) = &node.camera {
+            n.camera = Some(intrinsics::Intrinsics::from_file(&node.name, camera)?);
+            n.image_plane = intrinsics::ImagePlane::from_file(camera);
+        }
+        if let Some(cloud) = &node.cloud {
+            n.cloud = Some(cloud.clone());
+        }
+        if !node.collision.is_empty() {
+            n.collision = node.collision.clone();
+        }
+        if let Some(expressions) = &node.expressions {
+            let parsed = expr::NodeExpressions::new(node.t, node.r, node.euler, expressions)
+                .map_err(|e| FileTransformTreeError::Expression(format!("{}: {}", node.name, e)))?;
+            n.expressions = Some(parsed);
+        }
+        if let Some(label) = &node.label {
+            n.label.merge(label);
+        }
+        if !node.tags.is_empty() {
+            n.tags = node.tags.clone();
+        }
+        if node.group.is_some() {
+            n.group = node.group.clone();
+        }
+        if node.optical {
+            n.optical = true;
+        }
+        if let Some(tag) = &node.tag {
+            if n.label.text.is_none() {
+                n.label.text = Some(format!("tag {}", tag.id));
+            }
+            n.tag = Some(tag.clone());
+        }
+        if let Some(s) = node.s {
+            n.scale = DVec3::from_array(s).as_vec3();
+        }
+        n.metadata.extend(node.metadata.iter().map(|(k, v)| (k.clone(), v.clone())));
+        Ok(())
+    }
+    /// Moves `id` under `parent`, keeping its local pose. The caller keeps the
+    /// tree acyclic.
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) {
+        if let Some(p) = self.nodes[id].parent.take() {
+            self.nodes[p].children.retain(|&c| c != id);
+        }
+        if let Some(p) = parent {
+            self.nodes[p].children.push(id);
+            self.nodes[id].parent = Some(p);
+        }
+        self.mark_dirty(id);
+    }
+    /// Flags `id` for `update_world`, which also refreshes everything below it.
+    pub fn mark_dirty(&mut self, id: NodeId) {
+        if !self.nodes[id].dirty {
+            self.nodes[id].dirty = true;
+            self.changed.push(id);
+        }
+    }
+    /// Every node reachable from a root, parents before their children. Roots
+    /// come in id order and siblings in the order they were attached, so the
+    /// order doesn't depend on where parents appear in the input. Nodes on a
+    /// parent cycle are left out.
+    pub fn topological_order(&self) -> Vec<NodeId> {
+        let mut order: Vec<NodeId> = (0..self.nodes.len())
+            .filter(|&i| self.nodes[i].parent.is_none())
+            .collect();
+        let mut i = 0;
+        while i < order.len() {
+            order.extend(self.nodes[order[i]].children.iter().copied());
+            i += 1;
+        }
+        order
+    }
+    /// Recomputes the world pose of dirty nodes and of everything below them,
+    /// visiting only those subtrees.
+    pub fn update_world(&mut self) {
+        let _span = info_span!("update_world", changed = self.changed.len()).entered();
+        let start = Instant::now();
+        let changed = std::mem::take(&mut self.changed);
+        let mut stack = vec![];
+        for id in changed {
+            if !self.nodes[id].dirty {
+                // Already refreshed as part of a dirty ancestor's subtree.
+                continue;
+            }
+            // Start from the topmost dirty ancestor so parents are refreshed first.
+            let mut top = id;
+            let mut depth = 0;
+            while let Some(p) = self.nodes[top].parent.filter(|&p| self.nodes[p].dirty) {
+                top = p;
+                depth += 1;
+                if depth > self.nodes.len() {
+                    break; // parent cycle, which `apply` and `TryFrom` refuse to create
+                }
+            }
+            stack.push(top);
+            while let Some(n) = stack.pop() {
+                self.refresh(n);
+                stack.extend(self.nodes[n].children.iter().copied());
+                self.stats.refreshed += 1;
+            }
+        }
+        self.stats.elapsed += start.elapsed();
+    }
+    /// Returns and resets what `update_world` did since the last call.
+    pub fn take_update_stats(&mut self) -> UpdateStats {
+        std::mem::take(&mut self.stats)
+    }
+    /// Recomputes every world pose in topological order. The reference the
+    /// incremental `update_world` is tested and benchmarked against.
+    pub fn update_world_full(&mut self) {
+        self.changed.clear();
+        for id in self.topological_order() {
+            self.refresh(id);
+        }
+    }
+    /// Recomputes the world pose and scale of `n` from its parent's. A
+    /// parent's scale stretches the offsets of its children but not their
+    /// axes, so world poses stay rigid; the shear a non-uniform scale under a
+    /// rotation would add is dropped.
+    /// The world pose is composed in `f64` and only then narrowed, relative
+    /// to the origin, for rendering.
+    fn refresh(&mut self, n: NodeId) {
+        let (parent_world, parent_scale) =
+            self.nodes[n].parent.map_or((Isometry3::identity(), Vec3::ONE), |p| (self.nodes[p].world_f64, self.nodes[p].world_scale));
+        let local = self.scaled_local(n);
+        let render_local = self.narrow_local(n, &self.nodes[n].local_f64);
+        let origin = self.origin;
+        let node = &mut self.nodes[n];
+        node.world_f64 = parent_world * local;
+        node.world = origin::narrow(&node.world_f64, origin);
+        node.local = render_local;
+        node.world_scale = parent_scale * node.scale;
+        node.dirty = false;
+    }
+    /// Local pose of `n` with its offset stretched by its parent's scale, the
+    /// rigid step from the parent's world pose to its own.
+    fn scaled_local(&self, n: NodeId) -> Isometry3<f64> {
+        let node = &self.nodes[n];
+        let mut local = node.local_f64;
+        if let Some(scale) = node.parent.map(|p| self.nodes[p].world_scale).filter(|s| *s != Vec3::ONE) {
+            let scale = scale.as_dvec3();
+            local.translation.vector.component_mul_assign(&Vector3::new(scale.x, scale.y, scale.z));
+        }
+        local
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct FileTransformTree {
+    pub version: u32,
+    /// Length unit of translations; meters when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub units: Option<units::Units>,
+    /// Axis convention of poses; ROS (right-handed, Z up) when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub convention: Option<convention::Convention>,
+    /// Euler order of every node's `r` that doesn't give its own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub euler: Option<euler::EulerOrder>,
+    /// Snap node matrices that are not quite rotations to the nearest one
+    /// instead of refusing them.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub orthonormalize: bool,
+    /// Named values `t` and `r` expressions can use, e.g. {"wheelbase": 1.2}.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: expr::Params,
+    pub nodes: Vec<FileNode>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct FileNode {
+    pub name: String,
+    pub parent: Option<String>,
+    #[serde(default)]
+    pub t: [f64; 3],
+    #[serde(default)]
+    pub r: [f64; 3],
+    /// Scale along the frame's axes, as glTF and CAD scene graphs carry it.
+    /// It stretches the offsets of the children.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub s: Option<[f64; 3]>,
+    /// Row-major 4x4 or 3x4 homogeneous matrix, instead of `t` and `r`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matrix: Option<Vec<f64>>,
+    /// Order of the angles in `r`; intrinsic XYZ when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub euler: Option<euler::EulerOrder>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub joint: Option<joint::FileJoint>,
+    /// Row-major 3x3 positional or 6x6 pose covariance, in the node's frame.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub covariance: Option<Vec<f64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub twist: Option<twist::FileTwist>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub camera: Option<intrinsics::FileCamera>,
+    /// Point cloud drawn in this frame.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cloud: Option<clouds::FileCloud>,
+    /// Collision primitives posed in this frame.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub collision: Vec<collision::FileCollision>,
+    /// Expressions behind `t` and `r` components. Written as strings in place
+    /// of the numbers in tree files, e.g. "t": ["wheelbase / 2", 0, 0].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expressions: Option<expr::FileExpressions>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<labels::FileLabel>,
+    /// Free-form labels for filtering, e.g. "camera".
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Arbitrary user data, shown in the frames panel.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, serde_json::Value>,
+    /// Namespace the frame belongs to, instead of the part of its name before the last '/'.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// Other names updates and parent references may use for this frame.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// Camera optical frame (Z forward, X right, Y down), drawn with its
+    /// viewing direction.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub optical: bool,
+    /// Geodetic position of the frame, replacing `t`; see `geo`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub geo: Option<geo::FileGeo>,
+    /// Fiducial tag at this frame, drawn as a square and labeled with its id.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<fiducial::FileTag>,
+}
+
+impl From<&FileNode> for Isometry3d {
+    fn from(node: &FileNode) -> Self {
+        let [tx, ty, tz] = node.t;
+        Isometry3d::new(Vec3::new(tx as f32, ty as f32, tz as f32), node.rotation().as_quat())
+    }
+}
+
+impl FileNode {
+    /// Rotation `r` stands for in the node's Euler order.
+    pub fn rotation(&self) -> DQuat {
+        self.euler.unwrap_or_default().rotation(self.r)
+    }
+
+    /// Inverse of `Isometry3d::from(&FileNode)`. `r` is written as intrinsic XYZ.
+    pub fn set_pose(&mut self, pose: Isometry3d) {
+        self.set_pose_f64(&origin::widen(pose));
+    }
+
+    /// `set_pose` in full precision.
+    pub fn set_pose_f64(&mut self, pose: &Isometry3<f64>) {
+        let q = pose.rotation;
+        let (r, p, y) = DQuat::from_xyzw(q.i, q.j, q.k, q.w).to_euler(EulerRot::XYZ);
+        self.t = pose.translation.vector.into();
+        self.r = [r, p, y];
+        self.euler = None;
+    }
+}
+
+impl FileTransformTree {
+    /// Gives every node without its own Euler order the file's.
+    pub fn resolve_euler(&mut self) {
+        if let Some(order) = self.euler.take() {
+            for node in self.nodes.iter_mut().filter(|n| n.euler.is_none()) {
+                node.euler = Some(order);
+            }
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::open(path)?;
+        let reader = BufReader::new(file);
+        Ok(serde_json::from_reader(reader)?)
+    }
+    /// Names and aliases to node indices; a name or alias used twice is an error.
+    pub fn name_hash(&self) -> Result<HashMap<String, NodeId>, FileTransformTreeError> {
+        let mut map = HashMap::with_capacity(self.nodes.len());
+        for (id, node) in self.nodes.iter().enumerate() {
+            for name in std::iter::once(&node.name).chain(&node.aliases) {
+                if map.insert(name.clone(), id).is_some() {
+                    return Err(FileTransformTreeError::Duplicate(name.clone()));
+                }
+            }
+        }
+        Ok(map)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum FileTransformTreeError {
+    #[error("Unknown Parent")]
+    ParentMissing(String),
+
+    #[error("Duplicate Name")]
+    Duplicate(String),
+
+    #[error("Serialization Error")]
+    Serialization(String),
+
+    #[error("Invalid Covariance")]
+    Covariance(String),
+
+    #[error("Parent Cycle")]
+    Cycle(String),
+
+    #[error("Invalid Camera Intrinsics")]
+    Intrinsics(String),
+
+    #[error("Invalid Expression")]
+    Expression(String),
+
+    #[error("Invalid Matrix")]
+    Matrix(String),
+}
+
+impl TryFrom<FileTransformTree> for TransformTree {
+    type Error = FileTransformTreeError;
+
+    fn try_from(mut ftree: FileTransformTree) -> Result<Self, Self::Error> {
+        let _span = info_span!("build_tree", nodes = ftree.nodes.len()).entered();
+        // let name_map = ftree.name_hash()?;
+        ftree.resolve_euler();
+        ftree.resolve_matrices()?;
+        let geo_reference = geo::place_roots(&mut ftree.nodes);
+        let mut res = TransformTree { params: ftree.params.clone(), geo_reference, ..Default::default() };
+        for node in ftree.nodes.iter() {
+            let id = res.add_node(node.name.as_str(), res.local_from_file(node)?, None);
+            res.set_attributes(id, node)?;
+        }
+        let name_map = res.name_hash()?;
+        for node in ftree.nodes.iter() {
+            if let Some(p) = node.parent.clone() {
+                let parent = *name_map.get(&p).ok_or(FileTransformTreeError::ParentMissing(p))?;
+                res.set_parent(name_map[&node.name], Some(parent));
+            }
+        }
+        let order = res.topological_order();
+        if order.len() != res.nodes.len() {
+            let mut reached = vec![false; res.nodes.len()];
+            order.iter().for_each(|&id| reached[id] = true);
+            let id = reached.iter().position(|r| !r).unwrap_or_default();
+            return Err(FileTransformTreeError::Cycle(res.nodes[id].name.clone()));
+        }
+        res.update_world();
+        res.place_anchors(&ftree.nodes);
+        let origin = origin::pick_origin(&res);
+        if origin != DVec3::ZERO {
+            res.rebase(origin);
+        }
+        Ok(res)
+    }
+}
+
+/// The tree in file form: current local poses (joint origins, with the joint
+/// values apart), hierarchy, parameters and expressions, and the data each
+/// frame carries.
+impl From<&TransformTree> for FileTransformTree {
+    fn from(dag: &TransformTree) -> Self {
+        let nodes = dag
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(id, n)| {
+                let mut node = FileNode {
+                    name: n.name.clone(),
+                    parent: n.parent.map(|p| dag.nodes[p].name.clone()),
+                    tags: n.tags.clone(),
+                    metadata: n.metadata.clone(),
+                    group: n.group.clone(),
+                    aliases: n.aliases.clone(),
+                    optical: n.optical,
+                    tag: n.tag.clone(),
+                    s: (n.scale != Vec3::ONE).then(|| n.scale.as_dvec3().to_array()),
+                    joint: n.joint.as_ref().map(joint::Joint::to_file),
+                    covariance: n.covariance.map(|c| c.transpose().to_cols_array().map(f64::from).to_vec()),
+                    twist: n.twist.as_ref().map(twist::FileTwist::from),
+                    camera: n.camera.map(|c| c.to_file(n.image_plane.as_ref())),
+                    cloud: n.cloud.clone(),
+                    collision: n.collision.clone(),
+                    label: (n.label != labels::FileLabel::default()).then(|| n.label.clone()),
+                    ..Default::default()
+                };
+                match n.expressions.as_ref().and_then(|e| e.eval(&dag.params).ok().map(|pose| (e, pose))) {
+                    // Expressions give the file's own values, in the node's Euler order.
+                    Some((expressions, (t, r))) => {
+                        (node.t, node.r, node.euler) = (t, r, expressions.euler);
+                        node.expressions = Some(expressions.source.clone());
+                    }
+                    None => {
+                        // A joint's value is written apart from its origin.
+                        node.set_pose_f64(&n.joint.as_ref().map_or(n.local_f64, |j| dag.widen_local(id, j.origin)));
+                    }
+                }
+                node
+            })
+            .collect();
+        FileTransformTree { version: 1, params: dag.params.clone(), nodes, ..Default::default() }
+    }
+}
+
+pub fn load_transform_tree(path: impl AsRef<Path>) -> Result<TransformTree, FileTransformTreeError> {
+    match formats::load(path) {
+        Ok(dag) => TransformTree::try_from(dag),
+        Err(e) => Err(FileTransformTreeError::Serialization(e.to_string())),
+    }
+}
+
+pub fn load_animated_tree(path: impl AsRef<Path>) -> Result<(TransformTree, Option<timeline::Animation>), FileTransformTreeError> {
+    match formats::load_animated(path) {
+        Ok((dag, animation)) => Ok((TransformTree::try_from(dag)?, animation)),
+        Err(e) => Err(FileTransformTreeError::Serialization(e.to_string())),
+    }
+}
+
+pub fn viewer(dag: TransformTree, grid: grid::GridSettings) -> App {
+    viewer_with(dag, grid, DefaultPlugins.build())
+}
+
+/// `viewer` on `plugins`, the default plugins as an integration such as the
+/// OpenXR one has set them up.
+pub fn viewer_with(dag: TransformTree, grid: grid::GridSettings, mut plugins: PluginGroupBuilder) -> App {
+    let mut app = App::new();
+    if bevy::log::tracing::dispatcher::has_been_set() {
+        // `--profile` installed its subscriber already.
+        plugins = plugins.disable::<bevy::log::LogPlugin>();
+    }
+    app.insert_resource(dag)
+        .insert_resource(grid)
+        .init_resource::<Selection>()
+        .init_resource::<labels::LabelSettings>()
+        .init_resource::<bookmarks::Bookmarks>()
+        .init_resource::<style::Style>()
+        .init_resource::<camera::CameraFocus>()
+        .init_resource::<camera::Turntable>()
+        .init_resource::<gamepad::GamepadBindings>()
+        .init_resource::<tools::InterpolationPreview>()
+        .init_resource::<script::ScriptConsole>()
+        .init_resource::<batched::AxisBatching>()
+        .init_resource::<lod::LodSettings>()
+        .init_resource::<lod::FrameLod>()
+        .init_resource::<groups::CollapsedGroups>()
+        .init_resource::<smoothing::Smoothing>()
+        .init_resource::<split::SplitView>()
+        .init_resource::<stereo::Stereo>()
+        .init_resource::<pip::FrameView>()
+        .init_resource::<ik::IkDrag>()
+        .init_resource::<sweep::Sweep>()
+        .init_resource::<hud::HudSettings>()
+        .init_resource::<explode::ExplodedView>()
+        .init_resource::<explode::FrameOffsets>()
+        .init_resource::<diff::Heatmap>()
+        .init_resource::<average::PoseSampler>()
+        .init_resource::<plot::PosePlot>()
+        .init_resource::<edit::EditSettings>()
+        .init_resource::<workspace::Workspace>()
+        .init_resource::<collision::CollisionSettings>()
+        .init_resource::<collision::Overlaps>()
+        .add_plugins((plugins, FrameTimeDiagnosticsPlugin::default(), EguiPlugin::default(), PanOrbitCameraPlugin, MeshPickingPlugin, DebugGridPlugin::without_floor_grid(), clip::ClipPlugin))
+        .add_systems(Startup, (setup, grid::setup))
+        .add_systems(EguiPrimaryContextPass, (ui::joint_panel, ui::params_panel, ui::units_overlay, hud::draw_hud, hud::draw_stats, hud::draw_minimap, ui::view_panel, ui::bookmark_panel, ui::frames_panel, ui::tools_panel, ui::console_panel, ui::timeline_panel, ui::section_panel, ui::reference_panel, diff::heatmap_legend, plot::plot_panel))
+        .add_systems(Update, (
+            // Tree updates
+            (
+                (timeline::controls, timeline::advance, timeline::apply, timeline::update_hud)
+                    .chain()
+                    .run_if(resource_exists::<timeline::Timeline>),
+                stream::apply_updates.run_if(resource_exists::<stream::UpdateReceiver>),
+                kinematics::apply_joint_values.run_if(resource_exists::<kinematics::JointReceiver>),
+                smoothing::smooth_poses,
+                sweep::advance,
+            ).chain(),
+            // Entities following the tree
+            (
+                split::sync_panes,
+                stereo::sync_eyes,
+                pip::sync_frame_view,
+                groups::apply_collapse,
+                explode::update_offsets,
+                spawn_frame_markers,
+                sync_frames,
+                lod::update_lod,
+                sync_frame_spheres,
+                batched::sync_batches,
+                labels::sync_label_style,
+                stale::annotate_labels,
+                labels::update_labels,
+                clouds::sync_clouds,
+                collision::sync_collision,
+                collision::find_overlaps,
+                collision::highlight_overlaps,
+                workspace::sync_workspace,
+                intrinsics::sync_image_planes,
+                fiducial::sync_tags,
+                uncertainty::sync_ellipsoids.run_if(resource_exists::<uncertainty::Sigma>),
+                plot::sample_pose,
+                clip::sync_clip_materials,
+            ).chain(),
+            // Gizmos
+            (
+                draw_gizmo_axes,
+                twist::draw_twists,
+                joint::draw_joints,
+                intrinsics::draw_frustums,
+                intrinsics::draw_optical_axes,
+                selection::draw_selection,
+                tools::draw_interpolation,
+                diff::draw_heatmap.run_if(resource_exists::<diff::DiffTree>),
+                clip::draw_clip_plane,
+                explode::draw_leaders,
+            ),
+            // Input and settings
+            (
+                grid::apply_settings,
+                style::apply_background,
+                camera::frame_all,
+                bookmarks::shortcuts,
+                hud::toggle_stats,
+                hud::toggle_minimap,
+                selection::keyboard_navigation,
+                gamepad::gamepad_camera,
+                camera::animate_focus,
+                video::step.run_if(resource_exists::<video::VideoRender>),
+                camera::advance_turntable.run_if(not(resource_exists::<video::VideoRender>)),
+                camera::spin_turntable,
+                origin::follow_camera,
+            ).chain(),
+        ).chain());
+    app
+}
+
+#[derive(Component)]
+pub struct AxisOverlayLabel {
+    node: NodeId,
+}
+
+#[derive(Component)]
+pub struct FrameSphere {
+    node: NodeId,
+}
+
+/// Entity standing for a tree node. Its `Transform` is the node's local pose,
+/// relative to the origin for a root, and it is a child of its parent node's
+/// entity, so Bevy's transform and visibility propagation place anything
+/// attached to it.
+#[derive(Component)]
+pub struct FrameNode {
+    pub id: NodeId,
+}
+
+#[derive(Resource, Default)]
+pub struct Selection {
+    nodes: Vec<NodeId>,
+}
+
+/// Parent for the per-node labels, the scene root that root frames hang off
+/// (the other frames hang off their parents' entities), and the `FrameNode`
+/// entity of every node spawned so far, indexed by `NodeId`. Nodes added
+/// while running get theirs lazily.
+#[derive(Resource)]
+pub struct FrameMarkers {
+    labels: Entity,
+    root: Entity,
+    font: Handle<Font>,
+    frames: Vec<Entity>,
+}
+
+impl FrameMarkers {
+    pub fn entity(&self, id: NodeId) -> Option<Entity> {
+        self.frames.get(id).copied()
+    }
+}
+
+fn setup(mut commands: Commands, dag: Res<TransformTree>, asset_server: Res<AssetServer>) {
+    let (focus, radius) = camera::framing(&dag);
+    let transform = Transform::from_translation(focus + camera::VIEW_DIRECTION.normalize() * radius).looking_at(focus, Vec3::Y);
+
+    // Camera. Labels are laid out over its viewport, also when split view adds panes.
+    commands.spawn((
+        camera::MainCamera,
+        Camera3d::default(),
+        IsDefaultUiCamera,
+        transform,
+        PanOrbitCamera {
+            focus,
+            target_focus: focus,
+            ..default()
+        },
+    ));
+
+    // Light (not needed for gizmos, but good for if you add meshes later)
+    commands.spawn((
+        PointLight::default(),
+        Transform::from_xyz(2.0, 4.0, 2.0),
+    ));
+
+    let font = asset_server.load("fonts/FiraCode.ttf");
+    let labels = commands.spawn((Node {
+        position_type: PositionType::Absolute,
+        ..default()
+        },
+    )).id();
+    let root = commands.spawn((Transform::default(), Visibility::default())).id();
+    commands.insert_resource(FrameMarkers { labels, root, font, frames: vec![] });
+}
+
+fn spawn_frame_markers(mut commands: Commands, dag: Res<TransformTree>, style: Res<style::Style>, mut markers: ResMut<FrameMarkers>, mut meshes: ResMut<Assets<Mesh>>, mut materials: ResMut<Assets<StandardMaterial>>) {
+    if markers.frames.len() >= dag.nodes.len() {
+        return;
+    }
+    for id in markers.frames.len()..dag.nodes.len() {
+        let node = &dag.nodes[id];
+        commands.entity(markers.labels).with_children(|root| {
+            root.spawn((
+                AxisOverlayLabel {
+                    node: id
+                },
+                Text::new(node.label_text()),
+                TextFont {
+                    font: markers.font.clone(),
+                    font_size: node.label.font_size.unwrap_or(20.0),
+                    ..default()
+                },
+                Node {
+                    position_type: PositionType::Absolute,
+                    ..default()
+                },
+                TextColor(node.label_color(&style)),
+            ));
+        });
+        // Parented to the scene root for now; `sync_frames` moves it under its
+        // parent once every entity exists.
+        let frame = commands.spawn((
+            FrameNode { id },
+            Transform::from_isometry(node.local),
+            Visibility::default(),
+            ChildOf(markers.root),
+        )).with_children(|frame| {
+            frame.spawn((
+                FrameSphere {
+                    node: id
+                },
+                Mesh3d(meshes.add(Sphere::new(0.02))),
+                MeshMaterial3d(materials.add(StandardMaterial{
+                    base_color: Color::srgb(1.0, 1.0, 1.0),
+                    ..default()
+                })),
+                Transform::default(),
+            ))
+            .observe(selection::on_frame_click)
+            .observe(ik::on_drag_start)
+            .observe(ik::on_drag)
+            .observe(ik::on_drag_end)
+            .observe(edit::on_drag_start)
+            .observe(edit::on_drag)
+            .observe(edit::on_drag_end);
+        }).id();
+        markers.frames.push(frame);
+    }
+}
+
+/// Transform of frame `id`'s entity below its parent's, so the entities'
+/// global transforms match the world poses the gizmos are drawn at. A root's
+/// is its pose relative to the origin, the rest their stretched local poses,
+/// so the `f32` steps stay as small as the tree's.
+fn frame_transform(dag: &TransformTree, offsets: &explode::FrameOffsets, id: NodeId) -> Transform {
+    let node = &dag.nodes[id];
+    let mut local = match node.parent {
+        Some(_) => Transform::from_isometry(origin::narrow(&dag.scaled_local(id), DVec3::ZERO)),
+        None => Transform::from_isometry(node.local),
+    };
+    // An exploded frame's entity moves by its offset beyond its parent's.
+    let shift = offsets.get(id) - node.parent.map_or(Vec3::ZERO, |p| offsets.get(p));
+    if shift != Vec3::ZERO {
+        local.translation += node.parent.map_or(Quat::IDENTITY, |p| dag.nodes[p].world.rotation).inverse() * shift;
+    }
+    local
+}
+
+/// Mirrors local poses and parents from the tree onto the `FrameNode` entities.
+fn sync_frames(
+    mut commands: Commands,
+    dag: Res<TransformTree>,
+    markers: Res<FrameMarkers>,
+    offsets: Res<explode::FrameOffsets>,
+    mut frame_q: Query<(Entity, &FrameNode, &mut Transform, &ChildOf)>,
+) {
+    if !dag.is_changed() && !markers.is_changed() && !offsets.is_changed() {
+        return;
+    }
+    for (entity, frame, mut transform, child_of) in &mut frame_q {
+        let node = &dag.nodes[frame.id];
+        transform.set_if_neq(frame_transform(&dag, &offsets, frame.id));
+        let parent = node.parent.and_then(|p| markers.entity(p)).unwrap_or(markers.root);
+        if child_of.parent() != parent {
+            commands.entity(entity).insert(ChildOf(parent));
+        }
+    }
+}
+
+/// Hiding a frame only hides its own sphere, not the frames below it.
+fn sync_frame_spheres(
+    dag: Res<TransformTree>,
+    lod: Res<lod::FrameLod>,
+    clip: Res<clip::ClipPlane>,
+    mut sphere_q: Query<(&FrameSphere, &mut Visibility)>,
+) {
+    if !dag.is_changed() && !lod.is_changed() && !clip.is_changed() {
+        return;
+    }
+    for (sphere, mut visibility) in &mut sphere_q {
+        let node = &dag.nodes[sphere.node];
+        let hidden = !node.visible() || lod.is_culled(sphere.node) || clip.clips(node.world.translation.to_vec3());
+        visibility.set_if_neq(if hidden { Visibility::Hidden } else { Visibility::Inherited });
+    }
+}
+
+/// Draws the axis triad of every visible frame and the link to its parent,
+/// skipping those outside the camera's view or beyond the clipping plane. Axis tips and graying out stale
+/// frames are only done here, not in batched mode.
+fn draw_gizmo_axes(
+    dag: Res<TransformTree>,
+    style: Res<style::Style>,
+    batching: Res<batched::AxisBatching>,
+    staleness: Option<Res<stale::Staleness>>,
+    clip: Res<clip::ClipPlane>,
+    offsets: Res<explode::FrameOffsets>,
+    time: Res<Time>,
+    camera_q: Query<&GlobalTransform, With<camera::MainCamera>>,
+    frustum_q: Query<&Frustum, With<Camera3d>>,
+    mut gizmos: Gizmos,
+) {
+    if batching.active(&dag) {
+        return;
+    }
+    let _span = info_span!("draw_axes", nodes = dag.nodes.len()).entered();
+    let Ok(camera) = camera_q.single() else {
+        return;
+    };
+    // With split view, anything seen by one of the panes is drawn.
+    let frustums: Vec<&Frustum> = frustum_q.iter().collect();
+    let size = style.axis_scale;
+    let colors = style.axis_colors();
+
+    for (id, node) in dag.nodes.iter().enumerate().filter(|(_, n)| n.visible()) {
+        let o = offsets.position(&dag, id);
+        if clip.clips(o) {
+            continue;
+        }
+        if frustums.iter().any(|f| culling::in_view(f, o, size)) {
+            let stale = staleness.as_ref().is_some_and(|s| s.age(id, time.elapsed_secs_f64()).is_some());
+            let colors = if stale { [stale::STALE_COLOR; 3] } else { colors };
+            let pose = Isometry3d::new(o, node.world.rotation);
+            for (axis, (dir, color)) in [Vec3::X, Vec3::Y, Vec3::Z].into_iter().zip(colors).enumerate() {
+                gizmos.line(o, o + node.world.rotation * dir * size, color);
+                style.tips.draw(&mut gizmos, pose, axis, size, camera, color);
+            }
+        }
+        if let Some(p) = node.parent {
+            let parent = offsets.position(&dag, p);
+            if frustums.iter().any(|f| culling::segment_in_view(f, parent, o)) {
+                let color = style.links.color(&dag, id, style.link_color());
+                for (a, b) in style.links.segments(parent, o, size) {
+                    gizmos.line(a, b, color);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, TAU};
+
+    use super::*;
+
+    fn node(name: &str, parent: Option<&str>, t: [f64; 3], r: [f64; 3]) -> FileNode {
+        FileNode { name: name.to_string(), parent: parent.map(str::to_string), t, r, ..Default::default() }
+    }
+
+    fn tree(nodes: Vec<FileNode>) -> Result<TransformTree, FileTransformTreeError> {
+        TransformTree::try_from(FileTransformTree { version: 1, nodes, ..Default::default() })
+    }
+
+    fn chain() -> Vec<FileNode> {
+        vec![
+            node("base", None, [1.0, 0.0, 0.0], [0.0, 0.0, FRAC_PI_2]),
+            node("arm", Some("base"), [0.0, 2.0, 0.0], [FRAC_PI_4, 0.0, 0.0]),
+            node("wrist", Some("arm"), [0.0, 0.0, 3.0], [0.0, 0.5, 0.0]),
+            node("tool", Some("wrist"), [0.1, 0.2, 0.3], [0.0, 0.0, 0.0]),
+            node("camera", Some("base"), [0.0, 0.0, 1.0], [0.0, FRAC_PI_4, 0.0]),
+        ]
+    }
+
+    fn assert_same_world(a: &TransformTree, b: &TransformTree) {
+        assert_eq!(a.nodes.len(), b.nodes.len());
+        for na in &a.nodes {
+            let nb = &b.nodes[b.find(&na.name).unwrap()];
+            let dt = (na.world.translation - nb.world.translation).length();
+            assert!(dt < 1e-5, "{} translation differs by {}", na.name, dt);
+            let dr = na.world.rotation.angle_between(nb.world.rotation);
+            assert!(dr < 1e-5, "{} rotation differs by {}", na.name, dr);
+        }
+    }
+
+    #[test]
+    fn children_before_parents() {
+        let ordered = tree(chain()).unwrap();
+        let mut reversed = chain();
+        reversed.reverse();
+        assert_same_world(&ordered, &tree(reversed).unwrap());
+    }
+
+    #[test]
+    fn shuffled_file_order() {
+        let ordered = tree(chain()).unwrap();
+        let mut nodes = chain();
+        nodes.swap(0, 3);
+        nodes.swap(1, 4);
+        assert_same_world(&ordered, &tree(nodes).unwrap());
+    }
+
+    #[test]
+    fn world_composes_parents() {
+        let dag = tree(chain().into_iter().rev().collect()).unwrap();
+        let tool = &dag.nodes[dag.find("tool").unwrap()];
+        let expected = ["base", "arm", "wrist", "tool"]
+            .iter()
+            .map(|n| dag.nodes[dag.find(n).unwrap()].local)
+            .fold(Isometry3d::IDENTITY, |acc, local| acc * local);
+        assert!((tool.world.translation - expected.translation).length() < 1e-5);
+    }
+
+    #[test]
+    fn topological_order_puts_parents_first() {
+        let dag = tree(chain().into_iter().rev().collect()).unwrap();
+        let order = dag.topological_order();
+        assert_eq!(order.len(), dag.nodes.len());
+        for (i, &id) in order.iter().enumerate() {
+            if let Some(p) = dag.nodes[id].parent {
+                assert!(order[..i].contains(&p), "{} comes before its parent", dag.nodes[id].name);
+            }
+        }
+    }
+
+    #[test]
+    fn missing_parent_is_an_error() {
+        let err = tree(vec![node("a", Some("nowhere"), [0.0; 3], [0.0; 3])]).unwrap_err();
+        assert!(matches!(err, FileTransformTreeError::ParentMissing(p) if p == "nowhere"));
+    }
+
+    #[test]
+    fn parent_cycle_is_an_error() {
+        let err = tree(vec![
+            node("root", None, [0.0; 3], [0.0; 3]),
+            node("a", Some("b"), [0.0; 3], [0.0; 3]),
+            node("b", Some("a"), [0.0; 3], [0.0; 3]),
+        ])
+        .unwrap_err();
+        assert!(matches!(err, FileTransformTreeError::Cycle(_)));
+    }
+
+    #[test]
+    fn incremental_update_matches_full() {
+        let mut incremental = tree(chain()).unwrap();
+        for (name, t) in [("arm", [0.0, 1.0, 0.0]), ("tool", [1.0, 1.0, 1.0]), ("base", [2.0, 0.0, 0.0])] {
+            let id = incremental.find(name).unwrap();
+            let local = Isometry3d::from(&node(name, None, t, [0.3, 0.2, 0.1]));
+            incremental.set_local(id, local);
+        }
+        let mut full = incremental.clone();
+        incremental.update_world();
+        full.update_world_full();
+        assert_same_world(&incremental, &full);
+    }
+
+    #[test]
+    fn apply_with_forward_reference() {
+        let mut dag = TransformTree::default();
+        dag.apply(&node("child", Some("parent"), [1.0, 0.0, 0.0], [0.0; 3]));
+        dag.apply(&node("parent", None, [0.0, 5.0, 0.0], [0.0; 3]));
+        dag.update_world();
+        let child = &dag.nodes[dag.find("child").unwrap()];
+        assert!((child.world.translation.to_vec3() - Vec3::new(1.0, 5.0, 0.0)).length() < 1e-5);
+    }
+
+    #[test]
+    fn updates_without_a_parent_keep_theirs() {
+        let mut dag = tree(chain()).unwrap();
+        let (wrist, tool) = (dag.find("wrist").unwrap(), dag.find("tool").unwrap());
+        // Lines as `--stdin` reads them.
+        for line in [r#"{"name": "tool", "t": [0, 0, 0.5]}"#, r#"{"name": "tool", "parent": null, "t": [0, 0, 0.7]}"#] {
+            dag.apply(&serde_json::from_str::<FileNode>(line).unwrap());
+            assert_eq!(dag.nodes[tool].parent, Some(wrist));
+        }
+        dag.update_world();
+        let expected = dag.nodes[wrist].world.transform_point(Vec3::new(0.0, 0.0, 0.7));
+        assert!((dag.nodes[tool].world.translation.to_vec3() - Vec3::from(expected)).length() < 1e-5);
+
+        dag.apply(&serde_json::from_str::<FileNode>(r#"{"name": "tool", "parent": "base"}"#).unwrap());
+        assert_eq!(dag.nodes[tool].parent, dag.find("base"));
+    }
+
+    #[test]
+    fn aliases_resolve_to_the_same_node() {
+        let mut nodes = chain();
+        nodes[0].aliases = vec!["base_footprint".to_string()];
+        nodes[4].parent = Some("base_footprint".to_string());
+        let mut dag = tree(nodes).unwrap();
+        let base = dag.find("base").unwrap();
+        assert_eq!(dag.find("base_footprint"), Some(base));
+        assert_eq!(dag.nodes[dag.find("camera").unwrap()].parent, Some(base));
+
+        dag.apply(&node("base_footprint", None, [7.0, 0.0, 0.0], [0.0; 3]));
+        dag.update_world();
+        assert_eq!(dag.nodes.len(), 5);
+        assert!((dag.nodes[base].world.translation.to_vec3() - Vec3::new(7.0, 0.0, 0.0)).length() < 1e-5);
+    }
+
+    #[test]
+    fn alias_clashing_with_name() {
+        let mut nodes = chain();
+        nodes[1].aliases = vec!["tool".to_string()];
+        assert!(matches!(tree(nodes), Err(FileTransformTreeError::Duplicate(_))));
+    }
+
+    #[test]
+    fn joint_values_pose_the_tree() {
+        let mut nodes = chain();
+        nodes[1].joint = Some(joint::FileJoint { kind: joint::JointType::Revolute, axis: [1.0, 0.0, 0.0], limits: None, value: None });
+        let mut dag = tree(nodes).unwrap();
+        let values = kinematics::parse_values("arm=0.5 camera=1").unwrap();
+        assert_eq!(dag.set_joints(&values), vec!["camera".to_string()]);
+
+        let mut expected = chain();
+        expected[1].r[0] += 0.5;
+        assert_same_world(&dag, &tree(expected).unwrap());
+    }
+
+    #[test]
+    fn mirroring_twice_restores_the_subtree() {
+        let mut nodes = chain();
+        nodes[2].joint = Some(joint::FileJoint { kind: joint::JointType::Revolute, axis: [0.0, 1.0, 0.0], limits: None, value: None });
+        let mut dag = tree(nodes).unwrap();
+        dag.set_joint(dag.find("wrist").unwrap(), 0.3);
+        dag.update_world();
+        let original = dag.clone();
+        let arm = dag.find("arm").unwrap();
+        let camera = dag.find("camera");
+        dag.mirror_subtree(arm, camera, edit::MirrorPlane::Xz);
+        let tool = dag.find("tool").unwrap();
+        let mirrored = dag.nodes[camera.unwrap()].world.inverse() * dag.nodes[tool].world.translation.to_vec3();
+        let before = original.nodes[camera.unwrap()].world.inverse() * original.nodes[tool].world.translation.to_vec3();
+        assert!((mirrored - before * Vec3::new(1.0, -1.0, 1.0)).length() < 1e-4);
+
+        dag.mirror_subtree(arm, camera, edit::MirrorPlane::Xz);
+        assert_same_world(&dag, &original);
+    }
+
+    #[test]
+    fn duplicate_subtree_with_suffix() {
+        let mut dag = tree(chain()).unwrap();
+        let arm = dag.find("arm").unwrap();
+        let camera = dag.find("camera").unwrap();
+        let copy = dag.duplicate_subtree(arm, Some(camera), "", "_2").unwrap();
+        assert_eq!(dag.nodes.len(), 8);
+        assert_eq!(dag.nodes[copy].parent, Some(camera));
+        let tool = dag.find("tool_2").unwrap();
+        assert_eq!(dag.nodes[dag.nodes[tool].parent.unwrap()].name, "wrist_2");
+        let expected = dag.relative(arm, dag.find("tool").unwrap());
+        assert!((dag.relative(copy, tool).translation - expected.translation).length() < 1e-5);
+
+        assert!(matches!(dag.duplicate_subtree(arm, None, "", "_2"), Err(FileTransformTreeError::Duplicate(_))));
+        assert_eq!(dag.nodes.len(), 8);
+    }
+
+    #[test]
+    fn pasted_poses_round_trip() {
+        let mut nodes = chain();
+        nodes[2].t = [0.5, -1.0, 2.0];
+        let dag = tree(nodes).unwrap();
+        let wrist = dag.find("wrist").unwrap();
+        for format in snippets::PoseFormat::ALL {
+            let text = snippets::pose_text(&dag, wrist, format);
+            let (pose, _) = snippets::parse_pose(&text).unwrap();
+            let local = dag.nodes[wrist].local;
+            assert!((pose.translation - local.translation).length() < 1e-4, "{:?}: {}", format, text);
+            assert!(pose.rotation.angle_between(local.rotation) < 1e-4, "{:?}: {}", format, text);
+        }
+    }
+
+    #[test]
+    fn expressions_follow_parameters() {
+        let text = r#"{
+            "version": 1,
+            "params": {"wheelbase": 2.0, "tilt": 10},
+            "nodes": [
+                {"name": "base", "parent": null, "t": [0, 0, 0], "r": [0, 0, 0]},
+                {"name": "axle", "parent": "base", "t": ["wheelbase / 2", 0, "-(1 + 1) ^ 2"], "r": [0, "radians(tilt)", 0]}
+            ]
+        }"#;
+        let mut dag = TransformTree::try_from(schema::parse(text).unwrap()).unwrap();
+        let axle = dag.find("axle").unwrap();
+        assert!((dag.nodes[axle].local.translation.to_vec3() - Vec3::new(1.0, 0.0, -4.0)).length() < 1e-5);
+        assert!((dag.nodes[axle].local.rotation.angle_between(Quat::from_rotation_y(10f32.to_radians()))) < 1e-5);
+
+        dag.set_param("wheelbase", 3.0).unwrap();
+        assert!((dag.nodes[axle].world.translation.x - 1.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn millimeter_files_load_in_meters() {
+        let text = r#"{
+            "version": 1,
+            "units": "mm",
+            "params": {"reach": 400},
+            "nodes": [
+                {"name": "base", "parent": null, "t": [0, 0, 0], "r": [0, 0, 0]},
+                {"name": "tool", "parent": "base", "t": [250, "reach", 0], "r": [0, 0, 0],
+                 "collision": [{"shape": "box", "size": [100, 200, 300]}, {"shape": "capsule", "radius": 20, "length": 80}],
+                 "covariance": [4, 0, 0, 0, 4, 0, 0, 0, 4]},
+                {"name": "cam", "parent": "tool", "t": [0, 0, 0], "r": [0, 0, 0],
+                 "camera": {"fov": 60, "depth": 2000, "image_distance": 500},
+                 "tag": {"id": 3, "size": 160}}
+            ]
+        }"#;
+        let mut file = schema::parse(text).unwrap();
+        assert_eq!(units::to_meters(&mut file, None, None), units::Units::Mm);
+        assert_eq!(file.nodes[1].collision[0].shape, collision::Shape::Box { size: [0.1, 0.2, 0.3] });
+        assert!(matches!(file.nodes[1].collision[1].shape, collision::Shape::Capsule { radius, length } if (radius - 0.02).abs() < 1e-12 && (length - 0.08).abs() < 1e-12));
+        assert!((file.nodes[1].covariance.as_ref().unwrap()[0] - 4e-6).abs() < 1e-15);
+        let camera = file.nodes[2].camera.as_ref().unwrap();
+        assert_eq!((camera.depth, camera.image_distance), (Some(2.0), Some(0.5)));
+        assert!((file.nodes[2].tag.as_ref().unwrap().size - 0.16).abs() < 1e-12);
+        let mut dag = TransformTree::try_from(file.clone()).unwrap();
+        let tool = dag.find("tool").unwrap();
+        assert!((dag.nodes[tool].local.translation.to_vec3() - Vec3::new(0.25, 0.4, 0.0)).length() < 1e-5);
+        dag.set_param("reach", 500.0).unwrap();
+        assert!((dag.nodes[tool].world.translation.y - 0.5).abs() < 1e-5);
+
+        units::from_meters(&mut file, units::Units::Mm);
+        assert_eq!(file.units, Some(units::Units::Mm));
+        assert!((file.nodes[1].t[0] - 250.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn scale_bar_lengths_are_round() {
+        assert_eq!(hud::nice_length(0.37), 0.2);
+        assert_eq!(hud::nice_length(7.0), 5.0);
+        assert_eq!(hud::nice_length(120.0), 100.0);
+    }
+
+    #[test]
+    fn update_stats_count_refreshed_nodes() {
+        let mut dag = tree(chain()).unwrap();
+        dag.take_update_stats();
+        let wrist = dag.find("wrist").unwrap();
+        dag.set_local(wrist, Isometry3d::IDENTITY);
+        dag.update_world();
+        // The wrist and the tool below it.
+        assert_eq!(dag.take_update_stats().refreshed, 2);
+        assert_eq!(dag.take_update_stats().refreshed, 0);
+    }
+
+    #[test]
+    fn utm_scale_frames_keep_millimeters() {
+        let nodes = vec![
+            node("utm", None, [500_000.0, 4_000_000.0, 10.0], [0.0, 0.0, 0.0]),
+            node("rig", Some("utm"), [0.001, 0.0, 0.0], [0.0, 0.0, 0.0]),
+            node("other", None, [500_000.001, 4_000_000.002, 10.0], [0.0, 0.0, 0.0]),
+        ];
+        let mut dag = tree(nodes).unwrap();
+        let (rig, other) = (dag.find("rig").unwrap(), dag.find("other").unwrap());
+        let offset = dag.world_position(other) - dag.world_position(rig);
+        assert!((offset - DVec3::new(0.0, 0.002, 0.0)).length() < 1e-5);
+
+        dag.rebase(DVec3::new(500_010.0, 4_000_000.0, 0.0));
+        let offset = dag.world_position(other) - dag.world_position(rig);
+        assert!((offset - DVec3::new(0.0, 0.002, 0.0)).length() < 1e-5);
+        let file = FileTransformTree::from(&dag);
+        assert!((file.nodes[2].t[1] - 4_000_000.002).abs() < 1e-5);
+    }
+
+    #[test]
+    fn utm_scale_frames_below_roots_keep_millimeters() {
+        // A map frame with its UTM child, as tf trees have them, placed by a
+        // root transform as `--root-transform` does.
+        let mut file = FileTransformTree {
+            version: 1,
+            nodes: vec![
+                node("map", None, [0.0; 3], [0.0; 3]),
+                node("utm", Some("map"), [500_000.0, 4_000_000.0, 10.0], [0.0; 3]),
+                node("rig", Some("utm"), [0.001, 0.0, 0.0], [0.0; 3]),
+            ],
+            ..Default::default()
+        };
+        scene::add_root(&mut file, "site", scene::parse_pose("1000000.5 0 0 0 0 0").unwrap());
+        let mut dag = TransformTree::try_from(file).unwrap();
+        let (utm, rig) = (dag.find("utm").unwrap(), dag.find("rig").unwrap());
+        let offset = dag.world_position(rig) - dag.world_position(utm);
+        assert!((offset - DVec3::new(0.001, 0.0, 0.0)).length() < 1e-9);
+        assert!((dag.world_position(utm) - DVec3::new(1_500_000.5, 4_000_000.0, 10.0)).length() < 1e-9);
+        // Render poses are narrowed only once relative to the origin.
+        assert!(dag.origin().length() > origin::REBASE_DISTANCE);
+        dag.rebase(dag.world_position(utm));
+        assert!((dag.nodes[rig].world.translation.to_vec3() - Vec3::new(0.001, 0.0, 0.0)).length() < 1e-7);
+
+        // Expressions of a far root re-pose it in full precision.
+        let text = r#"{
+            "version": 1,
+            "params": {"east": 500000},
+            "nodes": [{"name": "gps", "t": ["east", 4000000, 0], "r": [0, 0, 0]}]
+        }"#;
+        let mut dag = TransformTree::try_from(schema::parse(text).unwrap()).unwrap();
+        let gps = dag.find("gps").unwrap();
+        dag.set_param("east", 500_000.003).unwrap();
+        assert!((dag.world_position(gps) - DVec3::new(500_000.003, 4_000_000.0, 0.0)).length() < 1e-9);
+        assert!(dag.nodes[gps].world.translation.length() < 1.0);
+    }
+
+    #[test]
+    fn geodetic_anchors_share_a_tangent_plane() {
+        let mut nodes = vec![
+            node("base", None, [0.0; 3], [0.0; 3]),
+            node("mast", Some("base"), [0.0, 0.0, 2.0], [0.0; 3]),
+            node("rover", None, [0.0; 3], [0.0; 3]),
+        ];
+        nodes[0].geo = Some(geo::FileGeo { lat: 47.0, lon: 8.0, alt: 400.0, convention: geo::GeoConvention::Enu });
+        nodes[2].geo = Some(geo::FileGeo { lat: 47.001, lon: 8.0, alt: 400.0, convention: geo::GeoConvention::Ned });
+        let dag = tree(nodes).unwrap();
+        let rover = dag.find("rover").unwrap();
+        // A thousandth of a degree of latitude is about 111 m north.
+        let p = dag.world_position(rover);
+        assert!(p.x.abs() < 1e-3 && (p.y - 111.2).abs() < 0.1, "{:?}", p);
+        let north = dag.nodes[rover].world.rotation * Vec3::X;
+        assert!(north.dot(Vec3::Y) > 0.999);
+
+        let geo = dag.geodetic(rover).unwrap();
+        assert!((geo.lat - 47.001).abs() < 1e-7 && (geo.lon - 8.0).abs() < 1e-7 && (geo.alt - 400.0).abs() < 1e-2);
+        let mast = dag.geodetic(dag.find("mast").unwrap()).unwrap();
+        assert!((mast.alt - 402.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn unity_files_convert_without_mirroring() {
+        let mut file = FileTransformTree {
+            version: 1,
+            convention: Some(convention::Convention::Unity),
+            nodes: vec![node("player", None, [1.0, 2.0, 3.0], [0.0, FRAC_PI_2, 0.0])],
+            ..Default::default()
+        };
+        assert_eq!(convention::to_ros(&mut file, None, None), convention::Convention::Unity);
+        let dag = TransformTree::try_from(file).unwrap();
+        let pose = dag.nodes[0].world;
+        // Unity right (X), up (Y), forward (Z) are ROS -Y, Z and X.
+        assert!((pose.translation.to_vec3() - Vec3::new(3.0, -1.0, 2.0)).length() < 1e-5);
+        // Turning right in Unity, about its up axis, turns forward to the right.
+        assert!((pose.rotation * Vec3::X - Vec3::NEG_Y).length() < 1e-5);
+    }
+
+    #[test]
+    fn converted_expressions_keep_their_pose() {
+        let text = r#"{
+            "version": 1,
+            "convention": "unity",
+            "params": {"lift": 2, "turn": 0.5},
+            "nodes": [{"name": "player", "t": [1, "lift", 3], "r": [0.2, "turn", 0.1]}]
+        }"#;
+        let mut file = schema::parse(text).unwrap();
+        convention::to_ros(&mut file, None, None);
+        let mut dag = TransformTree::try_from(file).unwrap();
+        let before = dag.nodes[0].world;
+        dag.set_param("lift", 2.0).unwrap();
+        let after = dag.nodes[0].world;
+        assert!((after.translation.to_vec3() - before.translation.to_vec3()).length() < 1e-5);
+        assert!(after.rotation.angle_between(before.rotation) < 1e-5);
+        // Unity up (Y) is ROS Z.
+        dag.set_param("lift", 5.0).unwrap();
+        assert!((dag.nodes[0].world.translation.to_vec3() - Vec3::new(3.0, -1.0, 5.0)).length() < 1e-5);
+    }
+
+    #[test]
+    fn optical_child_looks_along_body_x() {
+        let mut dag = tree(chain()).unwrap();
+        let camera = dag.find("camera").unwrap();
+        let optical = dag.add_optical_frame(camera).unwrap();
+        assert_eq!(dag.nodes[optical].name, "camera_optical");
+        let local = dag.nodes[optical].local.rotation;
+        assert!((local * Vec3::Z - Vec3::X).length() < 1e-6);
+        assert!((local * Vec3::X - Vec3::NEG_Y).length() < 1e-6);
+        assert!((local * Vec3::Y - Vec3::NEG_Z).length() < 1e-6);
+        assert!(dag.add_optical_frame(camera).is_err());
+    }
+
+    #[test]
+    fn euler_orders_from_file_and_node() {
+        let text = r#"{
+            "version": 1,
+            "euler": "rpy",
+            "nodes": [
+                {"name": "fixed_axes", "parent": null, "t": [0, 0, 0], "r": [0.1, 0.2, 0.3]},
+                {"name": "moving_axes", "parent": null, "t": [0, 0, 0], "r": [0.3, 0.2, 0.1], "euler": "zyx"},
+                {"name": "default", "parent": null, "t": [0, 0, 0], "r": [0.1, 0.2, 0.3], "euler": "xyz"}
+            ]
+        }"#;
+        let dag = TransformTree::try_from(schema::parse(text).unwrap()).unwrap();
+        let rotation = |name: &str| dag.nodes[dag.find(name).unwrap()].local.rotation;
+        let rpy = Quat::from_euler(EulerRot::ZYX, 0.3, 0.2, 0.1);
+        assert!(rotation("fixed_axes").angle_between(rpy) < 1e-5);
+        assert!(rotation("moving_axes").angle_between(rpy) < 1e-5);
+        assert!(rotation("default").angle_between(Quat::from_euler(EulerRot::XYZ, 0.1, 0.2, 0.3)) < 1e-5);
+    }
+
+    #[test]
+    fn matrix_poses_are_checked() {
+        let mut file = FileTransformTree {
+            version: 1,
+            nodes: vec![FileNode {
+                name: "camera".to_string(),
+                matrix: Some(vec![0.0, -1.0, 0.0, 1.0, 1.0, 0.0, 0.0, 2.0, 0.0, 0.0, 1.0, 3.0]),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let dag = TransformTree::try_from(file.clone()).unwrap();
+        let pose = dag.nodes[0].local;
+        assert!((pose.translation.to_vec3() - Vec3::new(1.0, 2.0, 3.0)).length() < 1e-6);
+        assert!(pose.rotation.angle_between(Quat::from_rotation_z(FRAC_PI_2 as f32)) < 1e-5);
+
+        file.nodes[0].matrix = Some(vec![1.0, 0.01, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+        assert!(matches!(TransformTree::try_from(file.clone()), Err(FileTransformTreeError::Matrix(_))));
+        file.orthonormalize = true;
+        let dag = TransformTree::try_from(file).unwrap();
+        assert!(dag.nodes[0].local.rotation.angle_between(Quat::IDENTITY) < 0.01);
+    }
+
+    #[test]
+    fn matrix_updates_are_applied() {
+        let mut dag = tree(chain()).unwrap();
+        let tool = dag.find("tool").unwrap();
+        let mut update = FileNode {
+            name: "tool".to_string(),
+            parent: Some("wrist".to_string()),
+            matrix: Some(vec![0.0, -1.0, 0.0, 1.0, 1.0, 0.0, 0.0, 2.0, 0.0, 0.0, 1.0, 3.0]),
+            ..Default::default()
+        };
+        dag.apply(&update);
+        dag.update_world();
+        let pose = dag.nodes[tool].local;
+        assert!((pose.translation.to_vec3() - Vec3::new(1.0, 2.0, 3.0)).length() < 1e-6);
+        assert!(pose.rotation.angle_between(Quat::from_rotation_z(FRAC_PI_2 as f32)) < 1e-5);
+
+        // A matrix that isn't a rotation leaves the frame where it was.
+        update.matrix = Some(vec![2.0, 0.0, 0.0, 5.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+        dag.apply(&update);
+        assert_eq!(dag.nodes[tool].local, pose);
+    }
+
+    #[test]
+    fn scale_stretches_child_offsets() {
+        let mut nodes = chain();
+        nodes[1].s = Some([2.0, 2.0, 2.0]);
+        let mut dag = tree(nodes).unwrap();
+        let (arm, wrist) = (dag.find("arm").unwrap(), dag.find("wrist").unwrap());
+        // The wrist sits 3 along the arm's Z, doubled.
+        assert!((dag.relative(arm, wrist).translation.to_vec3() - Vec3::new(0.0, 0.0, 6.0)).length() < 1e-5);
+        assert_eq!(dag.nodes[dag.find("tool").unwrap()].world_scale, Vec3::splat(2.0));
+        let world = dag.nodes[wrist].world;
+        dag.update_world_full();
+        assert!((dag.nodes[wrist].world.translation - world.translation).length() < 1e-6);
+        assert_eq!(FileTransformTree::from(&dag).nodes[1].s, Some([2.0, 2.0, 2.0]));
+
+        // Frame entities compose to the same world poses.
+        dag.nodes[arm].scale = Vec3::new(2.0, 1.0, 0.5);
+        dag.update_world_full();
+        let offsets = explode::FrameOffsets::default();
+        for id in 0..dag.nodes.len() {
+            let mut path = vec![id];
+            while let Some(parent) = dag.nodes[*path.last().unwrap()].parent {
+                path.push(parent);
+            }
+            let global =
+                path.iter().rev().fold(GlobalTransform::IDENTITY, |global, &n| global.mul_transform(frame_transform(&dag, &offsets, n)));
+            let world = dag.nodes[id].world;
+            assert!((global.translation() - world.translation.to_vec3()).length() < 1e-5);
+            assert!(global.rotation().angle_between(world.rotation) < 1e-5);
+        }
+    }
+
+    #[test]
+    fn launch_files_publish_static_frames() {
+        let mut nodes = chain();
+        nodes[1].joint = Some(joint::FileJoint { kind: joint::JointType::Revolute, axis: [0.0, 0.0, 1.0], limits: None, value: None });
+        let file = FileTransformTree { version: 1, nodes, ..Default::default() };
+        let script = formats::write("sh", &file).unwrap();
+        let commands: Vec<&str> = script.lines().filter(|l| l.starts_with("ros2 run")).collect();
+        // Every frame with a parent but the moving arm.
+        assert_eq!(commands.len(), file.nodes.iter().filter(|n| n.parent.is_some()).count() - 1);
+        assert!(!script.contains("'arm' &"));
+        let launch = formats::write("py", &file).unwrap();
+        assert_eq!(launch.matches("executable='static_transform_publisher'").count(), commands.len());
+        assert!(launch.contains("'--child-frame-id', 'wrist'"));
+    }
+
+    #[test]
+    fn exported_live_trees_keep_joints() {
+        let mut nodes = chain();
+        nodes[1].joint = Some(joint::FileJoint { kind: joint::JointType::Revolute, axis: [0.0, 0.0, 1.0], limits: Some([-1.0, 1.0]), value: None });
+        nodes[4].covariance = Some(vec![0.01, 0.0, 0.0, 0.0, 0.02, 0.0, 0.0, 0.0, 0.03]);
+        nodes[4].twist = Some(twist::FileTwist { linear: [1.0, 0.0, 0.0], angular: [0.0, 0.0, 0.5] });
+        let mut dag = tree(nodes).unwrap();
+        let arm = dag.find("arm").unwrap();
+        dag.set_joint(arm, 0.5);
+        dag.update_world();
+        let file = FileTransformTree::from(&dag);
+        let script = formats::write("sh", &file).unwrap();
+        assert!(!script.contains("'arm' &"));
+        assert!(formats::write("py", &file).unwrap().contains("'--child-frame-id', 'wrist'"));
+
+        let back = TransformTree::try_from(file).unwrap();
+        let joint = back.nodes[arm].joint.as_ref().unwrap();
+        assert_eq!((joint.value, joint.limits), (0.5, Some((-1.0, 1.0))));
+        assert_same_world(&dag, &back);
+        let camera = &back.nodes[dag.find("camera").unwrap()];
+        assert_eq!(camera.covariance, dag.nodes[dag.find("camera").unwrap()].covariance);
+        assert_eq!(camera.twist.unwrap().angular, Vec3::new(0.0, 0.0, 0.5));
+    }
+
+    #[test]
+    fn urdf_export_has_one_root_link() {
+        let mut nodes = chain();
+        nodes.push(node("beacon", None, [5.0, 0.0, 0.0], [0.0; 3]));
+        nodes[2].joint = Some(joint::FileJoint { kind: joint::JointType::Revolute, axis: [0.0, 1.0, 0.0], limits: None, value: None });
+        let file = FileTransformTree { version: 1, nodes, ..Default::default() };
+        let urdf = formats::write("urdf", &file).unwrap();
+        assert!(urdf.contains("<limit lower=\"-3.14"));
+        let back = tree(formats::parse("urdf", &urdf).unwrap().nodes).unwrap();
+        let dag = tree(file.nodes).unwrap();
+        assert_eq!(back.nodes.iter().filter(|n| n.parent.is_none()).count(), 1);
+        assert_eq!(back.nodes[back.find("world").unwrap()].parent, None);
+        for node in &dag.nodes {
+            let twin = &back.nodes[back.find(&node.name).unwrap()];
+            assert!((twin.world.translation - node.world.translation).length() < 1e-5, "{}", node.name);
+        }
+    }
+
+    #[test]
+    fn sdf_export_round_trips_scoped_names() {
+        let sdf = r#"<sdf version="1.9">
+  <model name="robot">
+    <link name="base"/>
+    <link name="arm"><pose>0 0 1 0 0 1.5707963</pose></link>
+    <joint name="shoulder" type="revolute">
+      <parent>base</parent>
+      <child>arm</child>
+      <axis><xyz>0 1 0</xyz><limit><lower>-1</lower><upper>1</upper></limit></axis>
+    </joint>
+    <model name="gripper">
+      <pose relative_to="arm">0.5 0 0 0 0 0</pose>
+      <link name="finger"><pose>0 0.1 0 0 0 0</pose></link>
+    </model>
+  </model>
+</sdf>"#;
+        let dag = tree(formats::parse("sdf", sdf).unwrap().nodes).unwrap();
+        let finger = dag.find("robot::gripper::finger").unwrap();
+        assert!((dag.nodes[finger].world.translation.to_vec3() - Vec3::new(-0.1, 0.5, 1.0)).length() < 1e-5);
+
+        let written = formats::write("sdf", &FileTransformTree::from(&dag)).unwrap();
+        assert!(!written.contains("::"), "{}", written);
+        let back = tree(formats::parse("sdf", &written).unwrap().nodes).unwrap();
+        for node in &dag.nodes {
+            let twin = &back.nodes[back.find(&format!("axisviz::{}", node.name.replace("::", "__"))).unwrap()];
+            assert!((twin.world.translation - node.world.translation).length() < 1e-5, "{}", node.name);
+            assert!(twin.world.rotation.angle_between(node.world.rotation) < 1e-5, "{}", node.name);
+        }
+        let arm = &back.nodes[back.find("axisviz::robot__arm").unwrap()];
+        assert_eq!(arm.joint.as_ref().map(|j| j.limits), Some(Some((-1.0, 1.0))));
+    }
+
+    #[test]
+    fn mjcf_sites_and_cameras_stay_what_they_were() {
+        let mjcf = r#"<mujoco>
+  <compiler angle="radian"/>
+  <default><geom size="0.1"/></default>
+  <worldbody>
+    <camera name="overhead" pos="0 0 3"/>
+    <body name="torso" pos="0 0 1">
+      <frame pos="0.2 0 0">
+        <site name="imu" quat="0 0 0 1"/>
+      </frame>
+      <body name="head" pos="0 0 0.5"/>
+    </body>
+  </worldbody>
+</mujoco>"#;
+        let file = formats::parse("mjcf", mjcf).unwrap();
+        let element = |file: &FileTransformTree, name: &str| {
+            let node = file.nodes.iter().find(|n| n.name == name).unwrap();
+            node.metadata.get("mjcf").and_then(|e| e.as_str()).map(str::to_string)
+        };
+        assert_eq!(element(&file, "imu").as_deref(), Some("site"));
+        assert_eq!(element(&file, "overhead").as_deref(), Some("camera"));
+        assert_eq!(element(&file, "head"), None);
+        let dag = tree(file.nodes).unwrap();
+        let imu = dag.find("imu").unwrap();
+        assert!((dag.nodes[imu].world.translation.to_vec3() - Vec3::new(0.2, 0.0, 1.0)).length() < 1e-6);
+
+        let written = formats::write("mjcf", &FileTransformTree::from(&dag)).unwrap();
+        assert!(written.contains("<site name=\"imu\""), "{}", written);
+        assert!(written.contains("<camera name=\"overhead\""), "{}", written);
+        assert_eq!(written.matches("<body").count(), 2);
+        let back = tree(formats::parse("mjcf", &written).unwrap().nodes).unwrap();
+        assert_same_world(&dag, &back);
+
+        let placed = mjcf.replace(r#"<geom size="0.1"/>"#, r#"<site pos="1 0 0"/>"#);
+        assert!(formats::parse("mjcf", &placed).is_err());
+        let included = mjcf.replace("<worldbody>", r#"<include file="arm.xml"/><worldbody>"#);
+        assert!(formats::parse("mjcf", &included).is_err());
+    }
+
+    #[test]
+    fn usd_export_nests_xforms() {
+        let mut nodes = chain();
+        nodes.push(node("left-eye", Some("camera"), [0.0; 3], [0.0; 3]));
+        let usda = formats::write("usda", &FileTransformTree { version: 1, nodes, ..Default::default() }).unwrap();
+        assert!(usda.starts_with("#usda 1.0"));
+        // World, base, arm, wrist.
+        assert!(usda.contains("\n            def Xform \"wrist\"\n"));
+        assert!(usda.contains("def Xform \"left_eye\" (\n                displayName = \"left-eye\""));
+        assert_eq!(usda.matches("def Xform").count(), 7);
+        assert_eq!(usda.matches('{').count(), usda.matches('}').count());
+    }
+
+    #[test]
+    fn calibration_yaml_imports() {
+        let kalibr = "
+cam0:
+  T_cam_imu:
+  - [1, 0, 0, 0.1]
+  - [0, 1, 0, 0]
+  - [0, 0, 1, 0]
+  - [0, 0, 0, 1]
+  camera_model: pinhole
+  intrinsics: [460, 458, 367, 248]
+  resolution: [752, 480]
+  rostopic: /cam0/image_raw
+cam1:
+  T_cam_imu:
+  - [1, 0, 0, -0.1]
+  - [0, 1, 0, 0]
+  - [0, 0, 1, 0]
+  - [0, 0, 0, 1]
+  intrinsics: [457, 456, 379, 255]
+  resolution: [752, 480]
+";
+        let dag = tree(formats::parse("yaml", kalibr).unwrap().nodes).unwrap();
+        let (imu, cam1) = (dag.find("imu").unwrap(), dag.find("cam1").unwrap());
+        assert_eq!(dag.nodes[cam1].parent, Some(imu));
+        assert!((dag.nodes[cam1].world.translation.to_vec3() - Vec3::new(0.1, 0.0, 0.0)).length() < 1e-6);
+        assert!(dag.nodes[cam1].optical);
+
+        let opencv = "%YAML:1.0
+---
+R: !!opencv-matrix
+   rows: 3
+   cols: 3
+   dt: d
+   data: [ 1., 0., 0., 0., 1., 0., 0., 0., 1. ]
+T: !!opencv-matrix
+   rows: 3
+   cols: 1
+   dt: d
+   data: [ -0.12, 0., 0. ]
+";
+        let dag = tree(formats::parse("yml", opencv).unwrap().nodes).unwrap();
+        let cam1 = dag.find("cam1").unwrap();
+        assert_eq!(dag.nodes[cam1].parent, dag.find("cam0"));
+        assert!((dag.nodes[cam1].world.translation.to_vec3() - Vec3::new(0.12, 0.0, 0.0)).length() < 1e-6);
+    }
+
+    #[test]
+    fn tag_maps_import_in_apriltag_convention() {
+        let layout = r#"{
+            "tags": [{"ID": 7, "pose": {
+                "translation": {"x": 1.0, "y": 0.0, "z": 0.5},
+                "rotation": {"quaternion": {"W": 0.0, "X": 0.0, "Y": 0.0, "Z": 1.0}}
+            }}],
+            "field": {"length": 16.5, "width": 8.1}
+        }"#;
+        let dag = tree(formats::parse("json", layout).unwrap().nodes).unwrap();
+        let tag = &dag.nodes[dag.find("tag_7").unwrap()];
+        assert_eq!(tag.label_text(), "tag 7");
+        // The tag faces back along -X, so its Z, into the tag, is +X.
+        assert!((tag.world.rotation * Vec3::Z - Vec3::X).length() < 1e-5);
+        assert!((tag.world.rotation * Vec3::Y - Vec3::NEG_Z).length() < 1e-5);
+
+        let aruco = "%YAML:1.0
+---
+aruco_bc_dict: ARUCO_MIP_36h12
+aruco_bc_nmarkers: 1
+aruco_bc_mInfoType: 1
+aruco_bc_markers:
+   - { id: 3, corners: [ [ 0.0, 0.1, 0.0 ], [ 0.1, 0.1, 0.0 ], [ 0.1, 0.0, 0.0 ], [ 0.0, 0.0, 0.0 ] ] }
+";
+        let dag = tree(formats::parse("yml", aruco).unwrap().nodes).unwrap();
+        let tag = &dag.nodes[dag.find("tag_3").unwrap()];
+        assert!((tag.world.translation.to_vec3() - Vec3::new(0.05, 0.05, 0.0)).length() < 1e-6);
+        assert!((tag.tag.as_ref().unwrap().size - 0.1).abs() < 1e-9);
+        // A marker lying face up is seen from above, so its Z, into the tag, points down.
+        assert!((tag.world.rotation * Vec3::Z - Vec3::NEG_Z).length() < 1e-5);
+    }
+
+    #[test]
+    fn reference_deltas_span_rebased_origins() {
+        let reference = tree(vec![node("gps", None, [500_000.0, 4_000_000.0, 0.0], [0.0; 3])]).unwrap();
+        let mut live = reference.clone();
+        live.rebase(DVec3::new(500_010.0, 4_000_000.0, 0.0));
+        let gps = live.find("gps").unwrap();
+        let mut local = live.nodes[gps].local;
+        local.translation += Vec3::new(0.0, 0.002, 0.0).into();
+        live.set_local(gps, local);
+        live.update_world();
+        let deltas = diff::frame_deltas(&reference, &live);
+        assert!((deltas[0].translation - Vec3::new(0.0, 0.002, 0.0)).length() < 1e-4, "{:?}", deltas[0].translation);
+    }
+
+    #[test]
+    fn heatmap_scales_to_the_worst_frame() {
+        let a = tree(chain()).unwrap();
+        let mut nodes = chain();
+        nodes[3].t[0] += 0.004;
+        nodes[4].r[2] += 0.02;
+        let b = tree(nodes).unwrap();
+        let deltas = diff::frame_deltas(&a, &b);
+        let mut heatmap = diff::Heatmap::default();
+        let (translation, rotation) = heatmap.scale(&deltas);
+        assert!((translation - 0.004).abs() < 1e-5 && (rotation - 0.02f32.to_degrees()).abs() < 1e-2);
+        heatmap.translation = Some(0.01);
+        assert_eq!(heatmap.scale(&deltas).0, 0.01);
+    }
+
+    #[test]
+    fn diff_report_aggregates_errors() {
+        let a = tree(chain()).unwrap();
+        let mut nodes = chain();
+        nodes[3].t[0] += 0.003;
+        nodes.push(node("extra", None, [0.0; 3], [0.0; 3]));
+        let b = tree(nodes).unwrap();
+        let report = diff::Report::new(&a, &b);
+        let s = &report.summary;
+        assert_eq!(s.frames, 5);
+        assert!((s.max_translation - 0.003).abs() < 1e-5);
+        assert!((s.mean_translation - 0.0006).abs() < 1e-5);
+        assert!((s.rmse_translation - 0.003 / 5f32.sqrt()).abs() < 1e-5);
+        assert_eq!(report.only_in_second, vec!["extra".to_string()]);
+        assert_eq!(report.csv().lines().count(), 1 + 5 + 3);
+    }
+
+    #[test]
+    fn averaged_poses_use_the_rotation_mean() {
+        let turn = |z: f64| [0.0, 0.0, z];
+        let trees: Vec<FileTransformTree> = [(0.9, 0.1), (1.1, 0.3), (1.0, 0.2 + TAU)]
+            .into_iter()
+            .map(|(x, z)| FileTransformTree { version: 1, nodes: vec![node("camera", None, [x, 0.0, 0.0], turn(z))], ..Default::default() })
+            .collect();
+        let averaged = average::average_trees(&trees);
+        let camera = &averaged.nodes[0];
+        assert!((camera.t[0] - 1.0).abs() < 1e-9);
+        // The angle a full turn off still counts as 0.2.
+        assert!(camera.rotation().angle_between(DQuat::from_rotation_z(0.2)) < 1e-9);
+        let covariance = camera.covariance.as_ref().unwrap();
+        // Turned into the camera frame, the variance along the parent's X keeps its total.
+        assert!((covariance[0] + covariance[4] + covariance[8] - 0.01).abs() < 1e-9);
+        let spread = camera.metadata["rotation_spread_deg"].as_f64().unwrap();
+        assert!((spread - (0.02f64 / 3.0).sqrt().to_degrees()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn pose_plot_unwraps_yaw_and_measures_jitter() {
+        let mut plot = plot::PosePlot::default();
+        plot.window = 1.0;
+        for (i, yaw) in [3.1f32, -3.1, 3.1, -3.1].into_iter().enumerate() {
+            let x = if i % 2 == 0 { 0.001 } else { -0.001 };
+            plot.push(i as f64 * 0.4, Isometry3d::new(Vec3::new(x, 0.0, 0.0), Quat::from_rotation_z(yaw)));
+        }
+        // The first sample fell out of the one second window.
+        let (std, range) = plot.spread(0);
+        assert!((range - 0.002).abs() < 1e-6 && std > 0.0);
+        // Crossing ±180° is a small step, not a full turn.
+        let (_, yaw_range) = plot.spread(5);
+        assert!((yaw_range - (TAU as f32 - 6.2)).abs() < 1e-3, "{}", yaw_range);
+    }
+
+    #[test]
+    fn pose_csv_follows_the_animation() {
+        let dag = tree(chain()).unwrap();
+        let mut track = timeline::Track::new("base");
+        track.push(0.0, Isometry3d::from_translation(Vec3::ZERO));
+        track.push(1.0, Isometry3d::from_translation(Vec3::new(2.0, 0.0, 0.0)));
+        let animation = timeline::Animation { tracks: vec![track] };
+        let (base, arm) = (dag.find("base").unwrap(), dag.find("arm").unwrap());
+        let csv = animation.pose_csv(&dag, &[base, arm], None, Some(2.0));
+        let rows: Vec<Vec<&str>> = csv.lines().skip(1).map(|l| l.split(',').collect()).collect();
+        assert_eq!(rows.len(), 3 * 2);
+        assert_eq!((rows[2][0], rows[2][1], rows[2][2]), ("0.5", "base", "1"));
+        // Relative to the base, the arm doesn't move.
+        let relative = animation.pose_csv(&dag, &[arm], Some(base), None);
+        let xs: Vec<&str> = relative.lines().skip(1).map(|l| l.split(',').nth(2).unwrap()).collect();
+        assert_eq!(xs[0], xs[1]);
+    }
+
+    #[test]
+    fn rendered_clips_cover_both_ends() {
+        use crate::video::{Output, frame_count};
+        assert_eq!(frame_count(2.0, 30.0), 61);
+        assert_eq!(frame_count(0.0, 30.0), 1);
+        assert_eq!(frame_count(0.1, 30.0), 4);
+        let frames = Output::Frames(PathBuf::from("out"));
+        assert_eq!(frames.path(12), PathBuf::from("out/frame_00012.png"));
+        assert_eq!(Output::Still(PathBuf::from("a.png")).path(3), PathBuf::from("a.png"));
+    }
+
+    #[test]
+    fn turntable_makes_one_seamless_turn() {
+        let mut turntable = camera::Turntable { seconds: 4.0, ..Default::default() };
+        assert!(!turntable.spinning());
+        turntable.start();
+        assert!(turntable.spinning());
+        assert!((turntable.angle(1.0) - std::f32::consts::FRAC_PI_2).abs() < 1e-6);
+        assert!((turntable.angle(9.0) - std::f32::consts::TAU).abs() < 1e-6);
+        // The pose at the full turn would repeat the first frame.
+        assert_eq!(video::turntable_frames(4.0, 30.0), 120);
+        assert!(video::is_video(Path::new("spin.gif")));
+        assert!(!video::is_video(Path::new("frames")));
+    }
+
+    #[test]
+    fn stereo_eyes_share_the_window() {
+        let [left, right] = stereo::eye_viewports(UVec2::new(1001, 600), false);
+        assert_eq!(left, (UVec2::ZERO, UVec2::new(500, 600)));
+        assert_eq!(right, (UVec2::new(500, 0), UVec2::new(501, 600)));
+        let [left, _] = stereo::eye_viewports(UVec2::new(1001, 600), true);
+        assert_eq!(left.0, UVec2::new(500, 0));
+    }
+
+    #[test]
+    fn gamepad_bindings_round_trip_through_the_config() {
+        assert_eq!(gamepad::deadzone(0.1, 0.15), 0.0);
+        assert!((gamepad::deadzone(-1.0, 0.15) + 1.0).abs() < 1e-6);
+        assert!((gamepad::deadzone(0.575, 0.15) - 0.5).abs() < 1e-6);
+        let mut config = config::Config::default();
+        config.gamepad.orbit_x = GamepadAxis::LeftStickX;
+        config.gamepad.invert_y = true;
+        let text = toml::to_string_pretty(&config).unwrap();
+        let loaded: config::Config = toml::from_str(&text).unwrap();
+        assert_eq!(loaded.gamepad, config.gamepad);
+    }
+
+    #[test]
+    fn clipping_plane_cuts_the_normal_side() {
+        let mut clip = clip::ClipPlane { point: Vec3::new(1.0, 0.0, 0.0), normal: Vec3::X, ..Default::default() };
+        assert!(!clip.clips(Vec3::new(2.0, 0.0, 0.0)));
+        clip.enabled = true;
+        assert!(clip.clips(Vec3::new(2.0, 5.0, 0.0)));
+        assert!(!clip.clips(Vec3::new(0.5, 5.0, 0.0)));
+        assert!((clip.distance(Vec3::new(-1.0, 0.0, 3.0)) + 2.0).abs() < 1e-6);
+        // The arm swings from the base at x = 1 over to x = -1.
+        clip.point = Vec3::ZERO;
+        let dag = tree(chain()).unwrap();
+        let world = |name: &str| dag.nodes[dag.find(name).unwrap()].world.translation.to_vec3();
+        assert!(clip.clips(world("base")));
+        assert!(!clip.clips(world("arm")));
+    }
+
+    #[test]
+    fn exploded_frames_move_away_from_their_parent() {
+        let mut nodes = chain();
+        nodes.push(node("imu", Some("base"), [0.0, 0.0, 0.0], [0.0, 0.0, 0.0]));
+        nodes.push(node("footprint", Some("base"), [0.0, 0.0, 0.0], [0.0, 0.0, 0.0]));
+        let dag = tree(nodes).unwrap();
+        assert!(explode::explode(&dag, 0.0).iter().all(|o| *o == Vec3::ZERO));
+        let offsets = explode::FrameOffsets { offsets: explode::explode(&dag, 0.5) };
+        let id = |name: &str| dag.find(name).unwrap();
+        assert_eq!(offsets.get(id("base")), Vec3::ZERO);
+        // The arm sits 2 m from the base along world -X; it moves 0.5 m further.
+        assert!(offsets.get(id("arm")).abs_diff_eq(Vec3::new(-0.5, 0.0, 0.0), 1e-5));
+        // Descendants carry their ancestors' offsets along.
+        let wrist = offsets.get(id("wrist")) - offsets.get(id("arm"));
+        assert!((wrist.length() - 0.5).abs() < 1e-5);
+        // Frames on top of their parent fan out in different directions.
+        let (imu, footprint) = (offsets.position(&dag, id("imu")), offsets.position(&dag, id("footprint")));
+        assert!(imu.distance(footprint) > 0.1);
+        assert!((imu.distance(offsets.position(&dag, id("base"))) - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn minimap_footprint_stays_bounded() {
+        let down = Ray3d::new(Vec3::new(1.0, 5.0, 2.0), Dir3::new(Vec3::new(0.0, -1.0, 1.0)).unwrap());
+        assert!(hud::ground_point(down, Vec3::Y, 1.0, 100.0).abs_diff_eq(Vec3::new(1.0, 1.0, 6.0), 1e-5));
+        // Looking up or far past the horizon ends at the reach, on the plane.
+        let up = Ray3d::new(Vec3::new(0.0, 5.0, 0.0), Dir3::new(Vec3::new(1.0, 1.0, 0.0)).unwrap());
+        assert!(hud::ground_point(up, Vec3::Y, 1.0, 10.0).abs_diff_eq(Vec3::new(10.0, 1.0, 0.0), 1e-5));
+        let shallow = Ray3d::new(Vec3::new(0.0, 5.0, 0.0), Dir3::new(Vec3::new(0.0, -0.01, 1.0)).unwrap());
+        assert!(hud::ground_point(shallow, Vec3::Y, 1.0, 10.0).abs_diff_eq(Vec3::new(0.0, 1.0, 10.0), 1e-5));
+        // Z-up scenes map X right and Y up the screen, and meet the floor along Z.
+        let plane = grid::GridPlane::Xy;
+        let p = Vec3::new(2.0, 3.0, 7.0);
+        assert_eq!(plane.top_down(p), Vec2::new(2.0, -3.0));
+        assert_eq!(plane.from_top_down(plane.top_down(p), 7.0), p);
+        let down = Ray3d::new(Vec3::new(1.0, 2.0, 5.0), Dir3::NEG_Z);
+        assert!(hud::ground_point(down, plane.up(), 1.0, 100.0).abs_diff_eq(Vec3::new(1.0, 2.0, 1.0), 1e-5));
+        let [x, y, z] = plane.orbit_axes();
+        assert!(x.cross(y).abs_diff_eq(z, 1e-6));
+    }
+
+    #[test]
+    fn bvh_channels_apply_in_file_order() {
+        let bvh = "HIERARCHY
+ROOT hips
+{
+  OFFSET 0 1 0
+  CHANNELS 6 Xposition Yposition Zposition Zrotation Xrotation Yrotation
+  JOINT knee
+  {
+    OFFSET 0 -1 0
+    CHANNELS 3 Zrotation Xrotation Yrotation
+    End Site
+    {
+      OFFSET 0 -2 0
+    }
+  }
+}
+MOTION
+Frames: 2
+Frame Time: 0.5
+0 0 0 0 0 0 0 0 0
+1 2 3 90 90 0 0 0 0
+";
+        let (file, animation) = formats::parse_animated("bvh", bvh).unwrap();
+        let dag = tree(file.nodes).unwrap();
+        let end = dag.find("knee_end").unwrap();
+        assert_eq!(dag.nodes[end].parent, dag.find("knee"));
+        assert!((dag.nodes[end].world.translation.to_vec3() - Vec3::new(0.0, -2.0, 0.0)).length() < 1e-6);
+
+        let hips = animation.unwrap().tracks[0].sample(0.5).unwrap();
+        assert!((hips.translation.to_vec3() - Vec3::new(1.0, 3.0, 3.0)).length() < 1e-5);
+        // Zrotation listed before Xrotation: rotate about Z, then about the turned X.
+        let (z, x) = (Quat::from_rotation_z(FRAC_PI_2 as f32), Quat::from_rotation_x(FRAC_PI_2 as f32));
+        assert!(hips.rotation.angle_between(z * x) < 1e-5);
+        assert!(hips.rotation.angle_between(x * z) > 0.1);
+    }
+
+    #[test]
+    fn dh_conventions_place_a_planar_arm() {
+        let table = r#"{ "convention": "both", "degrees": true, "joints": [
+            { "name": "link1", "a": 1.0, "alpha": 0.0, "d": 0.0, "theta": 90.0 },
+            { "name": "link2", "a": 0.5, "alpha": 0.0, "d": 0.0, "theta": -90.0 }
+        ] }"#;
+        let dag = tree(formats::parse("dh", table).unwrap().nodes).unwrap();
+        let end = |name: &str| dag.nodes[dag.find(name).unwrap()].world;
+        // Standard moves along each link's x after its rotation, modified before it.
+        let (standard, modified) = (end("link2_std"), end("link2_mdh"));
+        assert!((standard.translation.to_vec3() - Vec3::new(0.5, 1.0, 0.0)).length() < 1e-6);
+        assert!((modified.translation.to_vec3() - Vec3::new(1.0, 0.5, 0.0)).length() < 1e-6);
+        assert!(standard.rotation.angle_between(Quat::IDENTITY) < 1e-6);
+        assert!(modified.rotation.angle_between(Quat::IDENTITY) < 1e-6);
+    }
+
+    #[test]
+    fn ik_reaches_targets_within_limits() {
+        let revolute = |limits| Some(joint::FileJoint { kind: joint::JointType::Revolute, axis: [0.0, 0.0, 1.0], limits, value: Some(0.3) });
+        let mut nodes = vec![
+            node("base", None, [0.0; 3], [0.0; 3]),
+            node("shoulder", Some("base"), [0.0; 3], [0.0; 3]),
+            node("elbow", Some("shoulder"), [1.0, 0.0, 0.0], [0.0; 3]),
+            node("tool", Some("elbow"), [1.0, 0.0, 0.0], [0.0; 3]),
+        ];
+        nodes[1].joint = revolute(None);
+        nodes[2].joint = revolute(None);
+        let target = Vec3::new(1.0, 1.0, 0.0);
+        let mut dag = tree(nodes.clone()).unwrap();
+        let (elbow, tool) = (dag.find("elbow").unwrap(), dag.find("tool").unwrap());
+        assert_eq!(ik::chain(&dag, tool), vec![dag.find("shoulder").unwrap(), elbow]);
+        assert!(ik::solve(&mut dag, tool, target) < 1e-3);
+        assert!((dag.nodes[tool].world.translation.to_vec3() - target).length() < 1e-3);
+
+        // Reaching the target takes a right angle at the elbow, past its limit.
+        nodes[2].joint = revolute(Some([-0.5, 0.5]));
+        let mut dag = tree(nodes).unwrap();
+        assert!(ik::solve(&mut dag, tool, target) > 0.1);
+        let value = dag.nodes[elbow].joint.as_ref().unwrap().value;
+        assert!((-0.5..=0.5).contains(&value), "{}", value);
+    }
+
+    #[test]
+    fn pcd_clouds_parse() {
+        // A three-column normal ahead of x, y, z.
+        let ascii = b"VERSION .7\nFIELDS normal x y z\nSIZE 4 4 4 4\nTYPE F F F F\nCOUNT 3 1 1 1\nPOINTS 2\nDATA ascii\n0 0 1 1.5 2.5 3.5\n0 1 0 -1 -2 -3\n";
+        assert_eq!(clouds::parse_pcd(ascii).unwrap(), vec![[1.5, 2.5, 3.5], [-1.0, -2.0, -3.0]]);
+
+        let mut binary = b"FIELDS flags x y z\nSIZE 1 4 4 8\nTYPE U F F F\nCOUNT 2 1 1 1\nPOINTS 2\nDATA binary\n".to_vec();
+        for (x, y, z) in [(1.0f32, 2.0f32, 3.0f64), (4.0, 5.0, 6.0)] {
+            binary.extend([7, 9]);
+            binary.extend(x.to_le_bytes());
+            binary.extend(y.to_le_bytes());
+            binary.extend(z.to_le_bytes());
+        }
+        assert_eq!(clouds::parse_pcd(&binary).unwrap(), vec![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        assert!(clouds::parse_pcd(&binary[..binary.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn ply_clouds_parse() {
+        let ascii = b"ply\nformat ascii 1.0\nelement vertex 2\nproperty uchar red\nproperty float x\nproperty float y\nproperty float z\nend_header\n255 1 2 3\n0 4 5 6\n";
+        assert_eq!(clouds::parse_ply(ascii).unwrap(), vec![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+
+        let header = "ply\nformat binary_big_endian 1.0\nelement vertex 2\nproperty uchar red\nproperty double x\nproperty float y\nproperty short z\nend_header\n";
+        let mut big = header.as_bytes().to_vec();
+        for (x, y, z) in [(1.0f64, 2.0f32, 3i16), (-4.0, 5.0, -6)] {
+            big.push(255);
+            big.extend(x.to_be_bytes());
+            big.extend(y.to_be_bytes());
+            big.extend(z.to_be_bytes());
+        }
+        assert_eq!(clouds::parse_ply(&big).unwrap(), vec![[1.0, 2.0, 3.0], [-4.0, 5.0, -6.0]]);
+
+        // A vertex count whose size overflows is refused rather than wrapped.
+        let huge = header.replace("vertex 2", &format!("vertex {}", usize::MAX));
+        assert!(clouds::parse_ply(&[huge.as_bytes(), &big[header.len()..]].concat()).is_err());
+    }
+
+    #[cfg(feature = "xr")]
+    #[test]
+    fn headset_stands_below_the_focus() {
+        let root = xr::tracking_root(Vec3::new(1.0, 2.0, 3.0), grid::GridPlane::Xy);
+        assert!((root.translation - Vec3::new(1.0, 2.0, 0.0)).length() < 1e-6);
+        // The headset's up is the grid's.
+        assert!((root.rotation * Vec3::Y - Vec3::Z).length() < 1e-6);
+        let root = xr::tracking_root(Vec3::new(1.0, 2.0, 3.0), grid::GridPlane::Xz);
+        assert_eq!((root.translation, root.rotation), (Vec3::new(1.0, 0.0, 3.0), Quat::IDENTITY));
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn http_frame_names_are_percent_decoded() {
+        assert_eq!(http::percent_decode("cam%2Fleft").as_deref(), Some("cam/left"));
+        assert_eq!(http::percent_decode("base_link").as_deref(), Some("base_link"));
+        assert_eq!(http::percent_decode("bad%2"), None);
+        assert_eq!(http::percent_decode("%ff"), None);
+    }
+
+    #[cfg(feature = "rosbag")]
+    #[test]
+    fn cdr_tf_messages_decode() {
+        use std::f64::consts::FRAC_1_SQRT_2;
+        // Little-endian TFMessage; primitives are aligned to their size past the header.
+        let align = |body: &mut Vec<u8>, n: usize| body.resize(body.len().next_multiple_of(n), 0);
+        let mut body = 2u32.to_le_bytes().to_vec();
+        let transforms = [
+            ("/map", "odom", [1.0, 2.0, 3.0], [0.0, 0.0, 0.0, 1.0]),
+            ("odom", "base_link", [0.5, 0.0, 0.0], [0.0, 0.0, FRAC_1_SQRT_2, FRAC_1_SQRT_2]),
+        ];
+        for (parent, child, t, q) in transforms {
+            body.extend(7u32.to_le_bytes());
+            body.extend(500u32.to_le_bytes());
+            for name in [parent, child] {
+                align(&mut body, 4);
+                body.extend((name.len() as u32 + 1).to_le_bytes());
+                body.extend(name.as_bytes());
+                body.push(0);
+            }
+            for v in t.into_iter().chain(q) {
+                align(&mut body, 8);
+                body.extend(v.to_le_bytes());
+            }
+        }
+        let payload = [vec![0, 1, 0, 0], body].concat();
+
+        let decoded = formats::rosbag::decode_tf_message(&payload).unwrap();
+        assert_eq!(decoded.len(), 2);
+        assert_eq!((decoded[0].parent.as_str(), decoded[0].child.as_str()), ("map", "odom"));
+        assert_eq!((decoded[1].parent.as_str(), decoded[1].child.as_str()), ("odom", "base_link"));
+        assert_eq!(decoded[0].pose.translation.vector, nalgebra::Vector3::new(1.0, 2.0, 3.0));
+        assert!((decoded[1].pose.rotation.angle() - FRAC_PI_2).abs() < 1e-9);
+        assert!((decoded[1].pose.rotation.axis().unwrap().z - 1.0).abs() < 1e-9);
+
+        assert!(formats::rosbag::decode_tf_message(&payload[..payload.len() - 4]).is_err());
+        assert!(formats::rosbag::decode_tf_message(&payload[..18]).is_err());
+        assert!(formats::rosbag::decode_tf_message(&payload[..2]).is_err());
+    }
+
+    #[cfg(feature = "proto")]
+    #[test]
+    fn proto_updates_keep_their_parent_and_bounded_size() {
+        let dag = tree(chain()).unwrap();
+        let update = |name: &str, parent: Option<&str>| stream::proto::TransformUpdate {
+            name: name.to_string(),
+            parent: parent.map(str::to_string),
+            qw: 1.0,
+            ..Default::default()
+        };
+        assert_eq!(stream::update_node(update("wrist", None), &dag).parent.as_deref(), Some("arm"));
+        assert_eq!(stream::update_node(update("wrist", Some("base")), &dag).parent.as_deref(), Some("base"));
+        assert_eq!(stream::update_node(update("new", None), &dag).parent, None);
+
+        let mut small: &[u8] = &[3, 1, 2, 3];
+        assert_eq!(stream::read_delimited(&mut small).unwrap(), Some(vec![1, 2, 3]));
+        // A 2 GiB length prefix is refused before anything is allocated.
+        let mut huge: &[u8] = &[0x80, 0x80, 0x80, 0x80, 0x08];
+        assert_eq!(stream::read_delimited(&mut huge).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+}
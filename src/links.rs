@@ -0,0 +1,135 @@
+//! How the lines from each frame to its parent are drawn.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{NodeId, TransformTree};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkShape {
+    #[default]
+    Straight,
+    /// Along Z to the child's height, then along X and Y.
+    Elbow,
+    /// S-curve that leaves the parent and enters the child vertically.
+    Curved,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkColoring {
+    /// The style's link color.
+    #[default]
+    Uniform,
+    /// Hue by depth below the root.
+    Depth,
+    /// One hue per branch leaving a root.
+    Subtree,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LinkStyle {
+    pub shape: LinkShape,
+    pub dashed: bool,
+    pub coloring: LinkColoring,
+    /// Arrowhead at the child end, pointing from parent to child.
+    pub arrows: bool,
+}
+
+const CURVE_STEPS: usize = 16;
+
+impl LinkStyle {
+    /// Line segments of the link from `parent` to `child`. `size` (the axis
+    /// length) sets the dash and arrowhead lengths.
+    pub fn segments(&self, parent: Vec3, child: Vec3, size: f32) -> Vec<(Vec3, Vec3)> {
+        let points = self.path(parent, child);
+        let mut segments: Vec<(Vec3, Vec3)> = if self.dashed {
+            dashes(&points, size * 0.25)
+        } else {
+            points.windows(2).map(|w| (w[0], w[1])).collect()
+        };
+        if self.arrows
+            && let Some(dir) = points
+                .windows(2)
+                .rev()
+                .find_map(|w| Dir3::new(w[1] - w[0]).ok())
+        {
+            let back = child - dir * size * 0.3;
+            let side = dir.any_orthonormal_vector() * size * 0.12;
+            let up = dir.cross(side);
+            for offset in [side, -side, up, -up] {
+                segments.push((child, back + offset));
+            }
+        }
+        segments
+    }
+
+    fn path(&self, a: Vec3, b: Vec3) -> Vec<Vec3> {
+        match self.shape {
+            LinkShape::Straight => vec![a, b],
+            LinkShape::Elbow => vec![a, a.with_z(b.z), b],
+            LinkShape::Curved => {
+                let mid = (a.z + b.z) * 0.5;
+                let (c1, c2) = (a.with_z(mid), b.with_z(mid));
+                (0..=CURVE_STEPS)
+                    .map(|i| {
+                        let t = i as f32 / CURVE_STEPS as f32;
+                        let s = 1.0 - t;
+                        a * s * s * s + c1 * 3.0 * s * s * t + c2 * 3.0 * s * t * t + b * t * t * t
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Color of the link from node `id` to its parent.
+    pub fn color(&self, dag: &TransformTree, id: NodeId, uniform: Color) -> Color {
+        match self.coloring {
+            LinkColoring::Uniform => uniform,
+            LinkColoring::Depth => hue(ancestors(dag, id).count() - 1),
+            LinkColoring::Subtree => {
+                // The ancestor directly below a root identifies the branch.
+                let branch = ancestors(dag, id)
+                    .find(|&a| dag.nodes[a].parent.is_some_and(|p| dag.nodes[p].parent.is_none()))
+                    .unwrap_or(id);
+                hue(branch)
+            }
+        }
+    }
+}
+
+/// `id` followed by its parents up to the root.
+fn ancestors(dag: &TransformTree, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+    std::iter::successors(Some(id), |&n| dag.nodes[n].parent)
+}
+
+/// Well separated hues for consecutive indices (golden angle).
+fn hue(index: usize) -> Color {
+    Color::hsl((index as f32 * 137.508) % 360.0, 0.75, 0.55)
+}
+
+/// Splits a polyline into dashes of length `dash` separated by equal gaps.
+fn dashes(points: &[Vec3], dash: f32) -> Vec<(Vec3, Vec3)> {
+    let mut segments = vec![];
+    // Distance along the polyline, in dash units, where the current piece starts.
+    let mut along = 0.0;
+    for w in points.windows(2) {
+        let length = w[0].distance(w[1]);
+        if length <= 0.0 || dash <= 0.0 {
+            continue;
+        }
+        let mut t = 0.0;
+        while t < length {
+            let phase = along % (2.0 * dash);
+            let step = if phase < dash { dash - phase } else { 2.0 * dash - phase }.min(length - t);
+            if phase < dash {
+                segments.push((w[0].lerp(w[1], t / length), w[0].lerp(w[1], (t + step) / length)));
+            }
+            t += step;
+            along += step;
+        }
+    }
+    segments
+}
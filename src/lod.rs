@@ -0,0 +1,77 @@
+//! Level of detail for crowded views: when many frames are on screen, labels
+//! and pick spheres of frames that appear tiny are hidden until the camera
+//! gets closer.
+
+use bevy::prelude::*;
+
+use crate::TransformTree;
+use crate::camera::MainCamera;
+use crate::style::Style;
+
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct LodSettings {
+    pub enabled: bool,
+    /// Level of detail only kicks in with more frames than this on screen.
+    pub max_on_screen: usize,
+    /// Frames whose axes are shorter than this on screen lose their label and sphere.
+    pub min_pixels: f32,
+}
+
+impl Default for LodSettings {
+    fn default() -> Self {
+        LodSettings { enabled: true, max_on_screen: 200, min_pixels: 12.0 }
+    }
+}
+
+/// Per node, whether its label and sphere are currently hidden by level of detail.
+#[derive(Resource, Debug, Default)]
+pub struct FrameLod {
+    pub culled: Vec<bool>,
+}
+
+impl FrameLod {
+    pub fn is_culled(&self, id: usize) -> bool {
+        self.culled.get(id).copied().unwrap_or(false)
+    }
+}
+
+pub fn update_lod(
+    dag: Res<TransformTree>,
+    settings: Res<LodSettings>,
+    style: Res<Style>,
+    camera_q: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut lod: ResMut<FrameLod>,
+) {
+    let Ok((camera, cam_transform)) = camera_q.single() else {
+        return;
+    };
+    let Some(size) = camera.logical_viewport_size() else {
+        return;
+    };
+    let screen = Rect::from_corners(Vec2::ZERO, size);
+    let right = cam_transform.right();
+
+    let mut on_screen = 0;
+    let pixels: Vec<Option<f32>> = dag
+        .nodes
+        .iter()
+        .map(|node| {
+            let o = node.world.translation.to_vec3();
+            let a = camera.world_to_viewport(cam_transform, o).ok()?;
+            let b = camera.world_to_viewport(cam_transform, o + right * style.axis_scale).ok()?;
+            screen.contains(a).then(|| {
+                on_screen += 1;
+                a.distance(b)
+            })
+        })
+        .collect();
+
+    let crowded = settings.enabled && on_screen > settings.max_on_screen;
+    let culled: Vec<bool> = pixels
+        .iter()
+        .map(|px| crowded && px.is_some_and(|px| px < settings.min_pixels))
+        .collect();
+    if lod.culled != culled {
+        lod.culled = culled;
+    }
+}
@@ -1,368 +1,613 @@
-use std::path::{Path, PathBuf};
+#![cfg_attr(target_arch = "wasm32", allow(dead_code, unused_imports))]
 
-use bevy::asset::ron::de::Position;
-use bevy::camera::ViewportConversionError;
+use std::path::PathBuf;
+
+use axisviz::{
+    FileTransformTree, average, batched, camera, config, convention, diff, formats, grid, kinematics, load_animated_tree, load_transform_tree, print,
+    recording, scene, schema, smoothing, stale, stereo, stream, timeline, uncertainty, units, video, viewer,
+};
 use bevy::prelude::*;
-use bevy_debug_grid::DebugGridPlugin;
-use bevy_panorbit_camera::{PanOrbitCamera, PanOrbitCameraPlugin};
-use serde::{ Deserialize, Serialize };
-use clap::Parser;
-use nalgebra as na;
-use na::Isometry3;
-use anyhow::Result;
-use std::fs::File;
-use std::io::BufReader;
-use serde_json;
-use std::f64::consts::{PI, TAU, FRAC_PI_2, FRAC_PI_4};
-use std::convert::TryFrom;
-use thiserror::Error;
-use std::collections::HashMap;
-
-
-
-pub type NodeId = usize;
-
-#[derive(Debug)]
-struct TNode {
-    name: String,
-    parent: Option<NodeId>,
-    children: Vec<NodeId>,
-    local: Isometry3d,
-    world: Isometry3d,
-    dirty: bool,
-}
+use clap::{Parser, Subcommand};
+use nalgebra::Isometry3;
 
-#[derive(Debug, Resource)]
-struct TransformTree {
-    nodes: Vec<TNode>,
-}
+#[derive(Parser, Debug)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
 
-impl TransformTree {
-    fn add_node(&mut self, name: &str, local: Isometry3d, parent: Option<NodeId>) -> NodeId {
-        let id = self.nodes.len();
-        self.nodes.push(TNode {
-            name: name.to_string(),
-            parent: None,
-            children: vec![],
-            local,
-            world: Isometry3d::IDENTITY,
-            dirty: true
-        });
-        if let Some(p) = parent && p < id {
-            self.nodes[p].children.push(id);
-            self.nodes[id].parent = Some(p);
-            self.nodes[id].world = self.nodes[p].world * self.nodes[id].local;
-            self.nodes[id].dirty = false;
-        } else {
-            self.nodes[id].world = self.nodes[id].local;
-            self.nodes[id].dirty = false;
-        }
-        id
-    }
-    fn name_hash(&self) -> Result<HashMap<String, NodeId>, FileTransformTreeError> {
-        let mut map = HashMap::with_capacity(self.nodes.len());
-        for (id, node) in self.nodes.iter().enumerate() {
-            let name = &node.name;
-            if map.insert(name.clone(), id).is_some() {
-                return Err(FileTransformTreeError::Duplicate(name.clone()));
-            }
-        }
-        Ok(map)
-    }
-    fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) {
-        if let Some(p) = self.nodes[id].parent.take() {
-            self.nodes[p].children.retain(|&c| c != id);
-        }
-        if let Some(p) = parent {
-            self.nodes[p].children.push(id);
-            self.nodes[id].parent = Some(p);
-        }
-        self.mark_dirty(id);
-    }
-    fn mark_dirty(&mut self, id: NodeId) {
-        use std::collections::VecDeque;
-        let mut q = VecDeque::from([id]);
-        while let Some(n) = q.pop_front() {
-            if !self.nodes[n].dirty {
-                self.nodes[n].dirty = true;
-                q.extend(self.nodes[n].children.iter().copied());
-            }
-        }
-    }
-    fn update_world(&mut self) {
-        let roots: Vec<_> = (0..self.nodes.len())
-            .filter(|&i| self.nodes[i].parent.is_none())
-            .collect();
-        let mut stack: Vec<(NodeId, Isometry3d)> = roots.into_iter().map(|r| (r, Isometry3d::IDENTITY)).collect();
-        while let Some((id, parent_world)) = stack.pop() {
-            if self.nodes[id].dirty {
-                self.nodes[id].world = parent_world * self.nodes[id].local;
-                self.nodes[id].dirty = false;
-            }
-            let world = self.nodes[id].world;
-            for &child in &self.nodes[id].children {
-                stack.push((child, world));
-            }
-        }
-    }
-}
+    /// Tree files to load; several are merged into one scene
+    #[arg(required_unless_present = "stdin")]
+    filenames: Vec<PathBuf>,
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct FileTransformTree {
-    pub version: u32,
-    pub nodes: Vec<FileNode>,
-}
+    /// Length unit of the input files, overriding their `units` field
+    #[arg(long, value_enum)]
+    units: Option<units::Units>,
+
+    /// Axis convention of the input files, overriding their `convention` field
+    #[arg(long, value_enum)]
+    convention: Option<convention::Convention>,
+
+    /// Comma separated frame name prefixes, one per input file, e.g. robot1:,robot2:
+    #[arg(long = "prefix", value_delimiter = ',')]
+    prefixes: Vec<String>,
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct FileNode {
-    pub name: String,
-    pub parent: Option<String>,
-    pub t: [f64; 3],
-    pub r: [f64; 3],
+    /// Root offset "x y z roll pitch yaw" for each input file, in order. Repeatable
+    #[arg(long = "offset", value_parser = scene::parse_pose)]
+    offsets: Vec<Isometry3<f64>>,
+
+    /// Place everything below a virtual root frame at "x y z roll pitch yaw", e.g. a site or world frame
+    #[arg(long, value_parser = scene::parse_pose)]
+    root_transform: Option<Isometry3<f64>>,
+
+    /// Name of the frame added by --root-transform
+    #[arg(long, default_value = "world", requires = "root_transform")]
+    root_name: String,
+
+    /// Read node updates from standard input
+    #[arg(long)]
+    stdin: bool,
+
+    /// Read joint values from standard input, one JSON object per line, e.g. {"elbow": 0.5}
+    #[arg(long, conflicts_with = "stdin")]
+    stdin_joints: bool,
+
+    /// Encoding of updates on standard input
+    #[arg(long, value_enum, default_value_t = stream::StreamFormat::Json, requires = "stdin")]
+    stdin_format: stream::StreamFormat,
+
+    /// Show the tree in side-by-side stereo, one eye per half of the window
+    #[arg(long)]
+    stereo: bool,
+
+    /// Smooth live updates with this time constant in seconds; 0 shows them as they arrive
+    #[arg(long, default_value_t = 0.0)]
+    smooth: f32,
+
+    /// Gray out streamed frames that receive no update for this many seconds
+    #[arg(long, default_value_t = 2.0)]
+    stale_timeout: f64,
+
+    /// Reference tree to check the live frames against: drawn ghosted, with
+    /// each frame's error in the Reference window
+    #[arg(long)]
+    reference: Option<PathBuf>,
+
+    /// Record live updates to this file (.azl), which can be opened again to replay them
+    #[arg(long)]
+    record: Option<PathBuf>,
+
+    /// Comma separated time offsets (seconds) to draw ghosted frames at during playback, e.g. -0.5,-1.0
+    #[arg(long = "ghost", value_delimiter = ',', allow_negative_numbers = true)]
+    ghosts: Vec<f64>,
+
+    /// Number of standard deviations covariance ellipsoids are drawn at
+    #[arg(long, default_value_t = 1.0)]
+    sigma: f32,
+
+    #[command(flatten)]
+    joints: JointArgs,
+
+    #[command(flatten)]
+    grid: GridArgs,
+
+    /// Seconds the camera takes to move to a newly focused frame
+    #[arg(long, default_value_t = 0.5)]
+    focus_duration: f32,
+
+    #[arg(long, value_enum, default_value_t = camera::Easing::Smooth)]
+    focus_easing: camera::Easing,
+
+    /// Draw axes and links as batched meshes when the tree has more frames than this
+    #[arg(long, default_value_t = 2000)]
+    batch_axes_above: usize,
+
+    /// Serve the gRPC control API on this address, e.g. 127.0.0.1:50051
+    #[cfg(feature = "grpc")]
+    #[arg(long)]
+    grpc: Option<std::net::SocketAddr>,
+
+    /// Serve the HTTP/JSON API on this address, e.g. 127.0.0.1:8080
+    #[cfg(feature = "http")]
+    #[arg(long)]
+    http: Option<std::net::SocketAddr>,
+
+    /// MQTT broker to subscribe to, as host or host:port
+    #[cfg(feature = "mqtt")]
+    #[arg(long, requires = "mqtt_topics")]
+    mqtt: Option<String>,
+
+    /// Topic pattern to merge poses from, e.g. robots/+/tf; wildcard matches become frame prefixes. Repeatable
+    #[cfg(feature = "mqtt")]
+    #[arg(long = "mqtt-topic")]
+    mqtt_topics: Vec<String>,
+
+    /// rosbridge server to merge ROS1 /tf and /tf_static from, e.g. ws://localhost:9090
+    #[cfg(feature = "rosbridge")]
+    #[arg(long)]
+    rosbridge: Option<String>,
+
+    /// Write a Chrome trace of loading, tree updates, drawing and streaming to this file
+    #[cfg(feature = "profile")]
+    #[arg(long, value_name = "FILE")]
+    profile: Option<PathBuf>,
+
+    /// View the tree at true scale in an OpenXR headset, standing below the camera focus
+    #[cfg(feature = "xr")]
+    #[arg(long)]
+    xr: bool,
 }
 
-impl From<&FileNode> for Isometry3d {
-    fn from(node: &FileNode) -> Self {
-        let [tx, ty, tz] = node.t;
-        let [r, p, y] = node.r;
-        let rot = Quat::from_euler(EulerRot::XYZ, r as f32, p as f32, y as f32);
-        Isometry3d::new(Vec3::new(tx as f32, ty as f32, tz as f32), rot)
-    }
+#[derive(clap::Args, Debug)]
+struct JointArgs {
+    /// File of joint values to pose the tree with, as a JSON object or name=value pairs
+    #[arg(long = "joints")]
+    joint_file: Option<PathBuf>,
+
+    /// Joint value as name=value, radians or meters. Repeatable; applied after --joints
+    #[arg(long = "joint", value_parser = kinematics::parse_assignment, allow_negative_numbers = true)]
+    joint_values: Vec<(String, f64)>,
 }
 
-impl FileTransformTree {
-    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
-        Ok(serde_json::from_reader(reader)?)
-    }
-    pub fn name_hash(&self) -> Result<HashMap<String, NodeId>, FileTransformTreeError> {
-        let mut map = HashMap::with_capacity(self.nodes.len());
-        for (id, node) in self.nodes.iter().enumerate() {
-            let name = &node.name;
-            if map.insert(name.clone(), id).is_some() {
-                return Err(FileTransformTreeError::Duplicate(name.clone()));
-            }
+impl JointArgs {
+    /// Poses `dag` with the given joint values.
+    fn apply(&self, dag: &mut axisviz::TransformTree) -> anyhow::Result<()> {
+        let mut values = match &self.joint_file {
+            Some(path) => kinematics::parse_values(&std::fs::read_to_string(path)?)?,
+            None => kinematics::JointValues::new(),
+        };
+        values.extend(self.joint_values.iter().cloned());
+        let unknown = dag.set_joints(&values);
+        if !unknown.is_empty() {
+            anyhow::bail!("no movable joints named {}", unknown.join(", "));
         }
-        Ok(map)
+        Ok(())
     }
 }
 
-#[derive(Error, Debug)]
-pub enum FileTransformTreeError {
-    #[error("Unknown Parent")]
-    ParentMissing(String),
+#[derive(clap::Args, Debug)]
+struct GridArgs {
+    /// Don't draw the floor grid
+    #[arg(long)]
+    no_grid: bool,
 
-    #[error("Duplicate Name")]
-    Duplicate(String),
+    /// Distance between grid lines
+    #[arg(long, default_value_t = 1.0)]
+    grid_spacing: f32,
 
-    #[error("Serialization Error")]
-    Serialization(String),
-}
+    /// Number of grid cells from the origin to the edge
+    #[arg(long, default_value_t = 10)]
+    grid_count: usize,
 
-impl TryFrom<FileTransformTree> for TransformTree {
-    type Error = FileTransformTreeError;
+    #[arg(long, value_enum, default_value_t = grid::GridPlane::Xz)]
+    grid_plane: grid::GridPlane,
 
-    fn try_from(ftree: FileTransformTree) -> Result<Self, Self::Error> {
-        // let name_map = ftree.name_hash()?;
-        let mut res = TransformTree{
-            nodes: vec![],
-        };
-        for node in ftree.nodes.iter() {
-            res.add_node(node.name.as_str(), Isometry3d::from(node), None);
-        }
-        let name_map = res.name_hash()?;
-        for node in ftree.nodes.iter() {
-            if let Some(p) = node.parent.clone() {
-                res.set_parent(name_map[&node.name], Some(name_map[&p]));
-            }
+    /// Grid line color as "r,g,b" in 0..1
+    #[arg(long, value_delimiter = ',', num_args = 3, default_values_t = [0.5, 0.5, 0.5])]
+    grid_color: Vec<f32>,
+}
+
+impl From<&GridArgs> for grid::GridSettings {
+    fn from(args: &GridArgs) -> Self {
+        grid::GridSettings {
+            enabled: !args.no_grid,
+            spacing: args.grid_spacing,
+            count: args.grid_count,
+            plane: args.grid_plane,
+            color: [args.grid_color[0], args.grid_color[1], args.grid_color[2]],
         }
-        res.update_world();
-        Ok(res)
     }
 }
 
-fn load_transform_tree(path: impl AsRef<Path>) -> Result<TransformTree, FileTransformTreeError> {
-    match FileTransformTree::load(path) {
-        Ok(dag) => TransformTree::try_from(dag),
-        Err(e) => Err(FileTransformTreeError::Serialization(e.to_string())),
-    }
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Compare two trees: print per-frame deltas and show both overlaid
+    Diff {
+        a: PathBuf,
+        b: PathBuf,
+        /// Write per-frame and aggregate errors to this file (.csv or .json)
+        /// instead of opening a window
+        #[arg(long)]
+        report: Option<PathBuf>,
+        /// Exit with an error if any frame moved more than this many meters.
+        /// Implies no window
+        #[arg(long)]
+        max_translation: Option<f32>,
+        /// Exit with an error if any frame turned more than this many degrees.
+        /// Implies no window
+        #[arg(long)]
+        max_rotation: Option<f32>,
+    },
+    /// Convert between tree formats, chosen by file extension, without opening a window.
+    /// A `.launch.py` or `.sh` output publishes the static frames with
+    /// `static_transform_publisher`
+    Convert {
+        input: PathBuf,
+        output: PathBuf,
+        /// Length unit of the input, overriding its `units` field. JSON output
+        /// keeps the input's units; URDF, SDF, MJCF, USD and ROS launch files are written in meters
+        #[arg(long, value_enum)]
+        units: Option<units::Units>,
+        /// Axis convention of the input, overriding its `convention` field.
+        /// The output is written in the ROS convention
+        #[arg(long, value_enum)]
+        convention: Option<convention::Convention>,
+    },
+    /// Average repeated measurements of the same frames: mean translation,
+    /// mean rotation on SO(3) and their spread, shown as uncertainty ellipsoids
+    Average {
+        #[arg(required = true, num_args = 2..)]
+        files: Vec<PathBuf>,
+        /// Write the averaged tree here instead of opening a window
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Write the poses of frames over an animation or recording as CSV, one
+    /// row per frame and time, without opening a window
+    ExportPoses {
+        input: PathBuf,
+        output: PathBuf,
+        /// Comma separated frames to export; all when not given
+        #[arg(long, value_delimiter = ',')]
+        frames: Vec<String>,
+        /// Frame the poses are relative to, instead of the world
+        #[arg(long)]
+        relative_to: Option<String>,
+        /// Samples per second over the whole time range, instead of every key time
+        #[arg(long)]
+        rate: Option<f64>,
+    },
+    /// Print the hierarchy with local and world poses, without opening a window
+    Tree {
+        file: PathBuf,
+        #[command(flatten)]
+        joints: JointArgs,
+    },
+    /// Print the JSON Schema of the tree file format
+    Schema,
+    /// Render the tree offscreen to a PNG, or with --animate or --turntable
+    /// a PNG sequence or, through ffmpeg, a video or GIF
+    Render {
+        file: PathBuf,
+        /// A .png for a still; a directory, or a video such as .mp4 or .gif, when moving
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Render the whole animation or recording instead of one frame
+        #[arg(long)]
+        animate: bool,
+        /// Orbit the camera once around the tree over this many seconds
+        #[arg(long, value_name = "SECONDS")]
+        turntable: Option<f64>,
+        #[arg(long, default_value_t = 30.0)]
+        fps: f64,
+        #[arg(long, default_value_t = 1280)]
+        width: u32,
+        #[arg(long, default_value_t = 720)]
+        height: u32,
+    },
+    /// Play back a recorded session (.azl) or any animated file on the timeline
+    Replay {
+        session: PathBuf,
+        /// Playback rate, e.g. 0.5 for half speed
+        #[arg(long, default_value_t = 1.0)]
+        rate: f64,
+        /// Stop at the end instead of looping
+        #[arg(long)]
+        no_loop: bool,
+        /// Time (seconds) to start at
+        #[arg(long, default_value_t = 0.0)]
+        start: f64,
+    },
 }
 
-#[derive(Parser, Debug)]
-struct Args {
-    filename: PathBuf
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    axisviz::web::run();
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 fn main() {
     let args = Args::parse();
-    let ttree = FileTransformTree {
-        version: 1u32,
-        nodes: vec![
-            FileNode {
-                name: "arm_base".to_string(),
-                parent: None,
-                t: [0.,0.,0.],
-                r: [0.0, 0., 0.]
-            },
-            FileNode {
-                name: "lidar".to_string(),
-                parent: Some("arm_base".to_string()),
-                t: [0.5, 0., 0.],
-                r: [PI/2., 0., 0.]
-            }
-        ]
+    #[cfg(feature = "profile")]
+    let _trace = match args.profile.as_deref().map(axisviz::profile::start).transpose() {
+        Ok(guard) => guard,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
     };
-    println!("Json Tree:\n{}", serde_json::to_string(&ttree).unwrap_or("Failed to serialize".to_string()));
-
-    match load_transform_tree(args.filename) {
-        Ok(dag) => {
-            println!("Dag: {:?}", dag);
-            App::new()
-                .insert_resource(dag)
-                .add_plugins((DefaultPlugins, PanOrbitCameraPlugin, MeshPickingPlugin, DebugGridPlugin::with_floor_grid()))
-                .add_systems(Startup, setup)
-                .add_systems(Update, draw_gizmo_axes)
-                .run();
-        },
-        Err(e) => println!("Error: {:?}", e)
+    if let Some(Command::Convert { input, output, units, convention }) = &args.command {
+        let converted = formats::load(input).and_then(|mut tree| {
+            let source = units::to_meters(&mut tree, None, *units);
+            convention::to_ros(&mut tree, None, *convention);
+            if !matches!(formats::extension(output).as_str(), "urdf" | "sdf" | "world" | "xml" | "mjcf" | "py" | "sh" | "usda" | "usd") {
+                units::from_meters(&mut tree, source);
+            }
+            formats::save(output, &tree)
+        });
+        if let Err(e) = converted {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+        return;
     }
-}
-
-#[derive(Component)]
-struct AxisOverlayLabel {
-    node: NodeId,
-}
-
-#[derive(Resource, Default)]
-struct Selection {
-    nodes: Vec<NodeId>,
-}
-
-fn setup(mut commands: Commands, dag: Res<TransformTree>, asset_server: Res<AssetServer>, mut meshes: ResMut<Assets<Mesh>>, mut materials: ResMut<Assets<StandardMaterial>>) {
-    let focus = Vec3::ZERO;
-    let transform = Transform::from_xyz(3.0, 2.0, 3.0).looking_at(focus, Vec3::Y);
-
-    // Camera
-    commands.spawn((
-        Camera3d::default(),
-        transform,
-        PanOrbitCamera::default(),
-    ));
-
-    // Light (not needed for gizmos, but good for if you add meshes later)
-    commands.spawn((
-        PointLight::default(),
-        Transform::from_xyz(2.0, 4.0, 2.0),
-    ));
-
-    let font = asset_server.load("fonts/FiraCode.ttf");
-    commands.spawn((Node {
-        position_type: PositionType::Absolute,
-        ..default()
-        },
-    )).with_children(|root| {
-        for (id, node) in dag.nodes.iter().enumerate() {
-            root.spawn((
-                AxisOverlayLabel {
-                    node: id
-                },
-                Text::new(node.name.clone()),
-                TextFont {
-                    font: font.clone(),
-                    font_size: 20.0,
-                    ..default()
-                },
-                Node {
-                    position_type: PositionType::Absolute,
-                    ..default()
-                },
-                TextColor(Color::srgb(1.0, 1.0, 1.0)),
-            ));
+    if let Some(Command::Diff { a, b, report, max_translation, max_rotation }) = &args.command
+        && (report.is_some() || max_translation.is_some() || max_rotation.is_some())
+    {
+        let (a, b) = match (load_transform_tree(a), load_transform_tree(b)) {
+            (Ok(a), Ok(b)) => (a, b),
+            (Err(e), _) | (_, Err(e)) => {
+                eprintln!("Error: {:?}", e);
+                std::process::exit(1);
+            }
+        };
+        diff::print_deltas(&a, &b);
+        let result = diff::Report::new(&a, &b);
+        if let Some(path) = report
+            && let Err(e) = result.save(path)
+        {
+            eprintln!("Error: {}: {:#}", path.display(), e);
+            std::process::exit(1);
         }
-    });
-    commands.spawn((
-        Node {
-            position_type: PositionType::Absolute,
-            ..default()
-        },
-        Transform {
-            ..default()
+        let s = &result.summary;
+        println!(
+            "{} frames: translation mean {:.5} max {:.5} rmse {:.5}, rotation (deg) mean {:.4} max {:.4} rmse {:.4}",
+            s.frames, s.mean_translation, s.max_translation, s.rmse_translation, s.mean_rotation_deg, s.max_rotation_deg, s.rmse_rotation_deg
+        );
+        if max_translation.is_some_and(|max| s.max_translation > max) || max_rotation.is_some_and(|max| s.max_rotation_deg > max) {
+            eprintln!("Error: frames differ by more than the allowed error");
+            std::process::exit(1);
         }
-    )).with_children(
-        |root| {
-            for (id, node) in dag.nodes.iter().enumerate() {
-                root.spawn((
-                    Mesh3d(meshes.add(Sphere::new(0.02))),
-                    MeshMaterial3d(materials.add(StandardMaterial{
-                        base_color: Color::srgb(1.0, 1.0, 1.0),
-                        ..default()
-                    })),
-                    Transform {
-                        translation: node.world.translation.to_vec3(),
-                        ..default()
-                    }
-                )).observe(on_center_camera);
+        return;
+    }
+    if let Some(Command::Average { files, output }) = &args.command {
+        let trees: Result<Vec<FileTransformTree>, _> = files.iter().map(|f| load_transform_tree(f).map(|dag| FileTransformTree::from(&dag))).collect();
+        let averaged = match trees {
+            Ok(trees) => average::average_trees(&trees),
+            Err(e) => {
+                eprintln!("Error: {:?}", e);
+                std::process::exit(1);
             }
+        };
+        if let Some(output) = output {
+            if let Err(e) = formats::save(output, &averaged) {
+                eprintln!("Error: {}: {:#}", output.display(), e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        match axisviz::TransformTree::try_from(averaged) {
+            Ok(dag) => {
+                let mut app = viewer(dag, grid::GridSettings::from(&args.grid));
+                app.insert_resource(uncertainty::Sigma(args.sigma));
+                app.run();
+            }
+            Err(e) => {
+                eprintln!("Error: {:?}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if let Some(Command::ExportPoses { input, output, frames, relative_to, rate }) = &args.command {
+        let exported = load_animated_tree(input).map_err(anyhow::Error::from).and_then(|(dag, animation)| {
+            let find = |name: &String| dag.find(name).ok_or_else(|| anyhow::anyhow!("no frame named {}", name));
+            let ids = if frames.is_empty() {
+                (0..dag.nodes.len()).collect()
+            } else {
+                frames.iter().map(find).collect::<anyhow::Result<Vec<_>>>()?
+            };
+            let reference = relative_to.as_ref().map(find).transpose()?;
+            let csv = animation.unwrap_or_default().pose_csv(&dag, &ids, reference, *rate);
+            Ok(std::fs::write(output, csv)?)
         });
-}
+        if let Err(e) = exported {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if let Some(Command::Schema) = &args.command {
+        println!("{}", serde_json::to_string_pretty(&schema::json_schema()).unwrap_or_default());
+        return;
+    }
+    if let Some(Command::Tree { file, joints }) = &args.command {
+        match load_transform_tree(file).map_err(anyhow::Error::from).and_then(|mut dag| {
+            joints.apply(&mut dag)?;
+            Ok(dag)
+        }) {
+            Ok(dag) => print::print_tree(&dag),
+            Err(e) => {
+                eprintln!("Error: {:?}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
 
-fn on_center_camera(click: On<Pointer<Click>>, mut transforms: Query<&mut Transform>, mut camera_q: Query<&mut PanOrbitCamera>) {
-    let transform = transforms.get_mut(click.entity).unwrap();
-    println!("on_center_camera: {:?}", transform.translation);
-    if let Ok(mut camera) = camera_q.single_mut() {
-        camera.target_focus = transform.translation;
+    if let Some(Command::Replay { session, rate, no_loop, start }) = &args.command {
+        match load_animated_tree(session) {
+            Ok((dag, animation)) => {
+                let mut timeline = timeline::Timeline::new(animation.unwrap_or_default());
+                timeline.rate = *rate;
+                timeline.looping = !no_loop;
+                timeline.seek(*start);
+                let mut app = viewer(dag, grid::GridSettings::from(&args.grid));
+                app.insert_resource(timeline).add_systems(Startup, timeline::setup_hud);
+                app.run();
+            }
+            Err(e) => {
+                eprintln!("Error: {:?}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
     }
-}
 
-fn draw_gizmo_axes(dag: Res<TransformTree>, mut gizmos: Gizmos, camera_q: Query<(&Camera, &GlobalTransform), With<Camera3d>>, mut label_q: Query<(&mut Node, &AxisOverlayLabel, &mut Visibility)>) {
-    let size = 0.2;
+    if let Some(Command::Render { file, output, animate, turntable, fps, width, height }) = &args.command {
+        let (dag, animation) = match load_animated_tree(file) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                eprintln!("Error: {:?}", e);
+                std::process::exit(1);
+            }
+        };
+        let animation = animation.filter(|_| *animate);
+        if *animate && animation.is_none() {
+            eprintln!("Error: {} has no animation to render", file.display());
+            std::process::exit(1);
+        }
+        // A video goes through a PNG sequence in a scratch directory.
+        let moving = *animate || turntable.is_some();
+        let video_file = moving && video::is_video(output);
+        let frames_dir = if video_file { video::scratch_dir() } else { output.clone() };
+        let (out, frames) = match (turntable, &animation) {
+            (Some(seconds), _) => (video::Output::Frames(frames_dir.clone()), video::turntable_frames(*seconds, *fps)),
+            (None, Some(animation)) => (video::Output::Frames(frames_dir.clone()), video::frame_count(animation.duration(), *fps)),
+            (None, None) => (video::Output::Still(output.clone()), 1),
+        };
+        if let video::Output::Frames(dir) = &out
+            && let Err(e) = std::fs::create_dir_all(dir)
+        {
+            eprintln!("Error: {}: {}", dir.display(), e);
+            std::process::exit(1);
+        }
+        let mut app = viewer(dag, grid::GridSettings::from(&args.grid));
+        if let Some(animation) = animation {
+            let mut timeline = timeline::Timeline::new(animation);
+            timeline.playing = false;
+            app.insert_resource(timeline);
+        }
+        if let Some(seconds) = turntable {
+            let mut spin = camera::Turntable { seconds: *seconds as f32, ..default() };
+            spin.start();
+            app.insert_resource(spin);
+        }
+        app.insert_resource(video::VideoRender::new(out, UVec2::new(*width, *height), *fps, frames))
+            .add_systems(PostStartup, video::setup_target);
+        app.run();
+        if video_file {
+            let encoded = video::encode(&frames_dir, *fps, output);
+            let _ = std::fs::remove_dir_all(&frames_dir);
+            if let Err(e) = encoded {
+                eprintln!("Error: {}: {}", output.display(), e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(Command::Diff { a, b, .. }) = &args.command {
+        match (load_transform_tree(a), load_transform_tree(b)) {
+            (Ok(a), Ok(b)) => {
+                diff::print_deltas(&a, &b);
+                let mut app = viewer(a, grid::GridSettings::from(&args.grid));
+                app.insert_resource(diff::DiffTree(b))
+                    .add_systems(Update, diff::draw_diff);
+                app.run();
+            }
+            (Err(e), _) | (_, Err(e)) => {
+                eprintln!("Error: {:?}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
 
-    for node in dag.nodes.iter() {
-        let o = node.world.translation.to_vec3();
-        gizmos.line(o, o + node.world.rotation * Vec3::X * size, Color::srgb(1.0, 0.0, 0.0));
-        gizmos.line(o, o + node.world.rotation * Vec3::Y * size, Color::srgb(0.0, 1.0, 0.0));
-        gizmos.line(o, o + node.world.rotation * Vec3::Z * size, Color::srgb(0.0, 0.0, 1.0));
-        if let Some(p) = node.parent {
-            gizmos.line(dag.nodes[p].world.translation.to_vec3(), o, Color::srgb(1.0, 1.0, 0.0));
+    let files: Vec<_> = args
+        .filenames
+        .iter()
+        .enumerate()
+        .map(|(i, path)| scene::SceneFile {
+            path,
+            prefix: args.prefixes.get(i).map_or("", String::as_str),
+            offset: args.offsets.get(i).copied(),
+            units: args.units,
+            convention: args.convention,
+        })
+        .collect();
+    let root = args.root_transform.map(|pose| (args.root_name.as_str(), pose));
+    let (mut dag, animation) = match scene::load(&files, root) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            std::process::exit(1);
         }
+    };
+    if let Err(e) = args.joints.apply(&mut dag) {
+        eprintln!("Error: {:#}", e);
+        std::process::exit(1);
+    }
+
+    let mut recorder = recording::Recorder::default();
+    if let Some(path) = &args.record
+        && let Err(e) = recorder.start(path, &dag)
+    {
+        eprintln!("Error: {}: {}", path.display(), e);
+        std::process::exit(1);
     }
 
-    if let Ok((camera, cam_transform)) = camera_q.single() {
-        for (mut node, label, mut visibility) in &mut label_q {
-            let n = &dag.nodes[label.node];
-            let world_pos = n.world.translation.to_vec3();
-
-            match camera.world_to_viewport(cam_transform, world_pos) {
-                Ok(pos) => {
-                    *visibility = Visibility::Visible;
-                    node.left = Val::Px(pos.x);
-                    node.top = Val::Px(pos.y);
-                }
-                Err(ViewportConversionError::PastNearPlane | ViewportConversionError::PastFarPlane) => {
-                    *visibility = Visibility::Hidden;
-                }
-                Err(_) => {
-                    *visibility = Visibility::Hidden;
-                }
+    let grid = grid::GridSettings::from(&args.grid);
+    #[cfg(feature = "xr")]
+    let mut app = if args.xr { axisviz::xr::viewer(dag, grid) } else { viewer(dag, grid) };
+    #[cfg(not(feature = "xr"))]
+    let mut app = viewer(dag, grid);
+    app.insert_resource(uncertainty::Sigma(args.sigma))
+        .insert_resource(camera::CameraFocus::new(args.focus_duration, args.focus_easing))
+        .insert_resource(batched::AxisBatching { threshold: args.batch_axes_above })
+        .insert_resource(smoothing::Smoothing::new(args.smooth))
+        .insert_resource(stale::Staleness::new(args.stale_timeout))
+        .insert_resource(stereo::Stereo { enabled: args.stereo, ..default() });
+    if let Some(path) = &args.reference {
+        let file = scene::SceneFile { path, prefix: "", offset: None, units: args.units, convention: args.convention };
+        match scene::load(&[file], None) {
+            Ok((reference, _)) => {
+                app.insert_resource(diff::DiffTree(reference)).add_systems(Update, diff::draw_diff);
+            }
+            Err(e) => {
+                eprintln!("Error: {:?}", e);
+                std::process::exit(1);
             }
         }
     }
+    app.insert_resource(config::ConfigFile::for_input(args.filenames.first().map(PathBuf::as_path)))
+        .add_systems(PostStartup, config::apply_config)
+        .add_systems(Update, config::persist);
+    if let Some(animation) = animation {
+        app.insert_resource(timeline::Timeline::new(animation))
+            .insert_resource(timeline::Ghosts { offsets: args.ghosts.clone() })
+            .add_systems(Startup, timeline::setup_hud)
+            .add_systems(Update, timeline::draw_ghosts.after(timeline::apply));
+    }
+    let (tx, rx) = stream::UpdateReceiver::new();
+    if args.stdin {
+        stream::spawn_stdin_reader(&mut app, tx.clone(), args.stdin_format);
+    }
+    if args.stdin_joints {
+        let (joint_tx, joint_rx) = kinematics::JointReceiver::new();
+        kinematics::spawn_stdin_reader(joint_tx);
+        app.insert_resource(joint_rx);
+    }
+    #[cfg(feature = "grpc")]
+    if let Some(addr) = args.grpc {
+        axisviz::grpc::serve(&mut app, addr, tx.clone());
+    }
+    #[cfg(feature = "mqtt")]
+    if let Some(broker) = &args.mqtt
+        && let Err(e) = axisviz::mqtt::spawn_subscriber(broker, args.mqtt_topics.clone(), tx.clone())
+    {
+        eprintln!("mqtt: {}", e);
+    }
+    #[cfg(feature = "rosbridge")]
+    if let Some(url) = &args.rosbridge
+        && let Err(e) = axisviz::rosbridge::spawn_subscriber(url, tx.clone())
+    {
+        eprintln!("rosbridge: {}", e);
+    }
+    #[cfg(feature = "http")]
+    if let Some(addr) = args.http {
+        axisviz::http::serve(&mut app, addr);
+    }
+    app.insert_resource(rx).insert_resource(recorder);
+    app.run();
 }
-
-// fn handle_pointer_select(
-//     mut selection: ResMut<Selection>,
-//     dag: Res<TransformTree>,
-//     mut camera_q: Query<&mut PanOrbitCamera>,
-//     button: Res<ButtonInput<MouseButton>>,
-//     keys: Res<ButtonInput<KeyCode>>,
-//     windows: Query<&Window>,
-//     camera_transform_q: Query<(&Camera, &GlobalTransform), With<MeshPickingCamera>>,
-// ) {
-//     if !button.just_pressed(MouseButton::Left) {
-//         return;
-//     }
-//
-//     let shift = keys.pressed(KeyCode::ShiftLeft) || keys.pressed(KeyCode::ShiftRight);
-//     if let Some(hit_id) = ray
-// }
@@ -0,0 +1,57 @@
+//! Poses given as homogeneous matrices, as calibration tools write them. A
+//! node's `matrix` replaces its `t` and `r`; the rotation part must be
+//! orthonormal unless the file asks for it to be snapped to the nearest
+//! rotation with `"orthonormalize": true`.
+
+use bevy::math::{DQuat, DVec3};
+use bevy::prelude::*;
+use nalgebra as na;
+
+use crate::{FileTransformTree, FileTransformTreeError};
+
+/// Largest deviation of `RᵀR` from the identity accepted as a rotation;
+/// enough for matrices printed with six decimals.
+const TOLERANCE: f64 = 1e-4;
+
+/// Translation and rotation of a row-major 4x4 or 3x4 matrix.
+pub(crate) fn rigid(values: &[f64], orthonormalize: bool) -> Result<(DVec3, DQuat), String> {
+    match values.len() {
+        12 => {}
+        16 if values[12..] == [0.0, 0.0, 0.0, 1.0] => {}
+        16 => return Err("last row should be 0 0 0 1".to_string()),
+        n => return Err(format!("expected 16 or 12 values, got {}", n)),
+    }
+    let at = |row: usize, col: usize| values[row * 4 + col];
+    let m = na::Matrix3::from_fn(at);
+    let error = (m.transpose() * m - na::Matrix3::identity()).abs().max();
+    if m.determinant() <= 0.0 {
+        return Err("rotation part is a reflection".to_string());
+    }
+    let rotation = if error <= TOLERANCE {
+        m
+    } else if orthonormalize {
+        *na::Rotation3::from_matrix(&m).matrix()
+    } else {
+        return Err(format!("rotation part is not orthonormal (off by {:.1e}); set \"orthonormalize\": true", error));
+    };
+    let q = na::UnitQuaternion::from_matrix(&rotation);
+    Ok((DVec3::new(at(0, 3), at(1, 3), at(2, 3)), DQuat::from_xyzw(q.i, q.j, q.k, q.w)))
+}
+
+impl FileTransformTree {
+    /// Replaces each node's `matrix` with the `t` and `r` it stands for.
+    pub fn resolve_matrices(&mut self) -> Result<(), FileTransformTreeError> {
+        for node in &mut self.nodes {
+            let Some(values) = node.matrix.take() else {
+                continue;
+            };
+            let (t, rotation) =
+                rigid(&values, self.orthonormalize).map_err(|e| FileTransformTreeError::Matrix(format!("{}: {}", node.name, e)))?;
+            let (rx, ry, rz) = rotation.to_euler(EulerRot::XYZ);
+            node.t = t.to_array();
+            node.r = [rx, ry, rz];
+            node.euler = None;
+        }
+        Ok(())
+    }
+}
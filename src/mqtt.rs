@@ -0,0 +1,109 @@
+//! MQTT live source (`--mqtt <host[:port]> --mqtt-topic <pattern>`, feature `mqtt`).
+//!
+//! Payloads are JSON: a single node update as read by `--stdin`, an array of
+//! them, or a whole tree file. Frames are namespaced by the topic segments the
+//! pattern's wildcards matched, so `robots/+/tf` puts `base` published on
+//! `robots/r1/tf` at `r1/base`.
+
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Result;
+use rumqttc::{Client, Event, MqttOptions, Packet, QoS};
+use serde::Deserialize;
+
+use crate::{FileNode, FileTransformTree};
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Payload {
+    Tree(FileTransformTree),
+    Nodes(Vec<FileNode>),
+    Node(FileNode),
+}
+
+/// Connects to `broker` and forwards updates published on `topics` on a background thread.
+pub fn spawn_subscriber(broker: &str, topics: Vec<String>, tx: Sender<FileNode>) -> Result<()> {
+    let (host, port) = match broker.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse()?),
+        None => (broker, 1883),
+    };
+    let mut options = MqttOptions::new(format!("axisviz-{}", std::process::id()), host, port);
+    options.set_keep_alive(Duration::from_secs(10));
+    let (client, mut connection) = Client::new(options, 64);
+
+    thread::spawn(move || {
+        for event in connection.iter() {
+            match event {
+                // A reconnect starts a clean session, so subscribe on every
+                // connection, not just the first. `try_` as this thread is the
+                // one draining the request queue.
+                Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                    for topic in &topics {
+                        if let Err(e) = client.try_subscribe(topic, QoS::AtMostOnce) {
+                            eprintln!("mqtt: cannot subscribe to {}: {}", topic, e);
+                        }
+                    }
+                }
+                Ok(Event::Incoming(Packet::Publish(publish))) => {
+                    let Some(prefix) = topics.iter().find_map(|t| namespace(t, &publish.topic)) else {
+                        continue;
+                    };
+                    let nodes = match serde_json::from_slice::<Payload>(&publish.payload) {
+                        Ok(Payload::Tree(tree)) => tree.nodes,
+                        Ok(Payload::Nodes(nodes)) => nodes,
+                        Ok(Payload::Node(node)) => vec![node],
+                        Err(e) => {
+                            eprintln!("mqtt: skipping malformed message on {}: {}", publish.topic, e);
+                            continue;
+                        }
+                    };
+                    for mut node in nodes {
+                        node.name = format!("{}{}", prefix, node.name);
+                        // A missing parent stays missing, so `apply` keeps the frame's.
+                        node.parent = node.parent.map(|p| format!("{}{}", prefix, p));
+                        if tx.send(node).is_err() {
+                            return;
+                        }
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    // The connection reconnects on the next poll.
+                    eprintln!("mqtt: {}", e);
+                    thread::sleep(Duration::from_secs(1));
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Frame name prefix for a message on `topic`: the segments matched by the
+/// wildcards of `pattern`, each followed by `/`. `None` if the topic doesn't match.
+fn namespace(pattern: &str, topic: &str) -> Option<String> {
+    let mut topic = topic.split('/');
+    let mut prefix = String::new();
+    for level in pattern.split('/') {
+        match level {
+            "#" => {
+                for rest in topic.by_ref() {
+                    prefix.push_str(rest);
+                    prefix.push('/');
+                }
+                return Some(prefix);
+            }
+            "+" => {
+                prefix.push_str(topic.next()?);
+                prefix.push('/');
+            }
+            literal => {
+                if topic.next()? != literal {
+                    return None;
+                }
+            }
+        }
+    }
+    topic.next().is_none().then_some(prefix)
+}
@@ -0,0 +1,136 @@
+//! Floating origin for trees at GPS/UTM scale. An `f32` holds about seven
+//! significant digits, so a frame 4000 km from the origin only lands on a
+//! half-meter grid and jitters. The tree keeps local and world poses in
+//! `f64`, and an `f64` origin the render copies of the world poses are
+//! relative to; those are only narrowed to `f32` after the origin is taken
+//! off, so they stay small wherever the frames are. The origin moves along
+//! when the camera wanders far off.
+
+use bevy::math::DVec3;
+use bevy::prelude::*;
+use bevy_panorbit_camera::PanOrbitCamera;
+use nalgebra::{Isometry3, Quaternion, Translation3, UnitQuaternion, Vector3};
+
+use crate::camera::MainCamera;
+use crate::{FileNode, FileTransformTreeError, NodeId, TransformTree};
+
+/// Distance from the origin, in meters, past which a root at load or the
+/// camera focus while viewing moves the origin. A millimeter is still
+/// resolved this far out.
+pub const REBASE_DISTANCE: f64 = 10_000.0;
+
+/// Full-precision pose of a translation and an `[x, y, z, w]` quaternion, as
+/// streamed; a quaternion of unset fields means no rotation.
+pub fn pose_f64(t: [f64; 3], q: [f64; 4]) -> Isometry3<f64> {
+    let [x, y, z, w] = q;
+    let rotation = UnitQuaternion::try_new(Quaternion::new(w, x, y, z), 1e-12).unwrap_or_else(UnitQuaternion::identity);
+    Isometry3::from_parts(Translation3::new(t[0], t[1], t[2]), rotation)
+}
+
+/// `pose` in `f64`.
+pub fn widen(pose: Isometry3d) -> Isometry3<f64> {
+    let (t, q) = (pose.translation.to_vec3().as_dvec3(), pose.rotation.as_dquat());
+    pose_f64(t.to_array(), [q.x, q.y, q.z, q.w])
+}
+
+/// `pose` relative to `origin`, narrowed for rendering.
+pub fn narrow(pose: &Isometry3<f64>, origin: DVec3) -> Isometry3d {
+    let t = pose.translation.vector - Vector3::new(origin.x, origin.y, origin.z);
+    let q = pose.rotation;
+    Isometry3d::new(Vec3::new(t.x as f32, t.y as f32, t.z as f32), Quat::from_xyzw(q.i as f32, q.j as f32, q.k as f32, q.w as f32))
+}
+
+impl TransformTree {
+    /// World position the render origin stands for.
+    pub fn origin(&self) -> DVec3 {
+        self.origin
+    }
+
+    /// World position of a frame in full precision.
+    pub fn world_position(&self, id: NodeId) -> DVec3 {
+        let t = self.nodes[id].world_f64.translation.vector;
+        DVec3::new(t.x, t.y, t.z)
+    }
+
+    /// World pose of a frame in full precision.
+    pub fn world_f64(&self, id: NodeId) -> Isometry3<f64> {
+        self.nodes[id].world_f64
+    }
+
+    /// Local pose of a file entry in full precision; a root's is its world pose.
+    /// A `matrix` stands in for `t` and `r` and must be a rigid transform.
+    pub(crate) fn local_from_file(&self, node: &FileNode) -> Result<Isometry3<f64>, FileTransformTreeError> {
+        let Some(values) = &node.matrix else {
+            return Ok(crate::formats::isometry(node));
+        };
+        let (t, q) = crate::matrix::rigid(values, false).map_err(|e| FileTransformTreeError::Matrix(format!("{}: {}", node.name, e)))?;
+        Ok(pose_f64(t.to_array(), [q.x, q.y, q.z, q.w]))
+    }
+
+    /// Full-precision local pose of `id` for a render one, which for a root
+    /// is relative to the origin.
+    pub(crate) fn widen_local(&self, id: NodeId, local: Isometry3d) -> Isometry3<f64> {
+        let local = widen(local);
+        match self.nodes[id].parent {
+            Some(_) => local,
+            None => Translation3::new(self.origin.x, self.origin.y, self.origin.z) * local,
+        }
+    }
+
+    /// Render local pose of `id` for a full-precision one: a root's is
+    /// relative to the origin.
+    pub(crate) fn narrow_local(&self, id: NodeId, local: &Isometry3<f64>) -> Isometry3d {
+        narrow(local, if self.nodes[id].parent.is_some() { DVec3::ZERO } else { self.origin })
+    }
+
+    /// `set_world` in full precision.
+    pub fn set_world_f64(&mut self, id: NodeId, world: Isometry3<f64>) {
+        let parent = self.nodes[id].parent.map_or(Isometry3::identity(), |p| self.nodes[p].world_f64);
+        let local = parent.inverse() * world;
+        let render = self.narrow_local(id, &local);
+        if let Some(joint) = self.nodes[id].joint.as_mut() {
+            joint.origin = render * joint.motion(joint.value).inverse();
+        }
+        self.set_local_f64(id, local);
+        self.update_world();
+    }
+
+    /// Moves the origin; no frame moves in the world. Returns how far
+    /// everything moved on screen.
+    pub fn rebase(&mut self, origin: DVec3) -> Vec3 {
+        let shift = self.origin - origin;
+        self.origin = origin;
+        for id in 0..self.nodes.len() {
+            if self.nodes[id].parent.is_none() {
+                self.mark_dirty(id);
+            }
+        }
+        self.update_world();
+        shift.as_vec3()
+    }
+}
+
+/// Origin for a freshly built tree: the world position of the first frame
+/// past `REBASE_DISTANCE`, wherever it hangs in the tree, or zero when every
+/// frame is near.
+pub(crate) fn pick_origin(dag: &TransformTree) -> DVec3 {
+    (0..dag.nodes.len()).map(|id| dag.world_position(id)).find(|p| p.length() > REBASE_DISTANCE).unwrap_or(DVec3::ZERO)
+}
+
+/// Rebases on the camera focus once it is `REBASE_DISTANCE` from the origin,
+/// moving the camera along so the view doesn't jump.
+pub fn follow_camera(mut dag: ResMut<TransformTree>, mut camera_q: Query<(&mut PanOrbitCamera, &mut Transform), With<MainCamera>>) {
+    let Ok((mut camera, mut transform)) = camera_q.single_mut() else {
+        return;
+    };
+    let focus = camera.target_focus.as_dvec3();
+    if focus.length() < REBASE_DISTANCE {
+        return;
+    }
+    let origin = dag.origin() + focus;
+    let shift = dag.rebase(origin);
+    camera.focus += shift;
+    camera.target_focus += shift;
+    transform.translation += shift;
+    camera.force_update = true;
+}
@@ -0,0 +1,98 @@
+//! Picture-in-picture view from a frame, as if a camera were mounted there.
+
+use std::f32::consts::FRAC_PI_3;
+
+use bevy::camera::Viewport;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::{NodeId, TransformTree};
+
+/// Which way a mounted camera looks in its frame's axes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Mount {
+    /// Optical frame: looks along +Z with +Y down (ROS `*_optical_frame`).
+    #[default]
+    Optical,
+    /// Body frame: looks along +X with +Z up.
+    Body,
+}
+
+impl Mount {
+    /// Rotation from the frame's axes to a Bevy camera, which looks along -Z with +Y up.
+    fn rotation(self) -> Quat {
+        match self {
+            Mount::Optical => Quat::from_rotation_x(std::f32::consts::PI),
+            Mount::Body => Quat::from_mat3(&Mat3::from_cols(Vec3::NEG_Y, Vec3::Z, Vec3::NEG_X)),
+        }
+    }
+}
+
+#[derive(Resource, Debug)]
+pub struct FrameView {
+    /// Frame the inset renders from; `None` hides it.
+    pub node: Option<NodeId>,
+    pub mount: Mount,
+    /// Inset width as a fraction of the window width.
+    pub size: f32,
+    /// Vertical field of view in radians.
+    pub fov: f32,
+}
+
+impl Default for FrameView {
+    fn default() -> Self {
+        FrameView { node: None, mount: Mount::default(), size: 0.3, fov: FRAC_PI_3 }
+    }
+}
+
+#[derive(Component)]
+pub struct FrameViewCamera;
+
+/// Keeps the inset camera at its frame's pose in the bottom right corner of the window.
+pub fn sync_frame_view(
+    mut commands: Commands,
+    view: Res<FrameView>,
+    dag: Res<TransformTree>,
+    window_q: Query<&Window, With<PrimaryWindow>>,
+    mut camera_q: Query<(Entity, &mut Camera, &mut Transform, &mut Projection), With<FrameViewCamera>>,
+) {
+    let node = view.node.and_then(|id| dag.nodes.get(id));
+    let (Some(node), Ok(window)) = (node, window_q.single()) else {
+        for (entity, ..) in &camera_q {
+            commands.entity(entity).despawn();
+        }
+        return;
+    };
+    let size = window.physical_size();
+    let width = (size.x as f32 * view.size.clamp(0.05, 1.0)) as u32;
+    // 4:3 inset, inside the window with a small margin.
+    let inset = UVec2::new(width, width * 3 / 4).min(size).max(UVec2::ONE);
+    let position = size.saturating_sub(inset + UVec2::splat(8));
+    let viewport = Viewport { physical_position: position, physical_size: inset, ..default() };
+    let transform = Transform::from_isometry(node.world).with_rotation(node.world.rotation * view.mount.rotation());
+    // Frames with intrinsics use their own field of view.
+    let fov = node.camera.map_or(view.fov, |c| c.vertical_fov());
+    let projection = Projection::Perspective(PerspectiveProjection { fov, ..default() });
+
+    match camera_q.single_mut() {
+        Ok((_, mut camera, mut current, mut current_projection)) => {
+            camera.viewport = Some(viewport);
+            current.set_if_neq(transform);
+            if let (Projection::Perspective(p), Projection::Perspective(wanted)) = (&*current_projection, &projection)
+                && p.fov == wanted.fov
+            {
+                return;
+            }
+            *current_projection = projection;
+        }
+        Err(_) => {
+            commands.spawn((
+                FrameViewCamera,
+                Camera3d::default(),
+                Camera { order: 10, viewport: Some(viewport), ..default() },
+                projection,
+                transform,
+            ));
+        }
+    }
+}
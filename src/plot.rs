@@ -0,0 +1,150 @@
+//! Time plot of the selected frame's pose: x, y, z and roll, pitch, yaw over
+//! the last seconds of streaming or playback, with each channel's standard
+//! deviation and peak-to-peak range, so jitter and drift can be measured.
+
+use std::collections::VecDeque;
+use std::f32::consts::{PI, TAU};
+
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+use crate::style::Style;
+use crate::{NodeId, Selection, TransformTree};
+
+#[derive(Resource, Debug)]
+pub struct PosePlot {
+    /// Seconds shown.
+    pub window: f64,
+    /// Plot the pose in the world rather than relative to the parent.
+    pub world: bool,
+    node: Option<(NodeId, bool)>,
+    /// App time and x, y, z, roll, pitch, yaw, the angles unwrapped.
+    samples: VecDeque<(f64, [f32; 6])>,
+}
+
+impl Default for PosePlot {
+    fn default() -> Self {
+        PosePlot { window: 10.0, world: true, node: None, samples: VecDeque::new() }
+    }
+}
+
+impl PosePlot {
+    /// Adds a sample of `pose` at `time`, dropping those older than the window.
+    pub fn push(&mut self, time: f64, pose: Isometry3d) {
+        // Fixed-axis roll, pitch, yaw, as in ROS.
+        let (yaw, pitch, roll) = pose.rotation.to_euler(EulerRot::ZYX);
+        let mut values = [pose.translation.x, pose.translation.y, pose.translation.z, roll, pitch, yaw];
+        if let Some((_, last)) = self.samples.back() {
+            // Keep angles continuous across ±180°.
+            for (value, last) in values[3..].iter_mut().zip(&last[3..]) {
+                *value += ((last - *value) / TAU).round() * TAU;
+            }
+        }
+        self.samples.push_back((time, values));
+        while self.samples.front().is_some_and(|(t, _)| time - t > self.window) {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Standard deviation and peak-to-peak range of a channel over the window.
+    pub fn spread(&self, channel: usize) -> (f32, f32) {
+        let n = self.samples.len().max(1) as f32;
+        let values = self.samples.iter().map(|(_, v)| v[channel]);
+        let mean = values.clone().sum::<f32>() / n;
+        let std = (values.clone().map(|v| (v - mean).powi(2)).sum::<f32>() / n).sqrt();
+        let (min, max) = values.fold((f32::MAX, f32::MIN), |(lo, hi), v| (lo.min(v), hi.max(v)));
+        (std, if self.samples.is_empty() { 0.0 } else { max - min })
+    }
+}
+
+/// Samples the selected frame every frame; a new selection starts over.
+pub fn sample_pose(dag: Res<TransformTree>, selection: Res<Selection>, time: Res<Time>, mut plot: ResMut<PosePlot>) {
+    let node = selection.primary().filter(|&id| id < dag.nodes.len());
+    let key = node.map(|id| (id, plot.world));
+    if plot.node != key {
+        plot.node = key;
+        plot.samples.clear();
+    }
+    if let Some(id) = node {
+        let pose = if plot.world { dag.nodes[id].world } else { dag.nodes[id].local };
+        plot.push(time.elapsed_secs_f64(), pose);
+    }
+}
+
+fn color32(color: [f32; 3]) -> egui::Color32 {
+    egui::Color32::from_rgb((color[0] * 255.0) as u8, (color[1] * 255.0) as u8, (color[2] * 255.0) as u8)
+}
+
+/// Three channels against time, scaled to fit.
+fn draw_channels(ui: &mut egui::Ui, plot: &PosePlot, channels: [usize; 3], colors: [egui::Color32; 3], scale: f32, unit: &str) {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(360.0, 110.0), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_stroke(rect, 0.0, ui.visuals().widgets.noninteractive.bg_stroke, egui::StrokeKind::Inside);
+    let (Some((start, _)), Some((end, _))) = (plot.samples.front(), plot.samples.back()) else {
+        return;
+    };
+    let (mut lo, mut hi) = (f32::MAX, f32::MIN);
+    for (_, v) in &plot.samples {
+        for &c in &channels {
+            lo = lo.min(v[c] * scale);
+            hi = hi.max(v[c] * scale);
+        }
+    }
+    // A flat signal still gets some room.
+    let pad = ((hi - lo) * 0.05).max(1e-3);
+    let (lo, hi) = (lo - pad, hi + pad);
+    let span = (end - start).max(1e-3);
+    for (&c, color) in channels.iter().zip(colors) {
+        let points = plot
+            .samples
+            .iter()
+            .map(|(t, v)| {
+                let x = rect.min.x + rect.width() * ((t - start) / span) as f32;
+                let y = rect.max.y - rect.height() * (v[c] * scale - lo) / (hi - lo);
+                egui::pos2(x, y)
+            })
+            .collect();
+        painter.add(egui::Shape::line(points, egui::Stroke::new(1.5, color)));
+    }
+    let text = ui.visuals().text_color();
+    let font = egui::FontId::monospace(10.0);
+    painter.text(rect.left_top() + egui::vec2(3.0, 2.0), egui::Align2::LEFT_TOP, format!("{:.3} {}", hi, unit), font.clone(), text);
+    painter.text(rect.left_bottom() + egui::vec2(3.0, -2.0), egui::Align2::LEFT_BOTTOM, format!("{:.3} {}", lo, unit), font, text);
+}
+
+pub fn plot_panel(mut contexts: EguiContexts, mut plot: ResMut<PosePlot>, dag: Res<TransformTree>, style: Res<Style>) -> Result {
+    let colors = [color32(style.x), color32(style.y), color32(style.z)];
+    let (mut window, mut world) = (plot.window, plot.world);
+    egui::Window::new("Pose plot").default_open(false).show(contexts.ctx_mut()?, |ui| {
+        let Some((id, _)) = plot.node else {
+            ui.label("Select a frame to plot its pose over time.");
+            return;
+        };
+        ui.horizontal(|ui| {
+            ui.label(&dag.nodes[id].name);
+            ui.radio_value(&mut world, true, "World");
+            ui.radio_value(&mut world, false, "Parent");
+            ui.add(egui::DragValue::new(&mut window).speed(0.1).range(1.0..=300.0).suffix(" s"));
+        });
+        for (channels, names, scale, unit, spread_scale, spread_unit) in [
+            ([0, 1, 2], ["x", "y", "z"], 1.0, "m", 1000.0, "mm"),
+            ([3, 4, 5], ["roll", "pitch", "yaw"], 180.0 / PI, "°", 180.0 / PI, "°"),
+        ] {
+            draw_channels(ui, &plot, channels, colors, scale, unit);
+            egui::Grid::new(names[0]).show(ui, |ui| {
+                for ((&c, name), color) in channels.iter().zip(names).zip(colors) {
+                    let (std, range) = plot.spread(c);
+                    ui.colored_label(color, name);
+                    ui.monospace(format!("σ {:.3} {}", std * spread_scale, spread_unit));
+                    ui.monospace(format!("p-p {:.3} {}", range * spread_scale, spread_unit));
+                    ui.end_row();
+                }
+            });
+        }
+    });
+    if (window, world) != (plot.window, plot.world) {
+        plot.window = window;
+        plot.world = world;
+    }
+    Ok(())
+}
@@ -0,0 +1,65 @@
+//! Terminal rendering of a tree for `axisviz tree`.
+
+use std::io::IsTerminal;
+
+use bevy::math::DQuat;
+use bevy::prelude::*;
+use nalgebra::Isometry3;
+
+use crate::{NodeId, TransformTree};
+
+const NAME: &str = "\x1b[1;36m";
+const WORLD: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+/// Prints the hierarchy with each frame's local and world translation and
+/// roll/pitch/yaw (degrees, same convention as tree files). Colors are used
+/// when stdout is a terminal and `NO_COLOR` is unset.
+pub fn print_tree(dag: &TransformTree) {
+    let color = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    let roots = (0..dag.nodes.len()).filter(|&id| dag.nodes[id].parent.is_none());
+    for root in roots {
+        print_node(dag, root, "", None, color);
+    }
+}
+
+/// `last` is `None` for roots, otherwise whether the node is its parent's last child.
+fn print_node(dag: &TransformTree, id: NodeId, indent: &str, last: Option<bool>, color: bool) {
+    let node = &dag.nodes[id];
+    let (branch, child_indent) = match last {
+        None => ("", indent.to_string()),
+        Some(true) => ("└── ", format!("{}    ", indent)),
+        Some(false) => ("├── ", format!("{}│   ", indent)),
+    };
+    let (name, world, reset) = if color { (NAME, WORLD, RESET) } else { ("", "", "") };
+    println!(
+        "{}{}{}{}{}  {}  {}world {}{}",
+        indent,
+        branch,
+        name,
+        node.name,
+        reset,
+        pose(&node.local_f64),
+        world,
+        pose(&node.world_f64),
+        reset
+    );
+    let count = node.children.len();
+    for (i, &child) in node.children.iter().enumerate() {
+        print_node(dag, child, &child_indent, Some(i + 1 == count), color);
+    }
+}
+
+fn pose(pose: &Isometry3<f64>) -> String {
+    let (t, q) = (pose.translation, pose.rotation);
+    let (r, p, y) = DQuat::from_xyzw(q.i, q.j, q.k, q.w).to_euler(EulerRot::XYZ);
+    format!(
+        "t=[{:.4} {:.4} {:.4}] rpy=[{:.2} {:.2} {:.2}]",
+        t.x,
+        t.y,
+        t.z,
+        r.to_degrees(),
+        p.to_degrees(),
+        y.to_degrees()
+    )
+}
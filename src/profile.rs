@@ -0,0 +1,28 @@
+//! `--profile`: records tracing spans (tree loading, `update_world`, drawing,
+//! stream ingestion and, through Bevy's `trace` feature, every system) to a
+//! Chrome trace for chrome://tracing or https://ui.perfetto.dev.
+//!
+//! Tracy captures need no flag: build with the `tracy` feature and connect
+//! the Tracy profiler while the viewer runs.
+
+use std::path::Path;
+
+use anyhow::{Result, anyhow};
+use tracing_chrome::{ChromeLayerBuilder, FlushGuard};
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{EnvFilter, fmt};
+
+/// Installs the global subscriber, before the tree is loaded so loading is
+/// traced too; the viewer then leaves out Bevy's own log setup. The trace is
+/// complete once the returned guard drops.
+pub fn start(path: &Path) -> Result<FlushGuard> {
+    let (chrome, guard) = ChromeLayerBuilder::new().file(path).include_args(true).build();
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info,wgpu=error,naga=warn"));
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(std::io::stderr))
+        .with(chrome)
+        .try_init()
+        .map_err(|e| anyhow!("can't install the trace subscriber: {}", e))?;
+    Ok(guard)
+}
@@ -0,0 +1,132 @@
+//! Recording live updates (`--record out.azl` or the Tools panel) and reading
+//! recordings back as an animated tree.
+//!
+//! A recording is JSON lines, one per update: a node as read by `--stdin` plus
+//! `t`, seconds since recording started. It opens with a snapshot of the tree
+//! at `t = 0`, so it replays on its own.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use anyhow::Result;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::timeline::{Animation, Track};
+use crate::{FileNode, FileTransformTree, TransformTree};
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    t: f64,
+    #[serde(flatten)]
+    node: FileNode,
+}
+
+struct Session {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    start: Instant,
+}
+
+/// Where live updates are being recorded, if anywhere.
+#[derive(Resource, Default)]
+pub struct Recorder {
+    session: Option<Session>,
+}
+
+impl Recorder {
+    pub fn path(&self) -> Option<&Path> {
+        self.session.as_ref().map(|s| s.path.as_path())
+    }
+
+    /// Starts a new recording at `path` with a snapshot of `dag`.
+    pub fn start(&mut self, path: impl Into<PathBuf>, dag: &TransformTree) -> Result<()> {
+        let path = path.into();
+        let mut session = Session { writer: BufWriter::new(File::create(&path)?), path, start: Instant::now() };
+        for node in FileTransformTree::from(dag).nodes {
+            write_entry(&mut session.writer, 0.0, node)?;
+        }
+        session.writer.flush()?;
+        self.session = Some(session);
+        Ok(())
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(mut session) = self.session.take()
+            && let Err(e) = session.writer.flush()
+        {
+            eprintln!("record: {}: {}", session.path.display(), e);
+        }
+    }
+
+    /// Appends an update, stopping the recording if the file can't be written.
+    pub fn record(&mut self, node: &FileNode) {
+        let Some(session) = &mut self.session else {
+            return;
+        };
+        let t = session.start.elapsed().as_secs_f64();
+        if let Err(e) = write_entry(&mut session.writer, t, node.clone()) {
+            eprintln!("record: {}: {}", session.path.display(), e);
+            self.session = None;
+        }
+    }
+
+    pub fn flush(&mut self) {
+        if let Some(session) = &mut self.session
+            && let Err(e) = session.writer.flush()
+        {
+            eprintln!("record: {}: {}", session.path.display(), e);
+        }
+    }
+}
+
+fn write_entry(writer: &mut impl Write, t: f64, node: FileNode) -> Result<()> {
+    serde_json::to_writer(&mut *writer, &Entry { t, node })?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+/// Default file name for recordings started from the UI.
+pub fn default_path() -> PathBuf {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    PathBuf::from(format!("axisviz-{}.azl", secs))
+}
+
+/// Reads a recording: each frame starts at its first recorded pose and parent,
+/// and frames updated more than once get an animation track.
+pub fn parse(text: &str) -> Result<(FileTransformTree, Option<Animation>)> {
+    let mut tree = FileTransformTree { version: 1, ..Default::default() };
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut tracks: HashMap<String, Track> = HashMap::new();
+    for (i, line) in text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+        let entry: Entry = serde_json::from_str(line).map_err(|e| anyhow::anyhow!("line {}: {}", i + 1, e))?;
+        let pose = Isometry3d::from(&entry.node);
+        let name = entry.node.name.clone();
+        match index.get(&name) {
+            Some(&id) if tree.nodes[id].parent.is_none() => tree.nodes[id].parent = entry.node.parent.clone(),
+            Some(_) => {}
+            None => {
+                index.insert(name.clone(), tree.nodes.len());
+                tree.nodes.push(entry.node);
+            }
+        }
+        tracks.entry(name.clone()).or_insert_with(|| Track::new(name)).push(entry.t, pose);
+    }
+    // Parents only ever referenced, e.g. by updates that arrived before them.
+    let parents: Vec<String> = tree.nodes.iter().filter_map(|n| n.parent.clone()).collect();
+    for parent in parents {
+        if !index.contains_key(&parent) {
+            index.insert(parent.clone(), tree.nodes.len());
+            tree.nodes.push(FileNode { name: parent, ..Default::default() });
+        }
+    }
+
+    let mut tracks: Vec<Track> = tracks.into_values().filter(|t| t.times.len() > 1).collect();
+    tracks.sort_by(|a, b| a.node.cmp(&b.node));
+    Ok((tree, (!tracks.is_empty()).then_some(Animation { tracks })))
+}
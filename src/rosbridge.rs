@@ -0,0 +1,127 @@
+//! ROS1 `/tf` and `/tf_static` through a rosbridge websocket
+//! (`--rosbridge ws://host:9090`, feature `rosbridge`).
+
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use serde::Deserialize;
+use serde_json::json;
+use tungstenite::Message;
+
+use crate::FileNode;
+use crate::origin;
+
+#[derive(Deserialize)]
+struct Publish {
+    op: String,
+    #[serde(default)]
+    msg: Option<TfMessage>,
+}
+
+#[derive(Deserialize)]
+struct TfMessage {
+    transforms: Vec<TransformStamped>,
+}
+
+#[derive(Deserialize)]
+struct TransformStamped {
+    header: Header,
+    child_frame_id: String,
+    transform: Transform3,
+}
+
+#[derive(Deserialize)]
+struct Header {
+    frame_id: String,
+}
+
+#[derive(Deserialize)]
+struct Transform3 {
+    translation: Xyz,
+    rotation: Xyzw,
+}
+
+#[derive(Deserialize)]
+struct Xyz {
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+#[derive(Deserialize)]
+struct Xyzw {
+    x: f64,
+    y: f64,
+    z: f64,
+    w: f64,
+}
+
+/// ROS1 frame ids often carry a leading slash that tf2 ignores.
+fn frame_name(id: &str) -> String {
+    id.trim_start_matches('/').to_string()
+}
+
+impl From<&TransformStamped> for FileNode {
+    fn from(tf: &TransformStamped) -> Self {
+        let (t, r) = (&tf.transform.translation, &tf.transform.rotation);
+        let pose = origin::pose_f64([t.x, t.y, t.z], [r.x, r.y, r.z, r.w]);
+        let mut node = FileNode {
+            name: frame_name(&tf.child_frame_id),
+            parent: Some(frame_name(&tf.header.frame_id)),
+            ..Default::default()
+        };
+        node.set_pose_f64(&pose);
+        node
+    }
+}
+
+/// Connects to the rosbridge server at `url` and forwards TF updates on a
+/// background thread, reconnecting if the connection drops.
+pub fn spawn_subscriber(url: &str, tx: Sender<FileNode>) -> Result<()> {
+    // Fail early on a bad URL; later connection errors are retried.
+    let (mut socket, _) = tungstenite::connect(url).map_err(|e| anyhow!("{}: {}", url, e))?;
+    let url = url.to_string();
+    thread::spawn(move || {
+        loop {
+            for topic in ["/tf", "/tf_static"] {
+                let subscribe = json!({ "op": "subscribe", "topic": topic, "type": "tf2_msgs/TFMessage" });
+                if let Err(e) = socket.send(Message::Text(subscribe.to_string())) {
+                    eprintln!("rosbridge: {}", e);
+                }
+            }
+            loop {
+                let text = match socket.read() {
+                    Ok(Message::Text(text)) => text,
+                    Ok(_) => continue,
+                    Err(e) => {
+                        eprintln!("rosbridge: {}", e);
+                        break;
+                    }
+                };
+                let msg = match serde_json::from_str::<Publish>(&text) {
+                    Ok(Publish { op, msg: Some(msg) }) if op == "publish" => msg,
+                    Ok(_) => continue,
+                    Err(e) => {
+                        eprintln!("rosbridge: skipping malformed message: {}", e);
+                        continue;
+                    }
+                };
+                for tf in &msg.transforms {
+                    if tx.send(FileNode::from(tf)).is_err() {
+                        return;
+                    }
+                }
+            }
+            socket = loop {
+                thread::sleep(Duration::from_secs(1));
+                match tungstenite::connect(&url) {
+                    Ok((socket, _)) => break socket,
+                    Err(e) => eprintln!("rosbridge: {}", e),
+                }
+            };
+        }
+    });
+    Ok(())
+}
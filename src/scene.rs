@@ -0,0 +1,126 @@
+//! Composing several tree files into one scene.
+
+use std::path::Path;
+
+use bevy::math::DVec3;
+use nalgebra::Isometry3;
+
+use crate::origin;
+use crate::timeline::Animation;
+use crate::convention::{self, Convention};
+use crate::units::{self, Units};
+use crate::{FileNode, FileTransformTree, FileTransformTreeError, TransformTree, formats};
+
+/// One input file of a scene.
+pub struct SceneFile<'a> {
+    pub path: &'a Path,
+    /// Prepended to every frame name in the file.
+    pub prefix: &'a str,
+    /// Applied above the file's root frames.
+    pub offset: Option<Isometry3<f64>>,
+    /// Overrides the units the file declares.
+    pub units: Option<Units>,
+    /// Overrides the axis convention the file declares.
+    pub convention: Option<Convention>,
+}
+
+/// Parses "x y z roll pitch yaw" (spaces or commas, radians) into a pose,
+/// in full precision for map and UTM coordinates.
+pub fn parse_pose(text: &str) -> Result<Isometry3<f64>, String> {
+    let values = text
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<f64>().map_err(|e| format!("{:?}: {}", s, e)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let [x, y, z, roll, pitch, yaw] = values[..] else {
+        return Err(format!("expected 6 numbers \"x y z roll pitch yaw\", got {}", values.len()));
+    };
+    Ok(formats::isometry(&FileNode { t: [x, y, z], r: [roll, pitch, yaw], ..Default::default() }))
+}
+
+/// Prefixes every frame of `tree` and moves its roots by `offset`.
+pub fn place(tree: &mut FileTransformTree, prefix: &str, offset: Option<Isometry3<f64>>) {
+    for node in &mut tree.nodes {
+        node.name = format!("{}{}", prefix, node.name);
+        match &mut node.parent {
+            Some(parent) => *parent = format!("{}{}", prefix, parent),
+            None => {
+                if let Some(offset) = offset {
+                    let pose = offset * formats::isometry(node);
+                    node.set_pose_f64(&pose);
+                    // Expressions no longer describe the moved pose.
+                    node.expressions = None;
+                }
+            }
+        }
+    }
+}
+
+/// Makes the point cloud and camera image paths in a tree loaded from `path`
+/// relative to the working directory instead of the tree file.
+pub fn resolve_paths(tree: &mut FileTransformTree, path: &Path) {
+    let Some(dir) = path.parent() else {
+        return;
+    };
+    for node in &mut tree.nodes {
+        let cloud = node.cloud.as_mut().map(|c| &mut c.path);
+        let image = node.camera.as_mut().and_then(|c| c.image.as_mut());
+        for file in cloud.into_iter().chain(image) {
+            if Path::new(file.as_str()).is_relative() {
+                *file = dir.join(&*file).to_string_lossy().into_owned();
+            }
+        }
+    }
+}
+
+/// Adds a frame `name` at `pose` and hangs every current root below it.
+pub fn add_root(tree: &mut FileTransformTree, name: &str, pose: Isometry3<f64>) {
+    for node in tree.nodes.iter_mut().filter(|n| n.parent.is_none()) {
+        node.parent = Some(name.to_string());
+    }
+    let mut root = FileNode { name: name.to_string(), ..Default::default() };
+    root.set_pose_f64(&pose);
+    tree.nodes.insert(0, root);
+}
+
+/// Loads and merges `files` into one tree, and their animations into one,
+/// optionally below a virtual root frame `(name, pose)`.
+pub fn load(
+    files: &[SceneFile],
+    root: Option<(&str, Isometry3<f64>)>,
+) -> Result<(TransformTree, Option<Animation>), FileTransformTreeError> {
+    let mut merged = FileTransformTree { version: 1, ..Default::default() };
+    let mut animation: Option<Animation> = None;
+    let mut loaded_units = Units::M;
+    for file in files {
+        let (mut tree, mut anim) = formats::load_animated(file.path)
+            .map_err(|e| FileTransformTreeError::Serialization(format!("{}: {}", file.path.display(), e)))?;
+        let file_units = units::to_meters(&mut tree, anim.as_mut(), file.units);
+        convention::to_ros(&mut tree, anim.as_mut(), file.convention);
+        if loaded_units == Units::M {
+            loaded_units = file_units;
+        }
+        place(&mut tree, file.prefix, file.offset);
+        resolve_paths(&mut tree, file.path);
+        if let Some(mut anim) = anim {
+            for track in &mut anim.tracks {
+                track.node = format!("{}{}", file.prefix, track.node);
+                // Animated roots would otherwise lose the offset on playback.
+                let is_root = tree.nodes.iter().any(|n| n.name == track.node && n.parent.is_none());
+                if let (true, Some(offset)) = (is_root, file.offset) {
+                    let offset = origin::narrow(&offset, DVec3::ZERO);
+                    track.poses.iter_mut().for_each(|p| *p = offset * *p);
+                }
+            }
+            animation.get_or_insert_default().tracks.append(&mut anim.tracks);
+        }
+        merged.params.extend(tree.params);
+        merged.nodes.append(&mut tree.nodes);
+    }
+    if let Some((name, pose)) = root {
+        add_root(&mut merged, name, pose);
+    }
+    let mut dag = TransformTree::try_from(merged)?;
+    dag.units = loaded_units;
+    Ok((dag, animation))
+}
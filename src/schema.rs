@@ -0,0 +1,35 @@
+//! JSON Schema of the tree file format, and validation of JSON tree files
+//! against it so errors point at the offending field.
+
+use anyhow::{Result, anyhow, bail};
+use serde_json::Value;
+
+use crate::{FileTransformTree, FileTransformTreeError};
+
+pub fn json_schema() -> Value {
+    serde_json::to_value(schemars::schema_for!(FileTransformTree)).expect("schema serializes to JSON")
+}
+
+/// Parses a JSON tree file, reporting every schema violation with its path.
+/// Expressions in `t` and `r` are evaluated first.
+pub fn parse(text: &str) -> Result<FileTransformTree> {
+    let mut value: Value = serde_json::from_str(text)?;
+    crate::expr::substitute(&mut value)?;
+    let validator = jsonschema::validator_for(&json_schema()).map_err(|e| anyhow!("invalid schema: {}", e))?;
+    let errors: Vec<String> = validator
+        .iter_errors(&value)
+        .map(|e| {
+            let path = e.instance_path.to_string();
+            format!("{}: {}", if path.is_empty() { "/" } else { &path }, e)
+        })
+        .collect();
+    if !errors.is_empty() {
+        bail!("tree file does not match the schema:\n  {}", errors.join("\n  "));
+    }
+    let mut tree: FileTransformTree = serde_json::from_value(value)?;
+    tree.resolve_euler();
+    if let Err(FileTransformTreeError::Matrix(e)) = tree.resolve_matrices() {
+        bail!("/nodes: matrix of {}", e);
+    }
+    Ok(tree)
+}
@@ -0,0 +1,150 @@
+//! Rhai scripting against the loaded tree, run from the in-app console.
+//!
+//! Poses are maps shaped like tree file entries, `#{ t: [x, y, z], r: [roll, pitch, yaw] }`.
+//!
+//! - `frames()` names of all frames
+//! - `parent(name)` parent name, or `()` for roots
+//! - `local(name)`, `world(name)` pose relative to the parent / world
+//! - `relative(reference, name)` pose of `name` expressed in `reference`
+//! - `set_pose(name, t, r)` replaces the local pose
+//! - `add_frame(name)`, `add_frame(name, parent)` new frame at the parent origin
+//! - `print(..)` writes to the console
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use bevy::prelude::*;
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map};
+
+use crate::{FileNode, NodeId, TransformTree};
+
+type Shared = Rc<RefCell<TransformTree>>;
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+#[derive(Resource, Default)]
+pub struct ScriptConsole {
+    pub input: String,
+    pub log: Vec<String>,
+}
+
+/// Runs `script` against `dag`, returning the output lines. Changes the script
+/// makes stay in the tree even if it fails part way through.
+pub fn run(dag: &mut TransformTree, script: &str) -> Vec<String> {
+    let tree: Shared = Rc::new(RefCell::new(std::mem::take(dag)));
+    let output = Rc::new(RefCell::new(vec![]));
+
+    let mut engine = Engine::new();
+    let out = output.clone();
+    engine.on_print(move |s| out.borrow_mut().push(s.to_string()));
+    let out = output.clone();
+    engine.on_debug(move |s, _, pos| out.borrow_mut().push(format!("{:?}: {}", pos, s)));
+    register(&mut engine, &tree);
+
+    let result = engine.eval::<Dynamic>(script);
+    drop(engine);
+    *dag = std::mem::take(&mut *tree.borrow_mut());
+
+    let mut output = output.take();
+    match result {
+        Ok(value) if !value.is_unit() => output.push(value.to_string()),
+        Ok(_) => {}
+        Err(e) => output.push(format!("error: {}", e)),
+    }
+    output
+}
+
+fn register(engine: &mut Engine, tree: &Shared) {
+    let t = tree.clone();
+    engine.register_fn("frames", move || -> Array {
+        t.borrow().nodes.iter().map(|n| Dynamic::from(n.name.clone())).collect()
+    });
+
+    let t = tree.clone();
+    engine.register_fn("parent", move |name: &str| -> ScriptResult<Dynamic> {
+        let dag = t.borrow();
+        let id = lookup(&dag, name)?;
+        Ok(match dag.nodes[id].parent {
+            Some(p) => Dynamic::from(dag.nodes[p].name.clone()),
+            None => Dynamic::UNIT,
+        })
+    });
+
+    let t = tree.clone();
+    engine.register_fn("local", move |name: &str| -> ScriptResult<Map> {
+        let dag = t.borrow();
+        Ok(pose_map(dag.nodes[lookup(&dag, name)?].local))
+    });
+
+    let t = tree.clone();
+    engine.register_fn("world", move |name: &str| -> ScriptResult<Map> {
+        let dag = t.borrow();
+        Ok(pose_map(dag.nodes[lookup(&dag, name)?].world))
+    });
+
+    let t = tree.clone();
+    engine.register_fn("relative", move |reference: &str, name: &str| -> ScriptResult<Map> {
+        let dag = t.borrow();
+        let (reference, id) = (lookup(&dag, reference)?, lookup(&dag, name)?);
+        Ok(pose_map(dag.relative(reference, id)))
+    });
+
+    let t = tree.clone();
+    engine.register_fn("set_pose", move |name: &str, tr: Array, r: Array| -> ScriptResult<()> {
+        let mut dag = t.borrow_mut();
+        let id = lookup(&dag, name)?;
+        let node = FileNode { t: floats(&tr)?, r: floats(&r)?, ..Default::default() };
+        dag.set_local(id, Isometry3d::from(&node));
+        dag.update_world();
+        Ok(())
+    });
+
+    let t = tree.clone();
+    engine.register_fn("add_frame", move |name: &str| -> ScriptResult<()> { add_frame(&t, name, None) });
+
+    let t = tree.clone();
+    engine.register_fn("add_frame", move |name: &str, parent: &str| -> ScriptResult<()> {
+        add_frame(&t, name, Some(parent))
+    });
+}
+
+fn add_frame(tree: &Shared, name: &str, parent: Option<&str>) -> ScriptResult<()> {
+    let mut dag = tree.borrow_mut();
+    if dag.find(name).is_some() {
+        return Err(format!("frame {} already exists", name).into());
+    }
+    if let Some(p) = parent {
+        lookup(&dag, p)?;
+    }
+    dag.apply(&FileNode { name: name.to_string(), parent: parent.map(str::to_string), ..Default::default() });
+    dag.update_world();
+    Ok(())
+}
+
+fn lookup(dag: &TransformTree, name: &str) -> ScriptResult<NodeId> {
+    dag.find(name).ok_or_else(|| format!("unknown frame {}", name).into())
+}
+
+fn floats(array: &Array) -> ScriptResult<[f64; 3]> {
+    let mut res = [0.0; 3];
+    if array.len() != 3 {
+        return Err(format!("expected 3 numbers, got {}", array.len()).into());
+    }
+    for (v, value) in res.iter_mut().zip(array) {
+        *v = match (value.as_float(), value.as_int()) {
+            (Ok(f), _) => f,
+            (_, Ok(i)) => i as f64,
+            _ => return Err(format!("expected a number, got {}", value.type_name()).into()),
+        };
+    }
+    Ok(res)
+}
+
+fn pose_map(pose: Isometry3d) -> Map {
+    let t = pose.translation.to_vec3();
+    let (r, p, y) = pose.rotation.to_euler(EulerRot::XYZ);
+    let array = |v: [f32; 3]| -> Array { v.iter().map(|&x| Dynamic::from_float(x as f64)).collect() };
+    let mut map = Map::new();
+    map.insert("t".into(), Dynamic::from_array(array(t.to_array())));
+    map.insert("r".into(), Dynamic::from_array(array([r, p, y])));
+    map
+}
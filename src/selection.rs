@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+use bevy_egui::input::EguiWantsInput;
+
+use crate::camera::CameraFocus;
+use crate::explode::FrameOffsets;
+use crate::{FrameSphere, NodeId, Selection, TransformTree};
+
+/// Longest gap between two clicks on the same frame that counts as a double-click.
+const DOUBLE_CLICK_SECS: f64 = 0.35;
+
+impl Selection {
+    /// The most recently selected node.
+    pub fn primary(&self) -> Option<NodeId> {
+        self.nodes.last().copied()
+    }
+
+    /// The node selected before the primary one, which constraint tools move
+    /// the primary node relative to.
+    pub fn target(&self) -> Option<NodeId> {
+        self.nodes.iter().rev().nth(1).copied()
+    }
+
+    pub fn select(&mut self, id: NodeId) {
+        self.nodes.clear();
+        self.nodes.push(id);
+    }
+
+    /// Adds `id` to the selection, or removes it if already selected.
+    pub fn toggle(&mut self, id: NodeId) {
+        match self.nodes.iter().position(|&n| n == id) {
+            Some(i) => {
+                self.nodes.remove(i);
+            }
+            None => self.nodes.push(id),
+        }
+    }
+}
+
+/// Click selects a frame (Shift/Ctrl+click toggles it in a multi-selection);
+/// double-click focuses the camera on it.
+pub fn on_frame_click(
+    click: On<Pointer<Click>>,
+    sphere_q: Query<&FrameSphere>,
+    dag: Res<TransformTree>,
+    keys: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    mut selection: ResMut<Selection>,
+    mut focus: ResMut<CameraFocus>,
+    mut last_click: Local<Option<(Entity, f64)>>,
+) {
+    if click.button != PointerButton::Primary {
+        return;
+    }
+    let Ok(sphere) = sphere_q.get(click.entity) else {
+        return;
+    };
+    let now = time.elapsed_secs_f64();
+    let double = matches!(*last_click, Some((e, t)) if e == click.entity && now - t < DOUBLE_CLICK_SECS);
+    if double {
+        *last_click = None;
+        focus.focus_on(dag.nodes[sphere.node].world.translation.to_vec3(), None);
+        return;
+    }
+    *last_click = Some((click.entity, now));
+
+    if keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight, KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        selection.toggle(sphere.node);
+    } else {
+        selection.select(sphere.node);
+    }
+}
+
+/// Tab / Shift+Tab cycle through visible frames, P jumps to the parent and C to
+/// the first child. The camera re-focuses on the newly selected frame. Escape
+/// clears the selection.
+pub fn keyboard_navigation(
+    keys: Res<ButtonInput<KeyCode>>,
+    egui_input: Res<EguiWantsInput>,
+    dag: Res<TransformTree>,
+    mut selection: ResMut<Selection>,
+    mut focus: ResMut<CameraFocus>,
+) {
+    if egui_input.wants_any_keyboard_input() || dag.nodes.is_empty() {
+        return;
+    }
+    if keys.just_pressed(KeyCode::Escape) {
+        selection.nodes.clear();
+        return;
+    }
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let current = selection.primary();
+    let n = dag.nodes.len();
+    let visible = |id: &NodeId| dag.nodes[*id].visible();
+
+    let next = if keys.just_pressed(KeyCode::Tab) {
+        let start = current.unwrap_or(if shift { 0 } else { n - 1 });
+        (1..=n)
+            .map(|i| if shift { (start + n - i) % n } else { (start + i) % n })
+            .find(visible)
+    } else if keys.just_pressed(KeyCode::KeyP) {
+        current.and_then(|id| dag.nodes[id].parent)
+    } else if keys.just_pressed(KeyCode::KeyC) {
+        current.and_then(|id| dag.nodes[id].children.iter().copied().find(visible))
+    } else {
+        None
+    };
+
+    if let Some(id) = next {
+        selection.select(id);
+        focus.focus_on(dag.nodes[id].world.translation.to_vec3(), None);
+    }
+}
+
+pub fn draw_selection(dag: Res<TransformTree>, selection: Res<Selection>, offsets: Res<FrameOffsets>, mut gizmos: Gizmos) {
+    for &id in &selection.nodes {
+        if id >= dag.nodes.len() {
+            continue;
+        }
+        gizmos.sphere(Isometry3d::from_translation(offsets.position(&dag, id)), 0.04, Color::srgb(1.0, 0.6, 0.0));
+    }
+}
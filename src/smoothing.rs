@@ -0,0 +1,50 @@
+//! Exponential smoothing of streamed poses. With a time constant set, live
+//! updates become targets that each frame's local pose approaches by a
+//! frame-rate independent fraction, hiding jitter from bursty delivery.
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::timeline::interpolate;
+use crate::{NodeId, TransformTree};
+
+#[derive(Resource, Debug, Default)]
+pub struct Smoothing {
+    /// Seconds for the remaining distance to shrink by a factor of e; 0 disables smoothing.
+    pub time_constant: f32,
+    targets: HashMap<NodeId, Isometry3d>,
+}
+
+impl Smoothing {
+    pub fn new(time_constant: f32) -> Self {
+        Smoothing { time_constant, targets: HashMap::new() }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.time_constant > 0.0
+    }
+
+    /// Makes `pose` the target of `id` instead of its immediate local pose.
+    pub fn set_target(&mut self, id: NodeId, pose: Isometry3d) {
+        self.targets.insert(id, pose);
+    }
+}
+
+pub fn smooth_poses(time: Res<Time>, mut smoothing: ResMut<Smoothing>, mut dag: ResMut<TransformTree>) {
+    if smoothing.targets.is_empty() {
+        return;
+    }
+    let Smoothing { time_constant, targets } = &mut *smoothing;
+    let s = if *time_constant > 0.0 { 1.0 - (-time.delta_secs() / *time_constant).exp() } else { 1.0 };
+    targets.retain(|&id, target| {
+        let Some(node) = dag.nodes.get(id) else {
+            return false;
+        };
+        let pose = interpolate(node.local, *target, s);
+        let arrived = pose.translation.distance(target.translation) < 1e-5 && pose.rotation.angle_between(target.rotation) < 1e-5;
+        dag.set_local(id, if arrived { *target } else { pose });
+        !arrived
+    });
+    dag.update_world();
+}
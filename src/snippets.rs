@@ -0,0 +1,152 @@
+//! A frame's pose relative to its parent as text in the formats it is most
+//! often pasted into, and back.
+
+use anyhow::{Result, anyhow, bail};
+use bevy::math::{DMat4, DQuat, DVec3};
+use bevy::prelude::*;
+
+use crate::{FileNode, NodeId, TransformTree};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoseFormat {
+    /// Tree file node entry.
+    Json,
+    /// 4x4 homogeneous matrix, one row per line.
+    Matrix,
+    /// `ros2 run tf2_ros static_transform_publisher ...`
+    StaticTransformPublisher,
+    /// `np.array([[...], ...])` of the 4x4 matrix.
+    Numpy,
+    /// URDF `<origin xyz=".." rpy=".."/>`.
+    UrdfOrigin,
+}
+
+impl PoseFormat {
+    pub const ALL: [PoseFormat; 5] = [
+        PoseFormat::Json,
+        PoseFormat::Matrix,
+        PoseFormat::StaticTransformPublisher,
+        PoseFormat::Numpy,
+        PoseFormat::UrdfOrigin,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PoseFormat::Json => "JSON node",
+            PoseFormat::Matrix => "4x4 matrix",
+            PoseFormat::StaticTransformPublisher => "ROS static_transform_publisher",
+            PoseFormat::Numpy => "NumPy array",
+            PoseFormat::UrdfOrigin => "URDF <origin>",
+        }
+    }
+}
+
+/// Local pose of `id` written as `format`.
+pub fn pose_text(dag: &TransformTree, id: NodeId, format: PoseFormat) -> String {
+    let node = &dag.nodes[id];
+    let parent = node.parent.map(|p| dag.nodes[p].name.clone());
+    let pose = &node.local_f64;
+    let t = DVec3::from_array(pose.translation.vector.into());
+    let q = DQuat::from_xyzw(pose.rotation.i, pose.rotation.j, pose.rotation.k, pose.rotation.w);
+    let rows = DMat4::from_rotation_translation(q, t).transpose().to_cols_array_2d();
+    match format {
+        PoseFormat::Json => {
+            let mut file_node = FileNode { name: node.name.clone(), parent, ..Default::default() };
+            file_node.set_pose_f64(pose);
+            serde_json::to_string_pretty(&file_node).unwrap_or_default()
+        }
+        PoseFormat::Matrix => rows.map(|row| row.map(|v| v.to_string()).join(" ")).join("\n"),
+        PoseFormat::StaticTransformPublisher => {
+            format!(
+                "ros2 run tf2_ros static_transform_publisher --x {} --y {} --z {} --qx {} --qy {} --qz {} --qw {} --frame-id {} --child-frame-id {}",
+                t.x,
+                t.y,
+                t.z,
+                q.x,
+                q.y,
+                q.z,
+                q.w,
+                parent.as_deref().unwrap_or("world"),
+                node.name
+            )
+        }
+        PoseFormat::Numpy => {
+            let rows = rows.map(|row| format!("[{}]", row.map(|v| v.to_string()).join(", ")));
+            format!("np.array([\n    {},\n])", rows.join(",\n    "))
+        }
+        PoseFormat::UrdfOrigin => {
+            // Fixed-axis roll, pitch, yaw: yaw about Z, then pitch about Y, then roll about X.
+            let (yaw, pitch, roll) = q.to_euler(EulerRot::ZYX);
+            format!(r#"<origin xyz="{} {} {}" rpy="{} {} {}"/>"#, t.x, t.y, t.z, roll, pitch, yaw)
+        }
+    }
+}
+
+/// Reads a pose pasted in any of the formats above or a few close relatives,
+/// guessing the format from the text. Returns the pose and what it was read as:
+///
+/// - a JSON object with `t` and `r` (tree file convention),
+/// - `xyz="..." rpy="..."` attributes (URDF, fixed-axis roll, pitch, yaw),
+/// - 16 or 12 numbers: a row-major 4x4 or 3x4 matrix,
+/// - 7 numbers: x y z qx qy qz qw, as static_transform_publisher takes them,
+/// - 6 numbers: x y z roll pitch yaw, fixed-axis as in ROS.
+pub fn parse_pose(text: &str) -> Result<(Isometry3d, &'static str)> {
+    let text = text.trim();
+    if text.starts_with('{') {
+        let value: serde_json::Value = serde_json::from_str(text)?;
+        let vector = |key: &str| -> Result<Vec3> {
+            let numbers: Option<Vec<f32>> = match value.get(key) {
+                None => Some(vec![0.0; 3]),
+                Some(v) => v.as_array().and_then(|a| a.iter().map(|n| n.as_f64().map(|n| n as f32)).collect()),
+            };
+            match numbers.as_deref() {
+                Some(&[x, y, z]) => Ok(Vec3::new(x, y, z)),
+                _ => bail!("\"{}\" should be three numbers", key),
+            }
+        };
+        let (t, r) = (vector("t")?, vector("r")?);
+        let rotation = Quat::from_euler(EulerRot::XYZ, r.x, r.y, r.z);
+        return Ok((Isometry3d::new(t, rotation), "JSON node"));
+    }
+    if let (Some(xyz), Some(rpy)) = (attribute(text, "xyz"), attribute(text, "rpy")) {
+        let (t, r) = (numbers(xyz), numbers(rpy));
+        if let ([x, y, z], [roll, pitch, yaw]) = (t.as_slice(), r.as_slice()) {
+            let rotation = Quat::from_euler(EulerRot::ZYX, *yaw, *pitch, *roll);
+            return Ok((Isometry3d::new(Vec3::new(*x, *y, *z), rotation), "URDF origin"));
+        }
+        bail!("xyz and rpy should be three numbers each");
+    }
+    let n = numbers(text);
+    match n.len() {
+        16 | 12 => {
+            let row = |i: usize| Vec3::new(n[i * 4], n[i * 4 + 1], n[i * 4 + 2]);
+            let rotation = Mat3::from_cols(row(0), row(1), row(2)).transpose();
+            let t = Vec3::new(n[3], n[7], n[11]);
+            let kind = if n.len() == 16 { "4x4 matrix" } else { "3x4 matrix" };
+            Ok((Isometry3d::new(t, Quat::from_mat3(&rotation).normalize()), kind))
+        }
+        7 => {
+            let q = Quat::from_xyzw(n[3], n[4], n[5], n[6]);
+            let q = q.try_normalize().ok_or_else(|| anyhow!("quaternion has zero length"))?;
+            Ok((Isometry3d::new(Vec3::new(n[0], n[1], n[2]), q), "xyz + quaternion"))
+        }
+        6 => {
+            let rotation = Quat::from_euler(EulerRot::ZYX, n[5], n[4], n[3]);
+            Ok((Isometry3d::new(Vec3::new(n[0], n[1], n[2]), rotation), "xyz + rpy"))
+        }
+        count => bail!("can't tell the pose format from {} numbers", count),
+    }
+}
+
+/// Value of `name="..."` in `text`.
+fn attribute<'a>(text: &'a str, name: &str) -> Option<&'a str> {
+    let start = text.find(&format!("{}=\"", name))? + name.len() + 2;
+    let len = text[start..].find('"')?;
+    Some(&text[start..start + len])
+}
+
+/// Every number in `text`, skipping words, brackets, commas and flags.
+fn numbers(text: &str) -> Vec<f32> {
+    let tokens = text.split(|c: char| c.is_whitespace() || "[](),;=\"".contains(c));
+    tokens.filter_map(|token| token.parse::<f32>().ok()).filter(|v| v.is_finite()).collect()
+}
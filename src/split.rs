@@ -0,0 +1,167 @@
+//! Split view: up to three extra panes next to the main camera, each an
+//! orthographic top, front or side view of the same tree with its own pan
+//! and zoom.
+
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::camera::{ScalingMode, Viewport};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_panorbit_camera::PanOrbitCamera;
+
+use crate::TransformTree;
+use crate::camera::{self, MainCamera};
+use crate::grid::{GridPlane, GridSettings};
+use crate::stereo::Stereo;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaneView {
+    Top,
+    Front,
+    Side,
+}
+
+impl PaneView {
+    /// Views of the extra panes, in the order they are added.
+    pub const ORDER: [PaneView; 3] = [PaneView::Top, PaneView::Front, PaneView::Side];
+
+    /// Orbit yaw and pitch looking down the orbit's up axis, and along two
+    /// horizontal axes; see `GridPlane::orbit_axes`.
+    fn angles(self) -> (f32, f32) {
+        match self {
+            PaneView::Top => (0.0, FRAC_PI_2),
+            PaneView::Front => (0.0, 0.0),
+            PaneView::Side => (FRAC_PI_2, 0.0),
+        }
+    }
+}
+
+/// Number of panes, 1 to 4, including the main camera's.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SplitView {
+    pub panes: usize,
+}
+
+impl Default for SplitView {
+    fn default() -> Self {
+        SplitView { panes: 1 }
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Pane {
+    pub view: PaneView,
+    /// Position in the layout; the main camera is 0.
+    index: usize,
+    /// Floor the views were laid out for; the pane is replaced when it changes.
+    plane: GridPlane,
+}
+
+/// Pane rectangles (position, size) for `panes` panes in a window of `size`:
+/// side by side for two, one large pane and two stacked for three, a 2x2 grid for four.
+fn layout(panes: usize, size: UVec2) -> Vec<(UVec2, UVec2)> {
+    let half = size / 2;
+    let (w, h) = (size.x - half.x, size.y - half.y);
+    match panes {
+        2 => vec![(UVec2::ZERO, UVec2::new(half.x, size.y)), (UVec2::new(half.x, 0), UVec2::new(w, size.y))],
+        3 => vec![
+            (UVec2::ZERO, UVec2::new(half.x, size.y)),
+            (UVec2::new(half.x, 0), UVec2::new(w, half.y)),
+            (half, UVec2::new(w, h)),
+        ],
+        4 => vec![
+            (UVec2::ZERO, half),
+            (UVec2::new(half.x, 0), UVec2::new(w, half.y)),
+            (UVec2::new(0, half.y), UVec2::new(half.x, h)),
+            (half, UVec2::new(w, h)),
+        ],
+        _ => vec![(UVec2::ZERO, size)],
+    }
+}
+
+fn set_viewport(camera: &mut Camera, rect: Option<(UVec2, UVec2)>) {
+    let current = camera.viewport.as_ref().map(|v| (v.physical_position, v.physical_size));
+    if current != rect {
+        camera.viewport = rect.map(|(physical_position, physical_size)| Viewport {
+            physical_position,
+            physical_size,
+            ..default()
+        });
+    }
+}
+
+/// Spawns and removes pane cameras to match `SplitView` and fits every
+/// camera's viewport to the window. Stereo leaves the main camera alone.
+pub fn sync_panes(
+    mut commands: Commands,
+    split: Res<SplitView>,
+    stereo: Res<Stereo>,
+    grid: Res<GridSettings>,
+    dag: Res<TransformTree>,
+    window_q: Query<&Window, With<PrimaryWindow>>,
+    mut main_q: Query<&mut Camera, With<MainCamera>>,
+    mut pane_q: Query<(Entity, &Pane, &mut Camera), Without<MainCamera>>,
+) {
+    let Ok(window) = window_q.single() else {
+        return;
+    };
+    let Ok(mut main) = main_q.single_mut() else {
+        return;
+    };
+    if stereo.enabled {
+        for (entity, ..) in &pane_q {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+    let panes = split.panes.clamp(1, 4);
+    let size = window.physical_size().max(UVec2::ONE);
+    let rects = layout(panes, size);
+
+    set_viewport(&mut main, (panes > 1).then_some(rects[0]));
+    let mut present = vec![false; panes];
+    for (entity, pane, mut camera) in &mut pane_q {
+        if pane.index >= panes || pane.plane != grid.plane {
+            commands.entity(entity).despawn();
+        } else {
+            present[pane.index] = true;
+            set_viewport(&mut camera, Some(rects[pane.index]));
+        }
+    }
+
+    let (focus, radius) = camera::framing(&dag);
+    for index in (1..panes).filter(|&i| !present[i]) {
+        let view = PaneView::ORDER[index - 1];
+        let (yaw, pitch) = view.angles();
+        let (position, size) = rects[index];
+        commands.spawn((
+            Pane { view, index, plane: grid.plane },
+            Camera3d::default(),
+            Camera {
+                order: index as isize,
+                viewport: Some(Viewport { physical_position: position, physical_size: size, ..default() }),
+                ..default()
+            },
+            Projection::from(OrthographicProjection {
+                scaling_mode: ScalingMode::FixedVertical { viewport_height: radius * 2.0 },
+                ..OrthographicProjection::default_3d()
+            }),
+            // Orbiting is locked; panning and zooming stay free.
+            PanOrbitCamera {
+                focus,
+                target_focus: focus,
+                axis: grid.plane.orbit_axes(),
+                radius: Some(radius),
+                yaw: Some(yaw),
+                pitch: Some(pitch),
+                target_yaw: yaw,
+                target_pitch: pitch,
+                yaw_upper_limit: Some(yaw),
+                yaw_lower_limit: Some(yaw),
+                pitch_upper_limit: Some(pitch),
+                pitch_lower_limit: Some(pitch),
+                ..default()
+            },
+        ));
+    }
+}
@@ -0,0 +1,64 @@
+//! Stale frame detection for live sources: frames that stop receiving updates
+//! for longer than a timeout are drawn grayed out and their labels say for
+//! how long, so dead publishers stand out.
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::{AxisOverlayLabel, NodeId, TransformTree};
+
+/// Gray the axes of stale frames are drawn in.
+pub const STALE_COLOR: Color = Color::srgba(0.5, 0.5, 0.5, 0.4);
+
+#[derive(Resource, Debug)]
+pub struct Staleness {
+    /// Seconds without an update after which a streamed frame is stale.
+    pub timeout: f64,
+    /// App time of each streamed frame's last update. Frames only loaded from
+    /// files are never stale.
+    last_update: HashMap<NodeId, f64>,
+}
+
+impl Staleness {
+    pub fn new(timeout: f64) -> Self {
+        Staleness { timeout, last_update: HashMap::new() }
+    }
+
+    pub fn touch(&mut self, id: NodeId, now: f64) {
+        self.last_update.insert(id, now);
+    }
+
+    /// Seconds since `id` was last updated, if that is beyond the timeout.
+    pub fn age(&self, id: NodeId, now: f64) -> Option<f64> {
+        let age = now - self.last_update.get(&id)?;
+        (age > self.timeout).then_some(age)
+    }
+}
+
+/// Appends "stale (2.3s)" to the labels of stale frames and removes it again
+/// once they update.
+pub fn annotate_labels(
+    staleness: Option<Res<Staleness>>,
+    time: Res<Time>,
+    dag: Res<TransformTree>,
+    mut label_q: Query<(&AxisOverlayLabel, &mut Text)>,
+) {
+    let Some(staleness) = staleness else {
+        return;
+    };
+    let now = time.elapsed_secs_f64();
+    for (label, mut text) in &mut label_q {
+        if !staleness.last_update.contains_key(&label.node) {
+            continue;
+        }
+        let name = dag.nodes[label.node].label_text();
+        let wanted = match staleness.age(label.node, now) {
+            Some(age) => format!("{} stale ({:.1}s)", name, age),
+            None => name,
+        };
+        if text.0 != wanted {
+            text.0 = wanted;
+        }
+    }
+}
@@ -0,0 +1,98 @@
+//! Side-by-side stereo: the main camera draws one eye in half the window and
+//! a second camera, mounted on it an eye separation to the right, draws the
+//! other. With the real interpupillary distance the tree is seen at true
+//! scale in a phone headset or any side-by-side video viewer; swapping the
+//! halves suits cross-eyed free viewing instead.
+
+use bevy::camera::Viewport;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::camera::MainCamera;
+
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct Stereo {
+    pub enabled: bool,
+    /// Distance between the eyes in meters.
+    pub eye_separation: f32,
+    /// Left eye on the right, for cross-eyed viewing.
+    pub swap: bool,
+}
+
+impl Default for Stereo {
+    fn default() -> Self {
+        Stereo { enabled: false, eye_separation: 0.064, swap: false }
+    }
+}
+
+/// The right eye's camera, a child of the main camera.
+#[derive(Component)]
+pub struct StereoEye;
+
+/// Viewports (position, size) of the left and right eye in a window of `size`.
+pub fn eye_viewports(size: UVec2, swap: bool) -> [(UVec2, UVec2); 2] {
+    let half = size.x / 2;
+    let left = (UVec2::ZERO, UVec2::new(half, size.y));
+    let right = (UVec2::new(half, 0), UVec2::new(size.x - half, size.y));
+    if swap { [right, left] } else { [left, right] }
+}
+
+fn viewport((physical_position, physical_size): (UVec2, UVec2)) -> Viewport {
+    Viewport { physical_position, physical_size, ..default() }
+}
+
+/// Spawns or removes the right eye to match `Stereo` and splits the window
+/// between the eyes. Runs after the split view, which stereo replaces.
+pub fn sync_eyes(
+    mut commands: Commands,
+    stereo: Res<Stereo>,
+    window_q: Query<&Window, With<PrimaryWindow>>,
+    mut main_q: Query<(Entity, &mut Camera, &Projection), With<MainCamera>>,
+    mut eye_q: Query<(Entity, &mut Camera, &mut Transform, &mut Projection), (With<StereoEye>, Without<MainCamera>)>,
+) {
+    let Ok((main_entity, mut main, projection)) = main_q.single_mut() else {
+        return;
+    };
+    if !stereo.enabled {
+        for (entity, ..) in &eye_q {
+            commands.entity(entity).despawn();
+            main.viewport = None;
+        }
+        return;
+    }
+    let Ok(window) = window_q.single() else {
+        return;
+    };
+    let [left, right] = eye_viewports(window.physical_size().max(UVec2::ONE), stereo.swap);
+    if main.viewport.as_ref().map(|v| (v.physical_position, v.physical_size)) != Some(left) {
+        main.viewport = Some(viewport(left));
+    }
+    let offset = Vec3::X * stereo.eye_separation;
+    match eye_q.single_mut() {
+        Ok((_, mut camera, mut transform, mut eye_projection)) => {
+            if camera.viewport.as_ref().map(|v| (v.physical_position, v.physical_size)) != Some(right) {
+                camera.viewport = Some(viewport(right));
+            }
+            if transform.translation != offset {
+                transform.translation = offset;
+            }
+            // Zooming changes the main camera's field of view; the eyes match.
+            if let (Projection::Perspective(main), Projection::Perspective(eye)) = (projection, eye_projection.as_mut())
+                && main.fov != eye.fov
+            {
+                eye.fov = main.fov;
+            }
+        }
+        Err(_) => {
+            commands.spawn((
+                StereoEye,
+                Camera3d::default(),
+                // After the split view panes, which are gone while in stereo.
+                Camera { order: 4, viewport: Some(viewport(right)), ..default() },
+                projection.clone(),
+                Transform::from_translation(offset),
+                ChildOf(main_entity),
+            ));
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use std::io::BufRead;
+use std::sync::Mutex;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use bevy::log::tracing::field;
+use bevy::prelude::*;
+
+use crate::average::PoseSampler;
+use crate::recording::Recorder;
+use crate::smoothing::Smoothing;
+use crate::stale::Staleness;
+use crate::{FileNode, TransformTree};
+
+/// Channel end that live sources push node updates into. Each update uses the
+/// same shape as a `FileNode` entry in a tree file.
+#[derive(Resource)]
+pub struct UpdateReceiver(Mutex<Receiver<FileNode>>);
+
+impl UpdateReceiver {
+    pub fn new() -> (Sender<FileNode>, Self) {
+        let (tx, rx) = mpsc::channel();
+        (tx, UpdateReceiver(Mutex::new(rx)))
+    }
+}
+
+/// Encoding of updates read from standard input.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StreamFormat {
+    /// One JSON node per line.
+    #[default]
+    Json,
+    /// Length-delimited `TransformBatch` messages from proto/transform.proto.
+    #[cfg(feature = "proto")]
+    Proto,
+}
+
+/// Reads updates from standard input on a background thread.
+#[cfg_attr(not(feature = "proto"), allow(unused_variables))]
+pub fn spawn_stdin_reader(app: &mut App, tx: Sender<FileNode>, format: StreamFormat) {
+    match format {
+        StreamFormat::Json => spawn_json_reader(tx),
+        #[cfg(feature = "proto")]
+        StreamFormat::Proto => {
+            let (proto_tx, rx) = mpsc::channel();
+            spawn_proto_reader(proto_tx);
+            app.insert_resource(ProtoUpdates { rx: Mutex::new(rx), tx })
+                .add_systems(Update, resolve_proto_updates.before(apply_updates));
+        }
+    }
+}
+
+/// Reads newline-delimited JSON nodes from standard input.
+fn spawn_json_reader(tx: Sender<FileNode>) {
+    thread::spawn(move || {
+        let stdin = std::io::stdin();
+        for line in stdin.lock().lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    eprintln!("stdin: {}", e);
+                    break;
+                }
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<FileNode>(&line) {
+                Ok(node) => {
+                    if tx.send(node).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => eprintln!("stdin: skipping malformed update: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(feature = "proto")]
+pub mod proto {
+    include!(concat!(env!("OUT_DIR"), "/axisviz.stream.rs"));
+}
+
+/// Node update for `update`. An unset parent keeps the frame's current one,
+/// as the message documents, rather than making it a root.
+#[cfg(feature = "proto")]
+pub fn update_node(update: proto::TransformUpdate, dag: &TransformTree) -> FileNode {
+    let pose = crate::origin::pose_f64([update.x, update.y, update.z], [update.qx, update.qy, update.qz, update.qw]);
+    let parent = update.parent.or_else(|| {
+        let id = dag.find(&update.name)?;
+        dag.nodes[id].parent.map(|p| dag.nodes[p].name.clone())
+    });
+    let mut node = FileNode { name: update.name, parent, ..Default::default() };
+    node.set_pose_f64(&pose);
+    node
+}
+
+/// Proto updates read from standard input, waiting for their parent to be
+/// resolved against the tree on the main thread.
+#[cfg(feature = "proto")]
+#[derive(Resource)]
+pub struct ProtoUpdates {
+    rx: Mutex<Receiver<proto::TransformUpdate>>,
+    tx: Sender<FileNode>,
+}
+
+#[cfg(feature = "proto")]
+pub fn resolve_proto_updates(dag: Res<TransformTree>, updates: Res<ProtoUpdates>) {
+    let Ok(rx) = updates.rx.lock() else {
+        return;
+    };
+    for update in rx.try_iter() {
+        if updates.tx.send(update_node(update, &dag)).is_err() {
+            return;
+        }
+    }
+}
+
+/// Largest message accepted on standard input. A corrupt length prefix
+/// would otherwise allocate whatever it claims.
+#[cfg(feature = "proto")]
+const MAX_MESSAGE: usize = 1 << 20;
+
+/// Next varint length-prefixed message, or `None` at the end of the stream.
+#[cfg(feature = "proto")]
+pub fn read_delimited(reader: &mut impl std::io::Read) -> std::io::Result<Option<Vec<u8>>> {
+    let mut len = 0usize;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0u8];
+        if reader.read(&mut byte)? == 0 {
+            return Ok(None);
+        }
+        len |= ((byte[0] & 0x7f) as usize) << shift;
+        if byte[0] & 0x80 == 0 {
+            if len > MAX_MESSAGE {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("message of {} bytes exceeds the {} byte limit", len, MAX_MESSAGE),
+                ));
+            }
+            let mut buf = vec![0; len];
+            reader.read_exact(&mut buf)?;
+            return Ok(Some(buf));
+        }
+    }
+    Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "length prefix too long"))
+}
+
+/// Reads length-delimited `TransformBatch` messages from standard input.
+#[cfg(feature = "proto")]
+fn spawn_proto_reader(tx: Sender<proto::TransformUpdate>) {
+    use prost::Message;
+
+    thread::spawn(move || {
+        let mut stdin = std::io::BufReader::new(std::io::stdin().lock());
+        loop {
+            let buf = match read_delimited(&mut stdin) {
+                Ok(Some(buf)) => buf,
+                Ok(None) => break,
+                Err(e) => {
+                    eprintln!("stdin: {}", e);
+                    break;
+                }
+            };
+            let batch = match proto::TransformBatch::decode(buf.as_slice()) {
+                Ok(batch) => batch,
+                Err(e) => {
+                    eprintln!("stdin: skipping malformed batch: {}", e);
+                    continue;
+                }
+            };
+            for update in batch.updates {
+                if tx.send(update).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+}
+
+/// Merges pending updates into the tree. With smoothing on, poses of existing
+/// frames become smoothing targets instead of jumping.
+pub fn apply_updates(
+    mut dag: ResMut<TransformTree>,
+    rx: Res<UpdateReceiver>,
+    mut recorder: Option<ResMut<Recorder>>,
+    mut smoothing: ResMut<Smoothing>,
+    mut staleness: Option<ResMut<Staleness>>,
+    mut sampler: ResMut<PoseSampler>,
+    time: Res<Time>,
+) {
+    let Ok(rx) = rx.0.lock() else {
+        return;
+    };
+    let span = info_span!("ingest_updates", count = field::Empty).entered();
+    let mut count = 0;
+    for node in rx.try_iter() {
+        if let Some(recorder) = &mut recorder {
+            recorder.record(&node);
+        }
+        sampler.record(&node, time.elapsed_secs_f64());
+        let previous = dag.find(&node.name).map(|id| (id, dag.nodes[id].local));
+        dag.apply(&node);
+        if let (Some(staleness), Some(id)) = (&mut staleness, dag.find(&node.name)) {
+            staleness.touch(id, time.elapsed_secs_f64());
+        }
+        if let Some((id, local)) = previous
+            && smoothing.enabled()
+        {
+            smoothing.set_target(id, dag.nodes[id].local);
+            dag.set_local(id, local);
+        }
+        count += 1;
+    }
+    span.record("count", count);
+    if count > 0 {
+        dag.update_world();
+        if let Some(recorder) = &mut recorder {
+            recorder.flush();
+        }
+    }
+}
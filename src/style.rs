@@ -0,0 +1,82 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::links::LinkStyle;
+use crate::tips::AxisTips;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Palette {
+    /// Red, green, blue axes.
+    #[default]
+    Standard,
+    /// Okabe–Ito vermillion, yellow, blue axes; safe for red-green color blindness.
+    Deuteranopia,
+}
+
+/// Axis triad size and the colors frames are drawn with.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Style {
+    pub theme: Theme,
+    pub palette: Palette,
+    pub axis_scale: f32,
+    pub x: [f32; 3],
+    pub y: [f32; 3],
+    pub z: [f32; 3],
+    pub link: [f32; 3],
+    pub label: [f32; 3],
+    pub background: [f32; 3],
+    pub links: LinkStyle,
+    pub tips: AxisTips,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style::new(Theme::default(), Palette::default())
+    }
+}
+
+impl Style {
+    pub fn new(theme: Theme, palette: Palette) -> Self {
+        let ([x, y, z], link) = match palette {
+            Palette::Standard => ([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]], [1.0, 1.0, 0.0]),
+            Palette::Deuteranopia => (
+                [[0.835, 0.369, 0.0], [0.941, 0.894, 0.259], [0.0, 0.447, 0.698]],
+                [0.8, 0.475, 0.655],
+            ),
+        };
+        let (label, background, link) = match theme {
+            Theme::Dark => ([1.0, 1.0, 1.0], [0.12, 0.12, 0.12], link),
+            // Darken the link color so it stays visible on white.
+            Theme::Light => ([0.0, 0.0, 0.0], [0.95, 0.95, 0.95], link.map(|c| c * 0.7)),
+        };
+        Style { theme, palette, axis_scale: 0.2, x, y, z, link, label, background, links: LinkStyle::default(), tips: AxisTips::None }
+    }
+
+    pub fn axis_colors(&self) -> [Color; 3] {
+        [self.x, self.y, self.z].map(Color::srgb_from_array)
+    }
+
+    pub fn link_color(&self) -> Color {
+        Color::srgb_from_array(self.link)
+    }
+
+    pub fn label_color(&self) -> Color {
+        Color::srgb_from_array(self.label)
+    }
+}
+
+pub fn apply_background(style: Res<Style>, mut clear: ResMut<ClearColor>) {
+    if style.is_changed() {
+        clear.0 = Color::srgb_from_array(style.background);
+    }
+}
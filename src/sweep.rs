@@ -0,0 +1,84 @@
+//! Sweeping one parameter or joint value back and forth over a range, so
+//! the frames depending on it can be watched as it changes.
+
+use std::f64::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::{NodeId, TransformTree};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SweepTarget {
+    /// A tree file parameter.
+    Param(String),
+    /// The value of a movable joint.
+    Joint(NodeId),
+}
+
+#[derive(Resource, Debug)]
+pub struct Sweep {
+    pub target: Option<SweepTarget>,
+    pub min: f64,
+    pub max: f64,
+    /// Seconds for one sweep from `min` to `max` and back.
+    pub period: f64,
+    pub playing: bool,
+    /// Seconds into the current period.
+    phase: f64,
+}
+
+impl Default for Sweep {
+    fn default() -> Self {
+        Sweep { target: None, min: 0.0, max: 1.0, period: 4.0, playing: false, phase: 0.0 }
+    }
+}
+
+impl Sweep {
+    /// Starts sweeping `target` over its joint range, or around its current
+    /// value for a parameter.
+    pub fn set_target(&mut self, dag: &TransformTree, target: SweepTarget) {
+        (self.min, self.max) = match &target {
+            SweepTarget::Joint(id) => dag.nodes[*id].joint.as_ref().map_or((-1.0, 1.0), |j| {
+                let (lo, hi) = j.range();
+                (lo as f64, hi as f64)
+            }),
+            SweepTarget::Param(name) => {
+                let value = dag.params().get(name).copied().unwrap_or_default();
+                let half = value.abs().max(1.0) * 0.5;
+                (value - half, value + half)
+            }
+        };
+        self.target = Some(target);
+        self.phase = 0.0;
+    }
+
+    /// Value at the current phase, easing in and out at both ends.
+    pub fn value(&self) -> f64 {
+        let s = (1.0 - (TAU * self.phase / self.period).cos()) / 2.0;
+        self.min + (self.max - self.min) * s
+    }
+}
+
+pub fn advance(time: Res<Time>, mut sweep: ResMut<Sweep>, mut dag: ResMut<TransformTree>) {
+    if !sweep.playing {
+        return;
+    }
+    let Some(target) = sweep.target.clone() else {
+        return;
+    };
+    let period = sweep.period.max(0.1);
+    sweep.phase = (sweep.phase + time.delta_secs_f64()) % period;
+    let value = sweep.value();
+    match target {
+        SweepTarget::Param(name) => {
+            if let Err(e) = dag.set_param(&name, value) {
+                eprintln!("sweep: {}", e);
+                sweep.playing = false;
+            }
+        }
+        SweepTarget::Joint(id) => {
+            dag.set_joint(id, value as f32);
+            dag.update_world();
+        }
+    }
+}
@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_egui::input::EguiWantsInput;
+
+use crate::style::Style;
+use crate::{NodeId, Selection, TransformTree};
+
+/// Keyframed local poses for one node, sorted by time.
+#[derive(Debug, Clone)]
+pub struct Track {
+    pub node: String,
+    pub times: Vec<f64>,
+    pub poses: Vec<Isometry3d>,
+}
+
+impl Track {
+    pub fn new(node: impl Into<String>) -> Self {
+        Track { node: node.into(), times: vec![], poses: vec![] }
+    }
+
+    pub fn push(&mut self, time: f64, pose: Isometry3d) {
+        self.times.push(time);
+        self.poses.push(pose);
+    }
+
+    /// Pose at `time`, interpolated between the surrounding keys and clamped to the ends.
+    pub fn sample(&self, time: f64) -> Option<Isometry3d> {
+        let i = self.times.partition_point(|&t| t <= time);
+        match i {
+            0 => self.poses.first().copied(),
+            i if i == self.times.len() => self.poses.last().copied(),
+            i => {
+                let (t0, t1) = (self.times[i - 1], self.times[i]);
+                let s = if t1 > t0 { ((time - t0) / (t1 - t0)) as f32 } else { 0.0 };
+                Some(interpolate(self.poses[i - 1], self.poses[i], s))
+            }
+        }
+    }
+}
+
+/// Lerp translation and slerp rotation between two poses.
+pub fn interpolate(a: Isometry3d, b: Isometry3d, s: f32) -> Isometry3d {
+    Isometry3d::new(a.translation.lerp(b.translation, s), a.rotation.slerp(b.rotation, s))
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Animation {
+    pub tracks: Vec<Track>,
+}
+
+impl Animation {
+    pub fn duration(&self) -> f64 {
+        self.tracks
+            .iter()
+            .filter_map(|t| t.times.last().copied())
+            .fold(0.0, f64::max)
+    }
+}
+
+impl Animation {
+    /// Every key time of every track, in order.
+    pub fn key_times(&self) -> Vec<f64> {
+        let mut times: Vec<f64> = self.tracks.iter().flat_map(|t| t.times.iter().copied()).collect();
+        times.sort_by(f64::total_cmp);
+        times.dedup();
+        times
+    }
+
+    /// Poses the tree through the animation, as CSV rows of time, frame,
+    /// position, quaternion and fixed-axis roll, pitch, yaw (radians). Poses are
+    /// in the world, or relative to `reference`; times are every key time, or
+    /// steps of `1 / rate` seconds over the whole range.
+    pub fn pose_csv(&self, dag: &TransformTree, frames: &[NodeId], reference: Option<NodeId>, rate: Option<f64>) -> String {
+        let times = match rate.filter(|r| *r > 0.0) {
+            Some(rate) => (0..=(self.duration() * rate).floor() as usize).map(|i| i as f64 / rate).collect(),
+            None => self.key_times(),
+        };
+        let mut dag = dag.clone();
+        let tracks: Vec<(NodeId, &Track)> = self.tracks.iter().filter_map(|t| Some((dag.find(&t.node)?, t))).collect();
+        let mut lines = vec!["time,frame,x,y,z,qx,qy,qz,qw,roll,pitch,yaw".to_string()];
+        for time in times {
+            for &(id, track) in &tracks {
+                if let Some(pose) = track.sample(time) {
+                    dag.set_local(id, pose);
+                }
+            }
+            dag.update_world();
+            for &id in frames {
+                let (t, q) = match reference {
+                    Some(reference) => {
+                        let pose = dag.relative(reference, id);
+                        (pose.translation.to_vec3().as_dvec3(), pose.rotation)
+                    }
+                    None => (dag.world_position(id), dag.nodes[id].world.rotation),
+                };
+                let (yaw, pitch, roll) = q.to_euler(EulerRot::ZYX);
+                lines.push(format!(
+                    "{},{},{},{},{},{},{},{},{},{},{},{}",
+                    time, csv_field(&dag.nodes[id].name), t.x, t.y, t.z, q.x, q.y, q.z, q.w, roll, pitch, yaw
+                ));
+            }
+        }
+        lines.join("\n") + "\n"
+    }
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) { format!("\"{}\"", text.replace('"', "\"\"")) } else { text.to_string() }
+}
+
+#[derive(Resource)]
+pub struct Timeline {
+    pub animation: Animation,
+    pub time: f64,
+    pub playing: bool,
+    pub rate: f64,
+    pub looping: bool,
+}
+
+impl Timeline {
+    pub fn new(animation: Animation) -> Self {
+        Timeline { animation, time: 0.0, playing: true, rate: 1.0, looping: true }
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.animation.duration()
+    }
+
+    pub fn seek(&mut self, time: f64) {
+        self.time = time.clamp(0.0, self.duration());
+    }
+}
+
+#[derive(Component)]
+pub struct TimelineHud;
+
+pub fn setup_hud(mut commands: Commands) {
+    commands.spawn((
+        TimelineHud,
+        Text::new(""),
+        TextFont { font_size: 16.0, ..default() },
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(8.0),
+            left: Val::Px(8.0),
+            ..default()
+        },
+    ));
+}
+
+/// Space toggles playback, arrows seek by a second, `L` toggles looping and
+/// `[`/`]` halve or double the playback rate.
+pub fn controls(keys: Res<ButtonInput<KeyCode>>, egui_input: Res<EguiWantsInput>, mut timeline: ResMut<Timeline>) {
+    if egui_input.wants_any_keyboard_input() {
+        return;
+    }
+    if keys.just_pressed(KeyCode::Space) {
+        timeline.playing = !timeline.playing;
+    }
+    if keys.just_pressed(KeyCode::ArrowLeft) {
+        let t = timeline.time - 1.0;
+        timeline.seek(t);
+    }
+    if keys.just_pressed(KeyCode::ArrowRight) {
+        let t = timeline.time + 1.0;
+        timeline.seek(t);
+    }
+    if keys.just_pressed(KeyCode::KeyL) {
+        timeline.looping = !timeline.looping;
+    }
+    if keys.just_pressed(KeyCode::BracketLeft) {
+        timeline.rate *= 0.5;
+    }
+    if keys.just_pressed(KeyCode::BracketRight) {
+        timeline.rate *= 2.0;
+    }
+}
+
+pub fn advance(time: Res<Time>, mut timeline: ResMut<Timeline>) {
+    if !timeline.playing {
+        return;
+    }
+    let duration = timeline.duration();
+    let mut t = timeline.time + time.delta_secs_f64() * timeline.rate;
+    if t > duration {
+        if timeline.looping && duration > 0.0 {
+            t %= duration;
+        } else {
+            t = duration;
+            timeline.playing = false;
+        }
+    }
+    timeline.time = t;
+}
+
+pub fn apply(timeline: Res<Timeline>, mut dag: ResMut<TransformTree>) {
+    if !timeline.is_changed() {
+        return;
+    }
+    for track in &timeline.animation.tracks {
+        if let (Some(id), Some(pose)) = (dag.find(&track.node), track.sample(timeline.time)) {
+            dag.set_local(id, pose);
+        }
+    }
+    dag.update_world();
+}
+
+pub fn update_hud(timeline: Res<Timeline>, mut hud_q: Query<&mut Text, With<TimelineHud>>) {
+    for mut text in &mut hud_q {
+        text.0 = format!(
+            "{} {:.2} / {:.2}s  x{}{}",
+            if timeline.playing { ">" } else { "||" },
+            timeline.time,
+            timeline.duration(),
+            timeline.rate,
+            if timeline.looping { "  loop" } else { "" },
+        );
+    }
+}
+
+/// Time offsets (seconds, usually negative) at which ghost copies of the
+/// selected frames are drawn during playback.
+#[derive(Resource, Default)]
+pub struct Ghosts {
+    pub offsets: Vec<f64>,
+}
+
+/// Draws the selected frames (or every frame when nothing is selected) as they
+/// were at each ghost offset, fading with distance from the current time.
+pub fn draw_ghosts(
+    timeline: Res<Timeline>,
+    ghosts: Res<Ghosts>,
+    selection: Res<Selection>,
+    dag: Res<TransformTree>,
+    style: Res<Style>,
+    mut gizmos: Gizmos,
+) {
+    if ghosts.offsets.is_empty() {
+        return;
+    }
+    let size = style.axis_scale;
+    let tracks: HashMap<&str, &Track> = timeline
+        .animation
+        .tracks
+        .iter()
+        .map(|t| (t.node.as_str(), t))
+        .collect();
+    let nodes: Vec<_> = if selection.nodes.is_empty() {
+        (0..dag.nodes.len()).collect()
+    } else {
+        selection.nodes.clone()
+    };
+
+    for (i, offset) in ghosts.offsets.iter().enumerate() {
+        let time = (timeline.time + offset).clamp(0.0, timeline.duration());
+        let alpha = 0.5 / (i + 1) as f32;
+        for &id in &nodes {
+            // Walk up to the root, sampling each ancestor's track at the ghost time.
+            let mut world = Isometry3d::IDENTITY;
+            let mut cur = Some(id);
+            while let Some(n) = cur {
+                let node = &dag.nodes[n];
+                let local = tracks
+                    .get(node.name.as_str())
+                    .and_then(|t| t.sample(time))
+                    .unwrap_or(node.local);
+                world = local * world;
+                cur = node.parent;
+            }
+            let o = world.translation.to_vec3();
+            gizmos.line(o, o + world.rotation * Vec3::X * size, Color::srgba(1.0, 0.0, 0.0, alpha));
+            gizmos.line(o, o + world.rotation * Vec3::Y * size, Color::srgba(0.0, 1.0, 0.0, alpha));
+            gizmos.line(o, o + world.rotation * Vec3::Z * size, Color::srgba(0.0, 0.0, 1.0, alpha));
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Markers at the end of each axis line, so the axes can be told apart
+//! without relying on color (e.g. screenshots printed in grayscale).
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AxisTips {
+    #[default]
+    None,
+    /// "X", "Y" and "Z" drawn facing the camera just past each tip.
+    Letters,
+    /// Arrowheads at each tip.
+    Arrows,
+}
+
+/// Letter strokes in a square from -1 to 1, x right and y up.
+const LETTERS: [&[(Vec2, Vec2)]; 3] = [
+    &[(Vec2::new(-1.0, -1.0), Vec2::new(1.0, 1.0)), (Vec2::new(-1.0, 1.0), Vec2::new(1.0, -1.0))],
+    &[
+        (Vec2::new(-1.0, 1.0), Vec2::ZERO),
+        (Vec2::new(1.0, 1.0), Vec2::ZERO),
+        (Vec2::ZERO, Vec2::new(0.0, -1.0)),
+    ],
+    &[
+        (Vec2::new(-1.0, 1.0), Vec2::new(1.0, 1.0)),
+        (Vec2::new(1.0, 1.0), Vec2::new(-1.0, -1.0)),
+        (Vec2::new(-1.0, -1.0), Vec2::new(1.0, -1.0)),
+    ],
+];
+
+impl AxisTips {
+    /// Draws the marker for axis `axis` (0 = X, 1 = Y, 2 = Z) of a frame at
+    /// `pose` with axes `size` long, with letters facing a camera at `camera`.
+    pub fn draw(self, gizmos: &mut Gizmos, pose: Isometry3d, axis: usize, size: f32, camera: &GlobalTransform, color: Color) {
+        let dir = pose.rotation * [Dir3::X, Dir3::Y, Dir3::Z][axis];
+        let tip = pose.translation.to_vec3() + dir * size;
+        match self {
+            AxisTips::None => {}
+            AxisTips::Letters => {
+                let half = size * 0.08;
+                let center = tip + dir * half * 2.0;
+                let (right, up) = (camera.right() * half, camera.up() * half);
+                for &(a, b) in LETTERS[axis] {
+                    gizmos.line(center + right * a.x + up * a.y, center + right * b.x + up * b.y, color);
+                }
+            }
+            AxisTips::Arrows => {
+                let back = tip - dir * size * 0.2;
+                let side = dir.any_orthonormal_vector() * size * 0.08;
+                let up = dir.cross(side);
+                for offset in [side, -side, up, -up] {
+                    gizmos.line(tip, back + offset, color);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use bevy::prelude::*;
+
+use crate::style::Style;
+use crate::timeline::interpolate;
+use crate::{Selection, TransformTree};
+
+/// Draws intermediate poses between the first two selected frames.
+#[derive(Resource, Debug)]
+pub struct InterpolationPreview {
+    pub enabled: bool,
+    /// Number of poses drawn strictly between the two frames.
+    pub steps: usize,
+}
+
+impl Default for InterpolationPreview {
+    fn default() -> Self {
+        InterpolationPreview { enabled: false, steps: 8 }
+    }
+}
+
+pub fn draw_interpolation(
+    preview: Res<InterpolationPreview>,
+    selection: Res<Selection>,
+    dag: Res<TransformTree>,
+    style: Res<Style>,
+    mut gizmos: Gizmos,
+) {
+    let (true, [a, b, ..]) = (preview.enabled, selection.nodes.as_slice()) else {
+        return;
+    };
+    let (a, b) = (dag.nodes[*a].world, dag.nodes[*b].world);
+    let size = style.axis_scale * 0.75;
+    let [x, y, z] = style.axis_colors().map(|c| c.with_alpha(0.4));
+    for i in 1..=preview.steps {
+        let pose = interpolate(a, b, i as f32 / (preview.steps + 1) as f32);
+        let o = pose.translation.to_vec3();
+        gizmos.line(o, o + pose.rotation * Vec3::X * size, x);
+        gizmos.line(o, o + pose.rotation * Vec3::Y * size, y);
+        gizmos.line(o, o + pose.rotation * Vec3::Z * size, z);
+    }
+    gizmos.line(a.translation.to_vec3(), b.translation.to_vec3(), Color::srgba(1.0, 1.0, 1.0, 0.2));
+}
@@ -0,0 +1,77 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::TransformTree;
+
+/// Linear (m/s) and angular (rad/s) velocity of a node, in the node's frame.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FileTwist {
+    #[serde(default)]
+    pub linear: [f64; 3],
+    #[serde(default)]
+    pub angular: [f64; 3],
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Twist {
+    pub linear: Vec3,
+    pub angular: Vec3,
+}
+
+impl From<&Twist> for FileTwist {
+    fn from(twist: &Twist) -> Self {
+        FileTwist { linear: twist.linear.as_dvec3().to_array(), angular: twist.angular.as_dvec3().to_array() }
+    }
+}
+
+impl From<&FileTwist> for Twist {
+    fn from(twist: &FileTwist) -> Self {
+        let [vx, vy, vz] = twist.linear;
+        let [wx, wy, wz] = twist.angular;
+        Twist {
+            linear: Vec3::new(vx as f32, vy as f32, vz as f32),
+            angular: Vec3::new(wx as f32, wy as f32, wz as f32),
+        }
+    }
+}
+
+/// Linear velocity as a straight arrow showing one second of travel; angular
+/// velocity as an arc around the rotation axis whose sweep is one second of
+/// rotation (capped just short of a full turn), arrowhead in the direction of spin.
+pub fn draw_twists(dag: Res<TransformTree>, mut gizmos: Gizmos) {
+    let radius = 0.1;
+    let segments = 24;
+
+    for node in dag.nodes.iter() {
+        let Some(twist) = node.twist else {
+            continue;
+        };
+        let o = node.world.translation.to_vec3();
+        let linear = node.world.rotation * twist.linear;
+        if linear.length_squared() > 1e-12 {
+            gizmos.arrow(o, o + linear, Color::srgb(0.0, 1.0, 1.0));
+        }
+
+        let angular = node.world.rotation * twist.angular;
+        let speed = angular.length();
+        if speed < 1e-6 {
+            continue;
+        }
+        let axis = angular / speed;
+        let u = axis.any_orthonormal_vector();
+        let v = axis.cross(u);
+        let sweep = speed.min(1.9 * PI);
+        let points: Vec<Vec3> = (0..=segments)
+            .map(|i| {
+                let a = sweep * i as f32 / segments as f32;
+                o + radius * (a.cos() * u + a.sin() * v)
+            })
+            .collect();
+        gizmos.line(o, o + axis * radius, Color::srgb(1.0, 0.0, 1.0));
+        gizmos.linestrip(points[..segments].iter().copied(), Color::srgb(1.0, 0.0, 1.0));
+        gizmos.arrow(points[segments - 1], points[segments], Color::srgb(1.0, 0.0, 1.0));
+    }
+}
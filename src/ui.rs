@@ -0,0 +1,1077 @@
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use bevy_egui::{EguiClipboard, EguiContexts, egui};
+use bevy_panorbit_camera::PanOrbitCamera;
+
+use crate::average::PoseSampler;
+use crate::bookmarks::{Bookmark, Bookmarks};
+use crate::camera::{MainCamera, Turntable};
+use crate::clip::ClipPlane;
+use crate::collision::{CollisionSettings, Overlaps};
+use crate::diff::{self, DiffTree, Heatmap};
+use crate::edit::{ANGLE_STEPS, Axis, EditSettings, MirrorPlane, TRANSLATION_STEPS};
+use crate::explode::ExplodedView;
+use crate::gamepad::GamepadBindings;
+use crate::grid::{GridPlane, GridSettings};
+use crate::groups::{self, CollapsedGroups};
+use crate::hud::HudSettings;
+use crate::ik::IkDrag;
+use crate::joint::Joint;
+use crate::links::{LinkColoring, LinkShape};
+use crate::lod::LodSettings;
+use crate::pip::{FrameView, Mount};
+use crate::recording::{self, Recorder};
+use crate::script::{self, ScriptConsole};
+use crate::smoothing::Smoothing;
+use crate::snippets::{self, PoseFormat};
+use crate::split::SplitView;
+use crate::stereo::Stereo;
+use crate::sweep::{Sweep, SweepTarget};
+use crate::style::{Palette, Style, Theme};
+use crate::tips::AxisTips;
+use crate::timeline::Timeline;
+use crate::tools::InterpolationPreview;
+use crate::units::Units;
+use crate::video::{self, VideoRender};
+use crate::workspace::Workspace;
+use crate::{FileTransformTree, FileTransformTreeError, NodeId, Selection, TransformTree, formats};
+
+/// Frame rate of turntable exports.
+const TURNTABLE_FPS: f64 = 30.0;
+
+/// One slider per movable joint, within its limits.
+pub fn joint_panel(mut contexts: EguiContexts, mut dag: ResMut<TransformTree>) -> Result {
+    let movable: Vec<NodeId> = (0..dag.nodes.len())
+        .filter(|&id| dag.nodes[id].joint.as_ref().is_some_and(Joint::is_movable))
+        .collect();
+    if movable.is_empty() {
+        return Ok(());
+    }
+
+    let mut changes = vec![];
+    egui::SidePanel::right("joints").show(contexts.ctx_mut()?, |ui| {
+        ui.heading("Joints");
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for &id in &movable {
+                let node = &dag.nodes[id];
+                let Some(joint) = node.joint.as_ref() else {
+                    continue;
+                };
+                let mut value = joint.value;
+                let (lo, hi) = joint.range();
+                if ui.add(egui::Slider::new(&mut value, lo..=hi).text(node.name.as_str())).changed() {
+                    changes.push((id, value));
+                }
+            }
+        });
+    });
+
+    if !changes.is_empty() {
+        for (id, value) in changes {
+            dag.set_joint(id, value);
+        }
+        dag.update_world();
+    }
+    Ok(())
+}
+
+/// One field per tree file parameter; frames whose `t` or `r` use it move as it changes.
+pub fn params_panel(mut contexts: EguiContexts, mut dag: ResMut<TransformTree>, mut error: Local<Option<String>>) -> Result {
+    if dag.params().is_empty() {
+        return Ok(());
+    }
+    let mut changed = None;
+    egui::Window::new("Parameters").show(contexts.ctx_mut()?, |ui| {
+        egui::Grid::new("params").show(ui, |ui| {
+            for (name, &value) in dag.params() {
+                let mut v = value;
+                ui.label(name);
+                if ui.add(egui::DragValue::new(&mut v).speed(0.01)).changed() {
+                    changed = Some((name.clone(), v));
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(error) = error.as_deref() {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+    });
+    if let Some((name, value)) = changed {
+        *error = dag.set_param(&name, value).err().map(|e| e.to_string());
+    }
+    Ok(())
+}
+
+/// Length unit in the bottom left corner, so a tree loaded in the wrong units
+/// is explained on sight.
+pub fn units_overlay(mut contexts: EguiContexts, dag: Res<TransformTree>) -> Result {
+    let text = match dag.units() {
+        Units::M => "Units: m".to_string(),
+        units => format!("Units: {} (shown in m)", units.as_str()),
+    };
+    egui::Area::new(egui::Id::new("units"))
+        .anchor(egui::Align2::LEFT_BOTTOM, egui::vec2(8.0, -8.0))
+        .interactable(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            ui.label(egui::RichText::new(text).small().weak());
+        });
+    Ok(())
+}
+
+/// Display settings that can be changed while running.
+pub fn view_panel(
+    mut contexts: EguiContexts,
+    mut grid: ResMut<GridSettings>,
+    mut style: ResMut<Style>,
+    mut lod: ResMut<LodSettings>,
+    mut split: ResMut<SplitView>,
+    mut stereo: ResMut<Stereo>,
+    mut exploded: ResMut<ExplodedView>,
+    mut collision: ResMut<CollisionSettings>,
+    mut hud: ResMut<HudSettings>,
+    mut gamepad: ResMut<GamepadBindings>,
+    overlaps: Res<Overlaps>,
+    dag: Res<TransformTree>,
+    mut turntable: ResMut<Turntable>,
+    video: Option<Res<VideoRender>>,
+    mut commands: Commands,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+    let dark = style.theme == Theme::Dark;
+    if ctx.style().visuals.dark_mode != dark {
+        ctx.set_visuals(if dark { egui::Visuals::dark() } else { egui::Visuals::light() });
+    }
+    egui::Window::new("View").default_open(false).show(ctx, |ui| {
+        ui.collapsing("Colors", |ui| {
+            let (mut theme, mut palette) = (style.theme, style.palette);
+            egui::ComboBox::from_label("Theme")
+                .selected_text(format!("{:?}", theme))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut theme, Theme::Dark, "Dark");
+                    ui.selectable_value(&mut theme, Theme::Light, "Light");
+                });
+            egui::ComboBox::from_label("Axis palette")
+                .selected_text(format!("{:?}", palette))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut palette, Palette::Standard, "Standard (RGB)");
+                    ui.selectable_value(&mut palette, Palette::Deuteranopia, "Deuteranopia safe");
+                });
+            if (theme, palette) != (style.theme, style.palette) {
+                *style = Style { axis_scale: style.axis_scale, links: style.links.clone(), tips: style.tips, ..Style::new(theme, palette) };
+            }
+        });
+        ui.collapsing("Axes", |ui| {
+            let mut edited = style.clone();
+            ui.add(egui::Slider::new(&mut edited.axis_scale, 0.001..=10.0).logarithmic(true).text("Axis length"));
+            egui::ComboBox::from_label("Axis tips")
+                .selected_text(format!("{:?}", edited.tips))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut edited.tips, AxisTips::None, "None");
+                    ui.selectable_value(&mut edited.tips, AxisTips::Letters, "X/Y/Z letters");
+                    ui.selectable_value(&mut edited.tips, AxisTips::Arrows, "Arrowheads");
+                });
+            for (name, color) in [
+                ("X", &mut edited.x),
+                ("Y", &mut edited.y),
+                ("Z", &mut edited.z),
+                ("Links", &mut edited.link),
+                ("Labels", &mut edited.label),
+                ("Background", &mut edited.background),
+            ] {
+                ui.horizontal(|ui| {
+                    ui.color_edit_button_rgb(color);
+                    ui.label(name);
+                });
+            }
+            if edited != *style {
+                *style = edited;
+            }
+        });
+        ui.collapsing("Links", |ui| {
+            let mut links = style.links.clone();
+            egui::ComboBox::from_label("Shape")
+                .selected_text(format!("{:?}", links.shape))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut links.shape, LinkShape::Straight, "Straight");
+                    ui.selectable_value(&mut links.shape, LinkShape::Elbow, "Elbow");
+                    ui.selectable_value(&mut links.shape, LinkShape::Curved, "Curved");
+                });
+            egui::ComboBox::from_label("Color")
+                .selected_text(format!("{:?}", links.coloring))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut links.coloring, LinkColoring::Uniform, "Uniform");
+                    ui.selectable_value(&mut links.coloring, LinkColoring::Depth, "By depth");
+                    ui.selectable_value(&mut links.coloring, LinkColoring::Subtree, "By subtree");
+                });
+            ui.checkbox(&mut links.dashed, "Dashed");
+            ui.checkbox(&mut links.arrows, "Arrowheads (parent to child)");
+            if links != style.links {
+                style.links = links;
+            }
+        });
+        ui.collapsing("Grid", |ui| {
+            let mut settings = grid.clone();
+            ui.checkbox(&mut settings.enabled, "Show grid");
+            ui.add(egui::Slider::new(&mut settings.spacing, 0.01..=100.0).logarithmic(true).text("Spacing"));
+            ui.add(egui::Slider::new(&mut settings.count, 1..=200).text("Cells"));
+            egui::ComboBox::from_label("Plane")
+                .selected_text(format!("{:?}", settings.plane))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut settings.plane, GridPlane::Xz, "XZ (Y up)");
+                    ui.selectable_value(&mut settings.plane, GridPlane::Xy, "XY (Z up)");
+                });
+            ui.horizontal(|ui| {
+                ui.label("Color");
+                ui.color_edit_button_rgb(&mut settings.color);
+            });
+            if settings != *grid {
+                *grid = settings;
+            }
+        });
+        ui.collapsing("Overlays", |ui| {
+            let mut settings = hud.clone();
+            ui.checkbox(&mut settings.compass, "World axis compass");
+            ui.checkbox(&mut settings.scale_bar, "Scale bar at the focus");
+            ui.checkbox(&mut settings.stats, "Frame statistics (F3)");
+            ui.checkbox(&mut settings.minimap, "Top-down mini-map (F4)");
+            if settings != *hud {
+                *hud = settings;
+            }
+        });
+        ui.collapsing("Layout", |ui| {
+            let mut panes = split.panes;
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut panes, 1, "Single");
+                ui.selectable_value(&mut panes, 2, "2 panes");
+                ui.selectable_value(&mut panes, 3, "3 panes");
+                ui.selectable_value(&mut panes, 4, "4 panes");
+            });
+            ui.label("Extra panes show top, front and side views.");
+            if panes != split.panes {
+                split.panes = panes;
+            }
+            let mut settings = stereo.clone();
+            ui.checkbox(&mut settings.enabled, "Side-by-side stereo");
+            let mut millimeters = settings.eye_separation * 1000.0;
+            if ui.add(egui::Slider::new(&mut millimeters, 40.0..=80.0).text("Eye separation (mm)")).changed() {
+                settings.eye_separation = millimeters / 1000.0;
+            }
+            ui.checkbox(&mut settings.swap, "Swap eyes (cross-eyed viewing)");
+            if settings != *stereo {
+                *stereo = settings;
+            }
+        });
+        ui.collapsing("Exploded view", |ui| {
+            let mut distance = exploded.distance;
+            ui.add(egui::Slider::new(&mut distance, 0.0..=2.0).text("Distance from parent (m)"));
+            ui.label("Pulls apart nearly coincident frames; dashed lines lead back to their true positions.");
+            if distance != exploded.distance {
+                exploded.distance = distance;
+            }
+        });
+        ui.collapsing("Gamepad", |ui| {
+            let mut settings = gamepad.clone();
+            ui.add(egui::Slider::new(&mut settings.orbit_speed, 0.1..=10.0).logarithmic(true).text("Orbit speed (rad/s)"));
+            ui.add(egui::Slider::new(&mut settings.fly_speed, 0.1..=10.0).logarithmic(true).text("Fly speed (radii/s)"));
+            ui.add(egui::Slider::new(&mut settings.zoom_speed, 0.1..=10.0).logarithmic(true).text("Zoom speed"));
+            ui.add(egui::Slider::new(&mut settings.deadzone, 0.0..=0.5).text("Dead zone"));
+            ui.checkbox(&mut settings.invert_y, "Invert orbit Y");
+            ui.label("Button and stick bindings are kept in axisviz.toml.");
+            if settings != *gamepad {
+                *gamepad = settings;
+            }
+        });
+        ui.collapsing("Turntable", |ui| {
+            let mut seconds = turntable.seconds;
+            ui.add(egui::Slider::new(&mut seconds, 2.0..=60.0).text("Seconds per turn"));
+            if seconds != turntable.seconds {
+                turntable.seconds = seconds;
+            }
+            let mut export = turntable.export.clone();
+            ui.horizontal(|ui| {
+                ui.label("Export to");
+                ui.text_edit_singleline(&mut export).on_hover_text("A video or .gif, or a directory for PNG frames");
+            });
+            if export != turntable.export {
+                turntable.export = export;
+            }
+            if let Some(video) = &video {
+                ui.label(format!("Exporting frame {} of {}", video.progress() + 1, video.frames));
+                return;
+            }
+            ui.horizontal(|ui| {
+                if turntable.spinning() {
+                    if ui.button("Stop").clicked() {
+                        turntable.stop();
+                    }
+                } else if ui.button("Spin").clicked() {
+                    turntable.start();
+                }
+                if ui.add_enabled(!turntable.export.is_empty(), egui::Button::new("Export")).clicked() {
+                    let path = PathBuf::from(&turntable.export);
+                    let (dir, encode) = if video::is_video(&path) { (video::scratch_dir(), Some(path)) } else { (path, None) };
+                    if let Err(e) = std::fs::create_dir_all(&dir) {
+                        eprintln!("{}: {}", dir.display(), e);
+                        return;
+                    }
+                    let frames = video::turntable_frames(turntable.seconds as f64, TURNTABLE_FPS);
+                    turntable.start();
+                    let mut render = VideoRender::from_window(video::Output::Frames(dir), TURNTABLE_FPS, frames);
+                    render.encode = encode;
+                    commands.insert_resource(render);
+                }
+            });
+        });
+        ui.collapsing("Level of detail", |ui| {
+            let mut settings = lod.clone();
+            ui.checkbox(&mut settings.enabled, "Hide labels and spheres of tiny frames");
+            ui.add(egui::Slider::new(&mut settings.max_on_screen, 10..=5000).logarithmic(true).text("Above frames on screen"));
+            ui.add(egui::Slider::new(&mut settings.min_pixels, 1.0..=100.0).text("Min size (px)"));
+            if settings != *lod {
+                *lod = settings;
+            }
+        });
+        ui.collapsing("Collision", |ui| {
+            let mut shown = collision.shown;
+            ui.checkbox(&mut shown, "Show collision primitives");
+            if shown != collision.shown {
+                collision.shown = shown;
+            }
+            if overlaps.pairs.is_empty() {
+                ui.label("No overlapping frames");
+            } else {
+                ui.colored_label(egui::Color32::RED, format!("{} overlapping pairs", overlaps.pairs.len()));
+                egui::ScrollArea::vertical().max_height(150.0).show(ui, |ui| {
+                    for &(a, b) in &overlaps.pairs {
+                        ui.label(format!("{} ↔ {}", dag.nodes[a].name, dag.nodes[b].name));
+                    }
+                });
+            }
+        });
+    });
+    Ok(())
+}
+
+pub fn bookmark_panel(
+    mut contexts: EguiContexts,
+    mut bookmarks: ResMut<Bookmarks>,
+    mut camera_q: Query<&mut PanOrbitCamera, With<MainCamera>>,
+    mut name: Local<String>,
+) -> Result {
+    let Ok(mut camera) = camera_q.single_mut() else {
+        return Ok(());
+    };
+    egui::Window::new("Bookmarks").default_open(false).show(contexts.ctx_mut()?, |ui| {
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut *name);
+            if ui.button("Save view").clicked() && !name.trim().is_empty() {
+                bookmarks.insert(Bookmark::capture(name.trim().to_string(), &camera));
+                name.clear();
+            }
+        });
+        let mut remove = None;
+        for (i, bookmark) in bookmarks.views.iter().enumerate() {
+            ui.horizontal(|ui| {
+                let key = if i < 9 { format!("{} ", i + 1) } else { "  ".to_string() };
+                if ui.button(format!("{}{}", key, bookmark.name)).clicked() {
+                    bookmark.apply(&mut camera);
+                }
+                if ui.small_button("x").clicked() {
+                    remove = Some(i);
+                }
+            });
+        }
+        if let Some(i) = remove {
+            bookmarks.views.remove(i);
+        }
+    });
+    Ok(())
+}
+
+/// Frame list with per-frame visibility toggles, a tag filter and the tags and
+/// metadata of the selected frame.
+pub fn frames_panel(
+    mut contexts: EguiContexts,
+    mut dag: ResMut<TransformTree>,
+    selection: Res<Selection>,
+    edit: Res<EditSettings>,
+    mut collapsed: ResMut<CollapsedGroups>,
+    mut clipboard: Option<ResMut<EguiClipboard>>,
+    mut tag_filter: Local<Option<String>>,
+    mut paste_status: Local<Option<String>>,
+) -> Result {
+    let mut toggled = None;
+    let mut pasted = None;
+    let mut optical = None;
+    let mut filter = tag_filter.clone();
+    egui::Window::new("Frames").default_open(false).show(contexts.ctx_mut()?, |ui| {
+        let tags: BTreeSet<&String> = dag.nodes.iter().flat_map(|n| &n.tags).collect();
+        if !tags.is_empty() {
+            egui::ComboBox::from_label("Show only tag")
+                .selected_text(filter.as_deref().unwrap_or("All frames"))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut filter, None, "All frames");
+                    for tag in tags {
+                        ui.selectable_value(&mut filter, Some(tag.clone()), tag.as_str());
+                    }
+                });
+        }
+        let namespaces = groups::namespaces(&dag);
+        if !namespaces.is_empty() {
+            ui.collapsing("Groups", |ui| {
+                for ns in namespaces {
+                    let depth = ns.matches('/').count();
+                    let mut folded = collapsed.0.contains(&ns);
+                    ui.horizontal(|ui| {
+                        ui.add_space(depth as f32 * 12.0);
+                        if ui.checkbox(&mut folded, format!("Collapse {}", ns)).changed() {
+                            if folded {
+                                collapsed.0.insert(ns.clone());
+                            } else {
+                                collapsed.0.remove(&ns);
+                            }
+                        }
+                    });
+                }
+            });
+        }
+        if let Some(id) = selection.primary()
+            && let Some(node) = dag.nodes.get(id)
+        {
+            ui.collapsing(format!("Selected: {}", node.name), |ui| {
+                let t = node.local.translation;
+                let (roll, pitch, yaw) = node.local.rotation.to_euler(EulerRot::XYZ);
+                ui.label(format!("t: {:.3} {:.3} {:.3}", t.x, t.y, t.z));
+                ui.label(format!("r: {:.1}° {:.1}° {:.1}°", roll.to_degrees(), pitch.to_degrees(), yaw.to_degrees()));
+                let world = dag.world_position(id);
+                ui.label(format!("world: {:.3} {:.3} {:.3}", world.x, world.y, world.z));
+                if node.world_scale != Vec3::ONE {
+                    let s = node.world_scale;
+                    ui.colored_label(egui::Color32::YELLOW, format!("⚠ scaled {:.3} {:.3} {:.3}: not a rigid frame", s.x, s.y, s.z))
+                        .on_hover_text("Scale stretches the offsets of the children; distances measured across it are in scaled units.");
+                }
+                if let Some(geo) = dag.geodetic(id) {
+                    ui.label(format!("lat {:.7}° lon {:.7}° alt {:.2} m", geo.lat, geo.lon, geo.alt));
+                }
+                if edit.enabled {
+                    let state = if edit.snapping() { "Snapping" } else { "Ctrl snaps" };
+                    ui.label(format!("{} to {} cm / {}°", state, edit.translation_step * 100.0, edit.angle_step));
+                }
+                ui.menu_button("Copy as…", |ui| {
+                    for format in PoseFormat::ALL {
+                        if ui.button(format.name()).clicked() {
+                            ui.ctx().copy_text(snippets::pose_text(&dag, id, format));
+                            ui.close();
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    let paste = ui.add_enabled(clipboard.is_some(), egui::Button::new("Paste pose"));
+                    if paste.clicked()
+                        && let Some(text) = clipboard.as_mut().and_then(|c| c.get_text())
+                    {
+                        let world = ui.input(|i| i.modifiers.shift);
+                        match snippets::parse_pose(&text) {
+                            Ok((pose, kind)) => {
+                                pasted = Some((id, pose, world));
+                                let target = if world { "world" } else { "local" };
+                                *paste_status = Some(format!("Pasted {} as {} pose", kind, target));
+                            }
+                            Err(e) => *paste_status = Some(format!("Not a pose: {}", e)),
+                        }
+                    }
+                    paste.on_hover_text("Sets the local pose; Shift+click sets the world pose");
+                });
+                if let Some(status) = paste_status.as_deref() {
+                    ui.label(status);
+                }
+                ui.horizontal(|ui| {
+                    let mut flag = node.optical;
+                    if ui.checkbox(&mut flag, "Optical frame").on_hover_text("Z forward, X right, Y down").changed() {
+                        optical = Some((id, Some(flag)));
+                    }
+                    let free = dag.find(&format!("{}_optical", node.name)).is_none();
+                    if !node.optical && ui.add_enabled(free, egui::Button::new("Add optical child")).clicked() {
+                        optical = Some((id, None));
+                    }
+                });
+                if !node.aliases.is_empty() {
+                    ui.label(format!("Aliases: {}", node.aliases.join(", ")));
+                }
+                if !node.tags.is_empty() {
+                    ui.label(format!("Tags: {}", node.tags.join(", ")));
+                }
+                egui::Grid::new("metadata").striped(true).show(ui, |ui| {
+                    for (key, value) in &node.metadata {
+                        ui.label(key);
+                        ui.label(value.to_string());
+                        ui.end_row();
+                    }
+                });
+            });
+        }
+        ui.separator();
+        let row_height = ui.text_style_height(&egui::TextStyle::Body);
+        // Collapsed namespaces show as a single row for their representative frame.
+        let listed: Vec<NodeId> = (0..dag.nodes.len()).filter(|&id| !dag.nodes[id].collapsed).collect();
+        egui::ScrollArea::vertical().show_rows(ui, row_height, listed.len(), |ui, rows| {
+            for id in rows.map(|row| listed[row]) {
+                let node = &dag.nodes[id];
+                let mut shown = !node.hidden;
+                let mut text = node.group_summary.as_deref().unwrap_or(node.name.as_str()).to_string();
+                if node.scale != Vec3::ONE {
+                    text.push_str(" ⚠");
+                }
+                if ui.checkbox(&mut shown, text).changed() {
+                    toggled = Some(id);
+                }
+            }
+        });
+    });
+    if let Some(id) = toggled {
+        dag.nodes[id].hidden = !dag.nodes[id].hidden;
+    }
+    match optical {
+        Some((id, Some(flag))) => dag.nodes[id].optical = flag,
+        Some((id, None)) => {
+            if let Err(e) = dag.add_optical_frame(id) {
+                *paste_status = Some(e.to_string());
+            }
+        }
+        None => {}
+    }
+    if let Some((id, pose, world)) = pasted {
+        if world {
+            dag.set_world(id, pose);
+        } else {
+            dag.edit_local(id, pose);
+            dag.update_world();
+        }
+    }
+    if filter != *tag_filter {
+        for node in &mut dag.nodes {
+            node.hidden = filter.as_ref().is_some_and(|tag| !node.tags.contains(tag));
+        }
+        *tag_filter = filter;
+    }
+    Ok(())
+}
+
+/// Names and parent for copies made with "Duplicate".
+pub struct DuplicateForm {
+    prefix: String,
+    suffix: String,
+    /// Parent of the copy; `None` keeps the original's parent.
+    parent: Option<NodeId>,
+    error: Option<String>,
+}
+
+impl Default for DuplicateForm {
+    fn default() -> Self {
+        DuplicateForm { prefix: String::new(), suffix: "_copy".to_string(), parent: None, error: None }
+    }
+}
+
+/// X/Y/Z toggle for picking a frame axis.
+fn axis_picker(ui: &mut egui::Ui, id: &str, axis: &mut Axis) {
+    ui.push_id(id, |ui| {
+        for a in Axis::ALL {
+            ui.selectable_value(axis, a, format!("{:?}", a));
+        }
+    });
+}
+
+pub fn tools_panel(
+    mut contexts: EguiContexts,
+    mut interpolation: ResMut<InterpolationPreview>,
+    recorder: Option<ResMut<Recorder>>,
+    mut smoothing: ResMut<Smoothing>,
+    mut frame_view: ResMut<FrameView>,
+    mut ik: ResMut<IkDrag>,
+    mut edit: ResMut<EditSettings>,
+    mut workspace: ResMut<Workspace>,
+    selection: Res<Selection>,
+    mut dag: ResMut<TransformTree>,
+    mut constraint: Local<(Axis, Axis, f32)>,
+    mut mirror: Local<MirrorPlane>,
+    mut duplicate: Local<DuplicateForm>,
+    mut sweep: ResMut<Sweep>,
+    mut sampler: ResMut<PoseSampler>,
+    mut export: Local<Option<String>>,
+) -> Result {
+    egui::Window::new("Tools").default_open(false).show(contexts.ctx_mut()?, |ui| {
+        ui.collapsing("Move frames", |ui| {
+            ui.label("Drag a frame to move it, or with the right button to turn it. Hold Ctrl to snap.");
+            let current = (edit.enabled, edit.translation_step, edit.angle_step);
+            let (mut enabled, mut step, mut angle) = current;
+            ui.checkbox(&mut enabled, "Drag to move");
+            egui::ComboBox::from_label("Grid")
+                .selected_text(format!("{} cm", step * 100.0))
+                .show_ui(ui, |ui| {
+                    for s in TRANSLATION_STEPS {
+                        ui.selectable_value(&mut step, s, format!("{} cm", s * 100.0));
+                    }
+                });
+            egui::ComboBox::from_label("Angle")
+                .selected_text(format!("{}°", angle))
+                .show_ui(ui, |ui| {
+                    for a in ANGLE_STEPS {
+                        ui.selectable_value(&mut angle, a, format!("{}°", a));
+                    }
+                });
+            if (enabled, step, angle) != current {
+                edit.enabled = enabled;
+                edit.translation_step = step;
+                edit.angle_step = angle;
+                if enabled {
+                    ik.enabled = false;
+                }
+            }
+        });
+        ui.collapsing("Constraints", |ui| {
+            let pair = selection.primary().zip(selection.target());
+            let Some((id, target)) = pair else {
+                ui.label("Select the target frame, then Shift+click the frame to move.");
+                return;
+            };
+            ui.label(format!("Moves {} relative to {}", dag.nodes[id].name, dag.nodes[target].name));
+            let (axis, target_axis, distance) = &mut *constraint;
+            ui.horizontal(|ui| {
+                axis_picker(ui, "axis", axis);
+                ui.label("along target");
+                axis_picker(ui, "target axis", target_axis);
+            });
+            if ui.button("Align axis").clicked() {
+                dag.align_axis(id, *axis, target, *target_axis);
+            }
+            if ui.button("Make coincident").clicked() {
+                dag.make_coincident(id, target);
+            }
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(distance).speed(0.01).suffix(" m"));
+                if ui.button("Set distance along target axis").clicked() {
+                    dag.set_distance(id, target, *target_axis, *distance);
+                }
+            });
+        });
+        ui.collapsing("Mirror", |ui| {
+            let Some(id) = selection.primary() else {
+                ui.label("Select the root of the subtree to mirror; Shift+click a second frame to mirror across its planes instead of the world's.");
+                return;
+            };
+            let reference = selection.target();
+            ui.horizontal(|ui| {
+                for plane in MirrorPlane::ALL {
+                    ui.selectable_value(&mut *mirror, plane, format!("{:?}", plane).to_uppercase());
+                }
+            });
+            let plane = format!("{:?}", *mirror).to_uppercase();
+            let across = reference.map_or("world", |r| dag.nodes[r].name.as_str()).to_string();
+            if ui.button(format!("Mirror {} across {} plane of {}", dag.nodes[id].name, plane, across)).clicked() {
+                dag.mirror_subtree(id, reference, *mirror);
+            }
+        });
+        ui.collapsing("Duplicate", |ui| {
+            let Some(id) = selection.primary() else {
+                ui.label("Select the root of the subtree to copy.");
+                return;
+            };
+            let form = &mut *duplicate;
+            ui.horizontal(|ui| {
+                ui.label("Prefix");
+                ui.text_edit_singleline(&mut form.prefix);
+            });
+            ui.horizontal(|ui| {
+                ui.label("Suffix");
+                ui.text_edit_singleline(&mut form.suffix);
+            });
+            egui::ComboBox::from_label("Attach to")
+                .selected_text(form.parent.map_or("Same parent", |p| dag.nodes[p].name.as_str()))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut form.parent, None, "Same parent");
+                    for (p, node) in dag.nodes.iter().enumerate() {
+                        ui.selectable_value(&mut form.parent, Some(p), node.name.as_str());
+                    }
+                });
+            if ui.button(format!("Duplicate {} and its children", dag.nodes[id].name)).clicked() {
+                let parent = form.parent.or(dag.nodes[id].parent);
+                form.error = match dag.duplicate_subtree(id, parent, &form.prefix, &form.suffix) {
+                    Ok(_) => None,
+                    Err(FileTransformTreeError::Duplicate(name)) => Some(format!("A frame named {} already exists", name)),
+                    Err(e) => Some(e.to_string()),
+                };
+            }
+            if let Some(error) = &form.error {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+        });
+        ui.collapsing("Sweep", |ui| {
+            ui.label("Moves a parameter or joint back and forth so everything depending on it can be watched.");
+            let name = |target: &SweepTarget| match target {
+                SweepTarget::Param(name) => name.clone(),
+                SweepTarget::Joint(id) => format!("joint {}", dag.nodes[*id].name),
+            };
+            let mut targets: Vec<SweepTarget> = dag.params().keys().cloned().map(SweepTarget::Param).collect();
+            targets.extend(
+                (0..dag.nodes.len())
+                    .filter(|&id| dag.nodes[id].joint.as_ref().is_some_and(Joint::is_movable))
+                    .map(SweepTarget::Joint),
+            );
+            if targets.is_empty() {
+                ui.label("The tree has no parameters or movable joints.");
+                return;
+            }
+            let mut picked = sweep.target.clone();
+            egui::ComboBox::from_label("Sweep")
+                .selected_text(picked.as_ref().map_or("Nothing".to_string(), name))
+                .show_ui(ui, |ui| {
+                    for target in targets {
+                        let text = name(&target);
+                        ui.selectable_value(&mut picked, Some(target), text);
+                    }
+                });
+            if picked != sweep.target
+                && let Some(target) = picked
+            {
+                sweep.set_target(&dag, target);
+            }
+            let current = (sweep.min, sweep.max, sweep.period);
+            let (mut min, mut max, mut period) = current;
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut min).speed(0.01).prefix("from "));
+                ui.add(egui::DragValue::new(&mut max).speed(0.01).prefix("to "));
+            });
+            ui.add(egui::Slider::new(&mut period, 0.5..=30.0).logarithmic(true).text("Period (s)"));
+            if (min, max, period) != current {
+                (sweep.min, sweep.max, sweep.period) = (min, max, period);
+            }
+            let label = if sweep.playing { "Stop" } else { "Play" };
+            if ui.add_enabled(sweep.target.is_some(), egui::Button::new(label)).clicked() {
+                sweep.playing = !sweep.playing;
+            }
+        });
+        ui.collapsing("Inverse kinematics", |ui| {
+            ui.label("Experimental: drag a frame below movable joints to solve for joint values that reach the pointer.");
+            let mut enabled = ik.enabled;
+            ui.checkbox(&mut enabled, "Drag to solve");
+            if enabled != ik.enabled {
+                ik.enabled = enabled;
+                if enabled {
+                    edit.enabled = false;
+                }
+            }
+        });
+        ui.collapsing("Workspace", |ui| {
+            ui.label("Samples the joints above the selected frame and shows every position it can reach.");
+            let mut samples = workspace.samples;
+            ui.add(egui::Slider::new(&mut samples, 500..=50_000).logarithmic(true).text("Samples"));
+            if samples != workspace.samples {
+                workspace.samples = samples;
+            }
+            let reachable = selection.primary().filter(|&id| !crate::ik::chain(&dag, id).is_empty());
+            ui.horizontal(|ui| {
+                if ui.add_enabled(reachable.is_some(), egui::Button::new("Sample selected")).clicked()
+                    && let Some(id) = reachable
+                {
+                    workspace.sample(&dag, id);
+                }
+                if ui.button("Clear").clicked() {
+                    workspace.clear();
+                }
+            });
+            if let Some(node) = workspace.node.and_then(|id| dag.nodes.get(id)) {
+                ui.label(format!("{} positions of {}", workspace.len(), node.name));
+            }
+        });
+        ui.collapsing("Camera view", |ui| {
+            ui.label("Shows an inset rendered from a frame, as if a camera were mounted there.");
+            match frame_view.node.and_then(|id| dag.nodes.get(id)) {
+                Some(node) => ui.label(format!("Viewing from {}", node.name)),
+                None => ui.label("Not attached"),
+            };
+            ui.horizontal(|ui| {
+                if ui.add_enabled(selection.primary().is_some(), egui::Button::new("Attach to selected")).clicked() {
+                    frame_view.node = selection.primary();
+                }
+                if ui.button("Detach").clicked() {
+                    frame_view.node = None;
+                }
+            });
+            let current = (frame_view.mount, frame_view.size, frame_view.fov.to_degrees());
+            let (mut mount, mut size, mut fov) = current;
+            egui::ComboBox::from_label("Looks along")
+                .selected_text(match mount {
+                    Mount::Optical => "+Z (optical)",
+                    Mount::Body => "+X (body)",
+                })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut mount, Mount::Optical, "+Z (optical)");
+                    ui.selectable_value(&mut mount, Mount::Body, "+X (body)");
+                });
+            ui.add(egui::Slider::new(&mut fov, 10.0..=150.0).text("Vertical FOV (°)"));
+            ui.add(egui::Slider::new(&mut size, 0.1..=0.6).text("Inset size"));
+            if (mount, size, fov) != current {
+                frame_view.mount = mount;
+                frame_view.size = size;
+                frame_view.fov = fov.to_radians();
+            }
+        });
+        ui.collapsing("Stream smoothing", |ui| {
+            ui.label("Eases live updates in over a time constant; 0 shows them immediately.");
+            let mut tau = smoothing.time_constant;
+            ui.add(egui::Slider::new(&mut tau, 0.0..=2.0).text("Time constant (s)"));
+            if tau != smoothing.time_constant {
+                smoothing.time_constant = tau;
+            }
+        });
+        ui.collapsing("Pose averaging", |ui| {
+            ui.label("Averages streamed poses of each frame; the spread is drawn as an ellipsoid.");
+            let (mut active, mut window) = (sampler.active, sampler.window);
+            ui.checkbox(&mut active, "Sampling");
+            ui.add(egui::DragValue::new(&mut window).speed(0.1).range(0.0..=3600.0).prefix("Window ").suffix(" s (0: all)"));
+            if (active, window) != (sampler.active, sampler.window) {
+                sampler.active = active;
+                sampler.window = window;
+            }
+            let (frames, samples) = sampler.count();
+            ui.label(format!("{} samples of {} frames", samples, frames));
+            ui.horizontal(|ui| {
+                if ui.add_enabled(frames > 0, egui::Button::new("Add averaged frames")).clicked() {
+                    for (name, mean) in sampler.means() {
+                        let parent = dag.find(&name).and_then(|id| dag.nodes[id].parent).map(|p| dag.nodes[p].name.clone());
+                        dag.apply(&mean.file_node(format!("{}_avg", name), parent));
+                    }
+                    dag.update_world();
+                }
+                if ui.button("Clear").clicked() {
+                    sampler.clear();
+                }
+            });
+        });
+        if let Some(mut recorder) = recorder {
+            ui.collapsing("Recording", |ui| match recorder.path().map(|p| p.display().to_string()) {
+                Some(path) => {
+                    ui.label(format!("Recording live updates to {}", path));
+                    if ui.button("Stop").clicked() {
+                        recorder.stop();
+                    }
+                }
+                None => {
+                    ui.label("Saves live updates to a file the timeline can replay.");
+                    if ui.button("Record").clicked()
+                        && let Err(e) = recorder.start(recording::default_path(), &dag)
+                    {
+                        eprintln!("record: {}", e);
+                    }
+                }
+            });
+        }
+        ui.collapsing("Export tree", |ui| {
+            let path = export.get_or_insert_with(|| "frames.launch.py".to_string());
+            ui.horizontal(|ui| {
+                ui.label("Save to");
+                ui.text_edit_singleline(path)
+                    .on_hover_text("A ROS 2 launch file (.py), a shell script (.sh), or any other format by its extension");
+            });
+            if ui.add_enabled(!path.is_empty(), egui::Button::new("Export")).clicked() {
+                let path = PathBuf::from(&*path);
+                let written = formats::write(&formats::extension(&path), &FileTransformTree::from(&*dag))
+                    .and_then(|text| Ok(std::fs::write(&path, text)?));
+                if let Err(e) = written {
+                    eprintln!("{}: {}", path.display(), e);
+                }
+            }
+        });
+        ui.collapsing("Interpolation preview", |ui| {
+            ui.label("Shows poses between the first two selected frames.");
+            let (mut enabled, mut steps) = (interpolation.enabled, interpolation.steps);
+            ui.checkbox(&mut enabled, "Enabled");
+            ui.add(egui::Slider::new(&mut steps, 1..=64).text("Steps"));
+            if (enabled, steps) != (interpolation.enabled, interpolation.steps) {
+                interpolation.enabled = enabled;
+                interpolation.steps = steps;
+            }
+        });
+    });
+    Ok(())
+}
+
+/// Playback controls, shown when an animation or recording is loaded.
+pub fn timeline_panel(
+    mut contexts: EguiContexts,
+    timeline: Option<ResMut<Timeline>>,
+    dag: Res<TransformTree>,
+    selection: Res<Selection>,
+) -> Result {
+    let Some(mut timeline) = timeline else {
+        return Ok(());
+    };
+    egui::Window::new("Timeline").show(contexts.ctx_mut()?, |ui| {
+        ui.horizontal(|ui| {
+            if ui.button(if timeline.playing { "Pause" } else { "Play" }).clicked() {
+                timeline.playing = !timeline.playing;
+            }
+            let mut looping = timeline.looping;
+            if ui.checkbox(&mut looping, "Loop").changed() {
+                timeline.looping = looping;
+            }
+        });
+        let mut time = timeline.time;
+        let duration = timeline.duration();
+        if ui.add(egui::Slider::new(&mut time, 0.0..=duration).text("Time (s)")).changed() {
+            timeline.seek(time);
+        }
+        let mut rate = timeline.rate;
+        if ui.add(egui::Slider::new(&mut rate, 0.0625..=16.0).logarithmic(true).text("Rate")).changed() {
+            timeline.rate = rate;
+        }
+        if ui.button("Export CSV").on_hover_text("World poses of the selected frames, or all, at every key time").clicked() {
+            let frames: Vec<NodeId> =
+                if selection.nodes.is_empty() { (0..dag.nodes.len()).collect() } else { selection.nodes.clone() };
+            let path = recording::default_path().with_extension("csv");
+            if let Err(e) = std::fs::write(&path, timeline.animation.pose_csv(&dag, &frames, None, None)) {
+                eprintln!("{}: {}", path.display(), e);
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Clipping plane placement: drag the offset along the normal, or snap the
+/// plane to an axis, the view or the selected frame.
+pub fn section_panel(
+    mut contexts: EguiContexts,
+    mut clip: ResMut<ClipPlane>,
+    dag: Res<TransformTree>,
+    selection: Res<Selection>,
+    camera_q: Query<&GlobalTransform, With<MainCamera>>,
+) -> Result {
+    egui::Window::new("Section").default_open(false).show(contexts.ctx_mut()?, |ui| {
+        let mut edited = clip.clone();
+        ui.checkbox(&mut edited.enabled, "Clip everything beyond the plane");
+        ui.checkbox(&mut edited.show, "Show the plane");
+        ui.horizontal(|ui| {
+            ui.label("Normal");
+            for (name, axis) in [("X", Vec3::X), ("Y", Vec3::Y), ("Z", Vec3::Z)] {
+                if ui.button(name).clicked() {
+                    edited.normal = axis;
+                }
+            }
+            if ui.button("Flip").clicked() {
+                edited.normal = -edited.normal;
+            }
+            if ui.button("Toward the camera").on_hover_text("Cut away what is between the camera and the plane").clicked()
+                && let Ok(camera) = camera_q.single()
+            {
+                edited.normal = -camera.forward().as_vec3();
+            }
+        });
+        ui.label(format!("({:.3}, {:.3}, {:.3})", edited.normal.x, edited.normal.y, edited.normal.z));
+        let offset = edited.point.dot(edited.normal);
+        let mut dragged = offset;
+        ui.horizontal(|ui| {
+            ui.label("Offset along the normal");
+            ui.add(egui::DragValue::new(&mut dragged).speed(0.01).suffix(" m"));
+        });
+        edited.point += edited.normal * (dragged - offset);
+        ui.horizontal(|ui| {
+            ui.label("Through");
+            ui.add(egui::DragValue::new(&mut edited.point.x).speed(0.01).prefix("x "));
+            ui.add(egui::DragValue::new(&mut edited.point.y).speed(0.01).prefix("y "));
+            ui.add(egui::DragValue::new(&mut edited.point.z).speed(0.01).prefix("z "));
+        });
+        if let Some(id) = selection.primary()
+            && ui.button(format!("Through {}", dag.nodes[id].name)).clicked()
+        {
+            edited.point = dag.nodes[id].world.translation.to_vec3();
+        }
+        if edited != *clip {
+            *clip = edited;
+        }
+    });
+    Ok(())
+}
+
+/// Per-frame error of the tree against the reference, worst first, and the
+/// heatmap settings, shown when one is loaded.
+pub fn reference_panel(
+    mut contexts: EguiContexts,
+    dag: Res<TransformTree>,
+    reference: Option<Res<DiffTree>>,
+    mut heatmap: ResMut<Heatmap>,
+) -> Result {
+    let Some(reference) = reference else {
+        return Ok(());
+    };
+    let mut deltas = diff::frame_deltas(&reference.0, &dag);
+    deltas.sort_by(|a, b| b.distance().total_cmp(&a.distance()));
+    let missing = reference.0.nodes.iter().filter(|n| dag.find(&n.name).is_none()).count();
+    let mut settings = heatmap.clone();
+    egui::Window::new("Reference").default_open(false).show(contexts.ctx_mut()?, |ui| {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut settings.enabled, "Heatmap");
+            let (translation, rotation) = settings.scale(&deltas);
+            let mut fixed = settings.translation.is_some();
+            if ui.checkbox(&mut fixed, "Fixed scale").changed() {
+                (settings.translation, settings.rotation) = if fixed { (Some(translation), Some(rotation)) } else { (None, None) };
+            }
+            if let (Some(translation), Some(rotation)) = (settings.translation.as_mut(), settings.rotation.as_mut()) {
+                let mut mm = *translation * 1000.0;
+                if ui.add(egui::DragValue::new(&mut mm).speed(0.1).range(0.01..=1.0e6).suffix(" mm")).changed() {
+                    *translation = mm / 1000.0;
+                }
+                ui.add(egui::DragValue::new(rotation).speed(0.01).range(0.001..=180.0).suffix("°"));
+            }
+        });
+        if missing > 0 {
+            ui.label(format!("{} reference frames not in the tree yet", missing));
+        }
+        egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+            egui::Grid::new("reference_grid").striped(true).show(ui, |ui| {
+                ui.strong("Frame");
+                ui.strong("|dt| (mm)");
+                ui.strong("drot (°)");
+                ui.end_row();
+                for d in &deltas {
+                    ui.label(&d.name);
+                    ui.monospace(format!("{:.2}", d.distance() * 1000.0));
+                    ui.monospace(format!("{:.3}", d.rotation_deg));
+                    ui.end_row();
+                }
+            });
+        });
+    });
+    if settings != *heatmap {
+        *heatmap = settings;
+    }
+    Ok(())
+}
+
+/// Rhai console; `Ctrl+Enter` or "Run" evaluates the input against the tree.
+pub fn console_panel(mut contexts: EguiContexts, mut console: ResMut<ScriptConsole>, mut dag: ResMut<TransformTree>) -> Result {
+    let mut run = false;
+    egui::Window::new("Console").default_open(false).show(contexts.ctx_mut()?, |ui| {
+        egui::ScrollArea::vertical().max_height(200.0).stick_to_bottom(true).show(ui, |ui| {
+            for line in &console.log {
+                ui.monospace(line);
+            }
+        });
+        let edit = ui.add(egui::TextEdit::multiline(&mut console.input).code_editor().desired_rows(4));
+        run = edit.has_focus() && ui.input(|i| i.modifiers.ctrl && i.key_pressed(egui::Key::Enter));
+        ui.horizontal(|ui| {
+            run |= ui.button("Run").clicked();
+            if ui.button("Clear").clicked() {
+                console.log.clear();
+            }
+        });
+    });
+    if run && !console.input.trim().is_empty() {
+        let input = console.input.clone();
+        console.log.extend(input.lines().map(|l| format!("> {}", l)));
+        let output = script::run(&mut dag, &input);
+        console.log.extend(output);
+    }
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use na::{Matrix3, SymmetricEigen};
+use nalgebra as na;
+
+use crate::{FileTransformTreeError, NodeId, TransformTree};
+
+/// How many standard deviations the drawn ellipsoids span.
+#[derive(Resource)]
+pub struct Sigma(pub f32);
+
+#[derive(Component)]
+pub struct CovarianceEllipsoid;
+
+/// Positional covariance from a file entry: a row-major 3x3, or the translational
+/// block of a row-major 6x6 pose covariance.
+pub fn positional_covariance(name: &str, values: &[f64]) -> Result<Mat3, FileTransformTreeError> {
+    let m = match values.len() {
+        9 => Matrix3::from_row_slice(values),
+        36 => Matrix3::from_fn(|r, c| values[r * 6 + c]),
+        n => return Err(FileTransformTreeError::Covariance(format!("{}: expected 9 or 36 values, found {}", name, n))),
+    };
+    Ok(Mat3::from_cols_array(&m.cast::<f32>().as_slice().try_into().unwrap()))
+}
+
+/// Principal axes rotation and 1-sigma radii of a covariance matrix.
+fn principal_axes(cov: Mat3) -> (Quat, Vec3) {
+    let m = Matrix3::from_column_slice(&cov.to_cols_array());
+    let eigen = SymmetricEigen::new(m);
+    let mut axes = Mat3::from_cols_array(&eigen.eigenvectors.as_slice().try_into().unwrap());
+    if axes.determinant() < 0.0 {
+        axes.z_axis = -axes.z_axis;
+    }
+    let radii = Vec3::new(eigen.eigenvalues[0], eigen.eigenvalues[1], eigen.eigenvalues[2]).max(Vec3::ZERO);
+    (Quat::from_mat3(&axes).normalize(), Vec3::new(radii.x.sqrt(), radii.y.sqrt(), radii.z.sqrt()))
+}
+
+/// Keeps one translucent ellipsoid per node carrying a covariance, expressed in
+/// the node's own frame.
+pub fn sync_ellipsoids(
+    mut commands: Commands,
+    dag: Res<TransformTree>,
+    sigma: Res<Sigma>,
+    mut spawned: Local<HashMap<NodeId, Entity>>,
+    mut ellipsoid_q: Query<&mut Transform, With<CovarianceEllipsoid>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !dag.is_changed() && !sigma.is_changed() {
+        return;
+    }
+    for (id, node) in dag.nodes.iter().enumerate() {
+        let Some(cov) = node.covariance else {
+            continue;
+        };
+        let (rotation, radii) = principal_axes(cov);
+        let transform = Transform {
+            translation: node.world.translation.to_vec3(),
+            rotation: node.world.rotation * rotation,
+            scale: (radii * sigma.0).max(Vec3::splat(1e-4)),
+        };
+        match spawned.get(&id).and_then(|&e| ellipsoid_q.get_mut(e).ok()) {
+            Some(mut t) => *t = transform,
+            None => {
+                let e = commands.spawn((
+                    CovarianceEllipsoid,
+                    Mesh3d(meshes.add(Sphere::new(1.0))),
+                    MeshMaterial3d(materials.add(StandardMaterial {
+                        base_color: Color::srgba(0.3, 0.6, 1.0, 0.25),
+                        alpha_mode: AlphaMode::Blend,
+                        unlit: true,
+                        ..default()
+                    })),
+                    transform,
+                    Pickable::IGNORE,
+                )).id();
+                spawned.insert(id, e);
+            }
+        }
+    }
+}
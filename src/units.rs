@@ -0,0 +1,117 @@
+//! Length units of tree files. Everything is held in meters; files authored
+//! in millimeters or centimeters (typical of CAD exports) are scaled on
+//! import and back on export.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::FileTransformTree;
+use crate::collision::Shape;
+use crate::joint::JointType;
+use crate::timeline::Animation;
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Units {
+    Mm,
+    Cm,
+    #[default]
+    M,
+}
+
+impl Units {
+    /// Meters per unit.
+    pub fn scale(self) -> f64 {
+        match self {
+            Units::Mm => 0.001,
+            Units::Cm => 0.01,
+            Units::M => 1.0,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Units::Mm => "mm",
+            Units::Cm => "cm",
+            Units::M => "m",
+        }
+    }
+}
+
+/// Scales a row-major 3x3 positional or 6x6 pose covariance: each entry
+/// takes a factor per translational row and column.
+fn scale_covariance(values: &mut [f64], factor: f64) {
+    let n = if values.len() == 36 { 6 } else { 3 };
+    for (i, v) in values.iter_mut().enumerate() {
+        let (row, col) = (i / n, i % n);
+        *v *= factor.powi((row < 3) as i32 + (col < 3) as i32);
+    }
+}
+
+/// Multiplies every length of `tree` by `factor`: translations (expressions
+/// included), collision offsets and shapes, prismatic joint limits and
+/// values, camera depths, tag sizes, linear velocities and positional
+/// covariances, and the translations of its motion.
+fn scale(tree: &mut FileTransformTree, animation: Option<&mut Animation>, factor: f64) {
+    for node in &mut tree.nodes {
+        node.t = node.t.map(|v| v * factor);
+        if let Some(expressions) = node.expressions.as_mut() {
+            for e in expressions.t.iter_mut().flatten() {
+                *e = format!("({}) * {}", e, factor);
+            }
+        }
+        for collision in &mut node.collision {
+            collision.t = collision.t.map(|v| v * factor);
+            match &mut collision.shape {
+                Shape::Box { size } => *size = size.map(|v| v * factor),
+                Shape::Sphere { radius } => *radius *= factor,
+                Shape::Cylinder { radius, length } | Shape::Capsule { radius, length } => {
+                    *radius *= factor;
+                    *length *= factor;
+                }
+            }
+        }
+        if let Some(camera) = node.camera.as_mut() {
+            camera.depth = camera.depth.map(|d| d * factor);
+            camera.image_distance = camera.image_distance.map(|d| d * factor);
+        }
+        if let Some(tag) = node.tag.as_mut() {
+            tag.size *= factor;
+        }
+        if let Some(twist) = node.twist.as_mut() {
+            twist.linear = twist.linear.map(|v| v * factor);
+        }
+        if let Some(covariance) = node.covariance.as_mut() {
+            scale_covariance(covariance, factor);
+        }
+        if let Some(joint) = node.joint.as_mut().filter(|j| j.kind == JointType::Prismatic) {
+            joint.limits = joint.limits.map(|limits| limits.map(|v| v * factor));
+            joint.value = joint.value.map(|v| v * factor);
+        }
+    }
+    for track in animation.into_iter().flat_map(|a| &mut a.tracks) {
+        for pose in &mut track.poses {
+            pose.translation *= factor as f32;
+        }
+    }
+}
+
+/// Converts a loaded tree to meters. `units` overrides the file's own
+/// `units` field; returns the units it was in.
+pub fn to_meters(tree: &mut FileTransformTree, animation: Option<&mut Animation>, units: Option<Units>) -> Units {
+    let units = units.or(tree.units).unwrap_or_default();
+    if units != Units::M {
+        scale(tree, animation, units.scale());
+    }
+    tree.units = None;
+    units
+}
+
+/// Converts a tree in meters to `units` for writing, recording them in its
+/// `units` field.
+pub fn from_meters(tree: &mut FileTransformTree, units: Units) {
+    if units != Units::M {
+        scale(tree, None, 1.0 / units.scale());
+        tree.units = Some(units);
+    }
+}
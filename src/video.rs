@@ -0,0 +1,217 @@
+//! Frame-by-frame rendering of playback and turntables. Each step poses the
+//! timeline and camera for a fixed frame rate, waits an update for the pose
+//! to be drawn, and saves a PNG of it. `axisviz render` draws offscreen into
+//! an image with the window hidden; the View panel's turntable export
+//! captures the window itself. A PNG sequence becomes a video through ffmpeg.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use bevy::camera::RenderTarget;
+use bevy::prelude::*;
+use bevy::render::render_resource::TextureFormat;
+use bevy::render::view::screenshot::{Screenshot, ScreenshotCaptured, save_to_disk};
+use bevy::window::PrimaryWindow;
+
+use crate::camera::{MainCamera, Turntable};
+use crate::timeline::Timeline;
+
+/// Updates to wait before the first offscreen frame, while pipelines compile
+/// and assets load; frames before that can come out blank.
+const WARMUP: usize = 30;
+
+#[derive(Debug, Clone)]
+pub enum Output {
+    /// One PNG.
+    Still(PathBuf),
+    /// `frame_00000.png`, `frame_00001.png`, ... in a directory.
+    Frames(PathBuf),
+}
+
+impl Output {
+    pub fn path(&self, frame: usize) -> PathBuf {
+        match self {
+            Output::Still(path) => path.clone(),
+            Output::Frames(dir) => frame_path(dir, frame),
+        }
+    }
+}
+
+/// Frames covering `duration` seconds at `fps`, both ends included.
+pub fn frame_count(duration: f64, fps: f64) -> usize {
+    (duration * fps + 1e-9).floor() as usize + 1
+}
+
+/// Frames of one turntable revolution at `fps`. The last pose would repeat
+/// the first, so it is left out and the clip loops seamlessly.
+pub fn turntable_frames(seconds: f64, fps: f64) -> usize {
+    ((seconds * fps).round() as usize).max(1)
+}
+
+pub fn frame_path(dir: &Path, frame: usize) -> PathBuf {
+    dir.join(format!("frame_{:05}.png", frame))
+}
+
+/// Whether `path` names a video or GIF rather than a PNG or a directory.
+pub fn is_video(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext != "png")
+}
+
+/// Scratch directory for the frames of a video.
+pub fn scratch_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("axisviz-render-{}", std::process::id()))
+}
+
+/// Encodes the PNG sequence in `dir` to `output` with ffmpeg.
+pub fn encode(dir: &Path, fps: f64, output: &Path) -> io::Result<()> {
+    let mut ffmpeg = std::process::Command::new("ffmpeg");
+    ffmpeg.args(["-y", "-loglevel", "error", "-framerate", &fps.to_string(), "-i"]).arg(dir.join("frame_%05d.png"));
+    // Most players only take 4:2:0 video; a GIF builds its own palette.
+    if output.extension().is_none_or(|ext| ext != "gif") {
+        ffmpeg.args(["-pix_fmt", "yuv420p"]);
+    }
+    let status = ffmpeg.arg(output).status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("ffmpeg exited with {}", status)));
+    }
+    Ok(())
+}
+
+#[derive(Resource, Debug)]
+pub struct VideoRender {
+    pub output: Output,
+    pub size: UVec2,
+    pub fps: f64,
+    /// Frames to write.
+    pub frames: usize,
+    /// Video to encode the frames to once they are written, from the window.
+    /// `axisviz render` encodes after the app exits instead.
+    pub encode: Option<PathBuf>,
+    frame: usize,
+    warmup: usize,
+    /// The timeline and camera were moved to the current frame last update.
+    posed: bool,
+    captured: Arc<AtomicBool>,
+    waiting: bool,
+    /// Offscreen image drawn into; the window when not set.
+    target: Option<Handle<Image>>,
+}
+
+impl VideoRender {
+    /// `frames` frames at `fps`; the timeline, if any, should be paused.
+    pub fn new(output: Output, size: UVec2, fps: f64, frames: usize) -> Self {
+        VideoRender {
+            output,
+            size,
+            fps,
+            frames,
+            encode: None,
+            frame: 0,
+            warmup: WARMUP,
+            posed: false,
+            captured: Arc::new(AtomicBool::new(false)),
+            waiting: false,
+            target: None,
+        }
+    }
+
+    /// Captures the window of a running viewer, which is warmed up already,
+    /// and leaves it open when done.
+    pub fn from_window(output: Output, fps: f64, frames: usize) -> Self {
+        VideoRender { warmup: 0, ..VideoRender::new(output, UVec2::ZERO, fps, frames) }
+    }
+
+    /// Frames written so far.
+    pub fn progress(&self) -> usize {
+        self.frame
+    }
+}
+
+/// Points the main camera at an image of the output size and hides the window.
+pub fn setup_target(
+    mut video: ResMut<VideoRender>,
+    mut images: ResMut<Assets<Image>>,
+    mut camera_q: Query<&mut Camera, With<MainCamera>>,
+    mut window_q: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let image = Image::new_target_texture(video.size.x, video.size.y, TextureFormat::Rgba8UnormSrgb);
+    let target = images.add(image);
+    for mut camera in &mut camera_q {
+        camera.target = RenderTarget::Image(target.clone().into());
+    }
+    for mut window in &mut window_q {
+        window.visible = false;
+    }
+    video.target = Some(target);
+}
+
+/// Poses the timeline and turntable for a frame, waits an update for it to
+/// be drawn, then captures it. Offscreen renders exit after the last frame;
+/// window captures start encoding and stop.
+pub fn step(
+    mut commands: Commands,
+    mut video: ResMut<VideoRender>,
+    timeline: Option<ResMut<Timeline>>,
+    mut turntable: ResMut<Turntable>,
+    mut exit: MessageWriter<AppExit>,
+) {
+    if video.warmup > 0 {
+        video.warmup -= 1;
+        return;
+    }
+    if video.waiting {
+        if !video.captured.swap(false, Ordering::AcqRel) {
+            return;
+        }
+        video.waiting = false;
+        video.frame += 1;
+    }
+    if video.frame == video.frames {
+        finish(&mut commands, &video, &mut turntable, &mut exit);
+        return;
+    }
+    if !video.posed {
+        let time = video.frame as f64 / video.fps;
+        if let Some(mut timeline) = timeline {
+            timeline.seek(time);
+        }
+        if turntable.spinning() {
+            turntable.elapsed = Some(time as f32);
+        }
+        video.posed = true;
+        return;
+    }
+    let screenshot = match &video.target {
+        Some(target) => Screenshot::image(target.clone()),
+        None => Screenshot::primary_window(),
+    };
+    let captured = video.captured.clone();
+    commands
+        .spawn(screenshot)
+        .observe(save_to_disk(video.output.path(video.frame)))
+        .observe(move |_: On<ScreenshotCaptured>| captured.store(true, Ordering::Release));
+    video.posed = false;
+    video.waiting = true;
+}
+
+fn finish(commands: &mut Commands, video: &VideoRender, turntable: &mut Turntable, exit: &mut MessageWriter<AppExit>) {
+    if video.target.is_some() {
+        exit.write(AppExit::Success);
+        return;
+    }
+    turntable.stop();
+    commands.remove_resource::<VideoRender>();
+    if let (Output::Frames(dir), Some(output)) = (video.output.clone(), video.encode.clone()) {
+        let fps = video.fps;
+        // Encoding takes a while; the viewer stays responsive meanwhile.
+        std::thread::spawn(move || {
+            match encode(&dir, fps, &output) {
+                Ok(()) => info!("wrote {}", output.display()),
+                Err(e) => error!("{}: {}", output.display(), e),
+            }
+            let _ = std::fs::remove_dir_all(&dir);
+        });
+    }
+}
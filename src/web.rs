@@ -0,0 +1,89 @@
+//! Browser entry point. Trees come from a `?tree=<url>` query parameter or the
+//! "Open" file picker instead of the command line, and are fed to the viewer
+//! through the same update channel as `--stdin`.
+
+use std::path::Path;
+use std::sync::mpsc::Sender;
+
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+
+use crate::stream::UpdateReceiver;
+use crate::{FileNode, TransformTree, formats, grid};
+
+/// Sender half of the update channel that loaded trees are pushed into.
+#[derive(Resource, Clone)]
+pub struct TreeLoader(Sender<FileNode>);
+
+impl TreeLoader {
+    /// Parses `text` by the extension of `name` and queues every node.
+    fn send(&self, name: &str, text: &str) {
+        match formats::parse(&formats::extension(Path::new(name)), text) {
+            Ok(tree) => {
+                for node in tree.nodes {
+                    let _ = self.0.send(node);
+                }
+            }
+            Err(e) => error!("{}: {}", name, e),
+        }
+    }
+
+    pub fn fetch(&self, url: String) {
+        let loader = self.clone();
+        ehttp::fetch(ehttp::Request::get(&url), move |response| match response {
+            Ok(r) if r.ok => match r.text() {
+                Some(text) => loader.send(&url, text),
+                None => error!("{}: response is not text", url),
+            },
+            Ok(r) => error!("{}: {} {}", url, r.status, r.status_text),
+            Err(e) => error!("{}: {}", url, e),
+        });
+    }
+
+    pub fn pick_file(&self) {
+        let loader = self.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let Some(file) = rfd::AsyncFileDialog::new().pick_file().await else {
+                return;
+            };
+            let bytes = file.read().await;
+            match String::from_utf8(bytes) {
+                Ok(text) => loader.send(&file.file_name(), &text),
+                Err(e) => error!("{}: {}", file.file_name(), e),
+            }
+        });
+    }
+}
+
+fn query_url() -> Option<String> {
+    let search = web_sys::window()?.location().search().ok()?;
+    web_sys::UrlSearchParams::new_with_str(&search).ok()?.get("tree")
+}
+
+pub fn open_panel(mut contexts: EguiContexts, loader: Res<TreeLoader>, mut url: Local<String>) -> Result {
+    egui::Window::new("Open").default_open(false).show(contexts.ctx_mut()?, |ui| {
+        if ui.button("Open file...").clicked() {
+            loader.pick_file();
+        }
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut *url);
+            if ui.button("Load URL").clicked() && !url.trim().is_empty() {
+                loader.fetch(url.trim().to_string());
+            }
+        });
+    });
+    Ok(())
+}
+
+pub fn run() {
+    let (tx, rx) = UpdateReceiver::new();
+    let loader = TreeLoader(tx);
+    if let Some(url) = query_url() {
+        loader.fetch(url);
+    }
+    let mut app = crate::viewer(TransformTree::default(), grid::GridSettings::default());
+    app.insert_resource(rx)
+        .insert_resource(loader)
+        .add_systems(EguiPrimaryContextPass, open_panel);
+    app.run();
+}
@@ -0,0 +1,117 @@
+//! Reachable workspace of a frame below movable joints: joint space is sampled
+//! and the positions the frame reaches are drawn as a translucent point cloud.
+
+use bevy::asset::RenderAssetUsages;
+use bevy::mesh::PrimitiveTopology;
+use bevy::prelude::*;
+
+use crate::{FrameMarkers, NodeId, TransformTree, ik};
+
+#[derive(Resource, Debug)]
+pub struct Workspace {
+    /// Frame whose reach is shown; `None` hides the cloud.
+    pub node: Option<NodeId>,
+    pub samples: usize,
+    /// Sampled positions, in the frame the chain hangs from.
+    points: Vec<Vec3>,
+    /// Frame the chain hangs from, the parent of its topmost joint.
+    base: Option<NodeId>,
+}
+
+impl Default for Workspace {
+    fn default() -> Self {
+        Workspace { node: None, samples: 5000, points: vec![], base: None }
+    }
+}
+
+impl Workspace {
+    /// Samples the reach of `end` and shows it.
+    pub fn sample(&mut self, dag: &TransformTree, end: NodeId) {
+        let joints = ik::chain(dag, end);
+        let base = joints.first().and_then(|&id| dag.nodes[id].parent);
+        let to_base = base.map_or(Isometry3d::IDENTITY, |id| dag.nodes[id].world).inverse();
+        let mut posed = dag.clone();
+        let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+        self.points = (0..self.samples)
+            .map(|_| {
+                for &id in &joints {
+                    let Some(joint) = &posed.nodes[id].joint else {
+                        continue;
+                    };
+                    let (lo, hi) = joint.range();
+                    posed.set_joint(id, lo + (hi - lo) * rng.uniform());
+                }
+                posed.update_world();
+                to_base * posed.nodes[end].world.translation.to_vec3()
+            })
+            .collect();
+        self.node = Some(end);
+        self.base = base;
+    }
+
+    pub fn clear(&mut self) {
+        self.node = None;
+        self.points.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+}
+
+/// Small deterministic generator, so the same tree gives the same cloud.
+struct XorShift(u64);
+
+impl XorShift {
+    /// Uniform in 0..1.
+    fn uniform(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+#[derive(Component)]
+pub struct WorkspaceCloud;
+
+/// Rebuilds the cloud when the samples change, parented to the chain's base
+/// frame so it follows the tree.
+pub fn sync_workspace(
+    mut commands: Commands,
+    workspace: Res<Workspace>,
+    markers: Res<FrameMarkers>,
+    cloud_q: Query<Entity, With<WorkspaceCloud>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !workspace.is_changed() {
+        return;
+    }
+    for entity in &cloud_q {
+        commands.entity(entity).despawn();
+    }
+    if workspace.node.is_none() || workspace.is_empty() {
+        return;
+    }
+    let parent = workspace.base.and_then(|id| markers.entity(id)).unwrap_or(markers.root);
+    let mesh = Mesh::new(PrimitiveTopology::PointList, RenderAssetUsages::default())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, workspace.points.clone());
+    commands.spawn((
+        WorkspaceCloud,
+        Mesh3d(meshes.add(mesh)),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: Color::srgba(0.3, 0.8, 1.0, 0.35),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        })),
+        Transform::default(),
+        Pickable::IGNORE,
+        ChildOf(parent),
+    ));
+}
@@ -0,0 +1,41 @@
+//! OpenXR headset view (`--xr`, feature `xr`). The headset renders the scene
+//! at true scale, one meter in the tree to one meter in the room, so a sensor
+//! rig can be walked around. The wearer stands on the ground below the orbit
+//! camera's focus with the room's up along the grid's, so steering the
+//! desktop view moves them through the tree; the desktop window keeps its
+//! own camera and panels.
+
+use bevy::prelude::*;
+use bevy_mod_openxr::add_xr_plugins;
+use bevy_mod_xr::session::XrTrackingRoot;
+use bevy_panorbit_camera::PanOrbitCamera;
+
+use crate::camera::MainCamera;
+use crate::grid::{GridPlane, GridSettings};
+use crate::TransformTree;
+
+/// `viewer` with the OpenXR plugins, which set up the renderer for the
+/// headset and spawn its cameras below the tracking root.
+pub fn viewer(dag: TransformTree, grid: GridSettings) -> App {
+    let mut app = crate::viewer_with(dag, grid, add_xr_plugins(DefaultPlugins));
+    app.add_systems(Update, place_tracking_root);
+    app
+}
+
+/// Pose of the headset's play area: on the ground below `focus`, its Y up
+/// along the grid's.
+pub fn tracking_root(focus: Vec3, plane: GridPlane) -> Transform {
+    let up = plane.up();
+    Transform::from_translation(focus - up * focus.dot(up)).with_rotation(Quat::from_rotation_arc(Vec3::Y, up))
+}
+
+fn place_tracking_root(
+    grid: Res<GridSettings>,
+    orbit_q: Query<&PanOrbitCamera, With<MainCamera>>,
+    mut root_q: Query<&mut Transform, With<XrTrackingRoot>>,
+) {
+    let (Ok(orbit), Ok(mut root)) = (orbit_q.single(), root_q.single_mut()) else {
+        return;
+    };
+    root.set_if_neq(tracking_root(orbit.focus, grid.plane));
+}